@@ -0,0 +1,778 @@
+// In this file is all the stuff needed to use RPFM from a terminal, without starting the UI.
+// Every command here does the same as his counterpart in the UI, but prints the result instead of
+// showing it in a dialog. As a rule, there should be no GTK-related stuff in this module.
+
+extern crate failure;
+
+use std::cell::RefCell;
+use std::path::PathBuf;
+use std::rc::Rc;
+
+use failure::Error;
+
+use common::progress::{Progress, is_cancelled_error};
+use packfile;
+use packfile::packfile::{PackFile, PackedFile};
+use packfile::dependency_pack::*;
+use packedfile::db::DB;
+use packedfile::db::schemas::Schema;
+use packedfile::db::compatibility::CompatibilityReport;
+use packedfile::db::references::ReferenceReport;
+use packedfile::db::validation::ValidationReport;
+use packedfile::db::query::Query;
+use packedfile::db::key_rename::KeyRename;
+use packedfile::db::deep_clone::DeepClone;
+use packedfile::db::inbound_references::ReferenceIndex;
+use packedfile::db::patches::DBPatch;
+use packedfile::db::bulk_edit::BulkEdit;
+use packedfile::db::fragments::*;
+use packedfile::db::query::Expression;
+use packedfile::normalize::{normalize_packed_files, normalize_packed_files_before_save};
+use packedfile::db::assembly_kit::*;
+use packedfile::db::schemas_importer::import_schema;
+use packedfile::db::schemas_pfm::*;
+use packedfile::db::schemas_diff::SchemaDiff;
+use packedfile::db::schemas_docs::*;
+use packedfile::sqlite::*;
+use settings::*;
+
+/// This is the text we show when someone asks for help, or uses a command wrong.
+const HELP: &str = "Usage: rpfm-code <COMMAND> [ARGUMENTS] [--game=GAME]
+
+Commands:
+    check-tables <PACKFILE>     Lists the tables of the PackFile with a different version than the game's ones,
+                                and the ones without a definition in the schema.
+    check-references <PACKFILE> Lists the cells of the PackFile's tables referencing values that don't exist
+                                neither in the PackFile nor in the game's files.
+    validate <PACKFILE>         Lists the rows of the PackFile's tables with duplicated keys, empty required
+                                fields or fields longer than allowed.
+    export-sqlite <PACKFILE> <SQLITE_FILE> [--with-dependencies]
+                                Exports the tables and Locs of the PackFile (and the ones of the Dependency
+                                Database, if "--with-dependencies" is used) to a new SQLite database.
+    import-sqlite <PACKFILE> <SQLITE_FILE>
+                                Imports back the tables and Locs of the PackFile from a SQLite database
+                                created with "export-sqlite", and saves the PackFile.
+    query <PACKFILE> <QUERY> [--format=tsv|json] [--with-dependencies]
+                                Runs a query over the tables of the PackFile (and the ones of the Dependency
+                                Database, if "--with-dependencies" is used) and prints the result. Example:
+                                'land_units where category = \"inf_melee\" and morale > 50 select key, morale'
+    import-assembly-kit <PACKFILE> <RAW_DATA_FOLDER>
+                                Imports the tables from the "raw_data/db" folder of the Assembly Kit into the
+                                PackFile, and saves it.
+    export-assembly-kit <PACKFILE> <FOLDER>
+                                Exports the tables of the PackFile to Assembly Kit XML files in the folder.
+    import-schema <RAW_DATA_FOLDER>
+                                Merges the table definitions (TWaD_*.xml files) of the Assembly Kit into the
+                                schema of the game, keeping the manually decoded versions and descriptions.
+    import-schema-pfm <MASTER_SCHEMA>
+                                Merges the table definitions of a PFM schema file (master_schema.xml) into the
+                                schema of the game. Only the tables the game has are merged.
+    export-schema-pfm <MASTER_SCHEMA>
+                                Writes the schema of the game to a PFM schema file.
+    check-schema-overlay [--prune]
+                                Lists the definitions of your schema (the ones you decoded or imported) that are
+                                now in the downloaded schema. With "--prune", the identical ones are removed.
+    export-schema-overlay <FILE>
+                                Exports the definitions of your schema that are not in the downloaded schema to
+                                a file, so they can be contributed to RPFM's repository.
+    diff-schemas <OLD_SCHEMA> <NEW_SCHEMA> [--format=markdown|json]
+                                Prints the differences between two schema files: tables and versions added or
+                                removed, and fields renamed, retyped, moved or with different keys or references.
+    generate-schema-docs <FOLDER> [--format=markdown|html]
+                                Writes the docs of every table of the schema to a folder: one page per table with
+                                his versions, fields and references, an index, and a graph of the relations
+                                between tables (relations.dot).
+    rename-key <PACKFILE> <TABLE> <COLUMN> <OLD_KEY> <NEW_KEY> [--dry-run]
+                                Renames a key of a table, changing also the cells referencing it in the rest of
+                                the tables and the Loc keys of the row, and saves the PackFile. It prints all the
+                                changes first. With "--dry-run", nothing is changed.
+    clone-with-dependencies <PACKFILE> <TABLE> <COLUMN> <KEY> <KEY_PATTERN> [--dry-run]
+                                Clones a row (from the PackFile or the Dependency Database) with all the rows
+                                referencing it, directly or not, and their Loc entries, and saves the PackFile.
+                                The new keys are the old ones with "{key}" in the pattern replaced, like
+                                "{key}_copy". It prints all the new rows first. With "--dry-run", nothing is added.
+    find-references <PACKFILE> <TABLE> <COLUMN> <VALUE>
+                                Lists the rows of the PackFile and the Dependency Database (if there is one) that
+                                reference a value of a column, like the key of a row you want to delete.
+    generate-patch <PACKFILE> <FILE> [--table=TABLE] [--with-deletes]
+                                Compares the tables of a PackFile (or only TABLE) with the ones of the Dependency
+                                Database, and saves the added and modified rows as a patch (JSON). With
+                                "--with-deletes", the vanilla rows missing in the PackFile are deleted too.
+    apply-patch <PACKFILE> <FILE> [--dry-run]
+                                Applies a patch to the current tables of the Dependency Database and saves the
+                                result in the PackFile, as "db/<table>/<packfile name>". It prints the conflicts
+                                and the operations that couldn't be applied. With "--dry-run", nothing is saved.
+    bulk-edit <PACKFILE> <TABLE> <COLUMN> <EXPRESSION> [--where=FILTER] [--dry-run]
+                                Changes a column in all the rows of a table (or only the ones matching FILTER)
+                                to the result of an expression, like "round(cost * 1.15)" or "\"{key}_name\"",
+                                and saves the PackFile. It prints all the changes first. With "--dry-run",
+                                nothing is saved.
+    merge-tables <PACKFILE> [--table=TABLE] [--version=VERSION] [--name=NAME] [--dry-run]
+                                Merges the fragments of every table with more than one (or only TABLE) into one
+                                PackedFile, named like the first fragment or NAME, and saves the PackFile. Rows
+                                with duplicated keys are reported, and only the one the game uses is kept. With
+                                "--dry-run", nothing is saved.
+    split-table <PACKFILE> <TABLE_PATH> [--prefixes=PREFIX,PREFIX...] [--where=FILTER --name=NAME] [--dry-run]
+                                Moves the rows of a table (like "db/land_units_tables/my_mod") whose key starts
+                                with each prefix to a new PackedFile named "<name>_<prefix>", and the rows
+                                matching FILTER to a new PackedFile named NAME, and saves the PackFile. With
+                                "--dry-run", nothing is saved.
+    normalize <PACKFILE> [--deterministic-guids] [--dry-run]
+                                Sorts the rows of every DB Table (by their keys) and Loc (by key) of a PackFile,
+                                so the same data is always saved the same way, and saves the PackFile. With
+                                "--deterministic-guids" (or if it's enabled in the settings), the GUIDs of the
+                                tables are generated from their paths. With "--dry-run", nothing is saved.
+    generate-dependency-pack [--check]
+                                Generates the Dependency PackFile of the game from the DB Tables and Locs of all
+                                his official PackFiles, in load order. With "--check", it only lists the official
+                                PackFiles that changed since the Dependency PackFile was generated.
+    help                        Shows this message.
+
+Options:
+    --game=GAME                 The game to use for the command (warhammer_2, warhammer, attila). If it's
+                                not provided, the default game from the settings is used.
+
+Exit codes: 0 if everything went fine, 1 if there was an error, 2 if the command found problems, 3 if the command
+was cancelled.";
+
+/// `Arguments`: This struct holds the arguments of a command, already split in:
+/// - positional: the arguments without "--" at the start, in order.
+/// - options: the arguments in the "--name=value" format, as (name, value).
+/// - flags: the arguments in the "--name" format, without the "--".
+#[derive(Clone, Debug)]
+pub struct Arguments {
+    pub positional: Vec<String>,
+    pub options: Vec<(String, String)>,
+    pub flags: Vec<String>,
+}
+
+/// `CliContext`: This struct holds all the stuff the commands may need. It's the same stuff the UI
+/// loads on start, but without the `Rc<RefCell<>>` mess:
+/// - rpfm_path: the path where RPFM's stuff (settings, schemas,...) is.
+/// - supported_games: the list of games RPFM supports.
+/// - settings: the settings of RPFM, or the defaults if they don't exist.
+/// - game_selected: the game we're going to use for the command.
+/// - schema: the schema for the game selected, if it could be loaded.
+/// - dependency_database: the tables from the dependency PackFile of the game selected, if it exists. It's loaded
+///   the first time a command asks for it, as it's big and most commands don't need it.
+pub struct CliContext {
+    pub rpfm_path: PathBuf,
+    pub supported_games: Vec<GameInfo>,
+    pub settings: Settings,
+    pub game_selected: GameSelected,
+    pub schema: Option<Schema>,
+    dependency_database: RefCell<Option<Option<Rc<Vec<PackedFile>>>>>,
+}
+
+/// This function checks if the first argument RPFM received is one of our commands and, in that case,
+/// runs it. It returns the exit code of the command, or `None` if there is no command to run, so
+/// the UI can start normally (for example, when we open RPFM by double-clicking a PackFile).
+pub fn run(arguments: &[String], rpfm_path: &PathBuf) -> Option<i32> {
+
+    // If there is no command, there is nothing to do here.
+    let command = match arguments.get(1) {
+        Some(command) => command.to_owned(),
+        None => return None,
+    };
+
+    // Split the rest of the arguments.
+    let arguments = Arguments::new(&arguments[2..]);
+
+    // Depending on the command, we do one thing or another. If it's not one of our commands, we let the UI deal with it.
+    let result = match &*command {
+        "check-tables" => CliContext::new(rpfm_path, &arguments).and_then(|context| check_tables(&context, &arguments)),
+        "check-references" => CliContext::new(rpfm_path, &arguments).and_then(|context| check_references(&context, &arguments)),
+        "validate" => CliContext::new(rpfm_path, &arguments).and_then(|context| validate(&context, &arguments)),
+        "export-sqlite" => CliContext::new(rpfm_path, &arguments).and_then(|context| export_sqlite(&context, &arguments)),
+        "import-sqlite" => CliContext::new(rpfm_path, &arguments).and_then(|context| import_sqlite(&context, &arguments)),
+        "query" => CliContext::new(rpfm_path, &arguments).and_then(|context| query(&context, &arguments)),
+        "import-assembly-kit" => CliContext::new(rpfm_path, &arguments).and_then(|context| import_assembly_kit(&context, &arguments)),
+        "export-assembly-kit" => CliContext::new(rpfm_path, &arguments).and_then(|context| export_assembly_kit(&context, &arguments)),
+        "import-schema" => CliContext::new(rpfm_path, &arguments).and_then(|context| import_schema_assembly_kit(&context, &arguments)),
+        "import-schema-pfm" => CliContext::new(rpfm_path, &arguments).and_then(|context| import_schema_pfm(&context, &arguments)),
+        "export-schema-pfm" => CliContext::new(rpfm_path, &arguments).and_then(|context| export_schema_pfm(&context, &arguments)),
+        "check-schema-overlay" => CliContext::new(rpfm_path, &arguments).and_then(|context| check_schema_overlay(&context, &arguments)),
+        "export-schema-overlay" => CliContext::new(rpfm_path, &arguments).and_then(|context| export_schema_overlay(&context, &arguments)),
+        "diff-schemas" => diff_schemas(&arguments),
+        "generate-schema-docs" => CliContext::new(rpfm_path, &arguments).and_then(|context| generate_schema_docs(&context, &arguments)),
+        "rename-key" => CliContext::new(rpfm_path, &arguments).and_then(|context| rename_key(&context, &arguments)),
+        "clone-with-dependencies" => CliContext::new(rpfm_path, &arguments).and_then(|context| clone_with_dependencies(&context, &arguments)),
+        "find-references" => CliContext::new(rpfm_path, &arguments).and_then(|context| find_references(&context, &arguments)),
+        "generate-patch" => CliContext::new(rpfm_path, &arguments).and_then(|context| generate_patch(&context, &arguments)),
+        "apply-patch" => CliContext::new(rpfm_path, &arguments).and_then(|context| apply_patch(&context, &arguments)),
+        "bulk-edit" => CliContext::new(rpfm_path, &arguments).and_then(|context| bulk_edit(&context, &arguments)),
+        "merge-tables" => CliContext::new(rpfm_path, &arguments).and_then(|context| merge_tables(&context, &arguments)),
+        "split-table" => CliContext::new(rpfm_path, &arguments).and_then(|context| split_table(&context, &arguments)),
+        "normalize" => CliContext::new(rpfm_path, &arguments).and_then(|context| normalize(&context, &arguments)),
+        "generate-dependency-pack" => CliContext::new(rpfm_path, &arguments).and_then(|context| generate_dependency_pack(&context, &arguments)),
+        "help" | "--help" | "-h" => { println!("{}", HELP); Ok(0) }
+        _ => return None,
+    };
+
+    // If there has been an error, print it.
+    match result {
+        Ok(exit_code) => Some(exit_code),
+        Err(error) => {
+            eprintln!("Error: {}", error);
+            Some(if is_cancelled_error(&error) { 3 } else { 1 })
+        }
+    }
+}
+
+/// Implementation of `Arguments`.
+impl Arguments {
+
+    /// This function splits the raw arguments into positional arguments, options and flags.
+    pub fn new(raw_arguments: &[String]) -> Self {
+        let mut positional = vec![];
+        let mut options = vec![];
+        let mut flags = vec![];
+
+        for argument in raw_arguments {
+            if argument.starts_with("--") {
+                let argument = &argument[2..];
+                match argument.find('=') {
+                    Some(index) => options.push((argument[..index].to_owned(), argument[(index + 1)..].to_owned())),
+                    None => flags.push(argument.to_owned()),
+                }
+            }
+            else { positional.push(argument.to_owned()); }
+        }
+
+        Self {
+            positional,
+            options,
+            flags,
+        }
+    }
+
+    /// This function returns the positional argument at `index`, or an error with the name we provided if it doesn't exist.
+    pub fn get_positional(&self, index: usize, name: &str) -> Result<&str, Error> {
+        match self.positional.get(index) {
+            Some(argument) => Ok(argument.as_str()),
+            None => Err(format_err!("Missing argument: <{}>.\n\n{}", name, HELP)),
+        }
+    }
+
+    /// This function returns the value of the option with the provided name, if it exists.
+    pub fn get_option(&self, name: &str) -> Option<&str> {
+        self.options.iter().rev().find(|x| x.0 == name).map(|x| x.1.as_str())
+    }
+
+    /// This function returns if the provided flag has been used or not.
+    pub fn has_flag(&self, name: &str) -> bool {
+        self.flags.iter().any(|x| x == name)
+    }
+}
+
+/// Implementation of `CliContext`.
+impl CliContext {
+
+    /// This function loads everything a command may need, using the game from the "--game" option or
+    /// the default game from the settings.
+    pub fn new(rpfm_path: &PathBuf, arguments: &Arguments) -> Result<Self, Error> {
+
+        // Load the supported games and the settings, like the UI does.
+        let supported_games = GameInfo::new();
+        let settings = Settings::load(rpfm_path, &supported_games).unwrap_or_else(|_| Settings::new(&supported_games));
+
+        // Get the game we want to use, and check it's actually supported.
+        let game = arguments.get_option("game").unwrap_or(&*settings.default_game).to_owned();
+        if !supported_games.iter().any(|x| x.folder_name == game) {
+            return Err(format_err!("The game \"{}\" is not supported. Supported games are: {}.", game, supported_games.iter().map(|x| x.folder_name.to_owned()).collect::<Vec<String>>().join(", ")));
+        }
+
+        // Prepare the `GameSelected`, his schema and his dependency database.
+        let mut game_selected = GameSelected::new(&settings, rpfm_path, &supported_games);
+        game_selected.change_game_selected(&game, &settings.paths.game_paths.iter().filter(|x| x.game == game).map(|x| x.path.clone()).collect::<Option<PathBuf>>(), &supported_games);
+
+        let schema = match Schema::load(rpfm_path, &supported_games, &game) {
+            Ok((schema, warning)) => {
+                if let Some(warning) = warning { eprintln!("Warning: {}", warning); }
+                Some(schema)
+            }
+            Err(_) => None,
+        };
+
+        Ok(Self {
+            rpfm_path: rpfm_path.to_path_buf(),
+            supported_games,
+            settings,
+            game_selected,
+            schema,
+            dependency_database: RefCell::new(None),
+        })
+    }
+
+    /// This function returns the schema, or an error if there is no schema loaded for the game selected.
+    pub fn get_schema(&self) -> Result<&Schema, Error> {
+        match self.schema {
+            Some(ref schema) => Ok(schema),
+            None => Err(format_err!("There is no schema loaded for \"{}\".", self.game_selected.game)),
+        }
+    }
+
+    /// This function returns the dependency database, loading it the first time it's needed, or None if there is
+    /// none for the game selected.
+    pub fn load_dependency_database(&self) -> Option<Rc<Vec<PackedFile>>> {
+        let mut dependency_database = self.dependency_database.borrow_mut();
+        if dependency_database.is_none() {
+            *dependency_database = Some(match packfile::open_packfile(self.game_selected.game_dependency_packfile_path.to_path_buf()) {
+                Ok(pack_file) => Some(Rc::new(pack_file.data.packed_files)),
+                Err(_) => None,
+            });
+        }
+        dependency_database.as_ref().and_then(|x| x.clone())
+    }
+
+    /// This function returns the dependency database, or an error if there is none for the game selected.
+    pub fn get_dependency_database(&self) -> Result<Rc<Vec<PackedFile>>, Error> {
+        match self.load_dependency_database() {
+            Some(dependency_database) => Ok(dependency_database),
+            None => Err(format_err!("There is no Dependency Database for \"{0}\". Generate one with \"rpfm-code generate-dependency-pack --game={0}\" and try again.", self.game_selected.game)),
+        }
+    }
+
+    /// This function saves a PackFile changed by a command. Like in the UI, it's normalized first if it's enabled
+    /// in the settings, so his tables get sorted rows or deterministic GUIDs if the user wants them.
+    pub fn save_pack_file(&self, pack_file: &mut PackFile) -> Result<(), Error> {
+        if let Some(ref schema) = self.schema {
+            normalize_packed_files_before_save(pack_file, schema, &self.settings);
+        }
+        packfile::save_packfile(pack_file, None)
+    }
+}
+
+/*
+--------------------------------------------------------
+                        Commands
+--------------------------------------------------------
+*/
+
+/// This function prints the tables of a PackFile that are outdated or without a definition in the schema.
+fn check_tables(context: &CliContext, arguments: &Arguments) -> Result<i32, Error> {
+    let pack_file = packfile::open_packfile(PathBuf::from(arguments.get_positional(0, "PACKFILE")?))?;
+    let report = CompatibilityReport::new(&pack_file.data.packed_files, context.get_schema()?, &context.get_dependency_database()?);
+    print!("{}", report);
+    Ok(if report.is_empty() { 0 } else { 2 })
+}
+
+/// This function prints the cells of a PackFile's tables that reference values that don't exist.
+fn check_references(context: &CliContext, arguments: &Arguments) -> Result<i32, Error> {
+    let pack_file = packfile::open_packfile(PathBuf::from(arguments.get_positional(0, "PACKFILE")?))?;
+    let report = ReferenceReport::new(&pack_file.data.packed_files, &context.get_dependency_database()?, context.get_schema()?);
+    print!("{}", report);
+    Ok(if report.is_empty() { 0 } else { 2 })
+}
+
+/// This function prints the errors found validating the tables of a PackFile.
+fn validate(context: &CliContext, arguments: &Arguments) -> Result<i32, Error> {
+    let pack_file = packfile::open_packfile(PathBuf::from(arguments.get_positional(0, "PACKFILE")?))?;
+    let report = ValidationReport::new(&pack_file.data.packed_files, context.get_schema()?);
+    print!("{}", report);
+    Ok(if report.is_empty() { 0 } else { 2 })
+}
+
+/// This function exports the tables and Locs of a PackFile to a SQLite database.
+fn export_sqlite(context: &CliContext, arguments: &Arguments) -> Result<i32, Error> {
+    let pack_file = packfile::open_packfile(PathBuf::from(arguments.get_positional(0, "PACKFILE")?))?;
+    let sqlite_path = PathBuf::from(arguments.get_positional(1, "SQLITE_FILE")?);
+    let dependency_database = if arguments.has_flag("with-dependencies") { Some(context.get_dependency_database()?) } else { None };
+
+    let error_files = export_to_sqlite(&pack_file.data.packed_files, dependency_database.as_ref().map(|x| &x[..]), context.get_schema()?, &sqlite_path)?;
+    for path in &error_files {
+        eprintln!("Couldn't export: {}", path.join("/"));
+    }
+    println!("PackFile exported to: {}", sqlite_path.display());
+    Ok(if error_files.is_empty() { 0 } else { 2 })
+}
+
+/// This function imports the tables and Locs of a PackFile from a SQLite database, and saves the PackFile.
+fn import_sqlite(context: &CliContext, arguments: &Arguments) -> Result<i32, Error> {
+    let mut pack_file = packfile::open_packfile(PathBuf::from(arguments.get_positional(0, "PACKFILE")?))?;
+    let sqlite_path = PathBuf::from(arguments.get_positional(1, "SQLITE_FILE")?);
+
+    let paths = import_from_sqlite(&mut pack_file, context.get_schema()?, &sqlite_path)?;
+    context.save_pack_file(&mut pack_file)?;
+    println!("{} PackedFiles imported.", paths.len());
+    Ok(0)
+}
+
+/// This function runs a query over the tables of a PackFile and prints the result as TSV or JSON.
+fn query(context: &CliContext, arguments: &Arguments) -> Result<i32, Error> {
+    let pack_file = packfile::open_packfile(PathBuf::from(arguments.get_positional(0, "PACKFILE")?))?;
+    let query = Query::parse(arguments.get_positional(1, "QUERY")?)?;
+
+    // If we want the vanilla data too, it goes first, so the rows of the PackFile come after it.
+    let mut packed_files = vec![];
+    if arguments.has_flag("with-dependencies") { packed_files.extend_from_slice(&context.get_dependency_database()?); }
+    packed_files.extend_from_slice(&pack_file.data.packed_files);
+
+    let result = query.execute(&packed_files, context.get_schema()?)?;
+    match arguments.get_option("format").unwrap_or("tsv") {
+        "tsv" => print!("{}", result.to_tsv()),
+        "json" => println!("{}", result.to_json()?),
+        format => return Err(format_err!("Unknown format: \"{}\". Supported formats are: tsv, json.", format)),
+    }
+    Ok(0)
+}
+
+/// This function imports the tables from the Assembly Kit into a PackFile, and saves the PackFile.
+fn import_assembly_kit(context: &CliContext, arguments: &Arguments) -> Result<i32, Error> {
+    let mut pack_file = packfile::open_packfile(PathBuf::from(arguments.get_positional(0, "PACKFILE")?))?;
+    let raw_data_path = PathBuf::from(arguments.get_positional(1, "RAW_DATA_FOLDER")?);
+
+    let dependency_database = context.load_dependency_database();
+    let (tables, errors) = import_from_assembly_kit(&mut pack_file, &raw_data_path, context.get_schema()?, dependency_database.as_ref().map(|x| &x[..]))?;
+    if !tables.is_empty() { context.save_pack_file(&mut pack_file)?; }
+    for table in tables.iter().filter(|x| x.report.has_column_changes()) {
+        println!("{}:\n{}", table.path.join("/"), table.report);
+    }
+    for error in &errors {
+        eprintln!("Couldn't import: {}", error);
+    }
+    println!("{} tables imported.", tables.len());
+    Ok(if errors.is_empty() { 0 } else { 2 })
+}
+
+/// This function exports the tables of a PackFile to Assembly Kit XML files.
+fn export_assembly_kit(context: &CliContext, arguments: &Arguments) -> Result<i32, Error> {
+    let pack_file = packfile::open_packfile(PathBuf::from(arguments.get_positional(0, "PACKFILE")?))?;
+    let folder_path = PathBuf::from(arguments.get_positional(1, "FOLDER")?);
+
+    let error_files = export_to_assembly_kit(&pack_file.data.packed_files, context.get_schema()?, &folder_path)?;
+    for path in &error_files {
+        eprintln!("Couldn't export: {}", path.join("/"));
+    }
+    println!("Tables exported to: {}", folder_path.display());
+    Ok(if error_files.is_empty() { 0 } else { 2 })
+}
+
+/// This function merges the table definitions of the Assembly Kit into the schema of the game, and saves it.
+fn import_schema_assembly_kit(context: &CliContext, arguments: &Arguments) -> Result<i32, Error> {
+    let raw_data_path = PathBuf::from(arguments.get_positional(0, "RAW_DATA_FOLDER")?);
+
+    // If we don't have a schema for this game yet, we start with an empty one.
+    let mut schema = context.schema.clone().unwrap_or_else(Schema::new);
+    let report = import_schema(&mut schema, &raw_data_path, &context.get_dependency_database()?)?;
+
+    Schema::save(&schema, &context.rpfm_path, &context.supported_games, &context.game_selected.game)?;
+    print!("{}", report);
+    Ok(if report.conflicts.is_empty() && report.errors.is_empty() { 0 } else { 2 })
+}
+
+/// This function merges the table definitions of a PFM schema file into the schema of the game, and saves it.
+fn import_schema_pfm(context: &CliContext, arguments: &Arguments) -> Result<i32, Error> {
+    let pfm_schema_path = PathBuf::from(arguments.get_positional(0, "MASTER_SCHEMA")?);
+
+    // If we don't have a schema for this game yet, we start with an empty one.
+    let mut schema = context.schema.clone().unwrap_or_else(Schema::new);
+    let dependency_database = context.load_dependency_database();
+    let report = import_pfm_schema(&mut schema, &pfm_schema_path, dependency_database.as_ref().map(|x| &x[..]))?;
+
+    Schema::save(&schema, &context.rpfm_path, &context.supported_games, &context.game_selected.game)?;
+    print!("{}", report);
+    Ok(if report.conflicts.is_empty() && report.errors.is_empty() { 0 } else { 2 })
+}
+
+/// This function writes the schema of the game to a PFM schema file.
+fn export_schema_pfm(context: &CliContext, arguments: &Arguments) -> Result<i32, Error> {
+    let pfm_schema_path = PathBuf::from(arguments.get_positional(0, "MASTER_SCHEMA")?);
+
+    export_pfm_schema(context.get_schema()?, &pfm_schema_path)?;
+    println!("Schema exported to: {}", pfm_schema_path.display());
+    Ok(0)
+}
+
+/// This function lists the definitions of the user's schema that are now in the downloaded schema, and
+/// removes the identical ones if "--prune" is used.
+fn check_schema_overlay(context: &CliContext, arguments: &Arguments) -> Result<i32, Error> {
+
+    let report = Schema::check_overlay(&context.rpfm_path, &context.supported_games, &context.game_selected.game)?;
+    print!("{}", report);
+    if arguments.has_flag("prune") {
+        let removed = Schema::prune_overlay(&context.rpfm_path, &context.supported_games, &context.game_selected.game)?;
+        println!("{} definitions removed from your schema.", removed);
+    }
+    Ok(0)
+}
+
+/// This function exports the definitions of the user's schema that are not in the downloaded schema.
+fn export_schema_overlay(context: &CliContext, arguments: &Arguments) -> Result<i32, Error> {
+    let destination_path = PathBuf::from(arguments.get_positional(0, "FILE")?);
+
+    let count = Schema::export_overlay(&context.rpfm_path, &context.supported_games, &context.game_selected.game, &destination_path)?;
+    println!("{} definitions exported to: {}", count, destination_path.display());
+    Ok(0)
+}
+
+/// This function prints the differences between two schema files. It doesn't need the context, as it
+/// doesn't depend on the game selected.
+fn diff_schemas(arguments: &Arguments) -> Result<i32, Error> {
+    let old_schema = Schema::load_from_path(&PathBuf::from(arguments.get_positional(0, "OLD_SCHEMA")?))?;
+    let new_schema = Schema::load_from_path(&PathBuf::from(arguments.get_positional(1, "NEW_SCHEMA")?))?;
+
+    let diff = SchemaDiff::new(&old_schema, &new_schema);
+    match arguments.get_option("format") {
+        Some("json") => println!("{}", diff.to_json()?),
+        Some("markdown") | None => print!("{}", diff.to_markdown()),
+        Some(format) => return Err(format_err!("Unknown format: \"{}\". Valid formats are \"markdown\" and \"json\".", format)),
+    }
+    Ok(0)
+}
+
+/// This function writes the docs of the schema of the game to a folder.
+fn generate_schema_docs(context: &CliContext, arguments: &Arguments) -> Result<i32, Error> {
+    let folder_path = PathBuf::from(arguments.get_positional(0, "FOLDER")?);
+    let format = match arguments.get_option("format") {
+        Some("html") => DocsFormat::Html,
+        Some("markdown") | None => DocsFormat::Markdown,
+        Some(format) => return Err(format_err!("Unknown format: \"{}\". Valid formats are \"markdown\" and \"html\".", format)),
+    };
+
+    let count = generate_docs(context.get_schema()?, &folder_path, format)?;
+    println!("Docs of {} tables generated in: {}", count, folder_path.display());
+    Ok(0)
+}
+
+/// This function renames a key in the tables and Locs of a PackFile, and saves the PackFile.
+fn rename_key(context: &CliContext, arguments: &Arguments) -> Result<i32, Error> {
+    let mut pack_file = packfile::open_packfile(PathBuf::from(arguments.get_positional(0, "PACKFILE")?))?;
+    let table = arguments.get_positional(1, "TABLE")?;
+    let column = arguments.get_positional(2, "COLUMN")?;
+    let old_key = arguments.get_positional(3, "OLD_KEY")?;
+    let new_key = arguments.get_positional(4, "NEW_KEY")?;
+
+    let key_rename = KeyRename::new(&pack_file.data.packed_files, context.get_schema()?, table, column, old_key, new_key)?;
+    print!("{}", key_rename);
+    if key_rename.is_empty() || arguments.has_flag("dry-run") { return Ok(0) }
+
+    key_rename.apply(&mut pack_file, context.get_schema()?)?;
+    context.save_pack_file(&mut pack_file)?;
+    println!("\"{}\" renamed to \"{}\": {} cells and {} Loc keys changed.", old_key, new_key, key_rename.cells.len(), key_rename.loc_keys.len());
+    Ok(0)
+}
+
+/// This function clones a row with all the rows depending on it, and saves the PackFile. The Dependency
+/// Database is used if there is one, but it's not required.
+fn clone_with_dependencies(context: &CliContext, arguments: &Arguments) -> Result<i32, Error> {
+    let mut pack_file = packfile::open_packfile(PathBuf::from(arguments.get_positional(0, "PACKFILE")?))?;
+    let table = arguments.get_positional(1, "TABLE")?;
+    let column = arguments.get_positional(2, "COLUMN")?;
+    let key = arguments.get_positional(3, "KEY")?;
+    let key_pattern = arguments.get_positional(4, "KEY_PATTERN")?;
+
+    let dependency_database = context.load_dependency_database().unwrap_or_default();
+    let deep_clone = DeepClone::new(&pack_file.data.packed_files, &dependency_database, context.get_schema()?, table, column, key, key_pattern)?;
+    print!("{}", deep_clone);
+    if deep_clone.is_empty() || arguments.has_flag("dry-run") { return Ok(0) }
+
+    let packed_file_name = pack_file.extra_data.file_name.trim_right_matches(".pack").to_owned();
+    deep_clone.apply(&mut pack_file, context.get_schema()?, &packed_file_name)?;
+    context.save_pack_file(&mut pack_file)?;
+    println!("\"{}\" cloned: {} rows and {} Loc entries added.", key, deep_clone.rows.len(), deep_clone.loc_entries.len());
+    Ok(0)
+}
+
+/// This function prints the rows referencing a value of a column. The Dependency Database is used if there
+/// is one, but it's not required.
+fn find_references(context: &CliContext, arguments: &Arguments) -> Result<i32, Error> {
+    let pack_file = packfile::open_packfile(PathBuf::from(arguments.get_positional(0, "PACKFILE")?))?;
+    let table = arguments.get_positional(1, "TABLE")?;
+    let column = arguments.get_positional(2, "COLUMN")?;
+    let value = arguments.get_positional(3, "VALUE")?;
+
+    let dependency_database = context.load_dependency_database().unwrap_or_default();
+    let index = ReferenceIndex::new(&pack_file.data.packed_files, &dependency_database, context.get_schema()?);
+    let references = index.get_references(table, column, value);
+    if references.is_empty() { println!("There are no rows referencing \"{}\" in {}/{}.", value, table, column); }
+    for reference in &references {
+        println!("{}", reference);
+    }
+    for path in &index.undecodeable_tables {
+        println!("This table couldn't be decoded, so its references are not here: {}", path.join("/"));
+    }
+    Ok(0)
+}
+
+/// This function generates a patch with the differences between the tables of a PackFile and the vanilla ones.
+fn generate_patch(context: &CliContext, arguments: &Arguments) -> Result<i32, Error> {
+    let pack_file = packfile::open_packfile(PathBuf::from(arguments.get_positional(0, "PACKFILE")?))?;
+    let path = PathBuf::from(arguments.get_positional(1, "FILE")?);
+
+    let (patch, skipped_tables) = DBPatch::new(&pack_file.data.packed_files, &context.get_dependency_database()?, context.get_schema()?, arguments.get_option("table"), arguments.has_flag("with-deletes"))?;
+    print!("{}", patch);
+    for table in &skipped_tables {
+        println!("Not included in the patch: {}", table);
+    }
+
+    patch.save(&path)?;
+    println!("Patch of {} tables saved to: {}", patch.tables.len(), path.display());
+    Ok(0)
+}
+
+/// This function applies a patch to the current vanilla tables, and saves the result in the PackFile.
+fn apply_patch(context: &CliContext, arguments: &Arguments) -> Result<i32, Error> {
+    let mut pack_file = packfile::open_packfile(PathBuf::from(arguments.get_positional(0, "PACKFILE")?))?;
+    let patch = DBPatch::load(&PathBuf::from(arguments.get_positional(1, "FILE")?))?;
+
+    let packed_file_name = pack_file.extra_data.file_name.trim_right_matches(".pack").to_owned();
+    let report = patch.apply(&mut pack_file, &context.get_dependency_database()?, context.get_schema()?, &packed_file_name)?;
+    print!("{}", report);
+    if !arguments.has_flag("dry-run") { context.save_pack_file(&mut pack_file)?; }
+    Ok(if report.conflicts.is_empty() && report.skipped.is_empty() { 0 } else { 2 })
+}
+
+/// This function changes a column in all the PackedFiles of a table, and saves the PackFile.
+fn bulk_edit(context: &CliContext, arguments: &Arguments) -> Result<i32, Error> {
+    let mut pack_file = packfile::open_packfile(PathBuf::from(arguments.get_positional(0, "PACKFILE")?))?;
+    let table = arguments.get_positional(1, "TABLE")?;
+    let column = arguments.get_positional(2, "COLUMN")?;
+    let expression = arguments.get_positional(3, "EXPRESSION")?;
+
+    let table = if table.ends_with("_tables") { table.to_owned() } else { format!("{}_tables", table) };
+    let bulk_edit = BulkEdit::new(column, expression, arguments.get_option("where"))?;
+
+    let mut found = false;
+    let mut changes = 0;
+    let mut tables = vec![];
+    for (index, packed_file) in pack_file.data.packed_files.iter().enumerate() {
+        if packed_file.path.len() == 3 && packed_file.path[0] == "db" && packed_file.path[1] == table {
+            found = true;
+            let mut db = DB::read(&packed_file.data, &packed_file.path[1], context.get_schema()?)?;
+            let report = bulk_edit.apply(&table, &mut db.data)?;
+            println!("{}:", packed_file.path.join("/"));
+            print!("{}", report);
+            changes += report.changes.len();
+            if !report.changes.is_empty() { tables.push((index, db)); }
+        }
+    }
+
+    if !found { return Err(format_err!("There are no \"{}\" PackedFiles in the PackFile.", table)) }
+    if changes == 0 || arguments.has_flag("dry-run") { return Ok(0) }
+
+    for &(index, ref db) in &tables {
+        packfile::update_packed_file_data_db(db, &mut pack_file, index)?;
+    }
+    context.save_pack_file(&mut pack_file)?;
+    println!("{} cells changed in {} PackedFiles.", changes, tables.len());
+    Ok(0)
+}
+
+/// This function merges the fragments of the tables of a PackFile, and saves the PackFile.
+fn merge_tables(context: &CliContext, arguments: &Arguments) -> Result<i32, Error> {
+    let mut pack_file = packfile::open_packfile(PathBuf::from(arguments.get_positional(0, "PACKFILE")?))?;
+    let version = match arguments.get_option("version") {
+        Some(version) => Some(version.parse::<u32>().map_err(|_| format_err!("Invalid version: \"{}\".", version))?),
+        None => None,
+    };
+
+    let tables = match arguments.get_option("table") {
+        Some(table) => vec![if table.ends_with("_tables") { table.to_owned() } else { format!("{}_tables", table) }],
+        None => get_fragmented_tables(&pack_file.data.packed_files),
+    };
+    if tables.is_empty() { println!("There are no tables with more than one fragment in this PackFile."); }
+
+    let mut merges = vec![];
+    for table in &tables {
+        let merge = TableMerge::new(&pack_file.data.packed_files, context.get_schema()?, table, version, arguments.get_option("name"))?;
+        println!("{}", merge);
+        merges.push(merge);
+    }
+    if merges.is_empty() || arguments.has_flag("dry-run") { return Ok(0) }
+
+    for merge in &merges {
+        merge.apply(&mut pack_file)?;
+    }
+    context.save_pack_file(&mut pack_file)?;
+    println!("Fragments of {} tables merged.", merges.len());
+    Ok(0)
+}
+
+/// This function splits a table of a PackFile in several PackedFiles, and saves the PackFile.
+fn split_table(context: &CliContext, arguments: &Arguments) -> Result<i32, Error> {
+    let mut pack_file = packfile::open_packfile(PathBuf::from(arguments.get_positional(0, "PACKFILE")?))?;
+    let path = arguments.get_positional(1, "TABLE_PATH")?.split('/').map(|x| x.to_owned()).collect::<Vec<String>>();
+    let name = path.last().cloned().unwrap_or_default();
+
+    let mut rules = vec![];
+    if let Some(prefixes) = arguments.get_option("prefixes") {
+        for prefix in prefixes.split(',').filter(|x| !x.is_empty()) {
+            rules.push((format!("{}_{}", name, prefix), SplitRule::KeyPrefix(prefix.to_owned())));
+        }
+    }
+    if let Some(filter) = arguments.get_option("where") {
+        match arguments.get_option("name") {
+            Some(name) => rules.push((name.to_owned(), SplitRule::Filter(Expression::parse(filter)?))),
+            None => return Err(format_err!("The new PackedFile for the rows matching \"--where\" needs a name. Use \"--name=NAME\" to provide it.")),
+        }
+    }
+    if rules.is_empty() { return Err(format_err!("There is nothing to split. Use \"--prefixes\" or \"--where\" to choose the rows to move.")) }
+
+    let split = TableSplit::new(&pack_file.data.packed_files, context.get_schema()?, &path, &rules, context.settings.deterministic_guids)?;
+    print!("{}", split);
+    if split.is_empty() || arguments.has_flag("dry-run") { return Ok(0) }
+
+    split.apply(&mut pack_file)?;
+    context.save_pack_file(&mut pack_file)?;
+    println!("{} split into {} new PackedFiles.", path.join("/"), split.parts.len());
+    Ok(0)
+}
+
+/// This function sorts the rows of the DB Tables and Locs of a PackFile, and saves the PackFile.
+fn normalize(context: &CliContext, arguments: &Arguments) -> Result<i32, Error> {
+    let mut pack_file = packfile::open_packfile(PathBuf::from(arguments.get_positional(0, "PACKFILE")?))?;
+    let deterministic_guids = arguments.has_flag("deterministic-guids") || context.settings.deterministic_guids;
+
+    let report = normalize_packed_files(&mut pack_file, context.get_schema()?, true, deterministic_guids);
+    print!("{}", report);
+    if report.is_empty() || arguments.has_flag("dry-run") { return Ok(0) }
+
+    packfile::save_packfile(&mut pack_file, None)?;
+    println!("{} PackedFiles normalized.", report.changed.len());
+    Ok(0)
+}
+
+/// This function generates the Dependency PackFile of the game selected or, with "--check", checks if it's outdated.
+fn generate_dependency_pack(context: &CliContext, arguments: &Arguments) -> Result<i32, Error> {
+    let game_data_path = match context.game_selected.game_data_path {
+        Some(ref game_data_path) => game_data_path,
+        None => return Err(format_err!("The path of the game \"{}\" is not configured in the settings.", context.game_selected.game)),
+    };
+    let dependency_pack_file_path = &context.game_selected.game_dependency_packfile_path;
+
+    if arguments.has_flag("check") {
+        if !dependency_pack_file_path.is_file() {
+            println!("There is no Dependency PackFile for this game.");
+            return Ok(2)
+        }
+
+        let changed_sources = match DependencyPackInfo::load(dependency_pack_file_path) {
+            Ok(info) => info.get_changed_sources(game_data_path)?,
+            Err(_) => {
+                println!("The Dependency PackFile has no info about the PackFiles used to generate it, so it's outdated.");
+                return Ok(2)
+            }
+        };
+
+        if changed_sources.is_empty() {
+            println!("The Dependency PackFile is up to date.");
+            return Ok(0)
+        }
+
+        println!("The Dependency PackFile is outdated. These PackFiles have been added, removed or changed:");
+        for name in &changed_sources {
+            println!("  - {}", name);
+        }
+        return Ok(2)
+    }
+
+    // This takes a while, so we print the progress to stderr, keeping stdout for the result.
+    let progress = Progress::new(|status| eprintln!("[{}/{}] {}", status.current, status.total, status.message));
+    let info = generate_dependency_pack_file(&context.game_selected.game, game_data_path, dependency_pack_file_path, &progress)?;
+    println!("Dependency PackFile generated from:");
+    for source in &info.sources {
+        println!("  - {}", source.name);
+    }
+    Ok(0)
+}
@@ -11,6 +11,7 @@ use self::failure::Error;
 use packfile::packfile::PackFile;
 
 pub mod coding_helpers;
+pub mod progress;
 
 // This tells the compiler to only compile this mod when testing... I think.
 #[cfg(test)]
@@ -134,7 +135,6 @@ pub fn get_files_from_subdir(current_path: &Path) -> Result<Vec<PathBuf>, Error>
 /// This function takes a &Path and returns a Vec<PathBuf> with the paths of every file under the
 /// original &Path. This is a modification of the normal "get_files_from_subdir" where we only get
 /// the files in the current folder and with a special beginning.
-#[allow(dead_code)]
 pub fn get_assembly_kit_schemas(current_path: &Path) -> Result<Vec<PathBuf>, Error> {
 
     let mut file_list: Vec<PathBuf> = vec![];
@@ -145,16 +145,20 @@ pub fn get_assembly_kit_schemas(current_path: &Path) -> Result<Vec<PathBuf>, Err
             for file in files_in_current_path {
 
                 // Get his path
-                let file_path = file.unwrap().path().clone();
+                let file_path = file?.path();
+                let file_name = match file_path.file_stem() {
+                    Some(file_name) => file_name.to_string_lossy().to_string(),
+                    None => continue,
+                };
 
                 // If it's a file and starts with "TWaD_", to the file_list it goes (except if it's one of those special files).
                 if file_path.is_file() &&
-                    file_path.file_stem().unwrap().to_str().unwrap().to_string().starts_with("TWaD_") &&
-                    file_path.file_stem().unwrap().to_str().unwrap() != "TWaD_schema_validation" &&
-                    file_path.file_stem().unwrap().to_str().unwrap() != "TWaD_relationships" &&
-                    file_path.file_stem().unwrap().to_str().unwrap() != "TWaD_validation" &&
-                    file_path.file_stem().unwrap().to_str().unwrap() != "TWaD_tables" &&
-                    file_path.file_stem().unwrap().to_str().unwrap() != "TWaD_queries" {
+                    file_name.starts_with("TWaD_") &&
+                    file_name != "TWaD_schema_validation" &&
+                    file_name != "TWaD_relationships" &&
+                    file_name != "TWaD_validation" &&
+                    file_name != "TWaD_tables" &&
+                    file_name != "TWaD_queries" {
                     file_list.push(file_path);
                 }
             }
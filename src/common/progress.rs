@@ -0,0 +1,112 @@
+// In this file is the stuff to report the progress of long operations (opening or saving big PackFiles, generating
+// the Dependency PackFile,...) and to cancel them. The operations receive a `Progress` and call it from time to time.
+// What happens with the progress depends on who created the `Progress`: the UI sends it to a progress bar from
+// another thread, the CLI prints it, and the rest of the callers just ignore it.
+
+extern crate failure;
+
+use std::fmt;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use self::failure::{Error, Fail};
+
+/// `ProgressStatus`: This struct holds the current status of an operation. It has:
+/// - message: what the operation is doing right now.
+/// - current: the amount of steps already done.
+/// - total: the amount of steps of the operation. If it's 0, we don't know how many steps there are.
+#[derive(Clone, Debug)]
+pub struct ProgressStatus {
+    pub message: String,
+    pub current: usize,
+    pub total: usize,
+}
+
+/// `Progress`: This struct is what the long operations use to report their progress and to know if they have been
+/// cancelled. It has:
+/// - callback: the function called with every update, if any.
+/// - is_cancelled: the flag to cancel the operation. It's shared, so it can be set from another thread.
+pub struct Progress {
+    callback: Option<Box<Fn(&ProgressStatus) + Send>>,
+    is_cancelled: Arc<AtomicBool>,
+}
+
+/// `Cancelled`: This error is what the operations return when they have been cancelled through their `Progress`,
+/// so the callers can tell it apart from a real error.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Cancelled;
+
+/// Implementation of `ProgressStatus`.
+impl ProgressStatus {
+
+    /// This function returns how much of the operation is done, from 0.0 to 1.0.
+    pub fn get_fraction(&self) -> f64 {
+        if self.total == 0 { 0.0 } else { self.current as f64 / self.total as f64 }
+    }
+}
+
+/// Implementation of `Progress`.
+impl Progress {
+
+    /// This function creates a new `Progress` that calls the provided function with every update.
+    pub fn new<F: Fn(&ProgressStatus) + Send + 'static>(callback: F) -> Self {
+        Self {
+            callback: Some(Box::new(callback)),
+            is_cancelled: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    /// This function creates a new `Progress` that ignores the updates, for the callers that don't care about them.
+    pub fn none() -> Self {
+        Self {
+            callback: None,
+            is_cancelled: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    /// This function returns the flag that cancels the operation when it's set to true.
+    pub fn get_cancel_flag(&self) -> Arc<AtomicBool> {
+        self.is_cancelled.clone()
+    }
+
+    /// This function cancels the operation. It'll stop the next time it calls `update`.
+    pub fn cancel(&self) {
+        self.is_cancelled.store(true, Ordering::SeqCst);
+    }
+
+    /// This function checks if the operation has been cancelled.
+    pub fn is_cancelled(&self) -> bool {
+        self.is_cancelled.load(Ordering::SeqCst)
+    }
+
+    /// This function reports the current status of the operation, without checking if it has been cancelled. Use it
+    /// for the parts of an operation that cannot be stopped halfway, like writing a PackFile to disk.
+    pub fn report(&self, message: &str, current: usize, total: usize) {
+        if let Some(ref callback) = self.callback {
+            callback(&ProgressStatus { message: message.to_owned(), current, total });
+        }
+    }
+
+    /// This function reports the current status of the operation, and returns `Cancelled` if it has been cancelled,
+    /// so the operation can stop using `?`.
+    pub fn update(&self, message: &str, current: usize, total: usize) -> Result<(), Error> {
+        if self.is_cancelled() { return Err(Cancelled.into()) }
+        self.report(message, current, total);
+        Ok(())
+    }
+}
+
+/// Implementation of `Display` for `Cancelled`, with the message we show to the user.
+impl fmt::Display for Cancelled {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "The operation has been cancelled.")
+    }
+}
+
+/// Implementation of `Fail` for `Cancelled`, so it can be used as a `failure::Error`.
+impl Fail for Cancelled {}
+
+/// This function checks if an error is because the operation has been cancelled.
+pub fn is_cancelled_error(error: &Error) -> bool {
+    error.downcast_ref::<Cancelled>().is_some()
+}
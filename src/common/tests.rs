@@ -4,6 +4,7 @@
 // an unwrap means something got broken in the original function.
 
 use coding_helpers::*;
+use common::progress::*;
 
 /*
 --------------------------------------------------------
@@ -558,3 +559,21 @@ fn test_encode_packedfile_optional_string_u16() {
     // Check the encoder works for a proper encoded string.
     assert_eq!(encode_packedfile_optional_string_u16("Waha"), vec![1, 4, 0, 87, 0, 97, 0, 104, 0, 97, 0]);
 }
+
+/*
+--------------------------------------------------------
+                        Progress
+--------------------------------------------------------
+*/
+
+/// Test to make sure a cancelled operation stops the next time it calls `Progress::update()`, returning `Cancelled`.
+#[test]
+fn test_progress_cancelled() {
+    let progress = Progress::none();
+    assert_eq!(progress.update("Testing...", 0, 2).is_ok(), true);
+
+    // Check only the `Cancelled` error is reported as a cancellation, even with the same message.
+    progress.cancel();
+    assert_eq!(is_cancelled_error(&progress.update("Testing...", 1, 2).unwrap_err()), true);
+    assert_eq!(is_cancelled_error(&format_err!("The operation has been cancelled.")), false);
+}
@@ -51,11 +51,27 @@ use gtk::{
 };
 
 use common::*;
+use common::progress::is_cancelled_error;
 use packfile::packfile::PackFile;
 use packfile::packfile::PackedFile;
 use packedfile::*;
 use packedfile::db::schemas::*;
 use packedfile::db::schemas_importer::*;
+use packedfile::db::schemas_pfm::*;
+use packedfile::db::schemas_diff::SchemaDiff;
+use packedfile::db::schemas_docs::*;
+use packedfile::db::compatibility::CompatibilityReport;
+use packedfile::db::references::ReferenceReport;
+use packedfile::db::key_rename::KeyRenameUndo;
+use packedfile::db::deep_clone::DeepClone;
+use packedfile::db::fragments::*;
+use packedfile::db::dependency_cache::DependencyCache;
+use packfile::dependency_pack::*;
+use packedfile::normalize::{normalize_packed_files, normalize_packed_files_before_save};
+use packedfile::db::patches::DBPatch;
+use packedfile::db::validation::ValidationReport;
+use packedfile::sqlite::*;
+use packedfile::db::assembly_kit::*;
 use settings::*;
 use ui::*;
 use ui::packedfile_db::*;
@@ -85,6 +101,7 @@ macro_rules! clone {
     );
 }
 
+mod cli;
 mod common;
 mod ui;
 mod packfile;
@@ -97,10 +114,6 @@ mod updater;
 /// in two different places in every update.
 const VERSION: &str = env!("CARGO_PKG_VERSION");
 
-/// This constant is used to enable or disable the generation of a new Schema file in compile time.
-/// If you don't want to explicity create a new Schema for a game, leave this disabled.
-const GENERATE_NEW_SCHEMA: bool = false;
-
 /// This enum represent the current "Operational Mode" for RPFM. The allowed modes are:
 /// - `Normal`: Use the default behavior for everything. This is the Default mode.
 /// - `MyMod`: Use the `MyMod` specific behavior. This mode is used when you have a "MyMod" selected.
@@ -167,6 +180,24 @@ pub struct AppUI {
     pub menu_bar_patch_siege_ai_wh: SimpleAction,
     pub menu_bar_create_map_prefab_wh: SimpleAction,
     pub menu_bar_generate_dependency_pack_att: SimpleAction,
+    pub menu_bar_check_outdated_tables: SimpleAction,
+    pub menu_bar_check_references: SimpleAction,
+    pub menu_bar_validate_tables: SimpleAction,
+    pub menu_bar_clone_with_dependencies: SimpleAction,
+    pub menu_bar_merge_table_fragments: SimpleAction,
+    pub menu_bar_normalize_tables: SimpleAction,
+    pub menu_bar_export_sqlite: SimpleAction,
+    pub menu_bar_import_sqlite: SimpleAction,
+    pub menu_bar_generate_table_patch: SimpleAction,
+    pub menu_bar_apply_table_patch: SimpleAction,
+    pub menu_bar_import_assembly_kit: SimpleAction,
+    pub menu_bar_export_assembly_kit: SimpleAction,
+    pub menu_bar_import_schema_assembly_kit: SimpleAction,
+    pub menu_bar_import_schema_pfm: SimpleAction,
+    pub menu_bar_export_schema_pfm: SimpleAction,
+    pub menu_bar_check_schema_overlay: SimpleAction,
+    pub menu_bar_export_schema_overlay: SimpleAction,
+    pub menu_bar_generate_schema_docs: SimpleAction,
     pub menu_bar_check_updates: SimpleAction,
     pub menu_bar_check_schema_updates: SimpleAction,
     pub menu_bar_open_patreon: SimpleAction,
@@ -205,16 +236,7 @@ fn build_ui(application: &Application) {
     // executable is and use it as a base for all the path stuff. Note that this should only work on
     // release, as the way it works it's used by cargo to run the debug builds.
     let arguments = args().collect::<Vec<String>>();
-
-    // In debug mode, we just take the current path (so we don't break debug builds). In Release mode,
-    // we take the `.exe` path. We use unwrap here because in case of fail, we want to crash RPFM.
-    let rpfm_path: PathBuf = if cfg!(debug_assertions) {
-        std::env::current_dir().unwrap()
-    } else {
-        let mut path = std::env::current_exe().unwrap();
-        path.pop();
-        path
-    };
+    let rpfm_path = get_rpfm_path();
 
     // We create the `Clipboard`.
     let clipboard_atom = Atom::intern("CLIPBOARD");
@@ -288,6 +310,24 @@ fn build_ui(application: &Application) {
         menu_bar_patch_siege_ai_wh: SimpleAction::new("patch-siege-ai-wh", None),
         menu_bar_create_map_prefab_wh: SimpleAction::new("create-map-prefab-wh", None),
         menu_bar_generate_dependency_pack_att: SimpleAction::new("generate-dependency-pack-att", None),
+        menu_bar_check_outdated_tables: SimpleAction::new("check-outdated-tables", None),
+        menu_bar_check_references: SimpleAction::new("check-references", None),
+        menu_bar_validate_tables: SimpleAction::new("validate-tables", None),
+        menu_bar_clone_with_dependencies: SimpleAction::new("clone-with-dependencies", None),
+        menu_bar_merge_table_fragments: SimpleAction::new("merge-table-fragments", None),
+        menu_bar_normalize_tables: SimpleAction::new("normalize-tables", None),
+        menu_bar_export_sqlite: SimpleAction::new("export-sqlite", None),
+        menu_bar_import_sqlite: SimpleAction::new("import-sqlite", None),
+        menu_bar_generate_table_patch: SimpleAction::new("generate-table-patch", None),
+        menu_bar_apply_table_patch: SimpleAction::new("apply-table-patch", None),
+        menu_bar_import_assembly_kit: SimpleAction::new("import-assembly-kit", None),
+        menu_bar_export_assembly_kit: SimpleAction::new("export-assembly-kit", None),
+        menu_bar_import_schema_assembly_kit: SimpleAction::new("import-schema-assembly-kit", None),
+        menu_bar_import_schema_pfm: SimpleAction::new("import-schema-pfm", None),
+        menu_bar_export_schema_pfm: SimpleAction::new("export-schema-pfm", None),
+        menu_bar_check_schema_overlay: SimpleAction::new("check-schema-overlay", None),
+        menu_bar_export_schema_overlay: SimpleAction::new("export-schema-overlay", None),
+        menu_bar_generate_schema_docs: SimpleAction::new("generate-schema-docs", None),
         menu_bar_check_updates: SimpleAction::new("check-updates", None),
         menu_bar_check_schema_updates: SimpleAction::new("check-schema-updates", None),
         menu_bar_open_patreon: SimpleAction::new("open-patreon", None),
@@ -338,6 +378,24 @@ fn build_ui(application: &Application) {
     application.add_action(&app_ui.menu_bar_patch_siege_ai_wh);
     application.add_action(&app_ui.menu_bar_create_map_prefab_wh);
     application.add_action(&app_ui.menu_bar_generate_dependency_pack_att);
+    application.add_action(&app_ui.menu_bar_check_outdated_tables);
+    application.add_action(&app_ui.menu_bar_check_references);
+    application.add_action(&app_ui.menu_bar_validate_tables);
+    application.add_action(&app_ui.menu_bar_clone_with_dependencies);
+    application.add_action(&app_ui.menu_bar_merge_table_fragments);
+    application.add_action(&app_ui.menu_bar_normalize_tables);
+    application.add_action(&app_ui.menu_bar_export_sqlite);
+    application.add_action(&app_ui.menu_bar_import_sqlite);
+    application.add_action(&app_ui.menu_bar_generate_table_patch);
+    application.add_action(&app_ui.menu_bar_apply_table_patch);
+    application.add_action(&app_ui.menu_bar_import_assembly_kit);
+    application.add_action(&app_ui.menu_bar_export_assembly_kit);
+    application.add_action(&app_ui.menu_bar_import_schema_assembly_kit);
+    application.add_action(&app_ui.menu_bar_import_schema_pfm);
+    application.add_action(&app_ui.menu_bar_export_schema_pfm);
+    application.add_action(&app_ui.menu_bar_check_schema_overlay);
+    application.add_action(&app_ui.menu_bar_export_schema_overlay);
+    application.add_action(&app_ui.menu_bar_generate_schema_docs);
     application.add_action(&app_ui.menu_bar_open_patreon);
     application.add_action(&app_ui.menu_bar_about);
     application.add_action(&app_ui.menu_bar_check_updates);
@@ -377,19 +435,6 @@ fn build_ui(application: &Application) {
     // Then we display the "Tips" text.
     display_help_tips(&app_ui.packed_file_data_display);
 
-    // This is to get the new schemas. It's controlled by a global const.
-    if GENERATE_NEW_SCHEMA {
-
-        // These are the paths needed for the new schemas. First one should be `assembly_kit/raw_data/db`.
-        // The second one should contain all the tables of the game, extracted directly from `data.pack`.
-        let assembly_kit_schemas_path: PathBuf = PathBuf::from("/home/frodo45127/schema_stuff/db_schemas/");
-        let testing_tables_path: PathBuf = PathBuf::from("/home/frodo45127/schema_stuff/db_tables/");
-        match import_schema(&assembly_kit_schemas_path, &testing_tables_path, &rpfm_path) {
-            Ok(_) => show_dialog(&app_ui.window, true, "Schema successfully created."),
-            Err(error) => return show_dialog(&app_ui.window, false, format!("Error while creating a new DB Schema file:\n{}", error.cause())),
-        }
-    }
-
     // This variable is used to "Lock" the "Decode on select" feature of `app_ui.folder_tree_view`.
     // We need it to lock this feature when we open a secondary PackFile and want to import some
     // PackedFiles to our opened PackFile.
@@ -426,6 +471,9 @@ fn build_ui(application: &Application) {
     // We prepare the schema object to hold an Schema, leaving it as `None` by default.
     let schema: Rc<RefCell<Option<Schema>>> = Rc::new(RefCell::new(None));
 
+    // We prepare the stuff to undo the last key rename, so it's kept even if we change the table opened.
+    let key_rename_undo: Rc<RefCell<Option<KeyRenameUndo>>> = Rc::new(RefCell::new(None));
+
     // This specifies the "Operational Mode" RPFM should use. By default it's Normal.
     let mode = Rc::new(RefCell::new(Mode::Normal));
 
@@ -435,11 +483,12 @@ fn build_ui(application: &Application) {
     // Set the default game as selected game.
     app_ui.menu_bar_change_game_selected.change_state(&(&settings.borrow().default_game).to_variant());
 
-    // Try to open the dependency PackFile of our `game_selected`.
-    let dependency_database = match packfile::open_packfile(game_selected.borrow().game_dependency_packfile_path.to_path_buf()) {
-        Ok(pack_file) => Rc::new(RefCell::new(Some(pack_file.data.packed_files))),
-        Err(_) => Rc::new(RefCell::new(None)),
-    };
+    // The dependency PackFile of our `game_selected`. It's only loaded when something needs it, as it's big.
+    let dependency_database: Rc<RefCell<Option<Vec<PackedFile>>>> = Rc::new(RefCell::new(None));
+    check_dependency_pack_outdated(&app_ui, &game_selected.borrow());
+
+    // The cache with the decoded data of the dependency database. It's loaded (or built) the first time we open a DB Table.
+    let dependency_cache: Rc<RefCell<Option<DependencyCache>>> = Rc::new(RefCell::new(None));
 
     // Prepare the "MyMod" menu. This... atrocity needs to be in the following places for MyMod to open PackFiles:
     // - At the start of the program (here).
@@ -455,6 +504,7 @@ fn build_ui(application: &Application) {
         &game_selected,
         &supported_games,
         &dependency_database,
+        &dependency_cache,
         &pack_file_decoded,
         &pack_file_decoded_extra,
         &rpfm_path
@@ -468,7 +518,7 @@ fn build_ui(application: &Application) {
 
     // Same with schema updates.
     if settings.borrow().check_schema_updates_on_start {
-        check_schema_updates(VERSION, &rpfm_path, &supported_games.borrow(), &game_selected, &schema, None, Some(&app_ui.status_bar));
+        check_schema_updates(VERSION, &rpfm_path, &supported_games.borrow(), &game_selected, &schema, &dependency_cache, None, Some(&app_ui.status_bar));
     }
 
     // Concatenate and push again the last two messages of the Statusbar, to be able to show both message at the same time.
@@ -538,6 +588,7 @@ fn build_ui(application: &Application) {
     app_ui.menu_bar_new_packfile.connect_activate(clone!(
         app_ui,
         schema,
+        dependency_cache,
         game_selected,
         supported_games,
         rpfm_path,
@@ -590,8 +641,10 @@ fn build_ui(application: &Application) {
                 // Set the current "Operational Mode" to Normal, as this is a "New" mod.
                 set_my_mod_mode(&app_ui, &mode, None);
 
-                // Try to load the Schema for this PackFile's game.
-                *schema.borrow_mut() = Schema::load(&rpfm_path, &supported_games.borrow().iter().filter(|x| x.folder_name == *game_selected.borrow().game).map(|x| x.schema.to_owned()).collect::<String>()).ok();
+                // Try to load the Schema for this PackFile's game, and drop the cache of the dependency database, as it
+                // may have been built with another schema.
+                *schema.borrow_mut() = load_schema(&app_ui.window, &rpfm_path, &supported_games.borrow(), &game_selected.borrow().game);
+                *dependency_cache.borrow_mut() = None;
             }
         }
     ));
@@ -607,6 +660,7 @@ fn build_ui(application: &Application) {
         mode,
         supported_games,
         dependency_database,
+        dependency_cache,
         pack_file_decoded_extra,
         pack_file_decoded => move |_,_| {
 
@@ -648,6 +702,7 @@ fn build_ui(application: &Application) {
                         &supported_games.borrow(),
                         &game_selected,
                         &dependency_database,
+                        &dependency_cache,
                         &(false, None),
                         &pack_file_decoded,
                         &pack_file_decoded_extra
@@ -662,6 +717,7 @@ fn build_ui(application: &Application) {
     app_ui.menu_bar_save_packfile.connect_activate(clone!(
         pack_file_decoded,
         settings,
+        schema,
         app_ui => move |_,_| {
 
             // If our PackFile is editable...
@@ -670,8 +726,14 @@ fn build_ui(application: &Application) {
                 // If our PackFile already exists in the filesystem, we save it to that file directly.
                 if pack_file_decoded.borrow().extra_data.file_path.is_file() {
 
+                    // Validate the tables first, if we have to.
+                    if !validate_tables_before_save(&app_ui, &settings.borrow(), &schema.borrow(), &pack_file_decoded.borrow()) { return }
+
+                    // Sort the rows and fix the GUIDs of the tables, if we have to.
+                    normalize_tables_before_save(&settings.borrow(), &schema.borrow(), &mut pack_file_decoded.borrow_mut());
+
                     // We try to save the PackFile at the provided path...
-                    let success = match packfile::save_packfile(&mut *pack_file_decoded.borrow_mut(), None) {
+                    let success = match save_packfile_with_progress_dialog(&app_ui.window, &pack_file_decoded, None) {
                         Ok(_) => {
                             show_dialog(&app_ui.window, true, "PackFile succesfully saved.");
                             true
@@ -706,12 +768,16 @@ fn build_ui(application: &Application) {
         pack_file_decoded,
         game_selected,
         settings,
+        schema,
         app_ui,
         mode => move |_,_| {
 
             // If our PackFile is editable...
             if pack_file_decoded.borrow().is_editable(&settings.borrow()) {
 
+                // Validate the tables first, if we have to.
+                if !validate_tables_before_save(&app_ui, &settings.borrow(), &schema.borrow(), &pack_file_decoded.borrow()) { return }
+
                 // Create the FileChooserNative.
                 let file_chooser_save_packfile = FileChooserNative::new(
                     "Save PackFile as...",
@@ -753,8 +819,11 @@ fn build_ui(application: &Application) {
                     // If the new PackFile's name doesn't end in ".pack", we add it at the end.
                     if !file_path.ends_with(".pack") { file_path.set_extension("pack"); }
 
+                    // Sort the rows and fix the GUIDs of the tables, if we have to.
+                    normalize_tables_before_save(&settings.borrow(), &schema.borrow(), &mut pack_file_decoded.borrow_mut());
+
                     // We try to save the PackFile at the provided path...
-                    let success = match packfile::save_packfile(&mut *pack_file_decoded.borrow_mut(), Some(file_path.to_path_buf())) {
+                    let success = match save_packfile_with_progress_dialog(&app_ui.window, &pack_file_decoded, Some(file_path.to_path_buf())) {
                         Ok(_) => {
                             show_dialog(&app_ui.window, true, "PackFile succesfully saved.");
                             true
@@ -859,6 +928,7 @@ fn build_ui(application: &Application) {
         mode,
         application,
         dependency_database,
+        dependency_cache,
         pack_file_decoded_extra,
         schema => move |_,_| {
 
@@ -881,6 +951,7 @@ fn build_ui(application: &Application) {
                 schema,
                 mode,
                 dependency_database,
+                dependency_cache,
                 pack_file_decoded_extra,
                 application => move |_,_| {
 
@@ -920,6 +991,7 @@ fn build_ui(application: &Application) {
                                 &game_selected,
                                 &supported_games,
                                 &dependency_database,
+                                &dependency_cache,
                                 &pack_file_decoded,
                                 &pack_file_decoded_extra,
                                 &rpfm_path
@@ -1036,6 +1108,7 @@ fn build_ui(application: &Application) {
         rpfm_path,
         mode,
         dependency_database,
+        dependency_cache,
         pack_file_decoded_extra,
         pack_file_decoded => move |_,_| {
 
@@ -1057,6 +1130,7 @@ fn build_ui(application: &Application) {
                 rpfm_path,
                 game_selected,
                 dependency_database,
+                dependency_cache,
                 pack_file_decoded_extra,
                 pack_file_decoded => move |_,_| {
 
@@ -1134,6 +1208,7 @@ fn build_ui(application: &Application) {
                             &game_selected,
                             &supported_games,
                             &dependency_database,
+                            &dependency_cache,
                             &pack_file_decoded,
                             &pack_file_decoded_extra,
                             &rpfm_path
@@ -1189,6 +1264,7 @@ fn build_ui(application: &Application) {
         mode,
         supported_games,
         dependency_database,
+        dependency_cache,
         pack_file_decoded_extra,
         pack_file_decoded => move |_,_| {
 
@@ -1281,6 +1357,7 @@ fn build_ui(application: &Application) {
                         &game_selected,
                         &supported_games,
                         &dependency_database,
+                        &dependency_cache,
                         &pack_file_decoded,
                         &pack_file_decoded_extra,
                         &rpfm_path
@@ -1413,6 +1490,7 @@ fn build_ui(application: &Application) {
         app_ui,
         rpfm_path,
         schema,
+        dependency_cache,
         mode,
         settings,
         supported_games,
@@ -1430,14 +1508,13 @@ fn build_ui(application: &Application) {
                 // Change the `GameSelected` object.
                 game_selected.borrow_mut().change_game_selected(&new_state, &settings.borrow().paths.game_paths.iter().filter(|x| x.game == new_state).map(|x| x.path.clone()).collect::<Option<PathBuf>>(), &supported_games.borrow());
 
-                // Change the `Schema` for that game.
-                *schema.borrow_mut() = Schema::load(&rpfm_path, &supported_games.borrow().iter().filter(|x| x.folder_name == *game_selected.borrow().game).map(|x| x.schema.to_owned()).collect::<String>()).ok();
+                // Change the `Schema` for that game, and drop the cache of the dependency database.
+                *schema.borrow_mut() = load_schema(&app_ui.window, &rpfm_path, &supported_games.borrow(), &game_selected.borrow().game);
+                *dependency_cache.borrow_mut() = None;
 
-                // Change the `dependency_database` for that game.
-                *dependency_database.borrow_mut() = match packfile::open_packfile(game_selected.borrow().game_dependency_packfile_path.to_path_buf()) {
-                    Ok(data) => Some(data.data.packed_files),
-                    Err(_) => None,
-                };
+                // Drop the `dependency_database`, so the one of the new game is loaded when needed.
+                *dependency_database.borrow_mut() = None;
+                check_dependency_pack_outdated(&app_ui, &game_selected.borrow());
 
                 // If we have a PackFile opened....
                 if !pack_file_decoded.borrow().extra_data.file_name.is_empty() {
@@ -1469,9 +1546,9 @@ fn build_ui(application: &Application) {
     // When we hit the "Generate Dependency Pack" button.
     app_ui.menu_bar_generate_dependency_pack_wh2.connect_activate(clone!(
         app_ui,
-        rpfm_path,
+        dependency_database,
         game_selected => move |_,_| {
-            generate_dependency_pack(&app_ui, &rpfm_path, &game_selected);
+            generate_dependency_pack(&app_ui, &game_selected, &dependency_database);
         }
     ));
 
@@ -1496,9 +1573,9 @@ fn build_ui(application: &Application) {
     // When we hit the "Generate Dependency Pack" button (Warhammer).
     app_ui.menu_bar_generate_dependency_pack_wh.connect_activate(clone!(
         game_selected,
-        rpfm_path,
+        dependency_database,
         app_ui => move |_,_| {
-            generate_dependency_pack(&app_ui, &rpfm_path, &game_selected);
+            generate_dependency_pack(&app_ui, &game_selected, &dependency_database);
         }
     ));
 
@@ -1514,10 +1591,769 @@ fn build_ui(application: &Application) {
 
     // When we hit the "Generate Dependency Pack" button (Attila).
     app_ui.menu_bar_generate_dependency_pack_att.connect_activate(clone!(
+        app_ui,
+        dependency_database,
+        game_selected => move |_,_| {
+            generate_dependency_pack(&app_ui, &game_selected, &dependency_database);
+        }
+    ));
+
+    // When we hit the "Check Outdated Tables" button.
+    app_ui.menu_bar_check_outdated_tables.connect_activate(clone!(
+        app_ui,
+        schema,
+        game_selected,
+        dependency_database,
+        pack_file_decoded => move |_,_| {
+
+            // We need both, the schema and the dependency database, to know if a table is outdated.
+            load_dependency_database(&dependency_database, &game_selected.borrow());
+            match *schema.borrow() {
+                Some(ref schema) => match *dependency_database.borrow() {
+                    Some(ref dependency_database) => {
+                        let report = CompatibilityReport::new(&pack_file_decoded.borrow().data.packed_files, schema, dependency_database);
+                        show_report(&app_ui.window, "Outdated Tables", &report);
+                    }
+                    None => show_dialog(&app_ui.window, false, "To check the tables we need a Dependency Database created for this game. Create one and try again."),
+                }
+                None => show_dialog(&app_ui.window, false, "There is no schema loaded for this game."),
+            }
+        }
+    ));
+
+    // When we hit the "Validate Tables" button.
+    app_ui.menu_bar_validate_tables.connect_activate(clone!(
+        app_ui,
+        schema,
+        pack_file_decoded => move |_,_| {
+            match *schema.borrow() {
+                Some(ref schema) => {
+                    let report = ValidationReport::new(&pack_file_decoded.borrow().data.packed_files, schema);
+                    show_report(&app_ui.window, "Validation Errors", &report);
+                }
+                None => show_dialog(&app_ui.window, false, "There is no schema loaded for this game."),
+            }
+        }
+    ));
+
+    // When we hit the "Export to SQLite" button.
+    app_ui.menu_bar_export_sqlite.connect_activate(clone!(
+        app_ui,
+        schema,
+        game_selected,
+        dependency_database,
+        pack_file_decoded => move |_,_| {
+
+            // Without schema we cannot decode the tables, so we cannot export them.
+            if let Some(ref schema) = *schema.borrow() {
+
+                let file_chooser_export_sqlite = FileChooserNative::new(
+                    "Export to SQLite...",
+                    &app_ui.window,
+                    FileChooserAction::Save,
+                    "Save",
+                    "Cancel"
+                );
+
+                file_chooser_export_sqlite.set_do_overwrite_confirmation(true);
+                file_chooser_filter_packfile(&file_chooser_export_sqlite, "*.sqlite");
+                file_chooser_export_sqlite.set_current_name(&format!("{}.sqlite", pack_file_decoded.borrow().extra_data.file_name));
+
+                // If we hit "Accept"...
+                if file_chooser_export_sqlite.run() == gtk_response_accept {
+                    let path = file_chooser_export_sqlite.get_filename().unwrap();
+
+                    // If we have a dependency database, ask if we want it exported too.
+                    load_dependency_database(&dependency_database, &game_selected.borrow());
+                    let dependency_database = dependency_database.borrow();
+                    let dependency_database = match *dependency_database {
+                        Some(ref dependency_database) => if show_question(&app_ui.window, "Do you want to export the tables from the Dependency Database too?") { Some(&dependency_database[..]) } else { None },
+                        None => None,
+                    };
+
+                    match export_to_sqlite(&pack_file_decoded.borrow().data.packed_files, dependency_database, schema, &path) {
+                        Ok(error_files) => {
+                            if error_files.is_empty() { show_dialog(&app_ui.window, true, format!("PackFile successfully exported to:\n{}", path.display())); }
+                            else { show_dialog(&app_ui.window, true, format!("PackFile exported to:\n{}\n\nThe following PackedFiles couldn't be exported:\n{}", path.display(), error_files.iter().map(|x| x.join("/")).collect::<Vec<String>>().join("\n"))); }
+                        }
+                        Err(error) => show_dialog(&app_ui.window, false, error.cause()),
+                    }
+                }
+            }
+            else { show_dialog(&app_ui.window, false, "There is no schema loaded for this game."); }
+        }
+    ));
+
+    // When we hit the "Import from SQLite" button.
+    app_ui.menu_bar_import_sqlite.connect_activate(clone!(
+        app_ui,
+        schema,
+        is_packedfile_opened,
+        pack_file_decoded => move |_,_| {
+
+            // We cannot replace the PackedFiles if one of them is opened, as his view will not be updated.
+            if *is_packedfile_opened.borrow() {
+                return show_dialog(&app_ui.window, false, "You can't import from SQLite while there is a PackedFile opened in the right side. Pls close it by clicking in a Folder/PackFile before trying to import again.");
+            }
+
+            // Without schema we cannot encode the tables, so we cannot import them.
+            if let Some(ref schema) = *schema.borrow() {
+
+                let file_chooser_import_sqlite = FileChooserNative::new(
+                    "Import from SQLite...",
+                    &app_ui.window,
+                    FileChooserAction::Open,
+                    "Accept",
+                    "Cancel"
+                );
+
+                file_chooser_filter_packfile(&file_chooser_import_sqlite, "*.sqlite");
+
+                // If we hit "Accept"...
+                if file_chooser_import_sqlite.run() == gtk_response_accept {
+
+                    // Get the paths we have before importing, so we know which ones are new.
+                    let old_paths = pack_file_decoded.borrow().data.packed_files.iter().map(|x| x.path.to_vec()).collect::<Vec<Vec<String>>>();
+                    let result = import_from_sqlite(&mut pack_file_decoded.borrow_mut(), schema, &file_chooser_import_sqlite.get_filename().unwrap());
+                    match result {
+                        Ok(paths) => {
+
+                            // Set the mod as "Modified".
+                            set_modified(true, &app_ui.window, &mut pack_file_decoded.borrow_mut());
+
+                            // Add the new paths to the TreeView.
+                            for path in paths.iter().filter(|x| !old_paths.contains(x)) {
+                                update_treeview(
+                                    &app_ui.folder_tree_store,
+                                    &pack_file_decoded.borrow(),
+                                    &app_ui.folder_tree_selection,
+                                    TreeViewOperation::Add(path.to_vec()),
+                                    &TreePathType::None,
+                                );
+                            }
+
+                            show_dialog(&app_ui.window, true, format!("{} PackedFiles successfully imported.", paths.len()));
+                        }
+                        Err(error) => show_dialog(&app_ui.window, false, error.cause()),
+                    }
+                }
+            }
+            else { show_dialog(&app_ui.window, false, "There is no schema loaded for this game."); }
+        }
+    ));
+
+    // When we hit the "Generate Table Patch" button.
+    app_ui.menu_bar_generate_table_patch.connect_activate(clone!(
+        app_ui,
+        schema,
+        game_selected,
+        dependency_database,
+        pack_file_decoded => move |_,_| {
+
+            // We need both, the schema to decode the tables and the dependency database to compare them with vanilla.
+            load_dependency_database(&dependency_database, &game_selected.borrow());
+            match *schema.borrow() {
+                Some(ref schema) => match *dependency_database.borrow() {
+                    Some(ref dependency_database) => {
+                        let with_deletes = show_question(&app_ui.window, "Do the tables of this PackFile replace the full vanilla tables?\n\nIf you say yes, the vanilla rows that are not in them will be deleted by the patch. Otherwise, the patch will only add and modify rows.");
+                        let (patch, skipped_tables) = match DBPatch::new(&pack_file_decoded.borrow().data.packed_files, dependency_database, schema, None, with_deletes) {
+                            Ok(result) => result,
+                            Err(error) => return show_dialog(&app_ui.window, false, error.cause()),
+                        };
+
+                        let mut report = patch.to_string();
+                        if !skipped_tables.is_empty() {
+                            report.push_str(&format!("Tables not included in the patch:\n{}\n", skipped_tables.iter().map(|x| format!("  - {}", x)).collect::<Vec<String>>().join("\n")));
+                        }
+                        show_report(&app_ui.window, "Table Patch", &report);
+                        if patch.tables.is_empty() { return }
+
+                        let file_chooser_generate_table_patch = FileChooserNative::new(
+                            "Save Table Patch...",
+                            &app_ui.window,
+                            FileChooserAction::Save,
+                            "Save",
+                            "Cancel"
+                        );
+
+                        file_chooser_generate_table_patch.set_do_overwrite_confirmation(true);
+                        file_chooser_filter_packfile(&file_chooser_generate_table_patch, "*.json");
+                        file_chooser_generate_table_patch.set_current_name(&format!("{}.patch.json", pack_file_decoded.borrow().extra_data.file_name.trim_right_matches(".pack")));
+
+                        // If we hit "Accept"...
+                        if file_chooser_generate_table_patch.run() == gtk_response_accept {
+                            let path = file_chooser_generate_table_patch.get_filename().unwrap();
+                            match patch.save(&path) {
+                                Ok(_) => show_dialog(&app_ui.window, true, format!("Patch of {} tables saved to:\n{}", patch.tables.len(), path.display())),
+                                Err(error) => show_dialog(&app_ui.window, false, error.cause()),
+                            }
+                        }
+                    }
+                    None => show_dialog(&app_ui.window, false, "To generate a patch we need a Dependency Database created for this game. Create one and try again."),
+                }
+                None => show_dialog(&app_ui.window, false, "There is no schema loaded for this game."),
+            }
+        }
+    ));
+
+    // When we hit the "Apply Table Patch" button.
+    app_ui.menu_bar_apply_table_patch.connect_activate(clone!(
+        app_ui,
+        schema,
+        game_selected,
+        dependency_database,
+        is_packedfile_opened,
+        pack_file_decoded => move |_,_| {
+
+            // We cannot replace the PackedFiles if one of them is opened, as his view will not be updated.
+            if *is_packedfile_opened.borrow() {
+                return show_dialog(&app_ui.window, false, "You can't apply a patch while there is a PackedFile opened in the right side. Pls close it by clicking in a Folder/PackFile before trying to apply it again.");
+            }
+
+            // We need both, the schema to decode the tables and the dependency database to get the current vanilla tables.
+            load_dependency_database(&dependency_database, &game_selected.borrow());
+            match *schema.borrow() {
+                Some(ref schema) => match *dependency_database.borrow() {
+                    Some(ref dependency_database) => {
+                        let file_chooser_apply_table_patch = FileChooserNative::new(
+                            "Apply Table Patch...",
+                            &app_ui.window,
+                            FileChooserAction::Open,
+                            "Accept",
+                            "Cancel"
+                        );
+
+                        file_chooser_filter_packfile(&file_chooser_apply_table_patch, "*.json");
+
+                        // If we hit "Accept"...
+                        if file_chooser_apply_table_patch.run() == gtk_response_accept {
+                            let patch = match DBPatch::load(&file_chooser_apply_table_patch.get_filename().unwrap()) {
+                                Ok(patch) => patch,
+                                Err(error) => return show_dialog(&app_ui.window, false, error.cause()),
+                            };
+
+                            show_report(&app_ui.window, "Table Patch", &patch);
+                            if patch.tables.is_empty() || !show_question(&app_ui.window, format!("Do you want to apply this patch to the current vanilla tables? The result of each table will be saved in the PackFile as \"db/<table>/{}\", replacing it if it already exists.", pack_file_decoded.borrow().extra_data.file_name.trim_right_matches(".pack"))) { return }
+
+                            // Get the paths we have before applying, so we know which ones are new.
+                            let old_paths = pack_file_decoded.borrow().data.packed_files.iter().map(|x| x.path.to_vec()).collect::<Vec<Vec<String>>>();
+                            let packed_file_name = pack_file_decoded.borrow().extra_data.file_name.trim_right_matches(".pack").to_owned();
+                            let result = patch.apply(&mut pack_file_decoded.borrow_mut(), dependency_database, schema, &packed_file_name);
+                            match result {
+                                Ok(report) => {
+
+                                    // Set the mod as "Modified".
+                                    set_modified(true, &app_ui.window, &mut pack_file_decoded.borrow_mut());
+
+                                    // Add the new paths to the TreeView.
+                                    for path in report.tables.iter().filter(|x| !old_paths.contains(x)) {
+                                        update_treeview(
+                                            &app_ui.folder_tree_store,
+                                            &pack_file_decoded.borrow(),
+                                            &app_ui.folder_tree_selection,
+                                            TreeViewOperation::Add(path.to_vec()),
+                                            &TreePathType::None,
+                                        );
+                                    }
+
+                                    show_report(&app_ui.window, "Table Patch Applied", &report);
+                                }
+                                Err(error) => show_dialog(&app_ui.window, false, error.cause()),
+                            }
+                        }
+                    }
+                    None => show_dialog(&app_ui.window, false, "To apply a patch we need a Dependency Database created for this game. Create one and try again."),
+                }
+                None => show_dialog(&app_ui.window, false, "There is no schema loaded for this game."),
+            }
+        }
+    ));
+
+    // When we hit the "Check References" button.
+    app_ui.menu_bar_check_references.connect_activate(clone!(
+        app_ui,
+        schema,
+        game_selected,
+        dependency_database,
+        pack_file_decoded => move |_,_| {
+
+            // We need both, the schema to decode the tables and the dependency database to get the vanilla values.
+            load_dependency_database(&dependency_database, &game_selected.borrow());
+            match *schema.borrow() {
+                Some(ref schema) => match *dependency_database.borrow() {
+                    Some(ref dependency_database) => {
+                        let report = ReferenceReport::new(&pack_file_decoded.borrow().data.packed_files, dependency_database, schema);
+                        show_report(&app_ui.window, "Broken References", &report);
+                    }
+                    None => show_dialog(&app_ui.window, false, "To check the references we need a Dependency Database created for this game. Create one and try again."),
+                }
+                None => show_dialog(&app_ui.window, false, "There is no schema loaded for this game."),
+            }
+        }
+    ));
+
+    // When we hit the "Clone with Dependencies" button.
+    app_ui.menu_bar_clone_with_dependencies.connect_activate(clone!(
+        app_ui,
+        schema,
+        game_selected,
+        dependency_database,
+        is_packedfile_opened,
+        pack_file_decoded => move |_,_| {
+
+            // We cannot change the PackedFiles if one of them is opened, as his view will not be updated.
+            if *is_packedfile_opened.borrow() {
+                return show_dialog(&app_ui.window, false, "You can't clone rows while there is a PackedFile opened in the right side. Pls close it by clicking in a Folder/PackFile before trying to clone again.");
+            }
+
+            if let Some(ref schema) = *schema.borrow() {
+                if let Some((table, column, key, key_pattern)) = show_clone_with_dependencies_dialog(&app_ui.window) {
+
+                    // The dependency database is optional. Without it, we can only clone rows of the PackFile.
+                    load_dependency_database(&dependency_database, &game_selected.borrow());
+                    let deep_clone = match *dependency_database.borrow() {
+                        Some(ref dependency_database) => DeepClone::new(&pack_file_decoded.borrow().data.packed_files, dependency_database, schema, &table, &column, &key, &key_pattern),
+                        None => DeepClone::new(&pack_file_decoded.borrow().data.packed_files, &[], schema, &table, &column, &key, &key_pattern),
+                    };
+
+                    match deep_clone {
+                        Ok(deep_clone) => {
+                            show_report(&app_ui.window, "Clone with Dependencies", &deep_clone);
+                            if deep_clone.is_empty() || !show_question(&app_ui.window, format!("Do you want to add these {} rows and {} Loc entries to the PackFile?", deep_clone.rows.len(), deep_clone.loc_entries.len())) { return }
+
+                            let packed_file_name = pack_file_decoded.borrow().extra_data.file_name.trim_right_matches(".pack").to_owned();
+                            let result = deep_clone.apply(&mut pack_file_decoded.borrow_mut(), schema, &packed_file_name);
+                            match result {
+                                Ok(paths) => {
+
+                                    // Set the mod as "Modified".
+                                    set_modified(true, &app_ui.window, &mut pack_file_decoded.borrow_mut());
+
+                                    // Add the new PackedFiles to the TreeView.
+                                    for path in &paths {
+                                        update_treeview(
+                                            &app_ui.folder_tree_store,
+                                            &pack_file_decoded.borrow(),
+                                            &app_ui.folder_tree_selection,
+                                            TreeViewOperation::Add(path.to_vec()),
+                                            &TreePathType::None,
+                                        );
+                                    }
+
+                                    show_message_in_statusbar(&app_ui.status_bar, format!("\"{}\" cloned with {} rows and {} Loc entries.", key, deep_clone.rows.len(), deep_clone.loc_entries.len()));
+                                }
+                                Err(error) => show_dialog(&app_ui.window, false, error.cause()),
+                            }
+                        }
+                        Err(error) => show_dialog(&app_ui.window, false, error.cause()),
+                    }
+                }
+            }
+            else { show_dialog(&app_ui.window, false, "There is no schema loaded for this game."); }
+        }
+    ));
+
+    // When we hit the "Merge Table Fragments" button.
+    app_ui.menu_bar_merge_table_fragments.connect_activate(clone!(
+        app_ui,
+        schema,
+        is_packedfile_opened,
+        pack_file_decoded => move |_,_| {
+
+            // We cannot change the PackedFiles if one of them is opened, as his view will not be updated.
+            if *is_packedfile_opened.borrow() {
+                return show_dialog(&app_ui.window, false, "You can't merge tables while there is a PackedFile opened in the right side. Pls close it by clicking in a Folder/PackFile before trying to merge them again.");
+            }
+
+            if let Some(ref schema) = *schema.borrow() {
+
+                // Merge every table with more than one fragment. The ones we cannot merge are left as they are.
+                let mut merges = vec![];
+                let mut report = String::new();
+                for table in &get_fragmented_tables(&pack_file_decoded.borrow().data.packed_files) {
+                    match TableMerge::new(&pack_file_decoded.borrow().data.packed_files, schema, table, None, None) {
+                        Ok(merge) => {
+                            report.push_str(&format!("{}\n", merge));
+                            merges.push(merge);
+                        }
+                        Err(error) => report.push_str(&format!("{}: {}\n\n", table, error)),
+                    }
+                }
+
+                if merges.is_empty() {
+                    if report.is_empty() { return show_dialog(&app_ui.window, true, "There are no tables with more than one fragment in this PackFile."); }
+                    return show_report(&app_ui.window, "Merge Table Fragments", &report);
+                }
+                show_report(&app_ui.window, "Merge Table Fragments", &report);
+                if !show_question(&app_ui.window, format!("Do you want to merge the fragments of these {} tables?", merges.len())) { return }
+
+                for merge in &merges {
+                    let result = merge.apply(&mut pack_file_decoded.borrow_mut());
+                    if let Err(error) = result { show_dialog(&app_ui.window, false, error.cause()); }
+                }
+
+                // Set the mod as "Modified".
+                set_modified(true, &app_ui.window, &mut pack_file_decoded.borrow_mut());
+
+                // Rebuild the `TreeView`, as the fragments are gone.
+                app_ui.folder_tree_store.clear();
+                update_treeview(
+                    &app_ui.folder_tree_store,
+                    &*pack_file_decoded.borrow(),
+                    &app_ui.folder_tree_selection,
+                    TreeViewOperation::Build,
+                    &TreePathType::None,
+                );
+
+                show_message_in_statusbar(&app_ui.status_bar, format!("Fragments of {} tables merged.", merges.len()));
+            }
+            else { show_dialog(&app_ui.window, false, "There is no schema loaded for this game."); }
+        }
+    ));
+
+    // When we hit the "Normalize Tables and Locs" button.
+    app_ui.menu_bar_normalize_tables.connect_activate(clone!(
+        app_ui,
+        schema,
+        settings,
+        is_packedfile_opened,
+        pack_file_decoded => move |_,_| {
+
+            // We cannot change the PackedFiles if one of them is opened, as his view will not be updated.
+            if *is_packedfile_opened.borrow() {
+                return show_dialog(&app_ui.window, false, "You can't normalize the tables while there is a PackedFile opened in the right side. Pls close it by clicking in a Folder/PackFile before trying to normalize them again.");
+            }
+
+            if let Some(ref schema) = *schema.borrow() {
+                let report = normalize_packed_files(&mut pack_file_decoded.borrow_mut(), schema, true, settings.borrow().deterministic_guids);
+                if !report.is_empty() { set_modified(true, &app_ui.window, &mut pack_file_decoded.borrow_mut()); }
+                show_report(&app_ui.window, "Normalize Tables and Locs", &report);
+            }
+            else { show_dialog(&app_ui.window, false, "There is no schema loaded for this game."); }
+        }
+    ));
+
+    // When we hit the "Import from Assembly Kit" button.
+    app_ui.menu_bar_import_assembly_kit.connect_activate(clone!(
+        app_ui,
+        schema,
+        game_selected,
+        dependency_database,
+        is_packedfile_opened,
+        pack_file_decoded => move |_,_| {
+
+            // We cannot replace the PackedFiles if one of them is opened, as his view will not be updated.
+            if *is_packedfile_opened.borrow() {
+                return show_dialog(&app_ui.window, false, "You can't import from the Assembly Kit while there is a PackedFile opened in the right side. Pls close it by clicking in a Folder/PackFile before trying to import again.");
+            }
+
+            // Without schema we cannot encode the tables, so we cannot import them.
+            if let Some(ref schema) = *schema.borrow() {
+
+                let file_chooser_import_assembly_kit = FileChooserNative::new(
+                    "Select the \"raw_data/db\" folder of the Assembly Kit...",
+                    &app_ui.window,
+                    FileChooserAction::SelectFolder,
+                    "Accept",
+                    "Cancel"
+                );
+
+                // If we know where the game is, start in the Assembly Kit's folder.
+                if let Some(ref game_path) = game_selected.borrow().game_path {
+                    let mut raw_data_path = game_path.to_path_buf();
+                    raw_data_path.push("assembly_kit/raw_data/db");
+                    if raw_data_path.is_dir() { file_chooser_import_assembly_kit.set_current_folder(&raw_data_path); }
+                }
+
+                // If we hit "Accept"...
+                if file_chooser_import_assembly_kit.run() == gtk_response_accept {
+
+                    load_dependency_database(&dependency_database, &game_selected.borrow());
+
+                    // Get the paths we have before importing, so we know which ones are new.
+                    let old_paths = pack_file_decoded.borrow().data.packed_files.iter().map(|x| x.path.to_vec()).collect::<Vec<Vec<String>>>();
+                    let result = import_from_assembly_kit(
+                        &mut pack_file_decoded.borrow_mut(),
+                        &file_chooser_import_assembly_kit.get_filename().unwrap(),
+                        schema,
+                        dependency_database.borrow().as_ref().map(|x| &x[..]),
+                    );
+
+                    match result {
+                        Ok((tables, errors)) => {
+
+                            // If we imported something, set the mod as "Modified" and add the new paths to the TreeView.
+                            if !tables.is_empty() {
+                                set_modified(true, &app_ui.window, &mut pack_file_decoded.borrow_mut());
+                                for path in tables.iter().map(|x| &x.path).filter(|x| !old_paths.contains(*x)) {
+                                    update_treeview(
+                                        &app_ui.folder_tree_store,
+                                        &pack_file_decoded.borrow(),
+                                        &app_ui.folder_tree_selection,
+                                        TreeViewOperation::Add(path.to_vec()),
+                                        &TreePathType::None,
+                                    );
+                                }
+                            }
+
+                            // Report the tables with missing or extra columns, as their data may not be what the user expects.
+                            let changed_tables = tables.iter().filter(|x| x.report.has_column_changes()).collect::<Vec<&ImportedTable>>();
+                            if errors.is_empty() && changed_tables.is_empty() { show_dialog(&app_ui.window, true, format!("{} tables successfully imported.", tables.len())); }
+                            else {
+                                let mut report = format!("{} tables imported.", tables.len());
+                                for table in &changed_tables {
+                                    report.push_str(&format!("\n\n{}:\n{}", table.path.join("/"), table.report));
+                                }
+                                if !errors.is_empty() { report.push_str(&format!("\n\nThe following files couldn't be imported:\n{}", errors.join("\n"))); }
+                                show_report(&app_ui.window, "Import from Assembly Kit", report);
+                            }
+                        }
+                        Err(error) => show_dialog(&app_ui.window, false, error.cause()),
+                    }
+                }
+            }
+            else { show_dialog(&app_ui.window, false, "There is no schema loaded for this game."); }
+        }
+    ));
+
+    // When we hit the "Export to Assembly Kit" button.
+    app_ui.menu_bar_export_assembly_kit.connect_activate(clone!(
+        app_ui,
+        schema,
+        pack_file_decoded => move |_,_| {
+
+            // Without schema we cannot decode the tables, so we cannot export them.
+            if let Some(ref schema) = *schema.borrow() {
+
+                let file_chooser_export_assembly_kit = FileChooserNative::new(
+                    "Select the folder to export the tables to...",
+                    &app_ui.window,
+                    FileChooserAction::SelectFolder,
+                    "Accept",
+                    "Cancel"
+                );
+
+                // If we hit "Accept"...
+                if file_chooser_export_assembly_kit.run() == gtk_response_accept {
+                    let path = file_chooser_export_assembly_kit.get_filename().unwrap();
+                    match export_to_assembly_kit(&pack_file_decoded.borrow().data.packed_files, schema, &path) {
+                        Ok(error_files) => {
+                            if error_files.is_empty() { show_dialog(&app_ui.window, true, format!("Tables successfully exported to:\n{}", path.display())); }
+                            else { show_dialog(&app_ui.window, true, format!("Tables exported to:\n{}\n\nThe following PackedFiles couldn't be exported:\n{}", path.display(), error_files.iter().map(|x| x.join("/")).collect::<Vec<String>>().join("\n"))); }
+                        }
+                        Err(error) => show_dialog(&app_ui.window, false, error.cause()),
+                    }
+                }
+            }
+            else { show_dialog(&app_ui.window, false, "There is no schema loaded for this game."); }
+        }
+    ));
+
+    // When we hit the "Import Schema from Assembly Kit" button.
+    app_ui.menu_bar_import_schema_assembly_kit.connect_activate(clone!(
+        app_ui,
+        rpfm_path,
+        schema,
+        dependency_cache,
+        supported_games,
+        game_selected,
+        dependency_database => move |_,_| {
+
+            // The Assembly Kit doesn't have the versions of the tables, so we need the game's tables to get them.
+            load_dependency_database(&dependency_database, &game_selected.borrow());
+            if let Some(ref dependency_database) = *dependency_database.borrow() {
+
+                let file_chooser_import_schema = FileChooserNative::new(
+                    "Select the \"raw_data/db\" folder of the Assembly Kit...",
+                    &app_ui.window,
+                    FileChooserAction::SelectFolder,
+                    "Accept",
+                    "Cancel"
+                );
+
+                // If we know where the game is, start in the Assembly Kit's folder.
+                if let Some(ref game_path) = game_selected.borrow().game_path {
+                    let mut raw_data_path = game_path.to_path_buf();
+                    raw_data_path.push("assembly_kit/raw_data/db");
+                    if raw_data_path.is_dir() { file_chooser_import_schema.set_current_folder(&raw_data_path); }
+                }
+
+                // If we hit "Accept"...
+                if file_chooser_import_schema.run() == gtk_response_accept {
+
+                    // If we don't have a schema for this game yet, we start with an empty one.
+                    let mut new_schema = schema.borrow().clone().unwrap_or_else(Schema::new);
+                    match import_schema(&mut new_schema, &file_chooser_import_schema.get_filename().unwrap(), dependency_database) {
+                        Ok(report) => {
+                            match Schema::save(&new_schema, &rpfm_path, &supported_games.borrow(), &game_selected.borrow().game) {
+                                Ok(_) => {
+                                    let diff = SchemaDiff::new(&schema.borrow().clone().unwrap_or_else(Schema::new), &new_schema);
+                                    *schema.borrow_mut() = Some(new_schema);
+                                    *dependency_cache.borrow_mut() = None;
+                                    show_report(&app_ui.window, "Import Schema from Assembly Kit", format!("{}\n# Changes\n\n{}", report, diff.to_markdown()));
+                                }
+                                Err(error) => show_dialog(&app_ui.window, false, error.cause()),
+                            }
+                        }
+                        Err(error) => show_dialog(&app_ui.window, false, error.cause()),
+                    }
+                }
+            }
+            else { show_dialog(&app_ui.window, false, "There is no Dependency PackFile for this game. Generate it before importing the schema from the Assembly Kit."); }
+        }
+    ));
+
+    // When we hit the "Import Schema from PFM" button.
+    app_ui.menu_bar_import_schema_pfm.connect_activate(clone!(
         app_ui,
         rpfm_path,
+        schema,
+        dependency_cache,
+        supported_games,
+        game_selected,
+        dependency_database => move |_,_| {
+
+            let file_chooser_import_schema_pfm = FileChooserNative::new(
+                "Select PFM's schema file...",
+                &app_ui.window,
+                FileChooserAction::Open,
+                "Accept",
+                "Cancel"
+            );
+            file_chooser_filter_packfile(&file_chooser_import_schema_pfm, "*.xml");
+
+            // If we hit "Accept"...
+            if file_chooser_import_schema_pfm.run() == gtk_response_accept {
+
+                // If we don't have a schema for this game yet, we start with an empty one. If we have the game's
+                // tables, we only import the definitions of the tables the game has.
+                load_dependency_database(&dependency_database, &game_selected.borrow());
+                let mut new_schema = schema.borrow().clone().unwrap_or_else(Schema::new);
+                match import_pfm_schema(&mut new_schema, &file_chooser_import_schema_pfm.get_filename().unwrap(), dependency_database.borrow().as_ref().map(|x| &x[..])) {
+                    Ok(report) => {
+                        match Schema::save(&new_schema, &rpfm_path, &supported_games.borrow(), &game_selected.borrow().game) {
+                            Ok(_) => {
+                                let diff = SchemaDiff::new(&schema.borrow().clone().unwrap_or_else(Schema::new), &new_schema);
+                                *schema.borrow_mut() = Some(new_schema);
+                                *dependency_cache.borrow_mut() = None;
+                                show_report(&app_ui.window, "Import Schema from PFM", format!("{}\n# Changes\n\n{}", report, diff.to_markdown()));
+                            }
+                            Err(error) => show_dialog(&app_ui.window, false, error.cause()),
+                        }
+                    }
+                    Err(error) => show_dialog(&app_ui.window, false, error.cause()),
+                }
+            }
+        }
+    ));
+
+    // When we hit the "Export Schema to PFM" button.
+    app_ui.menu_bar_export_schema_pfm.connect_activate(clone!(
+        app_ui,
+        schema => move |_,_| {
+
+            if let Some(ref schema) = *schema.borrow() {
+                let file_chooser_export_schema_pfm = FileChooserNative::new(
+                    "Export Schema to PFM...",
+                    &app_ui.window,
+                    FileChooserAction::Save,
+                    "Save",
+                    "Cancel"
+                );
+                file_chooser_export_schema_pfm.set_current_name("master_schema.xml");
+                file_chooser_export_schema_pfm.set_do_overwrite_confirmation(true);
+
+                // If we hit "Save"...
+                if file_chooser_export_schema_pfm.run() == gtk_response_accept {
+                    let path = file_chooser_export_schema_pfm.get_filename().unwrap();
+                    match export_pfm_schema(schema, &path) {
+                        Ok(_) => show_dialog(&app_ui.window, true, format!("Schema successfully exported to:\n{}", path.display())),
+                        Err(error) => show_dialog(&app_ui.window, false, error.cause()),
+                    }
+                }
+            }
+            else { show_dialog(&app_ui.window, false, "There is no schema loaded for this game."); }
+        }
+    ));
+
+    // When we hit the "Check User Schema" button.
+    app_ui.menu_bar_check_schema_overlay.connect_activate(clone!(
+        app_ui,
+        rpfm_path,
+        supported_games,
+        game_selected => move |_,_| {
+
+            match Schema::check_overlay(&rpfm_path, &supported_games.borrow(), &game_selected.borrow().game) {
+                Ok(report) => {
+                    show_report(&app_ui.window, "User Schema", &report);
+
+                    // If some definitions are already in the downloaded schema, we offer to remove them from the user's one.
+                    if !report.identical.is_empty() && show_question(&app_ui.window, &format!("{} of your definitions are already in the downloaded schema, exactly as yours. Do you want to remove them from your schema?", report.identical.len())) {
+                        match Schema::prune_overlay(&rpfm_path, &supported_games.borrow(), &game_selected.borrow().game) {
+                            Ok(removed) => show_dialog(&app_ui.window, true, format!("{} definitions removed from your schema.", removed)),
+                            Err(error) => show_dialog(&app_ui.window, false, error.cause()),
+                        }
+                    }
+                }
+                Err(error) => show_dialog(&app_ui.window, false, error.cause()),
+            }
+        }
+    ));
+
+    // When we hit the "Export User Schema to Contribute" button.
+    app_ui.menu_bar_export_schema_overlay.connect_activate(clone!(
+        app_ui,
+        rpfm_path,
+        supported_games,
         game_selected => move |_,_| {
-            generate_dependency_pack(&app_ui, &rpfm_path, &game_selected);
+
+            let schema_file = supported_games.borrow().iter().filter(|x| x.folder_name == *game_selected.borrow().game).map(|x| x.schema.to_owned()).collect::<String>();
+            let file_chooser_export_schema_overlay = FileChooserNative::new(
+                "Export User Schema to Contribute...",
+                &app_ui.window,
+                FileChooserAction::Save,
+                "Save",
+                "Cancel"
+            );
+            file_chooser_export_schema_overlay.set_current_name(&schema_file);
+            file_chooser_export_schema_overlay.set_do_overwrite_confirmation(true);
+
+            // If we hit "Save"...
+            if file_chooser_export_schema_overlay.run() == gtk_response_accept {
+                let path = file_chooser_export_schema_overlay.get_filename().unwrap();
+                match Schema::export_overlay(&rpfm_path, &supported_games.borrow(), &game_selected.borrow().game, &path) {
+                    Ok(count) => show_dialog(&app_ui.window, true, format!("{} definitions exported to:\n{}\n\nYou can send this file to RPFM's repository to get them added to the schema.", count, path.display())),
+                    Err(error) => show_dialog(&app_ui.window, false, error.cause()),
+                }
+            }
+        }
+    ));
+
+    // When we hit the "Generate Schema Docs" button.
+    app_ui.menu_bar_generate_schema_docs.connect_activate(clone!(
+        app_ui,
+        schema => move |_,_| {
+
+            if let Some(ref schema) = *schema.borrow() {
+                let file_chooser_generate_schema_docs = FileChooserNative::new(
+                    "Select the folder for the Schema Docs...",
+                    &app_ui.window,
+                    FileChooserAction::SelectFolder,
+                    "Accept",
+                    "Cancel"
+                );
+
+                // If we hit "Accept"...
+                if file_chooser_generate_schema_docs.run() == gtk_response_accept {
+                    let path = file_chooser_generate_schema_docs.get_filename().unwrap();
+                    let format = if show_question(&app_ui.window, "Do you want the docs in HTML?\n\nIf you say no, they'll be generated in Markdown.") { DocsFormat::Html } else { DocsFormat::Markdown };
+                    match generate_docs(schema, &path, format) {
+                        Ok(count) => show_dialog(&app_ui.window, true, format!("Docs of {} tables generated in:\n{}", count, path.display())),
+                        Err(error) => show_dialog(&app_ui.window, false, error.cause()),
+                    }
+                }
+            }
+            else { show_dialog(&app_ui.window, false, "There is no schema loaded for this game."); }
         }
     ));
 
@@ -1540,8 +2376,9 @@ fn build_ui(application: &Application) {
         game_selected,
         rpfm_path,
         schema,
+        dependency_cache,
         app_ui => move |_,_| {
-            check_schema_updates(VERSION, &rpfm_path, &supported_games.borrow(), &game_selected, &schema, Some(&app_ui.window), None);
+            check_schema_updates(VERSION, &rpfm_path, &supported_games.borrow(), &game_selected, &schema, &dependency_cache, Some(&app_ui.window), None);
         }
     ));
 
@@ -1587,7 +2424,7 @@ fn build_ui(application: &Application) {
 
     // We check every action possible for the selected file when changing the cursor.
     app_ui.folder_tree_view.connect_cursor_changed(clone!(
-        dependency_database,
+        game_selected,
         pack_file_decoded,
         schema,
         app_ui => move |_| {
@@ -1658,8 +2495,9 @@ fn build_ui(application: &Application) {
                 },
             }
 
-            // If there is no dependency_database or schema for our GameSelected, ALWAYS disable creating new DB Tables.
-            if dependency_database.borrow().is_none() || schema.borrow().is_none() {
+            // If there is no dependency PackFile or schema for our GameSelected, ALWAYS disable creating new DB Tables.
+            // We check the file instead of the dependency_database, as that one is only loaded when needed.
+            if !game_selected.borrow().game_dependency_packfile_path.is_file() || schema.borrow().is_none() {
                 app_ui.folder_tree_view_create_db.set_enabled(false);
                 app_ui.folder_tree_view_mass_import_tsv_files.set_enabled(false);
             }
@@ -2485,6 +3323,7 @@ fn build_ui(application: &Application) {
 
     // When we hit the "Create DB Table" button.
     app_ui.folder_tree_view_create_db.connect_activate(clone!(
+        game_selected,
         dependency_database,
         pack_file_decoded,
         application,
@@ -2500,7 +3339,8 @@ fn build_ui(application: &Application) {
             // the accels working everywhere.
             if app_ui.folder_tree_view.has_focus() {
 
-                // Build the "Create DB Table" window.
+                // Build the "Create DB Table" window. It needs the dependency database to know the tables of the game.
+                load_dependency_database(&dependency_database, &game_selected.borrow());
                 show_create_packed_file_window(&application, &app_ui, &rpfm_path, &pack_file_decoded, PackedFileType::DB, &dependency_database, &schema);
             }
         }
@@ -2861,7 +3701,9 @@ fn build_ui(application: &Application) {
         supported_games,
         pack_file_decoded,
         dependency_database,
+        dependency_cache,
         is_packedfile_opened,
+        key_rename_undo,
         is_folder_tree_view_locked => move |_,_,_| {
 
         // Before anything else, we need to check if the `TreeView` is unlocked. Otherwise we don't do anything from here.
@@ -2956,9 +3798,11 @@ fn build_ui(application: &Application) {
                                 &is_packedfile_opened,
                                 &schema,
                                 &dependency_database,
+                                &dependency_cache,
                                 &game_selected,
                                 &supported_games,
-                                &settings.borrow()
+                                &settings.borrow(),
+                                &key_rename_undo
                             ) { return show_dialog(&app_ui.window, false, error.cause()) };
 
                             // Tell the program there is an open PackedFile.
@@ -3026,6 +3870,7 @@ fn build_ui(application: &Application) {
         game_selected,
         supported_games,
         dependency_database,
+        dependency_cache,
         pack_file_decoded_extra,
         pack_file_decoded => move |_, _, _, _, selection_data, info, _| {
 
@@ -3048,6 +3893,7 @@ fn build_ui(application: &Application) {
                             &supported_games.borrow(),
                             &game_selected,
                             &dependency_database,
+                            &dependency_cache,
                             &(false, None),
                             &pack_file_decoded,
                             &pack_file_decoded_extra
@@ -3076,6 +3922,7 @@ fn build_ui(application: &Application) {
             &supported_games.borrow(),
             &game_selected,
             &dependency_database,
+            &dependency_cache,
             &(false, None),
             &pack_file_decoded,
             &pack_file_decoded_extra
@@ -3100,11 +3947,16 @@ fn open_packfile(
     supported_games: &[GameInfo],
     game_selected: &Rc<RefCell<GameSelected>>,
     dependency_database: &Rc<RefCell<Option<Vec<PackedFile>>>>,
+    dependency_cache: &Rc<RefCell<Option<DependencyCache>>>,
     is_my_mod: &(bool, Option<String>),
     pack_file_decoded: &Rc<RefCell<PackFile>>,
     pack_file_decoded_extra: &Rc<RefCell<PackFile>>,
 ) -> Result<(), Error> {
-    match packfile::open_packfile(pack_file_path.to_path_buf()) {
+
+    // We open it in another thread, as big PackFiles (like the game's ones) take a while to open.
+    let path = pack_file_path.to_path_buf();
+    let result = run_with_progress(&app_ui.window, "Opening PackFile...", true, move |progress| packfile::open_packfile_with_progress(path, progress));
+    match result.and_then(|x| x) {
         Ok(pack_file_opened) => {
 
             // If there is no secondary PackFile opened using the "Data View" at the right side...
@@ -3192,11 +4044,8 @@ fn open_packfile(
                     },
                 }
 
-                // Change the `dependency_database` for that game.
-                *dependency_database.borrow_mut() = match packfile::open_packfile(game_selected.borrow().game_dependency_packfile_path.to_path_buf()) {
-                    Ok(data) => Some(data.data.packed_files),
-                    Err(_) => None,
-                };
+                // Drop the `dependency_database`, so the one of the new game is loaded when needed.
+                *dependency_database.borrow_mut() = None;
 
                 // Set the current "Operational Mode" to `Normal`.
                 set_my_mod_mode(app_ui, mode, None);
@@ -3205,8 +4054,9 @@ fn open_packfile(
             // Enable the "PackFile Management" actions.
             enable_packfile_actions(app_ui, game_selected, true);
 
-            // Try to load the Schema for this PackFile's game.
-            *schema.borrow_mut() = Schema::load(rpfm_path, &supported_games.iter().filter(|x| x.folder_name == *game_selected.borrow().game).map(|x| x.schema.to_owned()).collect::<String>()).ok();
+            // Try to load the Schema for this PackFile's game, and drop the cache of the dependency database.
+            *schema.borrow_mut() = load_schema(&app_ui.window, rpfm_path, supported_games, &game_selected.borrow().game);
+            *dependency_cache.borrow_mut() = None;
 
             // Test to see if every DB Table can be decoded.
             // let mut counter = 0;
@@ -3232,6 +4082,12 @@ fn open_packfile(
             Ok(())
         }
 
+        // If the user cancelled it, there is nothing wrong, so we just say it in the Statusbar.
+        Err(ref error) if is_cancelled_error(error) => {
+            show_message_in_statusbar(&app_ui.status_bar, "Opening the PackFile has been cancelled.");
+            Ok(())
+        }
+
         // In case of error while opening the PackFile, return the error.
         Err(error) => Err(error),
     }
@@ -3251,6 +4107,7 @@ fn build_my_mod_menu(
     game_selected: &Rc<RefCell<GameSelected>>,
     supported_games: &Rc<RefCell<Vec<GameInfo>>>,
     dependency_database: &Rc<RefCell<Option<Vec<PackedFile>>>>,
+    dependency_cache: &Rc<RefCell<Option<DependencyCache>>>,
     pack_file_decoded: &Rc<RefCell<PackFile>>,
     pack_file_decoded_extra: &Rc<RefCell<PackFile>>,
     rpfm_path: &PathBuf,
@@ -3320,6 +4177,7 @@ fn build_my_mod_menu(
                                         supported_games,
                                         game_selected,
                                         dependency_database,
+                                        dependency_cache,
                                         pack_file_decoded_extra,
                                         pack_file_decoded => move |_,_| {
 
@@ -3340,6 +4198,7 @@ fn build_my_mod_menu(
                                                     &supported_games.borrow(),
                                                     &game_selected,
                                                     &dependency_database,
+                                                    &dependency_cache,
                                                     &(true, Some(game_folder_name.borrow().to_owned())),
                                                     &pack_file_decoded,
                                                     &pack_file_decoded_extra
@@ -3380,7 +4239,7 @@ fn patch_siege_ai(
     }
     if sucessful_patching.0 {
         let mut success = false;
-        match packfile::save_packfile( &mut *pack_file_decoded.borrow_mut(), None) {
+        match save_packfile_with_progress_dialog(&app_ui.window, pack_file_decoded, None) {
             Ok(_) => {
                 success = true;
                 show_dialog(&app_ui.window, true, format!("{}\n\n{}", sucessful_patching.1, "PackFile succesfully saved."));
@@ -3410,36 +4269,44 @@ fn patch_siege_ai(
 /// This function serves as a common function for all the "Generate Dependency Pack" buttons from "Special Stuff".
 fn generate_dependency_pack(
     app_ui: &AppUI,
-    rpfm_path: &PathBuf,
     game_selected: &Rc<RefCell<GameSelected>>,
+    dependency_database: &Rc<RefCell<Option<Vec<PackedFile>>>>,
 ) {
 
-    // Get the data folder of game_selected and try to create our dependency PackFile.
-    match game_selected.borrow().game_data_path {
-        Some(ref path) => {
-            let mut data_pack_path = path.to_path_buf();
-            data_pack_path.push("data.pack");
-            match packfile::open_packfile(data_pack_path) {
-                Ok(ref mut data_packfile) => {
-                    data_packfile.data.packed_files.retain(|packed_file| packed_file.path.starts_with(&["db".to_owned()]));
-                    data_packfile.header.packed_file_count = data_packfile.data.packed_files.len() as u32;
-
-                    // Just in case the folder doesn't exists, we try to create it.
-                    let mut dep_packs_path = rpfm_path.clone();
-                    dep_packs_path.push("dependency_packs");
-
-                    match DirBuilder::new().create(&dep_packs_path) { Ok(_) | Err(_) => {}, }
-
-                    let pack_file_path = game_selected.borrow().game_dependency_packfile_path.to_path_buf();
-                    match packfile::save_packfile(data_packfile, Some(pack_file_path)) {
-                        Ok(_) => show_dialog(&app_ui.window, true, "Dependency pack created. Remember to re-create it if you update the game ;)."),
-                        Err(error) => show_dialog(&app_ui.window, false, format_err!("Error: generated dependency pack couldn't be saved. {:?}", error)),
-                    }
-                }
-                Err(_) => show_dialog(&app_ui.window, false, "Error: data.pack couldn't be open.")
-            }
-        },
-        None => show_dialog(&app_ui.window, false, "Error: data path of the game not found.")
+    // Get the data folder of game_selected and try to create our dependency PackFile from all his official PackFiles.
+    // As this reads a lot of big PackFiles, we do it in another thread.
+    let game = game_selected.borrow().game.to_owned();
+    let dependency_pack_file_path = game_selected.borrow().game_dependency_packfile_path.to_path_buf();
+    let game_data_path = match game_selected.borrow().game_data_path {
+        Some(ref path) => path.to_path_buf(),
+        None => return show_dialog(&app_ui.window, false, "Error: data path of the game not found."),
+    };
+
+    let result = run_with_progress(&app_ui.window, "Generating Dependency Pack...", true, move |progress| {
+        generate_dependency_pack_file(&game, &game_data_path, &dependency_pack_file_path, progress)
+    }).and_then(|x| x);
+
+    // If it worked, we drop the dependency database, so the new one is loaded when needed without restarting RPFM.
+    match result {
+        Ok(info) => {
+            *dependency_database.borrow_mut() = None;
+            show_dialog(&app_ui.window, true, format!("Dependency pack created from {} PackFiles. Remember to re-create it if you update the game ;).", info.sources.len()))
+        }
+        Err(ref error) if is_cancelled_error(error) => show_message_in_statusbar(&app_ui.status_bar, "The generation of the dependency pack has been cancelled."),
+        Err(error) => show_dialog(&app_ui.window, false, format_err!("Error: generated dependency pack couldn't be created. {}", error.cause())),
+    }
+}
+
+/// This function checks if the dependency PackFile of the game selected was generated from an older version of the
+/// game's PackFiles and, in that case, it tells the user to generate it again.
+fn check_dependency_pack_outdated(
+    app_ui: &AppUI,
+    game_selected: &GameSelected,
+) {
+    if let Some(ref game_data_path) = game_selected.game_data_path {
+        if is_dependency_pack_file_outdated(&game_selected.game_dependency_packfile_path, game_data_path) {
+            show_message_in_statusbar(&app_ui.status_bar, "The dependency PackFile is outdated (the game has been updated). Generate it again from the \"Special Stuff\" menu.");
+        }
     }
 }
 
@@ -3566,6 +4433,20 @@ fn enable_packfile_actions(app_ui: &AppUI, game_selected: &Rc<RefCell<GameSelect
             },
             _ => {},
         }
+
+        // The checks work for any game.
+        app_ui.menu_bar_check_outdated_tables.set_enabled(true);
+        app_ui.menu_bar_check_references.set_enabled(true);
+        app_ui.menu_bar_validate_tables.set_enabled(true);
+        app_ui.menu_bar_clone_with_dependencies.set_enabled(true);
+        app_ui.menu_bar_merge_table_fragments.set_enabled(true);
+        app_ui.menu_bar_normalize_tables.set_enabled(true);
+        app_ui.menu_bar_export_sqlite.set_enabled(true);
+        app_ui.menu_bar_import_sqlite.set_enabled(true);
+        app_ui.menu_bar_generate_table_patch.set_enabled(true);
+        app_ui.menu_bar_apply_table_patch.set_enabled(true);
+        app_ui.menu_bar_import_assembly_kit.set_enabled(true);
+        app_ui.menu_bar_export_assembly_kit.set_enabled(true);
     }
 
     // If we are disabling...
@@ -3582,6 +4463,50 @@ fn enable_packfile_actions(app_ui: &AppUI, game_selected: &Rc<RefCell<GameSelect
 
         // Disable Attila actions...
         app_ui.menu_bar_generate_dependency_pack_att.set_enabled(false);
+
+        // Disable the game-independent actions...
+        app_ui.menu_bar_check_outdated_tables.set_enabled(false);
+        app_ui.menu_bar_check_references.set_enabled(false);
+        app_ui.menu_bar_validate_tables.set_enabled(false);
+        app_ui.menu_bar_clone_with_dependencies.set_enabled(false);
+        app_ui.menu_bar_merge_table_fragments.set_enabled(false);
+        app_ui.menu_bar_normalize_tables.set_enabled(false);
+        app_ui.menu_bar_export_sqlite.set_enabled(false);
+        app_ui.menu_bar_import_sqlite.set_enabled(false);
+        app_ui.menu_bar_generate_table_patch.set_enabled(false);
+        app_ui.menu_bar_apply_table_patch.set_enabled(false);
+        app_ui.menu_bar_import_assembly_kit.set_enabled(false);
+        app_ui.menu_bar_export_assembly_kit.set_enabled(false);
+    }
+}
+
+/// This function validates the tables of a PackFile before saving it, if it's enabled in the settings.
+/// If there are errors, it shows them. It returns false if the errors should stop the PackFile from being saved.
+fn validate_tables_before_save(app_ui: &AppUI, settings: &Settings, schema: &Option<Schema>, pack_file: &PackFile) -> bool {
+
+    // If we don't have to validate or we can't do it, just let the PackFile be saved.
+    if !settings.validate_tables_on_save { return true }
+    if let Some(ref schema) = *schema {
+        let report = ValidationReport::new(&pack_file.data.packed_files, schema);
+        if !report.is_empty() {
+
+            // If the errors block the save, tell it to the user. Otherwise, we show the errors and keep saving.
+            if settings.block_save_on_validation_errors {
+                show_report(&app_ui.window, "Validation Errors (PackFile not saved)", &report);
+                return false
+            }
+            else { show_report(&app_ui.window, "Validation Errors", &report); }
+        }
+    }
+
+    true
+}
+
+/// This function sorts the rows of the DB Tables and Locs of a PackFile and replaces the GUIDs of his DB Tables
+/// with deterministic ones before saving it, if it's enabled in the settings. Tables we cannot decode are left as they are.
+fn normalize_tables_before_save(settings: &Settings, schema: &Option<Schema>, pack_file: &mut PackFile) {
+    if let Some(ref schema) = *schema {
+        normalize_packed_files_before_save(pack_file, schema, settings);
     }
 }
 
@@ -3605,9 +4530,33 @@ fn concatenate_check_update_messages(status_bar: &Statusbar) {
     status_bar.push(context_id, &new_text);
 }
 
+/// This function returns the path where RPFM's stuff (settings, schemas,...) is.
+///
+/// If we are opening a PackFile by double-clicking on it (for example, with file asociation in windows)
+/// our current dir is the one where the PackFile is, not where the `rpfm-code.exe` is. So RPFM gets
+/// confused and it doesn't find his settings, his schemas,... To fix this, we need to get the folder
+/// where the executable is and use it as a base for all the path stuff.
+fn get_rpfm_path() -> PathBuf {
+
+    // In debug mode, we just take the current path (so we don't break debug builds). In Release mode,
+    // we take the `.exe` path. We use unwrap here because in case of fail, we want to crash RPFM.
+    if cfg!(debug_assertions) {
+        std::env::current_dir().unwrap()
+    } else {
+        let mut path = std::env::current_exe().unwrap();
+        path.pop();
+        path
+    }
+}
+
 /// Main function.
 fn main() {
 
+    // If we received a command, run it without the UI and exit with his exit code.
+    if let Some(exit_code) = cli::run(&args().collect::<Vec<String>>(), &get_rpfm_path()) {
+        std::process::exit(exit_code);
+    }
+
     // We create the application.
     let application = Application::new("com.github.frodo45127.rpfm", gio::ApplicationFlags::NON_UNIQUE).expect("Initialization failed...");
 
@@ -0,0 +1,272 @@
+// In this file is all the stuff needed to import the data of the tables from the Assembly Kit, and to
+// export our tables back to it.
+//
+// The Assembly Kit has the data of each table in "assembly_kit/raw_data/db/table_name.xml" (without
+// the "_tables"), next to the "TWaD_table_name.xml" files with their definitions. Their structure is:
+//
+// <?xml version="1.0" encoding="UTF-8"?>
+// <dataroot>
+//   <land_units record_uuid="...">
+//     <key>wh_main_emp_inf_swordsmen</key>
+//     <category>inf_melee</category>
+//     <is_naval>false</is_naval>
+//     <animal/>
+//     ...
+//   </land_units>
+//   ...
+// </dataroot>
+//
+// Each row is an element with the name of the table, and each field is an element inside it with the
+// name of the column. Empty fields can be written as empty elements, or not written at all. On export, the
+// "record_uuid" of each row is generated from his keys, so exporting the same table twice gives the same file.
+
+extern crate failure;
+extern crate xml;
+
+use std::collections::HashMap;
+use std::fs::{File, DirBuilder};
+use std::io::{BufReader, Write};
+use std::path::PathBuf;
+
+use failure::Error;
+use self::xml::reader::{EventReader, XmlEvent};
+
+use common::get_files_from_subdir;
+use packfile::packfile::{PackFile, PackedFile};
+use packedfile::error::PackedFileError;
+use packedfile::get_game_table_version;
+use packedfile::serializers::ImportReport;
+use super::DB;
+use super::DBHeader;
+use super::DBData;
+use super::DecodedData;
+use super::schemas::*;
+
+/// `ImportedTable`: This struct holds a table imported from the Assembly Kit. It has:
+/// - path: the path of the PackedFile of the table.
+/// - report: what happened with the columns of the table.
+#[derive(Clone, Debug)]
+pub struct ImportedTable {
+    pub path: Vec<String>,
+    pub report: ImportReport,
+}
+
+/// This function reads the data of a table from an Assembly Kit XML file and returns it decoded with the
+/// provided definition, with the index column included, and the report of what happened with the columns.
+pub fn import_table_data(xml_path: &PathBuf, table_name: &str, table_definition: &TableDefinition) -> Result<(Vec<Vec<DecodedData>>, ImportReport), Error> {
+
+    let file = match File::open(xml_path) {
+        Ok(file) => file,
+        Err(_) => return Err(PackedFileError::ReadFile { path: xml_path.to_path_buf() }.into()),
+    };
+
+    // The rows are elements with the name of the table, without the "_tables".
+    let row_name = table_name.trim_right_matches("_tables");
+    let mut rows: Vec<HashMap<String, String>> = vec![];
+    let mut columns: Vec<String> = vec![];
+
+    let mut depth = 0;
+    let mut current_row: Option<HashMap<String, String>> = None;
+    let mut current_field: Option<String> = None;
+    let mut text = String::new();
+    for event in EventReader::new(BufReader::new(file)) {
+        match event? {
+            XmlEvent::StartElement { name, .. } => {
+                depth += 1;
+                if depth == 2 && name.local_name == row_name { current_row = Some(HashMap::new()); }
+                else if depth == 3 && current_row.is_some() {
+                    if !columns.contains(&name.local_name) { columns.push(name.local_name.to_owned()); }
+                    current_field = Some(name.local_name);
+                    text.clear();
+                }
+            }
+
+            XmlEvent::Characters(data) |
+            XmlEvent::CData(data) |
+            XmlEvent::Whitespace(data) => if current_field.is_some() { text.push_str(&data); },
+
+            XmlEvent::EndElement { .. } => {
+                if depth == 3 {
+                    if let (Some(row), Some(field)) = (current_row.as_mut(), current_field.take()) {
+                        row.insert(field, text.to_owned());
+                    }
+                }
+                else if depth == 2 {
+                    if let Some(row) = current_row.take() { rows.push(row); }
+                }
+                depth -= 1;
+            }
+            _ => {}
+        }
+    }
+
+    // Decode the rows. Fields not in the row are empty in the Assembly Kit, so they get their default value.
+    let entry_count = rows.len();
+    let mut entries = vec![];
+    for (index, row) in rows.iter().enumerate() {
+        let mut entry = vec![DecodedData::Index(format!("{:0count$}", index + 1, count = (entry_count.to_string().len() + 1)))];
+        for field in &table_definition.fields {
+            entry.push(match row.get(&field.field_name) {
+                Some(value) if !value.is_empty() => match DecodedData::from_text(value.trim(), &field.field_type) {
+                    Ok(data) => data,
+                    Err(_) => return Err(PackedFileError::InvalidRowValue { table: table_name.to_owned(), column: field.field_name.to_owned(), row: index }.into()),
+                },
+                _ => DecodedData::default(&field.field_type),
+            });
+        }
+        entries.push(entry);
+    }
+
+    let field_names = table_definition.fields.iter().map(|x| x.field_name.to_owned()).collect::<Vec<String>>();
+    let report = ImportReport {
+        rows: entry_count,
+        missing_columns: if entry_count > 0 { field_names.iter().filter(|x| !columns.contains(x)).cloned().collect() } else { vec![] },
+        extra_columns: columns.iter().filter(|x| !field_names.contains(x)).cloned().collect(),
+        version_change: None,
+    };
+
+    Ok((entries, report))
+}
+
+/// This function writes the data of one or more PackedFiles of the same table to an Assembly Kit XML file.
+/// The columns are written by name, so the PackedFiles can have different versions of the table.
+pub fn export_table_data(tables: &[&DBData], table_name: &str, xml_path: &PathBuf) -> Result<(), Error> {
+    let row_name = table_name.trim_right_matches("_tables");
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<dataroot>\n");
+
+    // Rows with the same keys get the same uuid, so we count them to give each one a different uuid.
+    let mut used_keys: HashMap<Vec<String>, usize> = HashMap::new();
+    for table in tables {
+        for entry in &table.entries {
+            let mut keys = table.table_definition.fields.iter().zip(entry[1..].iter()).filter(|x| x.0.field_is_key).map(|x| x.1.to_string()).collect::<Vec<String>>();
+
+            // If the table has no keys, the entire row is his key.
+            if keys.is_empty() { keys = entry[1..].iter().map(|x| x.to_string()).collect(); }
+            let repeats = {
+                let repeats = used_keys.entry(keys.to_vec()).or_insert(0);
+                *repeats += 1;
+                *repeats
+            };
+
+            let mut uuid_source = vec![table_name.to_owned()];
+            uuid_source.append(&mut keys);
+            if repeats > 1 { uuid_source.push(repeats.to_string()); }
+            xml.push_str(&format!("  <{} record_uuid=\"{}\">\n", row_name, DBHeader::get_deterministic_guid(&uuid_source).to_uppercase()));
+            for (field, value) in table.table_definition.fields.iter().zip(entry[1..].iter()) {
+                let value = value.to_string();
+                if value.is_empty() { xml.push_str(&format!("    <{}/>\n", field.field_name)); }
+                else { xml.push_str(&format!("    <{0}>{1}</{0}>\n", field.field_name, escape_xml(&value))); }
+            }
+            xml.push_str(&format!("  </{}>\n", row_name));
+        }
+    }
+    xml.push_str("</dataroot>\n");
+
+    match File::create(xml_path) {
+        Ok(mut file) => match file.write_all(xml.as_bytes()) {
+            Ok(_) => Ok(()),
+            Err(_) => Err(PackedFileError::WriteFile { path: xml_path.to_path_buf() }.into()),
+        },
+        Err(_) => Err(PackedFileError::CreateFile { path: xml_path.to_path_buf() }.into()),
+    }
+}
+
+/// This function imports every table from the provided "raw_data/db" folder of the Assembly Kit into the
+/// PackFile, replacing the PackedFiles that already exist. Each table is decoded with the version the game
+/// uses (from the dependency database) or, if we don't know it, with the newest version in the schema.
+///
+/// It returns the tables imported, with what happened with their columns, and the errors of the files that couldn't be imported.
+pub fn import_from_assembly_kit(
+    pack_file: &mut PackFile,
+    raw_data_path: &PathBuf,
+    schema: &Schema,
+    dependency_database: Option<&[PackedFile]>,
+) -> Result<(Vec<ImportedTable>, Vec<String>), Error> {
+
+    let mut packed_files = vec![];
+    let mut imported_tables = vec![];
+    let mut errors = vec![];
+    for xml_path in get_files_from_subdir(raw_data_path)?.iter().filter(|x| x.extension().map(|x| x == "xml").unwrap_or(false)) {
+        let file_name = xml_path.file_stem().unwrap().to_string_lossy().to_string();
+
+        // Ignore the definitions, as those are for `schemas_importer`.
+        if file_name.starts_with("TWaD_") { continue }
+
+        let table_name = format!("{}_tables", file_name);
+        let table_definition = match get_table_definition(&table_name, schema, dependency_database) {
+            Some(table_definition) => table_definition,
+            None => { errors.push(format!("{}: there is no definition for this table in the schema.", file_name)); continue }
+        };
+
+        match import_table_data(xml_path, &table_name, &table_definition) {
+            Ok((entries, report)) => {
+                let mut db = DB::new(&table_name, table_definition.version, table_definition);
+                db.data.entries = entries;
+                let data = db.save();
+                let path = vec!["db".to_owned(), table_name, file_name];
+                imported_tables.push(ImportedTable { path: path.to_vec(), report });
+                packed_files.push(PackedFile::read(data.len() as u32, path, data));
+            }
+            Err(error) => errors.push(format!("{}: {}", file_name, error)),
+        }
+    }
+
+    // Replace the PackedFiles that already exist, and add the new ones.
+    let mut new_packed_files = vec![];
+    for packed_file in packed_files {
+        match pack_file.data.packed_files.iter().position(|x| x.path == packed_file.path) {
+            Some(index) => pack_file.data.packed_files[index] = packed_file,
+            None => new_packed_files.push(packed_file),
+        }
+    }
+    pack_file.add_packedfiles(new_packed_files);
+
+    Ok((imported_tables, errors))
+}
+
+/// This function exports every table of the provided PackedFiles to Assembly Kit XML files in the provided
+/// folder, one file per table. It returns the paths of the PackedFiles we couldn't decode, and so, we couldn't export.
+pub fn export_to_assembly_kit(packed_files: &[PackedFile], schema: &Schema, folder_path: &PathBuf) -> Result<Vec<Vec<String>>, Error> {
+    DirBuilder::new().recursive(true).create(folder_path)?;
+
+    // Decode all the tables, grouped by table.
+    let mut tables: Vec<(String, Vec<DB>)> = vec![];
+    let mut error_files = vec![];
+    for packed_file in packed_files.iter().filter(|x| x.path.len() >= 3 && x.path[0] == "db") {
+        match DB::read(&packed_file.data, &packed_file.path[1], schema) {
+            Ok(db) => match tables.iter().position(|x| x.0 == packed_file.path[1]) {
+                Some(index) => tables[index].1.push(db),
+                None => tables.push((packed_file.path[1].to_owned(), vec![db])),
+            },
+            Err(_) => error_files.push(packed_file.path.to_vec()),
+        }
+    }
+
+    for &(ref table_name, ref dbs) in &tables {
+        let mut xml_path = folder_path.to_path_buf();
+        xml_path.push(format!("{}.xml", table_name.trim_right_matches("_tables")));
+        export_table_data(&dbs.iter().map(|x| &x.data).collect::<Vec<&DBData>>(), table_name, &xml_path)?;
+    }
+
+    Ok(error_files)
+}
+
+/// This function returns the definition we should use to import a table: the one of the version the game
+/// uses, if we know it, or the newest one in the schema.
+fn get_table_definition(table_name: &str, schema: &Schema, dependency_database: Option<&[PackedFile]>) -> Option<TableDefinition> {
+    if let Some(version) = dependency_database.and_then(|x| get_game_table_version(table_name, x)) {
+        if let Some(table_definition) = DB::get_schema(table_name, version, schema) {
+            return Some(table_definition);
+        }
+    }
+    DB::get_schema_versions_list(table_name, schema).and_then(|x| x.into_iter().max_by_key(|x| x.version))
+}
+
+/// This function escapes the characters that cannot be used as they are in a XML text.
+pub fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
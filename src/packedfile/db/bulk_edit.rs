@@ -0,0 +1,535 @@
+// In this file is the "Bulk Edit" stuff for DB Tables. This allows us to change all the cells of a column
+// at once, with an expression calculated for each row, and only in the rows matching a filter. Like these:
+//
+// - column "cost", expression `round(cost * 1.15)`, filter `category = "inf_melee"`.
+// - column "is_hidden", expression `true`, filter `key starts_with "wh2_dlc"`.
+// - column "onscreen_name", expression `"{key}_name"`.
+// - column "key", expression `key & "_copy"`.
+//
+// The expressions support numbers, texts, true/false, columns, parenthesis, the operators +, -, * and /, the
+// concatenation operator & (with less precedence than the rest), and the functions round(x [, decimals]),
+// floor(x), ceil(x), abs(x), min(x, y...), max(x, y...), concat(x, y...), replace(text, from, to), lower(text)
+// and upper(text). "+" depends on the type of the values: it adds numbers (including the values of number columns),
+// and concatenates if any of the sides is a text (including the values of string columns, even if they are numbers).
+// Texts are templates: "{column}" is replaced with the value of the column in the row.
+//
+// The filters are the same expressions we use in the queries and to filter the rows of the DB View.
+//
+// The result is converted to the type of the column: numbers are rounded for integer columns, and the rest
+// of the values are converted to text for string columns. If any row fails, nothing is changed.
+
+use std::fmt;
+
+use failure::Error;
+
+use packedfile::error::PackedFileError;
+use super::DBData;
+use super::DecodedData;
+use super::query::{Expression, get_column_index};
+use super::schemas::FieldType;
+
+/// `BulkEdit`: This struct holds a parsed bulk edit. It has:
+/// - column: the column to change.
+/// - expression: the expression to calculate the new value of each cell.
+/// - filter: the expression the rows must match to be changed, if any.
+#[derive(Clone, Debug)]
+pub struct BulkEdit {
+    pub column: String,
+    pub expression: ValueExpression,
+    pub filter: Option<Expression>,
+}
+
+/// `ValueExpression`: This enum holds a parsed expression that returns a value. It can be:
+/// - Number/Text/Boolean: a literal value. Texts are templates.
+/// - Column: the value of a column of the row.
+/// - Negative: the expression, negated.
+/// - Operation: an arithmetic operation between two expressions.
+/// - Function: a call to a function, with his arguments.
+#[derive(Clone, Debug)]
+pub enum ValueExpression {
+    Number(f64),
+    Text(String),
+    Boolean(bool),
+    Column(String),
+    Negative(Box<ValueExpression>),
+    Operation(Box<ValueExpression>, ArithmeticOperator, Box<ValueExpression>),
+    Function(String, Vec<ValueExpression>),
+}
+
+/// `ArithmeticOperator`: This enum holds the operators we can use in a `ValueExpression`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ArithmeticOperator {
+    Add,
+    Subtract,
+    Multiply,
+    Divide,
+    Concat,
+}
+
+/// `Value`: This enum holds the result of evaluating a `ValueExpression`.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Value {
+    Number(f64),
+    Text(String),
+    Boolean(bool),
+}
+
+/// `BulkEditReport`: This struct holds the result of a bulk edit. It has:
+/// - column: the changed column.
+/// - matched_rows: the amount of rows that matched the filter.
+/// - changes: the cells that changed, as (row, old value, new value). Rows start from 0.
+#[derive(Clone, Debug)]
+pub struct BulkEditReport {
+    pub column: String,
+    pub matched_rows: usize,
+    pub changes: Vec<(usize, String, String)>,
+}
+
+/// `Token`: This enum holds the tokens of a `ValueExpression`.
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    Number(f64),
+    Text(String),
+    Word(String),
+    Symbol(char),
+}
+
+/// This const holds the functions we support, with the minimum and maximum amount of arguments they accept.
+const FUNCTIONS: &[(&str, usize, usize)] = &[
+    ("round", 1, 2),
+    ("floor", 1, 1),
+    ("ceil", 1, 1),
+    ("abs", 1, 1),
+    ("min", 1, usize::max_value()),
+    ("max", 1, usize::max_value()),
+    ("concat", 1, usize::max_value()),
+    ("replace", 3, 3),
+    ("lower", 1, 1),
+    ("upper", 1, 1),
+];
+
+/// Implementation of `BulkEdit`.
+impl BulkEdit {
+
+    /// This function parses a bulk edit. An empty filter means no filter.
+    pub fn new(column: &str, expression: &str, filter: Option<&str>) -> Result<Self, Error> {
+        Ok(Self {
+            column: column.to_owned(),
+            expression: ValueExpression::parse(expression)?,
+            filter: match filter {
+                Some(filter) if !filter.trim().is_empty() => Some(Expression::parse(filter)?),
+                _ => None,
+            },
+        })
+    }
+
+    /// This function applies the bulk edit to the rows of a table, and returns what changed. If the new value
+    /// of any row is not valid for the column, it fails without changing anything. The table name is only used
+    /// so the columns can be written in "table.column" format, like in the queries.
+    pub fn apply(&self, table_name: &str, data: &mut DBData) -> Result<BulkEditReport, Error> {
+        let table_short_name = table_name.trim_right_matches("_tables");
+        let columns = data.table_definition.fields.iter().map(|x| format!("{}.{}", table_short_name, x.field_name)).collect::<Vec<String>>();
+        let column = get_column_index(&columns, &self.column)?;
+        let field_type = data.table_definition.fields[column].field_type.clone();
+
+        // Calculate all the new values first, so we don't leave the table half-changed if one fails.
+        let mut matched_rows = 0;
+        let mut new_values = vec![];
+        for (row, entry) in data.entries.iter().enumerate() {
+            let cells = &entry[1..];
+            if let Some(ref filter) = self.filter {
+                let row_text = cells.iter().map(|x| x.to_string()).collect::<Vec<String>>();
+                if !filter.evaluate(&columns, &row_text)? { continue }
+            }
+            matched_rows += 1;
+
+            let value = match self.expression.evaluate(&columns, cells) {
+                Ok(value) => value,
+                Err(error) => return Err(PackedFileError::InvalidRow { row, message: error.to_string() }.into()),
+            };
+            let new_value = match value.to_decoded_data(&field_type) {
+                Ok(new_value) => new_value,
+                Err(error) => return Err(PackedFileError::InvalidRow { row, message: error.to_string() }.into()),
+            };
+            if new_value.to_string() != cells[column].to_string() {
+                new_values.push((row, new_value));
+            }
+        }
+
+        let mut changes = vec![];
+        for (row, new_value) in new_values {
+            changes.push((row, data.entries[row][column + 1].to_string(), new_value.to_string()));
+            data.entries[row][column + 1] = new_value;
+        }
+
+        Ok(BulkEditReport {
+            column: data.table_definition.fields[column].field_name.to_owned(),
+            matched_rows,
+            changes,
+        })
+    }
+}
+
+/*
+--------------------------------------------------------
+                        Parsing
+--------------------------------------------------------
+*/
+
+/// Implementation of `ValueExpression`.
+impl ValueExpression {
+
+    /// This function parses a value expression.
+    pub fn parse(expression: &str) -> Result<Self, Error> {
+        let tokens = tokenize(expression)?;
+        let mut index = 0;
+        let expression = parse_concat(&tokens, &mut index)?;
+        match tokens.get(index) {
+            Some(token) => Err(PackedFileError::InvalidExpression { message: format!("Unexpected {} in the expression.", token) }.into()),
+            None => Ok(expression),
+        }
+    }
+}
+
+/// This function parses a list of expressions joined by "&".
+fn parse_concat(tokens: &[Token], index: &mut usize) -> Result<ValueExpression, Error> {
+    let mut expression = parse_sum(tokens, index)?;
+    while tokens.get(*index) == Some(&Token::Symbol('&')) {
+        *index += 1;
+        expression = ValueExpression::Operation(Box::new(expression), ArithmeticOperator::Concat, Box::new(parse_sum(tokens, index)?));
+    }
+    Ok(expression)
+}
+
+/// This function parses a list of expressions joined by "+" or "-".
+fn parse_sum(tokens: &[Token], index: &mut usize) -> Result<ValueExpression, Error> {
+    let mut expression = parse_product(tokens, index)?;
+    loop {
+        let operator = match tokens.get(*index) {
+            Some(&Token::Symbol('+')) => ArithmeticOperator::Add,
+            Some(&Token::Symbol('-')) => ArithmeticOperator::Subtract,
+            _ => break,
+        };
+        *index += 1;
+        expression = ValueExpression::Operation(Box::new(expression), operator, Box::new(parse_product(tokens, index)?));
+    }
+    Ok(expression)
+}
+
+/// This function parses a list of expressions joined by "*" or "/".
+fn parse_product(tokens: &[Token], index: &mut usize) -> Result<ValueExpression, Error> {
+    let mut expression = parse_unary(tokens, index)?;
+    loop {
+        let operator = match tokens.get(*index) {
+            Some(&Token::Symbol('*')) => ArithmeticOperator::Multiply,
+            Some(&Token::Symbol('/')) => ArithmeticOperator::Divide,
+            _ => break,
+        };
+        *index += 1;
+        expression = ValueExpression::Operation(Box::new(expression), operator, Box::new(parse_unary(tokens, index)?));
+    }
+    Ok(expression)
+}
+
+/// This function parses a negated expression, an expression between parenthesis, a function, a column or a value.
+fn parse_unary(tokens: &[Token], index: &mut usize) -> Result<ValueExpression, Error> {
+    let token = match tokens.get(*index) {
+        Some(token) => token.clone(),
+        None => return Err(PackedFileError::InvalidExpression { message: "Expected a value, but the expression ended.".to_owned() }.into()),
+    };
+    *index += 1;
+
+    match token {
+        Token::Symbol('-') => Ok(ValueExpression::Negative(Box::new(parse_unary(tokens, index)?))),
+        Token::Symbol('(') => {
+            let expression = parse_concat(tokens, index)?;
+            expect_symbol(tokens, index, ')')?;
+            Ok(expression)
+        }
+        Token::Number(number) => Ok(ValueExpression::Number(number)),
+        Token::Text(text) => Ok(ValueExpression::Text(text)),
+        Token::Word(word) => {
+            match &*word.to_lowercase() {
+                "true" => return Ok(ValueExpression::Boolean(true)),
+                "false" => return Ok(ValueExpression::Boolean(false)),
+                _ => {}
+            }
+
+            // If it's followed by a parenthesis, it's a function. Otherwise, it's a column.
+            if tokens.get(*index) != Some(&Token::Symbol('(')) { return Ok(ValueExpression::Column(word)) }
+            *index += 1;
+
+            let name = word.to_lowercase();
+            let mut arguments = vec![];
+            if tokens.get(*index) == Some(&Token::Symbol(')')) { *index += 1; }
+            else {
+                arguments.push(parse_concat(tokens, index)?);
+                while tokens.get(*index) == Some(&Token::Symbol(',')) {
+                    *index += 1;
+                    arguments.push(parse_concat(tokens, index)?);
+                }
+                expect_symbol(tokens, index, ')')?;
+            }
+
+            match FUNCTIONS.iter().find(|x| x.0 == name) {
+                Some(&(_, min, max)) if arguments.len() < min || arguments.len() > max => Err(PackedFileError::InvalidExpression { message: format!("Wrong amount of arguments for the function \"{}\".", name) }.into()),
+                Some(_) => Ok(ValueExpression::Function(name, arguments)),
+                None => Err(PackedFileError::InvalidExpression { message: format!("Unknown function \"{}\". The supported functions are: {}.", word, FUNCTIONS.iter().map(|x| x.0).collect::<Vec<&str>>().join(", ")) }.into()),
+            }
+        }
+        token => Err(PackedFileError::InvalidExpression { message: format!("Expected a value, but found {}.", token) }.into()),
+    }
+}
+
+/// This function checks that the token at `index` is the provided symbol, and advances the index.
+fn expect_symbol(tokens: &[Token], index: &mut usize, symbol: char) -> Result<(), Error> {
+    match tokens.get(*index) {
+        Some(&Token::Symbol(ref found)) if *found == symbol => { *index += 1; Ok(()) }
+        Some(token) => Err(PackedFileError::InvalidExpression { message: format!("Expected \"{}\", but found {}.", symbol, token) }.into()),
+        None => Err(PackedFileError::InvalidExpression { message: format!("Expected \"{}\", but the expression ended.", symbol) }.into()),
+    }
+}
+
+/// This function splits a value expression into tokens.
+fn tokenize(expression: &str) -> Result<Vec<Token>, Error> {
+    let mut tokens = vec![];
+    let mut chars = expression.chars().peekable();
+    while let Some(character) = chars.next() {
+        match character {
+            ' ' | '\t' | '\n' | '\r' => continue,
+            '+' | '-' | '*' | '/' | '&' | '(' | ')' | ',' => tokens.push(Token::Symbol(character)),
+
+            // Texts can be between double or single quotes, and use "\" to escape them.
+            '"' | '\'' => {
+                let mut text = String::new();
+                let mut closed = false;
+                while let Some(character_text) = chars.next() {
+                    if character_text == '\\' {
+                        if let Some(escaped) = chars.next() { text.push(escaped); }
+                    }
+                    else if character_text == character { closed = true; break; }
+                    else { text.push(character_text); }
+                }
+                if !closed { return Err(PackedFileError::InvalidExpression { message: "There is an unclosed text in the expression.".to_owned() }.into()); }
+                tokens.push(Token::Text(text));
+            }
+
+            _ if character.is_ascii_digit() || character == '.' => {
+                let mut number = character.to_string();
+                while let Some(&next) = chars.peek() {
+                    if next.is_ascii_digit() || next == '.' { number.push(next); chars.next(); }
+                    else { break; }
+                }
+                match number.parse::<f64>() {
+                    Ok(number) => tokens.push(Token::Number(number)),
+                    Err(_) => return Err(PackedFileError::InvalidExpression { message: format!("\"{}\" is not a valid number.", number) }.into()),
+                }
+            }
+
+            _ if character.is_alphabetic() || character == '_' => {
+                let mut word = character.to_string();
+                while let Some(&next) = chars.peek() {
+                    if next.is_alphanumeric() || next == '_' || next == '.' { word.push(next); chars.next(); }
+                    else { break; }
+                }
+                tokens.push(Token::Word(word));
+            }
+
+            _ => return Err(PackedFileError::InvalidExpression { message: format!("Unexpected character \"{}\" in the expression.", character) }.into()),
+        }
+    }
+    Ok(tokens)
+}
+
+/// Implementation of `Display` for `Token`, for the error messages.
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Token::Number(number) => write!(f, "the number {}", number),
+            Token::Text(ref text) => write!(f, "the text \"{}\"", text),
+            Token::Word(ref word) => write!(f, "\"{}\"", word),
+            Token::Symbol(symbol) => write!(f, "\"{}\"", symbol),
+        }
+    }
+}
+
+/*
+--------------------------------------------------------
+                        Evaluation
+--------------------------------------------------------
+*/
+
+/// Implementation of `ValueExpression`.
+impl ValueExpression {
+
+    /// This function calculates the value of the expression for a row. It requires the names of the columns
+    /// of the row (in "table.column" format) and his values (without index), in the same order.
+    pub fn evaluate(&self, columns: &[String], row: &[DecodedData]) -> Result<Value, Error> {
+        match *self {
+            ValueExpression::Number(number) => Ok(Value::Number(number)),
+            ValueExpression::Boolean(boolean) => Ok(Value::Boolean(boolean)),
+            ValueExpression::Text(ref text) => Ok(Value::Text(fill_template(text, columns, row))),
+            ValueExpression::Column(ref column) => Ok(Value::from(&row[get_column_index(columns, column)?])),
+            ValueExpression::Negative(ref expression) => Ok(Value::Number(-expression.evaluate(columns, row)?.to_number()?)),
+            ValueExpression::Operation(ref left, operator, ref right) => {
+                let left = left.evaluate(columns, row)?;
+                let right = right.evaluate(columns, row)?;
+
+                // If we are adding something that is not a number, we concatenate them. Texts with numbers are texts here,
+                // so adding to a string column doesn't turn "10" + "2" into "12".
+                let are_numbers = match (&left, &right) {
+                    (&Value::Number(_), &Value::Number(_)) => true,
+                    _ => false,
+                };
+                if operator == ArithmeticOperator::Concat || (operator == ArithmeticOperator::Add && !are_numbers) {
+                    return Ok(Value::Text(format!("{}{}", left, right)));
+                }
+
+                let left = left.to_number()?;
+                let right = right.to_number()?;
+                Ok(Value::Number(match operator {
+                    ArithmeticOperator::Subtract => left - right,
+                    ArithmeticOperator::Multiply => left * right,
+                    ArithmeticOperator::Divide => {
+                        if right == 0.0 { return Err(PackedFileError::DivisionByZero.into()); }
+                        left / right
+                    }
+                    _ => left + right,
+                }))
+            }
+            ValueExpression::Function(ref name, ref arguments) => {
+                let mut values = vec![];
+                for argument in arguments {
+                    values.push(argument.evaluate(columns, row)?);
+                }
+
+                Ok(match &**name {
+                    "round" => {
+                        let decimals = if values.len() > 1 { values[1].to_number()? } else { 0.0 };
+                        let multiplier = 10f64.powi(decimals as i32);
+                        Value::Number((values[0].to_number()? * multiplier).round() / multiplier)
+                    }
+                    "floor" => Value::Number(values[0].to_number()?.floor()),
+                    "ceil" => Value::Number(values[0].to_number()?.ceil()),
+                    "abs" => Value::Number(values[0].to_number()?.abs()),
+                    "min" | "max" => {
+                        let mut result = values[0].to_number()?;
+                        for value in &values[1..] {
+                            let value = value.to_number()?;
+                            result = if *name == "min" { result.min(value) } else { result.max(value) };
+                        }
+                        Value::Number(result)
+                    }
+                    "concat" => Value::Text(values.iter().map(|x| x.to_string()).collect::<String>()),
+                    "replace" => Value::Text(values[0].to_string().replace(&values[1].to_string(), &values[2].to_string())),
+                    "lower" => Value::Text(values[0].to_string().to_lowercase()),
+                    "upper" => Value::Text(values[0].to_string().to_uppercase()),
+                    _ => return Err(PackedFileError::UnknownFunction { name: name.to_owned() }.into()),
+                })
+            }
+        }
+    }
+}
+
+/// Implementation of `Value`.
+impl Value {
+
+    /// This function returns the value as a number, or an error if it's not a number.
+    pub fn to_number(&self) -> Result<f64, Error> {
+        match *self {
+            Value::Number(number) => Ok(number),
+            Value::Text(ref text) => text.trim().parse::<f64>().map_err(|_| PackedFileError::NotANumber { value: text.to_owned() }.into()),
+            Value::Boolean(boolean) => Err(PackedFileError::NotANumber { value: boolean.to_string() }.into()),
+        }
+    }
+
+    /// This function converts the value to the type of a column. Numbers are rounded to the nearest integer
+    /// for integer columns.
+    pub fn to_decoded_data(&self, field_type: &FieldType) -> Result<DecodedData, Error> {
+        match *field_type {
+            FieldType::Integer => {
+                let number = self.to_number()?.round();
+                if number < f64::from(i32::min_value()) || number > f64::from(i32::max_value()) {
+                    return Err(PackedFileError::NumberOutOfRange { value: number, field_type: FieldType::Integer }.into());
+                }
+                Ok(DecodedData::Integer(number as i32))
+            }
+            FieldType::LongInteger => {
+                let number = self.to_number()?.round();
+                if number < i64::min_value() as f64 || number > i64::max_value() as f64 {
+                    return Err(PackedFileError::NumberOutOfRange { value: number, field_type: FieldType::LongInteger }.into());
+                }
+                Ok(DecodedData::LongInteger(number as i64))
+            }
+            FieldType::Float => Ok(DecodedData::Float(self.to_number()? as f32)),
+            _ => DecodedData::from_text(&self.to_string(), field_type),
+        }
+    }
+}
+
+/// Implementation of `From<&DecodedData>` for `Value`.
+impl<'a> From<&'a DecodedData> for Value {
+    fn from(data: &'a DecodedData) -> Self {
+        match *data {
+            DecodedData::Boolean(data) => Value::Boolean(data),
+            DecodedData::Float(data) => Value::Number(data.to_string().parse::<f64>().unwrap_or_else(|_| f64::from(data))),
+            DecodedData::Integer(data) => Value::Number(f64::from(data)),
+            DecodedData::LongInteger(data) => Value::Number(data as f64),
+            _ => Value::Text(data.to_string()),
+        }
+    }
+}
+
+/// Implementation of `Display` for `Value`. Numbers without decimals are written as integers.
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Value::Number(number) => {
+                if number.fract() == 0.0 && number.abs() < 1e15 { write!(f, "{}", number as i64) }
+                else { write!(f, "{}", number) }
+            }
+            Value::Text(ref text) => write!(f, "{}", text),
+            Value::Boolean(boolean) => write!(f, "{}", boolean),
+        }
+    }
+}
+
+/// This function replaces the "{column}" parts of a text with the values of the columns in the row.
+/// Parts between braces that are not columns are left as they are.
+fn fill_template(text: &str, columns: &[String], row: &[DecodedData]) -> String {
+    let mut result = String::new();
+    let mut rest = text;
+    while let Some(start) = rest.find('{') {
+        result.push_str(&rest[..start]);
+        match rest[start..].find('}') {
+            Some(end) => {
+                let name = &rest[start + 1..start + end];
+                match get_column_index(columns, name) {
+                    Ok(index) if !name.is_empty() => result.push_str(&row[index].to_string()),
+                    _ => result.push_str(&rest[start..start + end + 1]),
+                }
+                rest = &rest[start + end + 1..];
+            }
+            None => {
+                result.push_str(&rest[start..]);
+                rest = "";
+            }
+        }
+    }
+    result.push_str(rest);
+    result
+}
+
+/// Implementation of `Display` for `BulkEditReport`, so we can show the result in the UI or print it in the terminal.
+impl fmt::Display for BulkEditReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "{} rows matched the filter, {} cells of the column \"{}\" changed.", self.matched_rows, self.changes.len(), self.column)?;
+        if !self.changes.is_empty() {
+            writeln!(f)?;
+            for &(row, ref old_value, ref new_value) in &self.changes {
+                writeln!(f, "  - Row {}: \"{}\" -> \"{}\".", row + 1, old_value, new_value)?;
+            }
+        }
+        Ok(())
+    }
+}
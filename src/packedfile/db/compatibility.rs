@@ -0,0 +1,131 @@
+// In this file we define the "Compatibility Report" for DB Tables. This report compares the tables
+// of a PackFile with the ones in the dependency database, so after every game patch we know which
+// tables of our mod need to be updated to the new version the game uses.
+
+use std::fmt;
+
+use packfile::packfile::PackedFile;
+use packedfile::get_game_table_version;
+use super::DB;
+use super::DBHeader;
+use super::schemas::Schema;
+
+/// `CompatibilityReport`: This struct holds the result of checking the tables of a PackFile against
+/// the game's tables. It has:
+/// - outdated_tables: tables whose version is different than the one used by the game.
+/// - undefined_tables: tables for which the loaded schema doesn't have a definition.
+/// - broken_tables: tables whose header couldn't even be read.
+#[derive(Clone, Debug)]
+pub struct CompatibilityReport {
+    pub outdated_tables: Vec<TableCompatibility>,
+    pub undefined_tables: Vec<TableCompatibility>,
+    pub broken_tables: Vec<Vec<String>>,
+}
+
+/// `TableCompatibility`: This struct holds the info of one of the tables of the report. It has:
+/// - path: the path of the table in the PackFile.
+/// - table_name: the name of the table (the folder under "db").
+/// - version: the version of the table in our PackFile.
+/// - game_version: the version of the table used by the game. `None` if the game doesn't have this table.
+#[derive(Clone, Debug)]
+pub struct TableCompatibility {
+    pub path: Vec<String>,
+    pub table_name: String,
+    pub version: u32,
+    pub game_version: Option<u32>,
+}
+
+/// Implementation of `CompatibilityReport`.
+impl CompatibilityReport {
+
+    /// This function checks every table in the provided list of PackedFiles against the dependency database
+    /// and the schema, and returns the report of the problems found.
+    pub fn new(
+        packed_files: &[PackedFile],
+        schema: &Schema,
+        dependency_database: &[PackedFile],
+    ) -> Self {
+
+        let mut outdated_tables = vec![];
+        let mut undefined_tables = vec![];
+        let mut broken_tables = vec![];
+
+        // For each table in our PackFile...
+        for packed_file in packed_files.iter().filter(|x| x.path.len() >= 3 && x.path[0] == "db") {
+
+            // Try to read his header. If we can't, the table is broken beyond any check we can do.
+            let header = match DBHeader::read(&packed_file.data, &mut 0) {
+                Ok(header) => header,
+                Err(_) => { broken_tables.push(packed_file.path.to_vec()); continue }
+            };
+
+            let table = TableCompatibility {
+                path: packed_file.path.to_vec(),
+                table_name: packed_file.path[1].to_owned(),
+                version: header.version,
+                game_version: get_game_table_version(&packed_file.path[1], dependency_database),
+            };
+
+            // If the schema doesn't have a definition for his version, we can neither decode it nor update it.
+            if DB::get_schema(&table.table_name, table.version, schema).is_none() {
+                undefined_tables.push(table.clone());
+            }
+
+            // If the game uses another version of this table, it's outdated.
+            if let Some(game_version) = table.game_version {
+                if game_version != table.version {
+                    outdated_tables.push(table);
+                }
+            }
+        }
+
+        Self {
+            outdated_tables,
+            undefined_tables,
+            broken_tables,
+        }
+    }
+
+    /// This function returns true if there are no problems in the report.
+    pub fn is_empty(&self) -> bool {
+        self.outdated_tables.is_empty() && self.undefined_tables.is_empty() && self.broken_tables.is_empty()
+    }
+}
+
+/// Implementation of `Display` for `CompatibilityReport`, so we can show it in the UI or print it in the terminal.
+impl fmt::Display for CompatibilityReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+
+        if self.is_empty() {
+            return writeln!(f, "All the tables in this PackFile use the same version as the game, and all of them have a definition in the schema.");
+        }
+
+        if !self.outdated_tables.is_empty() {
+            writeln!(f, "Tables with a different version than the game's one:")?;
+            for table in &self.outdated_tables {
+                writeln!(f, "  - {}: version {}, game uses version {}.", table.path.join("/"), table.version, table.game_version.unwrap_or(0))?;
+            }
+            writeln!(f)?;
+        }
+
+        if !self.undefined_tables.is_empty() {
+            writeln!(f, "Tables without a definition in the currently loaded schema:")?;
+            for table in &self.undefined_tables {
+                match table.game_version {
+                    Some(game_version) => writeln!(f, "  - {}: version {} (game uses version {}).", table.path.join("/"), table.version, game_version)?,
+                    None => writeln!(f, "  - {}: version {} (not in the game's files).", table.path.join("/"), table.version)?,
+                }
+            }
+            writeln!(f)?;
+        }
+
+        if !self.broken_tables.is_empty() {
+            writeln!(f, "Tables whose header couldn't be read:")?;
+            for path in &self.broken_tables {
+                writeln!(f, "  - {}", path.join("/"))?;
+            }
+        }
+
+        Ok(())
+    }
+}
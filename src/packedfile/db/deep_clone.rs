@@ -0,0 +1,400 @@
+// In this file is the "Clone with Dependencies" stuff for DB Tables. Creating something new (like an unit)
+// usually means copying a row, plus all the rows of other tables pointing to it (main_units, variants,
+// abilities,...) and his Loc entries, and giving all of them new keys. Here we do that automatically:
+// - We start from a row, in the PackFile or in the dependency database.
+// - We follow the references (`field_is_reference`) in reverse with the `ReferenceIndex`, getting every row
+//   that points to it, and every row that points to those rows, and so on.
+// - Every key of these rows that is not a reference gets a new value from a pattern, like "{key}_copy".
+//   References to cloned rows are changed to point to the clones. References to anything else are kept.
+// - The Loc entries of the cloned rows ("<table>_<column>_<key>") are cloned with the new keys. They are found
+//   in the same way as in the key rename (`get_loc_key_match`).
+//
+// Like the key rename, this is done in two steps, so the user can check what we are going to add first.
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt;
+
+use failure::Error;
+
+use packfile::packfile::{PackFile, PackedFile};
+use packedfile::error::PackedFileError;
+use packedfile::loc::{Loc, LocEntry};
+use super::DB;
+use super::DecodedData;
+use super::get_entry;
+use super::inbound_references::ReferenceIndex;
+use super::key_rename::get_loc_key_match;
+use super::schemas::{Schema, TableDefinition};
+
+/// `DeepClone`: This struct holds everything we need to add to the PackFile to clone a row with his
+/// dependencies. It has:
+/// - table: the table of the cloned row, with "_tables" at the end.
+/// - key: the key of the cloned row.
+/// - key_pattern: the pattern used to get the new keys. "{key}" is replaced with the old key.
+/// - rows: the new rows.
+/// - loc_entries: the new Loc entries.
+/// - skipped_rows: the dependent rows we cannot clone, and why.
+/// - undecodeable_tables: tables of the PackFile we couldn't decode, so they may have dependent rows we cannot clone.
+#[derive(Clone, Debug)]
+pub struct DeepClone {
+    pub table: String,
+    pub key: String,
+    pub key_pattern: String,
+    pub rows: Vec<ClonedRow>,
+    pub loc_entries: Vec<ClonedLocEntry>,
+    pub skipped_rows: Vec<String>,
+    pub undecodeable_tables: Vec<Vec<String>>,
+}
+
+/// `ClonedRow`: This struct holds one of the new rows. It has:
+/// - table: the table of the row, with "_tables" at the end.
+/// - source_path: the path of the table of the original row, in the PackFile or in the dependency database.
+/// - source_row: the position of the original row in his table, starting from 0.
+/// - table_definition: the definition of the table of the original row.
+/// - entry: the data of the new row, with the index of the original row.
+/// - old_keys: the keys of the original row, as text.
+/// - new_keys: the keys of the new row, as text.
+#[derive(Clone, Debug)]
+pub struct ClonedRow {
+    pub table: String,
+    pub source_path: Vec<String>,
+    pub source_row: usize,
+    pub table_definition: TableDefinition,
+    pub entry: Vec<DecodedData>,
+    pub old_keys: String,
+    pub new_keys: String,
+}
+
+/// `ClonedLocEntry`: This struct holds one of the new Loc entries. It has:
+/// - source_path: the path of the Loc of the original entry.
+/// - old_key: the key of the original entry.
+/// - entry: the new entry.
+/// - is_heuristic: if the entry is a guess, because the column in his key is not one we know.
+#[derive(Clone, Debug)]
+pub struct ClonedLocEntry {
+    pub source_path: Vec<String>,
+    pub old_key: String,
+    pub entry: LocEntry,
+    pub is_heuristic: bool,
+}
+
+/// Implementation of `DeepClone`.
+impl DeepClone {
+
+    /// This function gets all the rows and Loc entries we need to add to clone the row with the provided key
+    /// and all the rows depending on it. The row is searched first in the PackedFiles and then in the dependency
+    /// database. The table can be written with or without the "_tables" at the end.
+    pub fn new(
+        packed_files: &[PackedFile],
+        dependency_database: &[PackedFile],
+        schema: &Schema,
+        table: &str,
+        column: &str,
+        key: &str,
+        key_pattern: &str,
+    ) -> Result<Self, Error> {
+
+        if !key_pattern.contains("{key}") || key_pattern == "{key}" {
+            return Err(PackedFileError::InvalidKeyPattern.into());
+        }
+
+        let table_name = if table.ends_with("_tables") { table.to_owned() } else { format!("{}_tables", table) };
+
+        // Decode and index all the tables. The ones of the PackedFiles go first, so they take priority over the vanilla ones.
+        let index = ReferenceIndex::new(packed_files, dependency_database, schema);
+        let tables = index.get_tables();
+
+        // Find the row we want to clone.
+        let root = tables.iter().enumerate()
+            .filter(|&(_, table)| table.path[1] == table_name)
+            .filter_map(|(table_index, table)| {
+                let column_index = table.db.data.table_definition.fields.iter().position(|x| x.field_name == column)?;
+                table.db.data.entries.iter().position(|x| x[column_index + 1].to_string() == key).map(|row| (table_index, row))
+            })
+            .next();
+        let root = match root {
+            Some(root) => root,
+            None => return Err(PackedFileError::KeyNotFound { table: table_name.to_owned(), column: column.to_owned(), key: key.to_owned() }.into()),
+        };
+
+        // Follow the references in reverse, getting all the rows that depend on the row, directly or not.
+        let mut dependent_rows: Vec<(usize, usize)> = vec![];
+        let mut pending_rows = vec![root];
+        while let Some((table_index, row)) = pending_rows.pop() {
+            if dependent_rows.contains(&(table_index, row)) { continue }
+            dependent_rows.push((table_index, row));
+            pending_rows.extend(index.get_rows_referencing_row(table_index, row));
+        }
+
+        // Keep the order of the tables, so the rows of the PackedFiles are the ones we keep if a row is in both places.
+        dependent_rows.sort();
+
+        // Get the new values of all the keys that are not references, by table, column and old value.
+        let mut new_keys: BTreeMap<(String, String, String), String> = BTreeMap::new();
+        for &(table_index, row) in &dependent_rows {
+            let path = &tables[table_index].path;
+            let db = &tables[table_index].db;
+            for (field_index, field) in db.data.table_definition.fields.iter().enumerate() {
+                if field.field_is_key && field.field_is_reference.is_none() {
+                    let old_key = db.data.entries[row][field_index + 1].to_string();
+                    let new_key = key_pattern.replace("{key}", &old_key);
+                    new_keys.insert((path[1].to_owned(), field.field_name.to_owned(), old_key), new_key);
+                }
+            }
+        }
+
+        // Build the new rows.
+        let mut rows: Vec<ClonedRow> = vec![];
+        let mut skipped_rows = vec![];
+        let mut existing_keys: HashMap<String, HashSet<String>> = HashMap::new();
+        'rows: for &(table_index, row) in &dependent_rows {
+            let path = &tables[table_index].path;
+            let db = &tables[table_index].db;
+            let table_definition = &db.data.table_definition;
+            let old_entry = &db.data.entries[row];
+            let row_name = format!("{}, row {}", path.join("/"), row + 1);
+
+            let mut entry = vec![old_entry[0].clone()];
+            for (field_index, field) in table_definition.fields.iter().enumerate() {
+                let value = old_entry[field_index + 1].to_string();
+                let new_value = match field.field_is_reference {
+                    Some((ref referenced_table, ref referenced_column)) => new_keys.get(&(format!("{}_tables", referenced_table), referenced_column.to_owned(), value)),
+                    None if field.field_is_key => new_keys.get(&(path[1].to_owned(), field.field_name.to_owned(), value)),
+                    None => None,
+                };
+
+                match new_value {
+                    Some(new_value) => match DecodedData::from_text(new_value, &field.field_type) {
+                        Ok(data) => entry.push(data),
+                        Err(_) => {
+                            skipped_rows.push(format!("{}: \"{}\" is not a valid value for the column \"{}\".", row_name, new_value, field.field_name));
+                            continue 'rows;
+                        }
+                    },
+                    None => entry.push(old_entry[field_index + 1].clone()),
+                }
+            }
+
+            let key_indexes = table_definition.fields.iter().enumerate().filter(|x| x.1.field_is_key).map(|x| x.0).collect::<Vec<usize>>();
+            let old_keys = key_indexes.iter().map(|x| old_entry[x + 1].to_string()).collect::<Vec<String>>().join(", ");
+            let new_keys = key_indexes.iter().map(|x| entry[x + 1].to_string()).collect::<Vec<String>>().join(", ");
+
+            if entry[1..].iter().zip(old_entry[1..].iter()).all(|(x, y)| x.to_string() == y.to_string()) {
+                skipped_rows.push(format!("{}: none of its values changes, so it would be a copy of the original row.", row_name));
+                continue;
+            }
+
+            if !key_indexes.is_empty() {
+                if old_keys == new_keys {
+                    skipped_rows.push(format!("{}: its keys ({}) don't change, so the new row would have the same keys as the original one.", row_name, old_keys));
+                    continue;
+                }
+
+                // Rows that are both in the PackFile and in the dependency database are cloned only once.
+                if rows.iter().any(|x| x.table == path[1] && x.new_keys == new_keys) { continue; }
+
+                // Get the keys of every row of this table, in the PackFile and in the dependency database.
+                let table_keys = existing_keys.entry(path[1].to_owned()).or_insert_with(|| {
+                    tables.iter().filter(|x| x.path[1] == path[1]).map(|x| &x.db).flat_map(|db| {
+                        let key_indexes = db.data.table_definition.fields.iter().enumerate().filter(|x| x.1.field_is_key).map(|x| x.0).collect::<Vec<usize>>();
+                        db.data.entries.iter().map(|entry| key_indexes.iter().map(|x| entry[x + 1].to_string()).collect::<Vec<String>>().join(", ")).collect::<Vec<String>>()
+                    }).collect()
+                });
+                if table_keys.contains(&new_keys) {
+                    skipped_rows.push(format!("{}: there is already a row with the keys ({}).", row_name, new_keys));
+                    continue;
+                }
+            }
+
+            rows.push(ClonedRow {
+                table: path[1].to_owned(),
+                source_path: path.to_vec(),
+                source_row: row,
+                table_definition: table_definition.clone(),
+                entry,
+                old_keys,
+                new_keys,
+            });
+        }
+
+        // Clone the Loc entries of the cloned rows.
+        let mut loc_entries: Vec<ClonedLocEntry> = vec![];
+        let locs = packed_files.iter().chain(dependency_database.iter())
+            .filter(|x| x.path.last().map_or(false, |x| x.ends_with(".loc")))
+            .filter_map(|x| Loc::read(&x.data).ok().map(|loc| (x.path.to_vec(), loc)))
+            .collect::<Vec<(Vec<String>, Loc)>>();
+        let loc_keys = locs.iter().flat_map(|x| x.1.data.entries.iter().map(|x| x.key.to_owned())).collect::<HashSet<String>>();
+
+        // Get all the values of the columns of the renamed keys, as they are needed to know what Loc keys are from other rows.
+        let mut column_keys: HashMap<(String, String), HashSet<String>> = HashMap::new();
+        for &(ref table, ref column, _) in new_keys.keys() {
+            if rows.iter().any(|x| x.table == *table) {
+                column_keys.entry((table.to_owned(), column.to_owned())).or_insert_with(|| {
+                    tables.iter().filter(|x| x.path[1] == *table).map(|x| &x.db).flat_map(|db| {
+                        match db.data.table_definition.fields.iter().position(|x| x.field_name == *column) {
+                            Some(index) => db.data.entries.iter().map(|entry| entry[index + 1].to_string()).collect::<Vec<String>>(),
+                            None => vec![],
+                        }
+                    }).collect()
+                });
+            }
+        }
+
+        for &(ref path, ref loc) in &locs {
+            for entry in &loc.data.entries {
+                for (&(ref table, ref column, ref old_key), new_key) in &new_keys {
+                    let table_keys = match column_keys.get(&(table.to_owned(), column.to_owned())) {
+                        Some(table_keys) => table_keys,
+                        None => continue,
+                    };
+
+                    if let Some(is_heuristic) = get_loc_key_match(&entry.key, table.trim_right_matches("_tables"), old_key, table_keys) {
+                        let key = format!("{}{}", &entry.key[..entry.key.len() - old_key.len()], new_key);
+                        if !loc_keys.contains(&key) && !loc_entries.iter().any(|x| x.entry.key == key) {
+                            loc_entries.push(ClonedLocEntry {
+                                source_path: path.to_vec(),
+                                old_key: entry.key.to_owned(),
+                                entry: LocEntry::new(key, entry.text.to_owned(), entry.tooltip),
+                                is_heuristic,
+                            });
+                        }
+                    }
+                }
+            }
+        }
+
+        Ok(Self {
+            table: table_name,
+            key: key.to_owned(),
+            key_pattern: key_pattern.to_owned(),
+            rows,
+            loc_entries,
+            skipped_rows,
+            undecodeable_tables: index.undecodeable_tables.to_vec(),
+        })
+    }
+
+    /// This function returns true if there is nothing to add.
+    pub fn is_empty(&self) -> bool {
+        self.rows.is_empty() && self.loc_entries.is_empty()
+    }
+
+    /// This function adds the new rows and Loc entries to the PackFile, and returns the paths of the PackedFiles
+    /// it had to create. The rows are added to the first PackedFile of their table in the PackFile. If there is
+    /// none, we create a new one at "db/<table>/<packed_file_name>". The Loc entries go to "text/db/<packed_file_name>.loc".
+    pub fn apply(&self, pack_file: &mut PackFile, schema: &Schema, packed_file_name: &str) -> Result<Vec<Vec<String>>, Error> {
+        let mut changed_packed_files = vec![];
+        let mut new_packed_files = vec![];
+
+        // Get the tables we have to change, in order and without duplicates.
+        let mut tables: Vec<&str> = vec![];
+        for row in &self.rows {
+            if !tables.contains(&&*row.table) { tables.push(&row.table); }
+        }
+
+        for table in tables {
+            let rows = self.rows.iter().filter(|x| x.table == table).collect::<Vec<&ClonedRow>>();
+            match pack_file.data.packed_files.iter().position(|x| x.path.len() >= 3 && x.path[0] == "db" && x.path[1] == table) {
+                Some(index) => {
+                    let mut db = DB::read(&pack_file.data.packed_files[index].data, table, schema)?;
+                    for row in rows {
+                        let entry = get_entry(&row.table_definition, &row.entry, &db.data.table_definition);
+                        db.data.entries.push(entry);
+                    }
+                    update_indexes(&mut db);
+                    changed_packed_files.push((index, db.save()));
+                }
+                None => {
+                    let table_definition = rows[0].table_definition.clone();
+                    let mut db = DB::new(table, table_definition.version, table_definition.clone());
+                    for row in rows {
+                        let entry = get_entry(&row.table_definition, &row.entry, &table_definition);
+                        db.data.entries.push(entry);
+                    }
+                    update_indexes(&mut db);
+                    let data = db.save();
+                    new_packed_files.push(PackedFile::read(data.len() as u32, vec!["db".to_owned(), table.to_owned(), packed_file_name.to_owned()], data));
+                }
+            }
+        }
+
+        if !self.loc_entries.is_empty() {
+            let path = vec!["text".to_owned(), "db".to_owned(), format!("{}.loc", packed_file_name)];
+            match pack_file.data.packed_files.iter().position(|x| x.path == path) {
+                Some(index) => {
+                    let mut loc = Loc::read(&pack_file.data.packed_files[index].data)?;
+                    loc.data.entries.extend(self.loc_entries.iter().map(|x| x.entry.clone()));
+                    changed_packed_files.push((index, loc.save()));
+                }
+                None => {
+                    let mut loc = Loc::new();
+                    loc.data.entries.extend(self.loc_entries.iter().map(|x| x.entry.clone()));
+                    let data = loc.save();
+                    new_packed_files.push(PackedFile::read(data.len() as u32, path, data));
+                }
+            }
+        }
+
+        // Only touch the PackFile once everything has been encoded.
+        for (index, data) in changed_packed_files {
+            pack_file.data.packed_files[index].size = data.len() as u32;
+            pack_file.data.packed_files[index].data = data;
+        }
+        let new_paths = new_packed_files.iter().map(|x| x.path.to_vec()).collect::<Vec<Vec<String>>>();
+        pack_file.add_packedfiles(new_packed_files);
+
+        Ok(new_paths)
+    }
+}
+
+/// Implementation of `Display` for `DeepClone`, so we can show the preview in the UI or print it in the terminal.
+impl fmt::Display for DeepClone {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+
+        if self.is_empty() {
+            writeln!(f, "There is nothing to clone for \"{}\" in \"{}\".", self.key, self.table)?;
+        }
+
+        if !self.rows.is_empty() {
+            writeln!(f, "Rows to add:")?;
+            for row in &self.rows {
+                writeln!(f, "  - {}: ({}) -> ({}), from {}, row {}.", row.table, row.old_keys, row.new_keys, row.source_path.join("/"), row.source_row + 1)?;
+            }
+            writeln!(f)?;
+        }
+
+        if !self.loc_entries.is_empty() {
+            writeln!(f, "Loc entries to add:")?;
+            for loc_entry in &self.loc_entries {
+                let heuristic = if loc_entry.is_heuristic { " (heuristic, unknown column)" } else { "" };
+                writeln!(f, "  - \"{}\" -> \"{}\", from {}{}.", loc_entry.old_key, loc_entry.entry.key, loc_entry.source_path.join("/"), heuristic)?;
+            }
+            writeln!(f)?;
+        }
+
+        if !self.skipped_rows.is_empty() {
+            writeln!(f, "Dependent rows that will not be cloned:")?;
+            for row in &self.skipped_rows {
+                writeln!(f, "  - {}", row)?;
+            }
+            writeln!(f)?;
+        }
+
+        if !self.undecodeable_tables.is_empty() {
+            writeln!(f, "Tables of the PackFile that couldn't be decoded, so any row in them depending on the cloned rows will not be cloned:")?;
+            for path in &self.undecodeable_tables {
+                writeln!(f, "  - {}", path.join("/"))?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// This function regenerates the indexes of the rows of a table, after adding the new rows to it.
+fn update_indexes(db: &mut DB) {
+    let count = db.data.entries.len().to_string().len() + 1;
+    for (index, entry) in db.data.entries.iter_mut().enumerate() {
+        entry[0] = DecodedData::Index(format!("{:0count$}", index + 1, count = count));
+    }
+}
@@ -0,0 +1,189 @@
+// In this file is the cache of the Dependency Database. Every time we open a DB Table, the combos of his reference
+// columns need all the values of the referenced columns, and getting them means decoding again the referenced tables
+// of the Dependency Database, which for tables with a lot of reference columns takes ages.
+//
+// So we decode the Dependency Database once, keep only the values of every column referenced in the schema, and save
+// them to disk ("cache/<game>_dependency_cache.json"). The cache is rebuilt when the Dependency PackFile changes (we
+// check his modification time) or when the schema changes (we check a hash of it).
+//
+// The hash of the schema is only calculated when the cache is loaded from disk or built, as that's slow for big schemas.
+// Once loaded, the UI drops the cache every time it changes the schema, so opening a table only checks the timestamp.
+//
+// Note that the cache only keeps the values of the referenced columns, not the full decoded tables, as those would be
+// as big as the Dependency PackFile itself. Instead, the Dependency Database is only loaded the first time something
+// needs the full tables (the references search, the deep clone, the patches,...) or when the cache has to be rebuilt,
+// so opening and editing tables with a valid cache doesn't keep the Dependency PackFile in memory.
+
+extern crate serde_json;
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs::{DirBuilder, File};
+use std::io::{BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+use failure::Error;
+
+use packfile::packfile::PackedFile;
+use super::DB;
+use super::DBHeader;
+use super::schemas::Schema;
+
+/// `DependencyCache`: This struct holds the decoded data we need from the Dependency Database. It has:
+/// - game: the game of the Dependency Database.
+/// - pack_file_timestamp: the modification time of the Dependency PackFile when the cache was built.
+/// - schema_version: the hash of the schema used to build the cache.
+/// - table_versions: the version of each table in the Dependency Database.
+/// - reference_data: the values of every referenced column, as table -> column -> values, in the order
+///   they are in the tables, without duplicates.
+/// - undecodeable_tables: tables we couldn't decode, so their values are not in the cache.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct DependencyCache {
+    pub game: String,
+    pub pack_file_timestamp: u64,
+    pub schema_version: u64,
+    pub table_versions: BTreeMap<String, u32>,
+    pub reference_data: BTreeMap<String, BTreeMap<String, Vec<String>>>,
+    pub undecodeable_tables: Vec<Vec<String>>,
+}
+
+/// Implementation of `DependencyCache`.
+impl DependencyCache {
+
+    /// This function builds the cache from the tables of the Dependency Database.
+    pub fn new(
+        game: &str,
+        pack_file_timestamp: u64,
+        dependency_database: &[PackedFile],
+        schema: &Schema,
+    ) -> Self {
+
+        // Get every column referenced in the schema, by table.
+        let mut references: BTreeMap<String, BTreeSet<String>> = BTreeMap::new();
+        for table_definitions in &schema.tables_definitions {
+            for table_definition in &table_definitions.versions {
+                for &(ref table, ref column) in table_definition.fields.iter().filter_map(|x| x.field_is_reference.as_ref()) {
+                    references.entry(format!("{}_tables", table)).or_insert_with(BTreeSet::new).insert(column.to_owned());
+                }
+            }
+        }
+
+        let mut table_versions = BTreeMap::new();
+        let mut reference_data: BTreeMap<String, BTreeMap<String, Vec<String>>> = BTreeMap::new();
+        let mut undecodeable_tables = vec![];
+        for packed_file in dependency_database.iter().filter(|x| x.path.len() >= 3 && x.path[0] == "db") {
+            let table = &packed_file.path[1];
+            if let Ok(header) = DBHeader::read(&packed_file.data, &mut 0) {
+                table_versions.insert(table.to_owned(), header.version);
+            }
+
+            let columns = match references.get(table) {
+                Some(columns) => columns,
+                None => continue,
+            };
+
+            match DB::read(&packed_file.data, table, schema) {
+                Ok(db) => {
+                    let table_data = reference_data.entry(table.to_owned()).or_insert_with(BTreeMap::new);
+                    for (index, field) in db.data.table_definition.fields.iter().enumerate().filter(|x| columns.contains(&x.1.field_name)) {
+                        let values = table_data.entry(field.field_name.to_owned()).or_insert_with(Vec::new);
+                        let mut known_values = values.iter().cloned().collect::<BTreeSet<String>>();
+                        for entry in &db.data.entries {
+                            let value = entry[index + 1].to_string();
+                            if known_values.insert(value.to_owned()) { values.push(value); }
+                        }
+                    }
+                }
+                Err(_) => undecodeable_tables.push(packed_file.path.to_vec()),
+            }
+        }
+
+        Self {
+            game: game.to_owned(),
+            pack_file_timestamp,
+            schema_version: get_schema_version(schema),
+            table_versions,
+            reference_data,
+            undecodeable_tables,
+        }
+    }
+
+    /// This function loads the cache of a game from disk, if it's still valid for the current Dependency PackFile and
+    /// schema. It returns None if there is no Dependency PackFile, or if the cache has to be built again.
+    pub fn load_if_valid(
+        rpfm_path: &Path,
+        game: &str,
+        dependency_pack_file_path: &Path,
+        schema: &Schema,
+    ) -> Option<Self> {
+        let pack_file_timestamp = get_timestamp(dependency_pack_file_path)?;
+        let cache = Self::load(&get_cache_path(rpfm_path, game)).ok()?;
+        if cache.is_valid(game, pack_file_timestamp, get_schema_version(schema)) { Some(cache) } else { None }
+    }
+
+    /// This function builds the cache of a game from the Dependency Database and saves it to disk. Errors saving the
+    /// cache are ignored, as the cache is still valid in memory. It returns None if there is no Dependency PackFile.
+    pub fn build(
+        rpfm_path: &Path,
+        game: &str,
+        dependency_pack_file_path: &Path,
+        dependency_database: &[PackedFile],
+        schema: &Schema,
+    ) -> Option<Self> {
+        let pack_file_timestamp = get_timestamp(dependency_pack_file_path)?;
+        let cache = Self::new(game, pack_file_timestamp, dependency_database, schema);
+        let _ = cache.save(&get_cache_path(rpfm_path, game));
+        Some(cache)
+    }
+
+    /// This function checks if the cache was built for the provided game, Dependency PackFile timestamp and schema version.
+    pub fn is_valid(&self, game: &str, pack_file_timestamp: u64, schema_version: u64) -> bool {
+        self.game == game && self.pack_file_timestamp == pack_file_timestamp && self.schema_version == schema_version
+    }
+
+    /// This function checks if the cache is still valid for the Dependency PackFile in the provided path. It doesn't
+    /// check the schema, so the cache must be dropped every time the schema changes.
+    pub fn is_up_to_date(&self, game: &str, dependency_pack_file_path: &Path) -> bool {
+        match get_timestamp(dependency_pack_file_path) {
+            Some(pack_file_timestamp) => self.game == game && self.pack_file_timestamp == pack_file_timestamp,
+            None => false,
+        }
+    }
+
+    /// This function loads a cache from a file.
+    pub fn load(path: &PathBuf) -> Result<Self, Error> {
+        let file = BufReader::new(File::open(path)?);
+        Ok(serde_json::from_reader(file)?)
+    }
+
+    /// This function saves the cache to a file, creating his folder if it doesn't exist.
+    pub fn save(&self, path: &PathBuf) -> Result<(), Error> {
+        if let Some(folder) = path.parent() { DirBuilder::new().recursive(true).create(folder)?; }
+        let mut file = File::create(path)?;
+        file.write_all(serde_json::to_string(self)?.as_bytes())?;
+        Ok(())
+    }
+
+    /// This function returns the values of a column in the Dependency Database, if it's a referenced column.
+    /// The table is the complete name, with "_tables" at the end.
+    pub fn get_values(&self, table: &str, column: &str) -> Option<&[String]> {
+        self.reference_data.get(table).and_then(|x| x.get(column)).map(|x| &x[..])
+    }
+}
+
+/// This function returns the path of the cache file of a game.
+pub fn get_cache_path(rpfm_path: &Path, game: &str) -> PathBuf {
+    rpfm_path.join(PathBuf::from(format!("cache/{}_dependency_cache.json", game)))
+}
+
+/// This function returns the modification time of a file, in seconds, or None if we can't get it.
+fn get_timestamp(path: &Path) -> Option<u64> {
+    path.metadata().and_then(|x| x.modified()).ok().and_then(|x| x.duration_since(UNIX_EPOCH).ok()).map(|x| x.as_secs())
+}
+
+/// This function returns the "version" of a schema, used to know if the cache was built with the current one. It's the
+/// FNV-1a hash of the schema, so any local change to the schema (like decoding a new table) invalidates the cache.
+fn get_schema_version(schema: &Schema) -> u64 {
+    let schema = serde_json::to_vec(schema).unwrap_or_default();
+    schema.iter().fold(0xcbf2_9ce4_8422_2325, |hash, byte| (hash ^ u64::from(*byte)).wrapping_mul(0x100_0000_01b3))
+}
@@ -0,0 +1,332 @@
+// In this file is the stuff to merge and split the fragments of DB Tables. The game loads every file under
+// "db/<table>/", so a table can be split in as many files (fragments) as we want. If two fragments have rows
+// with the same keys, the game uses the row of the fragment whose name goes first in alphabetical order.
+//
+// - Merging puts all the fragments of a table in one file. Fragments with a different version are updated to
+//   the newest one (or the one requested), matching the columns by name. Rows with duplicated keys are reported,
+//   and only the one the game uses is kept.
+// - Splitting moves the rows matching a filter, or with a key starting with a prefix, to new fragments.
+//
+// The merged table keeps the header of the first fragment with the final version. Every new fragment of a split
+// gets a new GUID (generated from his path, if deterministic GUIDs are enabled), so we don't end up with different
+// files with the same GUID.
+
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+
+use failure::Error;
+
+use packfile::packfile::{PackFile, PackedFile};
+use packedfile::error::PackedFileError;
+use super::DB;
+use super::DBHeader;
+use super::DecodedData;
+use super::get_entry;
+use super::query::Expression;
+use super::schemas::Schema;
+
+/// `TableMerge`: This struct holds the result of merging the fragments of a table. It has:
+/// - table: the name of the table, with "_tables" at the end.
+/// - fragments: the paths of the merged fragments, with their versions, in the order the game loads them.
+/// - path: the path of the merged table.
+/// - duplicated_keys: the rows discarded because a previous fragment has a row with the same keys, as (keys,
+///   path of the fragment with the row we keep, path of the fragment with the discarded row).
+/// - merged: the merged table.
+#[derive(Clone)]
+pub struct TableMerge {
+    pub table: String,
+    pub fragments: Vec<(Vec<String>, u32)>,
+    pub path: Vec<String>,
+    pub duplicated_keys: Vec<(String, Vec<String>, Vec<String>)>,
+    pub merged: DB,
+}
+
+/// This enum represents the ways we can choose the rows to move to a new fragment when splitting a table.
+/// - Filter: the rows matching an expression, like the ones used to filter the rows of the DB View.
+/// - KeyPrefix: the rows whose first key column starts with the provided text.
+#[derive(Clone, Debug)]
+pub enum SplitRule {
+    Filter(Expression),
+    KeyPrefix(String),
+}
+
+/// `TableSplit`: This struct holds the result of splitting a table. It has:
+/// - path: the path of the split table.
+/// - parts: the new fragments, with the rows moved to them. Rules without rows don't create a fragment.
+/// - remaining: the table with the rows that didn't match any rule. If it's empty, the table is deleted.
+#[derive(Clone)]
+pub struct TableSplit {
+    pub path: Vec<String>,
+    pub parts: Vec<(Vec<String>, DB)>,
+    pub remaining: DB,
+}
+
+/// Implementation of `TableMerge`.
+impl TableMerge {
+
+    /// This function merges all the fragments of a table in the provided PackedFiles. If `version` is not provided,
+    /// the newest version of the fragments is used. If `name` is not provided, the merged table takes the name of
+    /// the first fragment, so it keeps the same priority. It fails if the table has less than two fragments, or
+    /// if any of them cannot be decoded.
+    pub fn new(
+        packed_files: &[PackedFile],
+        schema: &Schema,
+        table: &str,
+        version: Option<u32>,
+        name: Option<&str>,
+    ) -> Result<Self, Error> {
+        let mut fragments = packed_files.iter().filter(|x| x.path.len() == 3 && x.path[0] == "db" && x.path[1] == table).collect::<Vec<&PackedFile>>();
+        if fragments.len() < 2 { return Err(PackedFileError::NothingToMerge { table: table.to_owned() }.into()) }
+        fragments.sort_by(|x, y| x.path[2].cmp(&y.path[2]));
+
+        let mut tables = vec![];
+        for packed_file in &fragments {
+            match DB::read(&packed_file.data, table, schema) {
+                Ok(db) => tables.push((packed_file.path.to_vec(), db)),
+                Err(_) => return Err(PackedFileError::UnmergeableTable { path: packed_file.path.to_vec() }.into()),
+            }
+        }
+
+        let version = match version {
+            Some(version) => version,
+            None => tables.iter().map(|x| x.1.header.version).max().unwrap_or(0),
+        };
+        let table_definition = match DB::get_schema(table, version, schema) {
+            Some(table_definition) => table_definition,
+            None => return Err(PackedFileError::DefinitionNotInSchema { table: table.to_owned(), version }.into()),
+        };
+
+        // Keep the header of the first fragment with the final version, so we don't change the GUID without reason.
+        let header = match tables.iter().find(|x| x.1.header.version == version) {
+            Some(&(_, ref db)) => db.header.clone(),
+            None => {
+                let mut header = tables[0].1.header.clone();
+                header.version = version;
+                header.version_marker = true;
+                header
+            }
+        };
+
+        // Without keys, every row is different.
+        let key_columns = table_definition.fields.iter().enumerate().filter(|x| x.1.field_is_key).map(|x| x.0).collect::<Vec<usize>>();
+        let mut row_keys: HashMap<Vec<String>, Vec<String>> = HashMap::new();
+        let mut duplicated_keys = vec![];
+        let mut entries = vec![];
+        for &(ref path, ref db) in &tables {
+            for entry in &db.data.entries {
+                let entry = if db.header.version == version { entry[1..].to_vec() } else { get_entry(&db.data.table_definition, entry, &table_definition) };
+                if !key_columns.is_empty() {
+                    let keys = key_columns.iter().map(|x| entry[*x].to_string()).collect::<Vec<String>>();
+                    let existing_path = row_keys.get(&keys).cloned();
+                    match existing_path {
+                        Some(existing_path) => {
+                            let key_text = key_columns.iter().zip(keys.iter()).map(|(column, key)| format!("{}: {}", table_definition.fields[*column].field_name, key)).collect::<Vec<String>>().join(", ");
+                            duplicated_keys.push((key_text, existing_path, path.to_vec()));
+                            continue
+                        }
+                        None => { row_keys.insert(keys, path.to_vec()); }
+                    }
+                }
+                entries.push(entry);
+            }
+        }
+
+        let count = entries.len().to_string().len() + 1;
+        let mut merged = DB::new(table, version, table_definition);
+        merged.header = header;
+        merged.data.entries = entries.into_iter().enumerate().map(|(index, mut entry)| {
+            entry.insert(0, DecodedData::Index(format!("{:0count$}", index + 1, count = count)));
+            entry
+        }).collect();
+
+        Ok(Self {
+            table: table.to_owned(),
+            path: vec!["db".to_owned(), table.to_owned(), name.map_or_else(|| tables[0].0[2].to_owned(), |x| x.to_owned())],
+            fragments: tables.iter().map(|x| (x.0.to_vec(), x.1.header.version)).collect(),
+            duplicated_keys,
+            merged,
+        })
+    }
+
+    /// This function replaces the fragments of the table in the PackFile with the merged table.
+    pub fn apply(&self, pack_file: &mut PackFile) -> Result<(), Error> {
+        let mut indexes = vec![];
+        for &(ref path, _) in &self.fragments {
+            match pack_file.data.packed_files.iter().position(|x| x.path == *path) {
+                Some(index) => indexes.push(index),
+                None => return Err(PackedFileError::PackedFileNoLongerExists { path: path.to_vec() }.into()),
+            }
+        }
+
+        // If the name of the merged table is not one of the fragments, it cannot exist in the PackFile.
+        if !self.fragments.iter().any(|x| x.0 == self.path) && pack_file.data.packed_files.iter().any(|x| x.path == self.path) {
+            return Err(PackedFileError::PackedFileAlreadyExists { path: self.path.to_vec() }.into());
+        }
+
+        // Remove them from the last to the first, so the indexes are still valid.
+        indexes.sort();
+        for index in indexes.iter().rev() {
+            pack_file.remove_packedfile(*index);
+        }
+
+        let data = self.merged.save();
+        pack_file.add_packedfiles(vec![PackedFile::read(data.len() as u32, self.path.to_vec(), data)]);
+        Ok(())
+    }
+
+    /// This function returns the versions of the fragments that are not the final version of the table.
+    pub fn get_version_mismatches(&self) -> Vec<&(Vec<String>, u32)> {
+        self.fragments.iter().filter(|x| x.1 != self.merged.header.version).collect()
+    }
+}
+
+/// Implementation of `TableSplit`.
+impl TableSplit {
+
+    /// This function splits a table. Each rule is applied in order with the name of his new fragment, and each row
+    /// goes to the fragment of the first rule it matches. It fails if a new fragment already exists in the PackFile.
+    pub fn new(
+        packed_files: &[PackedFile],
+        schema: &Schema,
+        path: &[String],
+        rules: &[(String, SplitRule)],
+        deterministic_guids: bool,
+    ) -> Result<Self, Error> {
+        if path.len() != 3 || path[0] != "db" { return Err(PackedFileError::NotADBTable { path: path.to_vec() }.into()) }
+        let db = match packed_files.iter().find(|x| x.path == path) {
+            Some(packed_file) => DB::read(&packed_file.data, &path[1], schema)?,
+            None => return Err(PackedFileError::PackedFileNotFound { path: path.to_vec() }.into()),
+        };
+
+        let table_short_name = path[1].trim_right_matches("_tables");
+        let columns = db.data.table_definition.fields.iter().map(|x| format!("{}.{}", table_short_name, x.field_name)).collect::<Vec<String>>();
+        let key_column = db.data.table_definition.fields.iter().position(|x| x.field_is_key).unwrap_or(0);
+
+        // Get the fragment each row goes to. None means it stays in the table.
+        let mut parts: BTreeMap<usize, Vec<Vec<DecodedData>>> = BTreeMap::new();
+        let mut remaining = vec![];
+        for entry in &db.data.entries {
+            let row = entry[1..].iter().map(|x| x.to_string()).collect::<Vec<String>>();
+            let mut part = None;
+            for (index, &(_, ref rule)) in rules.iter().enumerate() {
+                let is_match = match *rule {
+                    SplitRule::Filter(ref expression) => expression.evaluate(&columns, &row)?,
+                    SplitRule::KeyPrefix(ref prefix) => row.get(key_column).map_or(false, |x| x.starts_with(prefix)),
+                };
+                if is_match { part = Some(index); break; }
+            }
+
+            match part {
+                Some(index) => parts.entry(index).or_insert_with(|| vec![]).push(entry.to_vec()),
+                None => remaining.push(entry.to_vec()),
+            }
+        }
+
+        let mut new_parts = vec![];
+        for (index, entries) in parts {
+            let new_path = vec!["db".to_owned(), path[1].to_owned(), rules[index].0.to_owned()];
+            if packed_files.iter().any(|x| x.path == new_path) || new_parts.iter().any(|x: &(Vec<String>, DB)| x.0 == new_path) {
+                return Err(PackedFileError::PackedFileAlreadyExists { path: new_path.to_vec() }.into());
+            }
+
+            // Same header, but with his own GUID.
+            let mut part = db.clone();
+            part.header = DBHeader {
+                guid: DBHeader::get_new_guid(&new_path, deterministic_guids),
+                ..db.header.clone()
+            };
+            part.data.entries = entries;
+            new_parts.push((new_path, part));
+        }
+
+        let mut table = db.clone();
+        table.data.entries = remaining;
+        Ok(Self {
+            path: path.to_vec(),
+            parts: new_parts,
+            remaining: table,
+        })
+    }
+
+    /// This function puts the new fragments in the PackFile, and removes the moved rows from the split table.
+    /// If the split table has no rows left, it's deleted. It returns the paths of the new fragments.
+    pub fn apply(&self, pack_file: &mut PackFile) -> Result<Vec<Vec<String>>, Error> {
+        let index = match pack_file.data.packed_files.iter().position(|x| x.path == self.path) {
+            Some(index) => index,
+            None => return Err(PackedFileError::PackedFileNoLongerExists { path: self.path.to_vec() }.into()),
+        };
+
+        if self.remaining.data.entries.is_empty() { pack_file.remove_packedfile(index); }
+        else {
+            let data = self.remaining.save();
+            pack_file.data.packed_files[index] = PackedFile::read(data.len() as u32, self.path.to_vec(), data);
+        }
+
+        let mut packed_files = vec![];
+        for &(ref path, ref db) in &self.parts {
+            let data = db.save();
+            packed_files.push(PackedFile::read(data.len() as u32, path.to_vec(), data));
+        }
+        pack_file.add_packedfiles(packed_files);
+        Ok(self.parts.iter().map(|x| x.0.to_vec()).collect())
+    }
+
+    /// This function checks if the split doesn't move any row.
+    pub fn is_empty(&self) -> bool {
+        self.parts.is_empty()
+    }
+}
+
+/// This function returns the tables with more than one fragment in the provided PackedFiles, sorted by name.
+pub fn get_fragmented_tables(packed_files: &[PackedFile]) -> Vec<String> {
+    let mut fragments: BTreeMap<&str, usize> = BTreeMap::new();
+    for packed_file in packed_files.iter().filter(|x| x.path.len() == 3 && x.path[0] == "db") {
+        *fragments.entry(&packed_file.path[1][..]).or_insert(0) += 1;
+    }
+    fragments.iter().filter(|x| *x.1 > 1).map(|x| x.0.to_string()).collect()
+}
+
+/// Implementation of `Display` for `TableMerge`, so we can show what a merge does before applying it.
+impl fmt::Display for TableMerge {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "{}: {} fragments merged into {} (version {}, {} rows):", self.table, self.fragments.len(), self.path.join("/"), self.merged.header.version, self.merged.data.entries.len())?;
+        for &(ref path, version) in &self.fragments {
+            writeln!(f, "  - {} (version {}).", path.join("/"), version)?;
+        }
+
+        let version_mismatches = self.get_version_mismatches();
+        if !version_mismatches.is_empty() {
+            writeln!(f)?;
+            writeln!(f, "Fragments updated to the version {}. Columns that don't exist in it are lost, and the new ones get their default value:", self.merged.header.version)?;
+            for &&(ref path, version) in &version_mismatches {
+                writeln!(f, "  - {} (version {}).", path.join("/"), version)?;
+            }
+        }
+
+        if !self.duplicated_keys.is_empty() {
+            writeln!(f)?;
+            writeln!(f, "Rows with duplicated keys. Only the row the game uses (from the first fragment) is kept:")?;
+            for &(ref keys, ref kept, ref discarded) in &self.duplicated_keys {
+                writeln!(f, "  - ({}): kept from {}, discarded from {}.", keys, kept.join("/"), discarded.join("/"))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Implementation of `Display` for `TableSplit`, so we can show what a split does before applying it.
+impl fmt::Display for TableSplit {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.is_empty() {
+            return writeln!(f, "No rows of {} match the rules, so nothing is split.", self.path.join("/"));
+        }
+
+        writeln!(f, "{} split into:", self.path.join("/"))?;
+        for &(ref path, ref db) in &self.parts {
+            writeln!(f, "  - {}: {} rows.", path.join("/"), db.data.entries.len())?;
+        }
+        if self.remaining.data.entries.is_empty() { writeln!(f, "No rows are left in {}, so it's deleted.", self.path.join("/"))?; }
+        else { writeln!(f, "{} rows are left in {}.", self.remaining.data.entries.len(), self.path.join("/"))?; }
+        Ok(())
+    }
+}
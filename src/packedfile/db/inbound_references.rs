@@ -0,0 +1,356 @@
+// In this file is the "Inbound References" stuff for DB Tables. The references in the schema (`field_is_reference`)
+// tell us where a column gets his values from. Here we do the opposite: we index them so we can know which rows,
+// in which tables, use a value of a row. This is used to know what is going to break before deleting a row, and to
+// delete all the rows depending on it (cascade delete) if the user wants it.
+//
+// Rows in the dependency database cannot be deleted, so if they depend on a deleted row, they are only reported.
+//
+// The index is also used by the "Clone with Dependencies", to get the rows it has to clone.
+
+use std::collections::HashMap;
+use std::fmt;
+
+use failure::Error;
+
+use packfile::packfile::{PackFile, PackedFile};
+use packedfile::error::PackedFileError;
+use super::{DB, DecodedData};
+use super::schemas::{Schema, TableDefinition};
+
+/// `ReferenceIndex`: This struct holds the decoded tables of a PackFile and the dependency database, and
+/// the rows referencing every value. It has:
+/// - tables: the decoded tables, with their path and if they are from the dependency database or not.
+/// - references: the rows referencing each value, by referenced table (without "_tables"), column and value.
+///   Each row is stored as (table, row, column), being all of them positions in `tables`.
+/// - undecodeable_tables: tables of the PackFile we couldn't decode, so they may have references we don't know about.
+#[derive(Clone)]
+pub struct ReferenceIndex {
+    tables: Vec<IndexedTable>,
+    references: HashMap<(String, String, String), Vec<(usize, usize, usize)>>,
+    pub undecodeable_tables: Vec<Vec<String>>,
+}
+
+/// `IndexedTable`: This struct holds one of the decoded tables of a `ReferenceIndex`. It has:
+/// - path: the path of the table, in the PackFile or in the dependency database.
+/// - db: the decoded table.
+/// - is_from_dependency_database: if the table is from the dependency database, instead of the PackFile.
+#[derive(Clone)]
+pub struct IndexedTable {
+    pub path: Vec<String>,
+    pub db: DB,
+    pub is_from_dependency_database: bool,
+}
+
+/// `InboundReference`: This struct holds one cell referencing a value. It has:
+/// - path: the path of the table of the cell.
+/// - row: the row of the cell, starting from 0.
+/// - column: the name of the column of the cell.
+/// - keys: the keys of the row of the cell, as text, so the user can identify it.
+/// - referenced_column: the column of the referenced value, in the format "table/column".
+/// - value: the referenced value.
+/// - is_from_dependency_database: if the cell is in the dependency database, instead of the PackFile.
+#[derive(Clone, Debug, PartialEq)]
+pub struct InboundReference {
+    pub path: Vec<String>,
+    pub row: usize,
+    pub column: String,
+    pub keys: String,
+    pub referenced_column: String,
+    pub value: String,
+    pub is_from_dependency_database: bool,
+}
+
+/// `CascadeDelete`: This struct holds all the rows we need to delete to delete rows of a table without
+/// leaving broken references behind. It has:
+/// - path: the path of the table with the rows the user wants to delete.
+/// - rows: the rows the user wants to delete, starting from 0.
+/// - dependent_rows: the rows of the PackFile depending on them, directly or not, which will be deleted too.
+/// - broken_references: the rows of the dependency database depending on them, which cannot be deleted.
+/// - undecodeable_tables: tables of the PackFile we couldn't decode, so they may have references we don't know about.
+#[derive(Clone, Debug)]
+pub struct CascadeDelete {
+    pub path: Vec<String>,
+    pub rows: Vec<usize>,
+    pub dependent_rows: Vec<InboundReference>,
+    pub broken_references: Vec<InboundReference>,
+    pub undecodeable_tables: Vec<Vec<String>>,
+}
+
+/// Implementation of `ReferenceIndex`.
+impl ReferenceIndex {
+
+    /// This function decodes all the tables of the provided PackedFiles and dependency database, and indexes
+    /// all their references. Undecodeable tables of the dependency database are ignored.
+    pub fn new(packed_files: &[PackedFile], dependency_database: &[PackedFile], schema: &Schema) -> Self {
+        let mut tables = vec![];
+        let mut undecodeable_tables = vec![];
+        for packed_file in packed_files.iter().filter(|x| x.path.len() >= 3 && x.path[0] == "db") {
+            match DB::read(&packed_file.data, &packed_file.path[1], schema) {
+                Ok(db) => tables.push(IndexedTable { path: packed_file.path.to_vec(), db, is_from_dependency_database: false }),
+                Err(_) => undecodeable_tables.push(packed_file.path.to_vec()),
+            }
+        }
+        for packed_file in dependency_database.iter().filter(|x| x.path.len() >= 3 && x.path[0] == "db") {
+            if let Ok(db) = DB::read(&packed_file.data, &packed_file.path[1], schema) {
+                tables.push(IndexedTable { path: packed_file.path.to_vec(), db, is_from_dependency_database: true });
+            }
+        }
+
+        let mut references: HashMap<(String, String, String), Vec<(usize, usize, usize)>> = HashMap::new();
+        for (table_index, table) in tables.iter().enumerate() {
+            for (column, field) in table.db.data.table_definition.fields.iter().enumerate() {
+                if let Some((ref referenced_table, ref referenced_column)) = field.field_is_reference {
+                    for (row, entry) in table.db.data.entries.iter().enumerate() {
+                        let value = entry[column + 1].to_string();
+                        if value.is_empty() { continue }
+                        references.entry((referenced_table.to_owned(), referenced_column.to_owned(), value)).or_insert_with(Vec::new).push((table_index, row, column));
+                    }
+                }
+            }
+        }
+
+        Self { tables, references, undecodeable_tables }
+    }
+
+    /// This function returns all the cells referencing the provided value of a column. The table can be
+    /// written with or without the "_tables" at the end.
+    pub fn get_references(&self, table: &str, column: &str, value: &str) -> Vec<InboundReference> {
+        let table_short_name = table.trim_right_matches("_tables");
+        let key = (table_short_name.to_owned(), column.to_owned(), value.to_owned());
+        match self.references.get(&key) {
+            Some(references) => references.iter().map(|&(table_index, row, column_index)| {
+                let table = &self.tables[table_index];
+                InboundReference {
+                    path: table.path.to_vec(),
+                    row,
+                    column: table.db.data.table_definition.fields[column_index].field_name.to_owned(),
+                    keys: table.get_keys(row),
+                    referenced_column: format!("{}_tables/{}", table_short_name, column),
+                    value: value.to_owned(),
+                    is_from_dependency_database: table.is_from_dependency_database,
+                }
+            }).collect(),
+            None => vec![],
+        }
+    }
+
+    /// This function returns all the cells referencing any of the values of a row of a table of the PackFile.
+    pub fn get_references_to_row(&self, path: &[String], row: usize) -> Vec<InboundReference> {
+        match self.get_table_index(path, false) {
+            Some(table_index) => self.get_references_to_row_by_index(table_index, row),
+            None => vec![],
+        }
+    }
+
+    /// This function returns the decoded tables of the index, the ones of the PackFile first.
+    pub fn get_tables(&self) -> &[IndexedTable] {
+        &self.tables
+    }
+
+    /// This function returns the rows referencing any of the values of a row, as (table, row), being both of them
+    /// positions in `get_tables()`.
+    pub fn get_rows_referencing_row(&self, table_index: usize, row: usize) -> Vec<(usize, usize)> {
+        let table = &self.tables[table_index];
+        let table_short_name = table.path[1].trim_right_matches("_tables");
+        let mut rows = vec![];
+        if let Some(entry) = table.db.data.entries.get(row) {
+            for (column, field) in table.db.data.table_definition.fields.iter().enumerate() {
+                let value = entry[column + 1].to_string();
+                if value.is_empty() { continue }
+                if let Some(references) = self.references.get(&(table_short_name.to_owned(), field.field_name.to_owned(), value)) {
+                    rows.extend(references.iter().map(|&(table_index, row, _)| (table_index, row)));
+                }
+            }
+        }
+        rows
+    }
+
+    /// This function returns the position in `tables` of the table with the provided path.
+    fn get_table_index(&self, path: &[String], is_from_dependency_database: bool) -> Option<usize> {
+        self.tables.iter().position(|x| x.path == path && x.is_from_dependency_database == is_from_dependency_database)
+    }
+
+    /// This function returns all the cells referencing any of the values of a row, with his index, of a table
+    /// with the provided definition. The row doesn't need to be in the index, so it can be used with rows not saved yet.
+    pub fn get_references_to_entry(&self, table: &str, table_definition: &TableDefinition, entry: &[DecodedData]) -> Vec<InboundReference> {
+        let mut references = vec![];
+        for (column, field) in table_definition.fields.iter().enumerate() {
+            let value = entry[column + 1].to_string();
+            if !value.is_empty() {
+                references.extend(self.get_references(table, &field.field_name, &value));
+            }
+        }
+        references
+    }
+
+    /// This function returns all the cells referencing any of the values of a row of one of the tables of the index.
+    fn get_references_to_row_by_index(&self, table_index: usize, row: usize) -> Vec<InboundReference> {
+        let table = &self.tables[table_index];
+        match table.db.data.entries.get(row) {
+            Some(entry) => self.get_references_to_entry(&table.path[1], &table.db.data.table_definition, entry),
+            None => vec![],
+        }
+    }
+
+    /// This function returns true if the provided value of a column exists in any row of his table, in the
+    /// PackFile or in the dependency database, ignoring the rows in `ignored_rows` (as (table, row)).
+    fn is_value_provided(&self, table: &str, column: &str, value: &str, ignored_rows: &[(usize, usize)]) -> bool {
+        self.tables.iter().enumerate().filter(|&(_, x)| x.path[1] == table).any(|(table_index, x)| {
+            match x.db.data.table_definition.fields.iter().position(|field| field.field_name == column) {
+                Some(column) => x.db.data.entries.iter().enumerate().any(|(row, entry)| entry[column + 1].to_string() == value && !ignored_rows.contains(&(table_index, row))),
+                None => false,
+            }
+        })
+    }
+}
+
+/// Implementation of `IndexedTable`.
+impl IndexedTable {
+
+    /// This function returns the keys of a row, as text. If the table has no keys, it returns the full row.
+    fn get_keys(&self, row: usize) -> String {
+        let fields = &self.db.data.table_definition.fields;
+        let entry = &self.db.data.entries[row];
+        let key_indexes = if fields.iter().any(|x| x.field_is_key) {
+            fields.iter().enumerate().filter(|x| x.1.field_is_key).map(|x| x.0).collect::<Vec<usize>>()
+        } else { (0..fields.len()).collect::<Vec<usize>>() };
+        key_indexes.iter().map(|x| entry[x + 1].to_string()).collect::<Vec<String>>().join(", ")
+    }
+}
+
+/// Implementation of `CascadeDelete`.
+impl CascadeDelete {
+
+    /// This function gets all the rows depending on the provided rows of a table of the PackFile. A row only
+    /// depends on another one if the value it references is not in any other row we keep (like the vanilla
+    /// row of a table that overwrites it).
+    pub fn new(index: &ReferenceIndex, path: &[String], rows: &[usize]) -> Result<Self, Error> {
+        let table_index = match index.get_table_index(path, false) {
+            Some(table_index) => table_index,
+            None => return Err(PackedFileError::UndecodeableTable { path: path.to_vec() }.into()),
+        };
+
+        // The rows we are going to delete, as (table, row). We keep adding rows until no more rows depend on them.
+        let mut deleted_rows = rows.iter().map(|x| (table_index, *x)).collect::<Vec<(usize, usize)>>();
+        let mut dependent_rows = vec![];
+        let mut broken_references: Vec<InboundReference> = vec![];
+        let mut current_row = 0;
+        while current_row < deleted_rows.len() {
+            let (table_index, row) = deleted_rows[current_row];
+            current_row += 1;
+
+            for reference in index.get_references_to_row_by_index(table_index, row) {
+                {
+                    let (referenced_table, referenced_column) = reference.referenced_column.split_at(reference.referenced_column.find('/').unwrap());
+                    if index.is_value_provided(referenced_table, &referenced_column[1..], &reference.value, &deleted_rows) { continue }
+                }
+
+                let referencing_row = (index.get_table_index(&reference.path, reference.is_from_dependency_database).unwrap(), reference.row);
+                if reference.is_from_dependency_database {
+                    if !broken_references.contains(&reference) { broken_references.push(reference); }
+                }
+                else if !deleted_rows.contains(&referencing_row) {
+                    deleted_rows.push(referencing_row);
+                    dependent_rows.push(reference);
+                }
+            }
+        }
+
+        Ok(Self {
+            path: path.to_vec(),
+            rows: rows.to_vec(),
+            dependent_rows,
+            broken_references,
+            undecodeable_tables: index.undecodeable_tables.to_vec(),
+        })
+    }
+
+    /// This function deletes the rows and all the rows depending on them from the PackFile. The PackFile must
+    /// be the same we used to get the rows, without any change since then. If any of the tables fails to encode,
+    /// the PackFile is left untouched.
+    pub fn apply(&self, pack_file: &mut PackFile, schema: &Schema) -> Result<(), Error> {
+
+        // Get the rows to delete by table, without duplicates.
+        let mut rows_by_path: Vec<(&[String], Vec<usize>)> = vec![(&self.path[..], self.rows.to_vec())];
+        for reference in &self.dependent_rows {
+            match rows_by_path.iter().position(|x| x.0 == &*reference.path) {
+                Some(position) => rows_by_path[position].1.push(reference.row),
+                None => rows_by_path.push((&reference.path[..], vec![reference.row])),
+            }
+        }
+
+        let mut new_packed_files = vec![];
+        for (path, mut rows) in rows_by_path {
+            let index = match pack_file.data.packed_files.iter().position(|x| x.path == path) {
+                Some(index) => index,
+                None => return Err(PackedFileError::PackedFileNoLongerExists { path: path.to_vec() }.into()),
+            };
+            let mut db = DB::read(&pack_file.data.packed_files[index].data, &path[1], schema)?;
+
+            // Delete them from the last one to the first one, so the positions of the rest don't change.
+            rows.sort();
+            rows.dedup();
+            for row in rows.iter().rev() {
+                if *row >= db.data.entries.len() {
+                    return Err(PackedFileError::RowNoLongerExists { path: path.to_vec(), row: *row }.into());
+                }
+                db.data.entries.remove(*row);
+            }
+            new_packed_files.push((index, db.save()));
+        }
+
+        for (index, data) in new_packed_files {
+            pack_file.data.packed_files[index].size = data.len() as u32;
+            pack_file.data.packed_files[index].data = data;
+        }
+
+        Ok(())
+    }
+}
+
+/// Implementation of `Display` for `InboundReference`.
+impl fmt::Display for InboundReference {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}, row {} ({}), column \"{}\" references \"{}\" in {}.", self.path.join("/"), self.row + 1, self.keys, self.column, self.value, self.referenced_column)?;
+        if self.is_from_dependency_database { write!(f, " (Dependency Database)")?; }
+        Ok(())
+    }
+}
+
+/// Implementation of `Display` for `CascadeDelete`, so we can show the preview in the UI or print it in the terminal.
+impl fmt::Display for CascadeDelete {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+
+        writeln!(f, "Rows to delete from {}: {}.", self.path.join("/"), self.rows.iter().map(|x| (x + 1).to_string()).collect::<Vec<String>>().join(", "))?;
+        writeln!(f)?;
+
+        if self.dependent_rows.is_empty() {
+            writeln!(f, "There are no rows in the PackFile depending on them.")?;
+            writeln!(f)?;
+        }
+        else {
+            writeln!(f, "Dependent rows that will be deleted too:")?;
+            for reference in &self.dependent_rows {
+                writeln!(f, "  - {}", reference)?;
+            }
+            writeln!(f)?;
+        }
+
+        if !self.broken_references.is_empty() {
+            writeln!(f, "Rows of the Dependency Database that will have broken references, as they cannot be deleted:")?;
+            for reference in &self.broken_references {
+                writeln!(f, "  - {}", reference)?;
+            }
+            writeln!(f)?;
+        }
+
+        if !self.undecodeable_tables.is_empty() {
+            writeln!(f, "Tables of the PackFile that couldn't be decoded, so any row in them depending on the deleted rows will not be deleted:")?;
+            for path in &self.undecodeable_tables {
+                writeln!(f, "  - {}", path.join("/"))?;
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,328 @@
+// In this file is the "Key Rename" stuff for DB Tables. Renaming the key of a row (like the key of an
+// unit) means we have to change every cell referencing it (through `field_is_reference`) in the rest
+// of the tables of the PackFile, and the Loc entries of the row, like `land_units_onscreen_name_<key>`.
+// Otherwise, we end up with a bunch of broken references. This is done in three steps:
+// - We get all the cells and Loc keys we have to change (`KeyRename::new`), so the user can check them.
+// - We change them (`KeyRename::apply`), keeping a copy of the PackedFiles we changed.
+// - If the user doesn't like the result, we restore that copy (`KeyRenameUndo::undo`).
+//
+// The Loc keys are matched with `get_loc_key_match`, which is also used by the "Clone with Dependencies".
+
+use std::collections::HashSet;
+use std::fmt;
+use std::path::PathBuf;
+
+use failure::Error;
+
+use packfile::packfile::{PackFile, PackedFile};
+use packedfile::error::PackedFileError;
+use packedfile::loc::Loc;
+use super::DB;
+use super::DecodedData;
+use super::schemas::{Schema, LOCALISED_COLUMNS};
+
+/// `KeyRename`: This struct holds all the changes we need to do to rename a key. It has:
+/// - table: the table of the key, with "_tables" at the end.
+/// - column: the column of the key.
+/// - old_key: the current key.
+/// - new_key: the key we want to use instead.
+/// - cells: the cells of the tables we have to change, including the ones of the source table.
+/// - loc_keys: the Loc entries we have to change. Their column is always "key".
+/// - undecodeable_tables: tables we couldn't decode, so they may have references we cannot change.
+#[derive(Clone, Debug)]
+pub struct KeyRename {
+    pub table: String,
+    pub column: String,
+    pub old_key: String,
+    pub new_key: String,
+    pub cells: Vec<KeyRenameChange>,
+    pub loc_keys: Vec<KeyRenameChange>,
+    pub undecodeable_tables: Vec<Vec<String>>,
+}
+
+/// `KeyRenameChange`: This struct holds one of the cells we have to change. It has:
+/// - path: the path of the table or Loc in the PackFile.
+/// - row: the row of the cell, starting from 0.
+/// - column: the name of the column of the cell.
+/// - old_value: the current value of the cell.
+/// - new_value: the value the cell will have after the rename.
+/// - is_heuristic: if the change is a guess, because it's a Loc key with a column we don't know. Always false for cells.
+#[derive(Clone, Debug)]
+pub struct KeyRenameChange {
+    pub path: Vec<String>,
+    pub row: usize,
+    pub column: String,
+    pub old_value: String,
+    pub new_value: String,
+    pub is_heuristic: bool,
+}
+
+/// `KeyRenameUndo`: This struct holds what we need to undo a rename. It has:
+/// - pack_file_path: the path of the PackFile where we did the rename, so we don't restore them in another PackFile.
+/// - old_key: the old key, to tell the user what we are undoing.
+/// - new_key: the new key, to tell the user what we are undoing.
+/// - packed_files: the PackedFiles changed by the rename, as they were before it.
+#[derive(Clone, Debug)]
+pub struct KeyRenameUndo {
+    pub pack_file_path: PathBuf,
+    pub old_key: String,
+    pub new_key: String,
+    pub packed_files: Vec<PackedFile>,
+}
+
+/// Implementation of `KeyRename`.
+impl KeyRename {
+
+    /// This function gets all the cells and Loc keys of the provided PackedFiles we need to change to rename
+    /// a key. The table can be written with or without the "_tables" at the end. It fails if the new key is
+    /// empty or if it's already used in the source table.
+    pub fn new(
+        packed_files: &[PackedFile],
+        schema: &Schema,
+        table: &str,
+        column: &str,
+        old_key: &str,
+        new_key: &str,
+    ) -> Result<Self, Error> {
+
+        if new_key.is_empty() { return Err(PackedFileError::EmptyKey.into()) }
+        if old_key == new_key { return Err(PackedFileError::SameKey.into()) }
+
+        let table_name = if table.ends_with("_tables") { table.to_owned() } else { format!("{}_tables", table) };
+        let table_short_name = table_name.trim_right_matches("_tables").to_owned();
+        let reference = (table_short_name.to_owned(), column.to_owned());
+
+        let mut cells = vec![];
+        let mut loc_keys = vec![];
+        let mut undecodeable_tables = vec![];
+        let mut is_column_found = false;
+
+        // The Locs are checked at the end, as we need all the keys of the source table to check their keys.
+        let mut locs = vec![];
+        let mut table_keys = HashSet::new();
+
+        for packed_file in packed_files {
+
+            // DB Tables. We change the key in the source table and the cells referencing it in any table.
+            if packed_file.path.len() >= 3 && packed_file.path[0] == "db" {
+                let db = match DB::read(&packed_file.data, &packed_file.path[1], schema) {
+                    Ok(db) => db,
+                    Err(_) => {
+                        undecodeable_tables.push(packed_file.path.to_vec());
+                        continue
+                    }
+                };
+
+                let is_source_table = packed_file.path[1] == table_name;
+                for (index, field) in db.data.table_definition.fields.iter().enumerate() {
+                    let is_source_column = is_source_table && field.field_name == column;
+                    if is_source_column { is_column_found = true; }
+                    if !is_source_column && field.field_is_reference.as_ref() != Some(&reference) { continue }
+
+                    for (row, entry) in db.data.entries.iter().enumerate() {
+                        let value = entry[index + 1].to_string();
+                        if is_source_column && value == new_key {
+                            return Err(PackedFileError::KeyAlreadyUsed { key: new_key.to_owned(), path: packed_file.path.to_vec(), row }.into());
+                        }
+
+                        if value == old_key {
+                            cells.push(KeyRenameChange {
+                                path: packed_file.path.to_vec(),
+                                row,
+                                column: field.field_name.to_owned(),
+                                old_value: value.to_owned(),
+                                new_value: new_key.to_owned(),
+                                is_heuristic: false,
+                            });
+                        }
+
+                        if is_source_column { table_keys.insert(value); }
+                    }
+                }
+            }
+
+            else if packed_file.path.last().map_or(false, |x| x.ends_with(".loc")) {
+                if let Ok(loc) = Loc::read(&packed_file.data) {
+                    locs.push((packed_file.path.to_vec(), loc));
+                }
+            }
+        }
+
+        if !is_column_found {
+            return Err(PackedFileError::ColumnNotInTables { table: table_name.to_owned(), column: column.to_owned() }.into());
+        }
+
+        // Loc PackedFiles. Their keys are "<table without _tables>_<column>_<key>".
+        for &(ref path, ref loc) in &locs {
+            for (row, entry) in loc.data.entries.iter().enumerate() {
+                if let Some(is_heuristic) = get_loc_key_match(&entry.key, &table_short_name, old_key, &table_keys) {
+                    loc_keys.push(KeyRenameChange {
+                        path: path.to_vec(),
+                        row,
+                        column: "key".to_owned(),
+                        old_value: entry.key.to_owned(),
+                        new_value: format!("{}{}", &entry.key[..entry.key.len() - old_key.len()], new_key),
+                        is_heuristic,
+                    });
+                }
+            }
+        }
+
+        Ok(Self {
+            table: table_name,
+            column: column.to_owned(),
+            old_key: old_key.to_owned(),
+            new_key: new_key.to_owned(),
+            cells,
+            loc_keys,
+            undecodeable_tables,
+        })
+    }
+
+    /// This function returns true if there is nothing to change.
+    pub fn is_empty(&self) -> bool {
+        self.cells.is_empty() && self.loc_keys.is_empty()
+    }
+
+    /// This function does the rename in the provided PackFile, and returns what we need to undo it. The
+    /// PackFile must be the same we used to get the changes, without any change since then. If any of the
+    /// PackedFiles fails to encode, the PackFile is left untouched.
+    pub fn apply(&self, pack_file: &mut PackFile, schema: &Schema) -> Result<KeyRenameUndo, Error> {
+
+        // Get the paths of all the PackedFiles we have to change, without duplicates.
+        let mut paths: Vec<&Vec<String>> = vec![];
+        for change in self.cells.iter().chain(self.loc_keys.iter()) {
+            if !paths.contains(&&change.path) { paths.push(&change.path); }
+        }
+
+        // Change them in memory first, so we don't leave the PackFile half-renamed if something fails.
+        let mut new_packed_files = vec![];
+        for path in paths {
+            let index = match pack_file.data.packed_files.iter().position(|x| x.path == *path) {
+                Some(index) => index,
+                None => return Err(PackedFileError::PackedFileNoLongerExists { path: path.to_vec() }.into()),
+            };
+            let packed_file = &pack_file.data.packed_files[index];
+
+            let data = if path[0] == "db" {
+                let mut db = DB::read(&packed_file.data, &path[1], schema)?;
+                for change in self.cells.iter().filter(|x| x.path == *path) {
+                    let column = match db.data.table_definition.fields.iter().position(|x| x.field_name == change.column) {
+                        Some(column) => column,
+                        None => return Err(PackedFileError::ColumnNoLongerExists { path: path.to_vec(), column: change.column.to_owned() }.into()),
+                    };
+                    let field_type = db.data.table_definition.fields[column].field_type.clone();
+                    if !db.data.entries.get(change.row).map_or(false, |x| x[column + 1].to_string() == change.old_value) {
+                        return Err(PackedFileError::RowChanged { path: path.to_vec(), row: change.row }.into());
+                    }
+                    db.data.entries[change.row][column + 1] = DecodedData::from_text(&change.new_value, &field_type)?;
+                }
+                db.save()
+            }
+
+            else {
+                let mut loc = Loc::read(&packed_file.data)?;
+                for change in self.loc_keys.iter().filter(|x| x.path == *path) {
+                    if !loc.data.entries.get(change.row).map_or(false, |x| x.key == change.old_value) {
+                        return Err(PackedFileError::RowChanged { path: path.to_vec(), row: change.row }.into());
+                    }
+                    loc.data.entries[change.row].key = change.new_value.to_owned();
+                }
+                loc.save()
+            };
+
+            new_packed_files.push((index, data));
+        }
+
+        // Then, replace the PackedFiles, keeping a copy of the old ones.
+        let mut old_packed_files = vec![];
+        for (index, data) in new_packed_files {
+            let packed_file = &mut pack_file.data.packed_files[index];
+            old_packed_files.push(packed_file.clone());
+            packed_file.size = data.len() as u32;
+            packed_file.data = data;
+        }
+
+        Ok(KeyRenameUndo {
+            pack_file_path: pack_file.extra_data.file_path.to_path_buf(),
+            old_key: self.old_key.to_owned(),
+            new_key: self.new_key.to_owned(),
+            packed_files: old_packed_files,
+        })
+    }
+}
+
+/// Implementation of `KeyRenameUndo`.
+impl KeyRenameUndo {
+
+    /// This function restores the PackedFiles changed by a rename, losing any change done to them after it.
+    /// It returns the paths of the PackedFiles that couldn't be restored because they no longer exist.
+    pub fn undo(&self, pack_file: &mut PackFile) -> Result<Vec<Vec<String>>, Error> {
+        if pack_file.extra_data.file_path != self.pack_file_path {
+            return Err(PackedFileError::UndoFromAnotherPackFile { old_key: self.old_key.to_owned(), new_key: self.new_key.to_owned() }.into());
+        }
+
+        let mut missing_paths = vec![];
+        for old_packed_file in &self.packed_files {
+            match pack_file.data.packed_files.iter_mut().find(|x| x.path == old_packed_file.path) {
+                Some(packed_file) => *packed_file = old_packed_file.clone(),
+                None => missing_paths.push(old_packed_file.path.to_vec()),
+            }
+        }
+
+        Ok(missing_paths)
+    }
+}
+
+/// This function checks if a Loc key is the key of a localised column of a row, "<table without _tables>_<column>_<key>".
+/// As the key can have underscores, we ignore the keys that are from another row of the table with a longer key
+/// ending in the same way, like "land_units_onscreen_name_emp_swordsmen" when the key is "swordsmen" and
+/// "emp_swordsmen" is also a key of the table. It returns None if the Loc key is not from the row, and Some(true)
+/// if it's only a guess because the column is not one of the localised columns we know.
+pub fn get_loc_key_match(loc_key: &str, table_short_name: &str, key: &str, table_keys: &HashSet<String>) -> Option<bool> {
+    let prefix = format!("{}_", table_short_name);
+    let suffix = format!("_{}", key);
+    if loc_key.len() <= prefix.len() + suffix.len() || !loc_key.starts_with(&prefix) || !loc_key.ends_with(&suffix) { return None }
+
+    let column_and_key = &loc_key[prefix.len()..];
+    if table_keys.iter().any(|x| x.len() > key.len() && x.ends_with(&suffix) && column_and_key.ends_with(&format!("_{}", x))) { return None }
+
+    let column = &column_and_key[..column_and_key.len() - suffix.len()];
+    Some(!LOCALISED_COLUMNS.contains(&column))
+}
+
+/// Implementation of `Display` for `KeyRename`, so we can show the preview in the UI or print it in the terminal.
+impl fmt::Display for KeyRename {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+
+        if self.is_empty() {
+            writeln!(f, "There is nothing to change to rename \"{}\" to \"{}\" in \"{}/{}\".", self.old_key, self.new_key, self.table, self.column)?;
+        }
+
+        if !self.cells.is_empty() {
+            writeln!(f, "Cells to change:")?;
+            for change in &self.cells {
+                writeln!(f, "  - {}, row {}, column \"{}\": \"{}\" -> \"{}\".", change.path.join("/"), change.row + 1, change.column, change.old_value, change.new_value)?;
+            }
+            writeln!(f)?;
+        }
+
+        if !self.loc_keys.is_empty() {
+            writeln!(f, "Loc keys to change:")?;
+            for change in &self.loc_keys {
+                let heuristic = if change.is_heuristic { " (heuristic, unknown column)" } else { "" };
+                writeln!(f, "  - {}, row {}: \"{}\" -> \"{}\"{}.", change.path.join("/"), change.row + 1, change.old_value, change.new_value, heuristic)?;
+            }
+            writeln!(f)?;
+        }
+
+        if !self.undecodeable_tables.is_empty() {
+            writeln!(f, "Tables that couldn't be decoded, so any reference in them will not be changed:")?;
+            for path in &self.undecodeable_tables {
+                writeln!(f, "  - {}", path.join("/"))?;
+            }
+        }
+
+        Ok(())
+    }
+}
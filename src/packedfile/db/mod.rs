@@ -13,6 +13,7 @@ extern crate failure;
 extern crate csv;
 extern crate uuid;
 
+use std::fmt;
 use std::fs::File;
 use std::io::Write;
 use std::path::PathBuf;
@@ -22,10 +23,102 @@ use failure::Error;
 use self::csv::{ ReaderBuilder, WriterBuilder, QuoteStyle };
 use common::coding_helpers::*;
 use super::SerializableToTSV;
+use packedfile::error::PackedFileError;
 use self::schemas::*;
 
 pub mod schemas;
 pub mod schemas_importer;
+pub mod schemas_pfm;
+pub mod schemas_diff;
+pub mod schemas_docs;
+pub mod compatibility;
+pub mod references;
+pub mod key_rename;
+pub mod deep_clone;
+pub mod inbound_references;
+pub mod patches;
+pub mod bulk_edit;
+pub mod fragments;
+pub mod validation;
+pub mod query;
+pub mod assembly_kit;
+pub mod dependency_cache;
+
+// This tells the compiler to only compile this mod when testing.
+#[cfg(test)]
+pub mod tests;
+
+/// Implementation of `Display` for `DecodedData`. It returns the value of the field as text, the same way
+/// we write it in a TSV file, so we can compare fields of different types and show them to the user.
+impl fmt::Display for DecodedData {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            DecodedData::Boolean(data) => write!(f, "{}", data),
+            DecodedData::Float(data) => write!(f, "{}", data),
+            DecodedData::Integer(data) => write!(f, "{}", data),
+            DecodedData::LongInteger(data) => write!(f, "{}", data),
+            DecodedData::Index(ref data) |
+            DecodedData::StringU8(ref data) |
+            DecodedData::StringU16(ref data) |
+            DecodedData::OptionalStringU8(ref data) |
+            DecodedData::OptionalStringU16(ref data) => write!(f, "{}", data),
+        }
+    }
+}
+
+/// Implementation of `DecodedData`.
+impl DecodedData {
+
+    /// This function creates a `DecodedData` of the provided type from his text, the same text `Display` returns.
+    /// Booleans also accept "1" and "0", as some programs export them that way.
+    pub fn from_text(text: &str, field_type: &FieldType) -> Result<Self, Error> {
+        Ok(match *field_type {
+            FieldType::Boolean => match &*text.to_lowercase() {
+                "true" | "1" => DecodedData::Boolean(true),
+                "false" | "0" => DecodedData::Boolean(false),
+                _ => return Err(PackedFileError::InvalidBoolean { value: text.to_owned() }.into()),
+            },
+            FieldType::Float => DecodedData::Float(text.parse::<f32>()?),
+            FieldType::Integer => DecodedData::Integer(text.parse::<i32>()?),
+            FieldType::LongInteger => DecodedData::LongInteger(text.parse::<i64>()?),
+            FieldType::StringU8 => DecodedData::StringU8(text.to_owned()),
+            FieldType::StringU16 => DecodedData::StringU16(text.to_owned()),
+            FieldType::OptionalStringU8 => DecodedData::OptionalStringU8(text.to_owned()),
+            FieldType::OptionalStringU16 => DecodedData::OptionalStringU16(text.to_owned()),
+        })
+    }
+
+    /// This function returns the value new rows should have in the provided field: his default value from
+    /// the schema if it has one (and it's valid), or the default value of his type.
+    pub fn default_for_field(field: &Field) -> Self {
+        field.field_default_value.as_ref()
+            .and_then(|x| DecodedData::from_text(x, &field.field_type).ok())
+            .unwrap_or_else(|| DecodedData::default(&field.field_type))
+    }
+
+    /// This function returns the default value for a field of the provided type.
+    pub fn default(field_type: &FieldType) -> Self {
+        match *field_type {
+            FieldType::Boolean => DecodedData::Boolean(false),
+            FieldType::Float => DecodedData::Float(0.0),
+            FieldType::Integer => DecodedData::Integer(0),
+            FieldType::LongInteger => DecodedData::LongInteger(0),
+            FieldType::StringU8 => DecodedData::StringU8(String::new()),
+            FieldType::StringU16 => DecodedData::StringU16(String::new()),
+            FieldType::OptionalStringU8 => DecodedData::OptionalStringU8(String::new()),
+            FieldType::OptionalStringU16 => DecodedData::OptionalStringU16(String::new()),
+        }
+    }
+}
+
+/// This function returns a row (without index) of a table with the `to` definition from a row (with index)
+/// of a table with the `from` definition. Columns are matched by name, and the missing ones get their default value.
+pub fn get_entry(from: &TableDefinition, entry: &[DecodedData], to: &TableDefinition) -> Vec<DecodedData> {
+    to.fields.iter().map(|field| match from.fields.iter().position(|x| x.field_name == field.field_name) {
+        Some(index) if from.fields[index].field_type == field.field_type => entry[index + 1].clone(),
+        _ => DecodedData::default_for_field(field),
+    }).collect()
+}
 
 /// These two const are the markers we need to check in the header of every DB file.
 const GUID_MARKER: &[u8] = &[253, 254, 252, 255];
@@ -125,7 +218,7 @@ impl DB {
                     (db_type == "models_naval_tables" && header.version == 11) ||
                     (db_type == "models_sieges_tables" && (header.version == 2 ||
                                                             header.version == 3))
-                { return Err(format_err!("This specific table version use an unimplemented type (List), so is undecodeable, for now.")) }
+                { return Err(PackedFileError::UnsupportedTableVersion { table: db_type.to_owned(), version: header.version }.into()) }
 
                 // Then, we try to get the schema for our table, if exists.
                 match Self::get_schema(db_type, header.version, master_schema) {
@@ -147,7 +240,7 @@ impl DB {
                             Err(error) => Err(error)
                         }
                     }
-                    None => Err(format_err!("Schema for this DB Table not found"))
+                    None => Err(PackedFileError::TableDefinitionNotFound { table: db_type.to_owned(), version: header.version }.into())
                 }
 
             }
@@ -218,11 +311,11 @@ impl DB {
             }
 
             // If the table doesn't have the version we asked for...
-            else { return Err(format_err!("Error while deleting the definition for version {} of table {}:\nThis table doesn't have this version decoded.", version, table_name)); }
+            else { return Err(PackedFileError::DefinitionVersionNotFound { table: table_name.to_owned(), version }.into()); }
         }
 
         // If the table hasn't been found in the Schema...
-        else { return Err(format_err!("Error while deleting the definition for version {} of table {}:\nThis table is not in the currently loaded Schema.", version, table_name)); }
+        else { return Err(PackedFileError::TableNotInSchema { table: table_name.to_owned(), version }.into()); }
     }
 }
 
@@ -232,7 +325,8 @@ impl DBHeader {
 
     /// This function creates a new DBHeader from nothing. For the GUID, we generate a random GUID.
     /// This is the same Assembly Kit does every time you export a table (it generates a random GUID)
-    /// so I guess the GUID doesn't really affects how the table works.
+    /// so I guess the GUID doesn't really affects how the table works. We don't know the path of the table
+    /// here, so if deterministic GUIDs are enabled, it's replaced when normalizing the PackFile before saving it.
     pub fn new(version: u32) -> Self {
         Self {
             guid: format!("{}", Uuid::new_v4()),
@@ -243,6 +337,23 @@ impl DBHeader {
         }
     }
 
+    /// This function returns a new GUID for the table with the provided path: the one generated from the path if
+    /// `deterministic_guids` is true, or a random one otherwise.
+    pub fn get_new_guid(path: &[String], deterministic_guids: bool) -> String {
+        if deterministic_guids { Self::get_deterministic_guid(path) } else { format!("{}", Uuid::new_v4()) }
+    }
+
+    /// This function returns a GUID generated from the path of a table, for when we don't want random GUIDs. The
+    /// same path always gets the same GUID, so saving the same table twice gives the same bytes. It's the FNV-1a
+    /// hash of the path (twice, with different offsets, to get 128 bits) written like a GUID.
+    pub fn get_deterministic_guid(path: &[String]) -> String {
+        let path = path.join("/");
+        let hash = |offset: u64| path.bytes().fold(offset, |hash, byte| (hash ^ u64::from(byte)).wrapping_mul(0x100_0000_01b3));
+        let high = hash(0xcbf2_9ce4_8422_2325);
+        let low = hash(0x6c62_272e_07bb_0142);
+        format!("{:08x}-{:04x}-{:04x}-{:04x}-{:012x}", high >> 32, (high >> 16) & 0xffff, high & 0xffff, low >> 48, low & 0xffff_ffff_ffff)
+    }
+
     /// This function creates a decoded DBHeader from a encoded PackedFile. It also return an index,
     /// to know where the body starts.
     pub fn read(packed_file_header: &[u8], mut index: &mut usize) -> Result<Self, Error> {
@@ -400,7 +511,7 @@ impl DBData {
                     }
 
                     // Otherwise, return error.
-                    else { return Err(format_err!("Error: trying to decode a bool without a byte.")) }
+                    else { return Err(PackedFileError::TruncatedData { field_type: field_type.clone(), column: field_list[column].field_name.to_owned(), row: (row_number - 1) as usize, offset: *index }.into()) }
                 }
 
                 // If it's a float field...
@@ -418,7 +529,7 @@ impl DBData {
                     }
 
                     // Otherwise, return error.
-                    else { return Err(format_err!("Error: trying to decode a Float without enough bytes.")) }
+                    else { return Err(PackedFileError::TruncatedData { field_type: field_type.clone(), column: field_list[column].field_name.to_owned(), row: (row_number - 1) as usize, offset: *index }.into()) }
                 }
 
                 // If it's an integer field...
@@ -435,7 +546,7 @@ impl DBData {
                     }
 
                     // Otherwise, return error.
-                    else { return Err(format_err!("Error: trying to decode a signed Integer without enough bytes.")) }
+                    else { return Err(PackedFileError::TruncatedData { field_type: field_type.clone(), column: field_list[column].field_name.to_owned(), row: (row_number - 1) as usize, offset: *index }.into()) }
                 }
 
                 // If it's a long integer (i64)...
@@ -452,14 +563,14 @@ impl DBData {
                     }
 
                     // Otherwise, return error.
-                    else { return Err(format_err!("Error: trying to decode a signed Long Integer without enough bytes.")) }
+                    else { return Err(PackedFileError::TruncatedData { field_type: field_type.clone(), column: field_list[column].field_name.to_owned(), row: (row_number - 1) as usize, offset: *index }.into()) }
                 }
 
                 // If it's a common StringU8...
                 FieldType::StringU8 => {
 
-                    // Check if the index does even exist, to avoid crashes. +1 because strings start with an u16 with his size.
-                    if packed_file_data.get(*index + 1).is_some() {
+                    // Check if we have the entire string, to avoid crashes. Strings start with an u16 with his size.
+                    if is_string_complete(packed_file_data, *index, 1) {
 
                         // Try to decode the field. If it succeed, add the field to the row. Otherwise, return error.
                         match decode_packedfile_string_u8(&packed_file_data[*index..], &mut index) {
@@ -469,14 +580,14 @@ impl DBData {
                     }
 
                     // Otherwise, return error.
-                    else { return Err(format_err!("Error: trying to decode a StringU8 without enought bytes.")) }
+                    else { return Err(PackedFileError::TruncatedData { field_type: field_type.clone(), column: field_list[column].field_name.to_owned(), row: (row_number - 1) as usize, offset: *index }.into()) }
                 }
 
                 // If it's a StringU16...
                 FieldType::StringU16 => {
 
-                    // Check if the index does even exist, to avoid crashes. +1 because strings start with an u16 with his size.
-                    if packed_file_data.get(*index + 1).is_some() {
+                    // Check if we have the entire string, to avoid crashes. Strings start with an u16 with his size.
+                    if is_string_complete(packed_file_data, *index, 2) {
 
                         // Try to decode the field. If it succeed, add the field to the row. Otherwise, return error.
                         match decode_packedfile_string_u16(&packed_file_data[*index..], &mut index) {
@@ -486,14 +597,15 @@ impl DBData {
                     }
 
                     // Otherwise, return error.
-                    else { return Err(format_err!("Error: trying to decode a StringU16 without enought bytes.")) }
+                    else { return Err(PackedFileError::TruncatedData { field_type: field_type.clone(), column: field_list[column].field_name.to_owned(), row: (row_number - 1) as usize, offset: *index }.into()) }
                 }
 
                 // If it's an optional StringU8...
                 FieldType::OptionalStringU8 => {
 
-                    // Check if the index does even exist, to avoid crashes.
-                    if packed_file_data.get(*index).is_some() {
+                    // Check if we have the entire string, to avoid crashes. If the boolean at the start is not true,
+                    // there is no string after it.
+                    if packed_file_data.get(*index).map_or(false, |x| *x != 1 || is_string_complete(packed_file_data, *index + 1, 1)) {
 
                         // Try to decode the field. If it succeed, add the field to the row. Otherwise, return error.
                         match decode_packedfile_optional_string_u8(&packed_file_data[*index..], &mut index) {
@@ -501,14 +613,15 @@ impl DBData {
                             Err(error) => return Err(error)
                         };
                     }
-                    else { return Err(format_err!("Error: trying to decode an OptionalStringU8 without enought bytes.")) }
+                    else { return Err(PackedFileError::TruncatedData { field_type: field_type.clone(), column: field_list[column].field_name.to_owned(), row: (row_number - 1) as usize, offset: *index }.into()) }
                 }
 
                 // If it's an optional StringU16...
                 FieldType::OptionalStringU16 => {
 
-                    // Check if the index does even exist, to avoid crashes.
-                    if packed_file_data.get(*index).is_some() {
+                    // Check if we have the entire string, to avoid crashes. If the boolean at the start is not true,
+                    // there is no string after it.
+                    if packed_file_data.get(*index).map_or(false, |x| *x != 1 || is_string_complete(packed_file_data, *index + 1, 2)) {
 
                         // Try to decode the field. If it succeed, add the field to the row. Otherwise, return error.
                         match decode_packedfile_optional_string_u16(&packed_file_data[*index..], &mut index) {
@@ -516,7 +629,7 @@ impl DBData {
                             Err(error) => return Err(error)
                         };
                     }
-                    else { return Err(format_err!("Error: trying to decode an OptionalStringU16 without enought bytes.")) }
+                    else { return Err(PackedFileError::TruncatedData { field_type: field_type.clone(), column: field_list[column].field_name.to_owned(), row: (row_number - 1) as usize, offset: *index }.into()) }
                 }
             }
         }
@@ -558,6 +671,16 @@ impl DBData {
     }
 }
 
+/// This function checks if the data has all the bytes of the string starting at `index`, including the u16 with his
+/// size. `char_size` is the amount of bytes of each character: 1 for StringU8, 2 for StringU16.
+fn is_string_complete(packed_file_data: &[u8], index: usize, char_size: usize) -> bool {
+    if packed_file_data.len() < index + 2 { return false }
+    match decode_integer_u16(&packed_file_data[index..index + 2]) {
+        Ok(size) => packed_file_data.len() >= index + 2 + size as usize * char_size,
+        Err(_) => false,
+    }
+}
+
 
 /// Implementation of `SerializableToTSV` for `DBData`.
 impl SerializableToTSV for DBData {
@@ -594,17 +717,17 @@ impl SerializableToTSV for DBData {
 
                                 // If the name or version are the defaults, return error.
                                 if table_name == "error" || table_version == 99999 {
-                                    return Err(format_err!("This TSV file's first line is incorrect."));
+                                    return Err(PackedFileError::TSVWrongHeader.into());
                                 }
 
                                 // If any of them doesn't match the name and version of the table we are importing to, return error.
                                 if table_name != packed_file_type || table_version != self.table_definition.version {
-                                    return Err(format_err!("This TSV file belongs to another table/version."));
+                                    return Err(PackedFileError::TSVWrongTable { table: table_name.to_owned(), version: table_version }.into());
                                 }
                             }
 
                             // If it fails, return error.
-                            Err(_) => return Err(format_err!("This TSV file's first row doesn't contain the name of the table and his version.")),
+                            Err(_) => return Err(PackedFileError::TSVMissingTableInfo.into()),
                         }
                     }
 
@@ -637,12 +760,12 @@ impl SerializableToTSV for DBData {
 
                                 // If the entry lenght doesn't match with the one of the current table, return error.
                                 else {
-                                    return Err(format_err!("Error while trying import the TSV file:\n{}\n\nIf you see this message, you probably tried to import a .tsv file into a table with different structure.", &tsv_file_path.display()));
+                                    return Err(PackedFileError::ImportTSVWrongStructure { path: tsv_file_path.to_path_buf() }.into());
                                 }
                             }
 
                             // If it fails, return error.
-                            Err(_) => return Err(format_err!("Error while trying import the TSV file:\n{}", &tsv_file_path.display())),
+                            Err(_) => return Err(PackedFileError::ImportTSV { path: tsv_file_path.to_path_buf() }.into()),
                         }
                     }
                 }
@@ -655,7 +778,7 @@ impl SerializableToTSV for DBData {
             }
 
             // If we couldn't read the TSV file, return error.
-            Err(_) => Err(format_err!("Error while trying to read the TSV file:\n{}.", &tsv_file_path.display()))
+            Err(_) => Err(PackedFileError::ReadTSVFile { path: tsv_file_path.to_path_buf() }.into())
         }
     }
 
@@ -686,10 +809,10 @@ impl SerializableToTSV for DBData {
             Ok(mut file) => {
                 match file.write_all(String::from_utf8(writer.into_inner()?)?.as_bytes()) {
                     Ok(_) => Ok(format!("DB PackedFile successfully exported:\n{}", packed_file_path.display())),
-                    Err(_) => Err(format_err!("Error while writing the following file to disk:\n{}", packed_file_path.display()))
+                    Err(_) => Err(PackedFileError::WriteFile { path: packed_file_path.to_path_buf() }.into())
                 }
             }
-            Err(_) => Err(format_err!("Error while trying to write the following file to disk:\n{}", packed_file_path.display()))
+            Err(_) => Err(PackedFileError::CreateFile { path: packed_file_path.to_path_buf() }.into())
         }
     }
 }
@@ -0,0 +1,534 @@
+// In this file is all the stuff needed to make and apply patches of DB Tables. A patch is a list of operations
+// (add, modify and delete rows) over the vanilla tables, identifying the rows by their keys and the cells by the
+// name of their columns, instead of their positions. This way, when a game update changes a vanilla table, we can
+// apply the same changes to the new version of the table, instead of redoing them by hand.
+//
+// Patches are generated by comparing the tables of a PackFile with the ones of the dependency database, and saved
+// as JSON. When applying them, everything that cannot be applied cleanly (rows or columns that no longer exist,
+// cells that vanilla changed too,...) is reported, so the user can check it.
+
+extern crate serde_json;
+extern crate failure;
+
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::fs::File;
+use std::io::{BufReader, Write};
+use std::path::PathBuf;
+
+use failure::Error;
+
+use packfile::packfile::{PackFile, PackedFile};
+use packedfile::error::PackedFileError;
+use super::{DB, DecodedData, get_entry};
+use super::schemas::{Schema, TableDefinition};
+
+/// `DBPatch`: This struct holds a patch for one or more tables. It's what we save in the patch files. It has:
+/// - tables: the patches of each table.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct DBPatch {
+    pub tables: Vec<TablePatch>,
+}
+
+/// `TablePatch`: This struct holds the patch of a table. It has:
+/// - table: the name of the table, with "_tables" at the end.
+/// - version: the version of the table when the patch was generated. It's informative only.
+/// - key_columns: the columns used to identify the rows.
+/// - operations: the operations to do to the vanilla table, in order.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct TablePatch {
+    pub table: String,
+    pub version: u32,
+    pub key_columns: Vec<String>,
+    pub operations: Vec<PatchOperation>,
+}
+
+/// This enum represents the operations we can do to a row of a vanilla table. Rows and keys are
+/// stored as "column name -> value" maps, with the values as text.
+/// - Add: adds a new row, with all his values.
+/// - Modify: changes some cells of the row with the provided keys.
+/// - Delete: deletes the row with the provided keys.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub enum PatchOperation {
+    Add { row: BTreeMap<String, String> },
+    Modify { key: BTreeMap<String, String>, changes: BTreeMap<String, CellChange> },
+    Delete { key: BTreeMap<String, String> },
+}
+
+/// `CellChange`: This struct holds a change to a cell. It has:
+/// - old: the vanilla value when the patch was generated, or None if the column didn't exist in vanilla.
+/// - new: the value we want in the cell.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct CellChange {
+    pub old: Option<String>,
+    pub new: String,
+}
+
+/// `PatchReport`: This struct holds the result of applying a patch. It has:
+/// - applied: the amount of operations applied.
+/// - conflicts: the operations applied over cells or rows vanilla changed too. Our value is the one we keep.
+/// - skipped: the operations or parts of operations we couldn't apply, and why.
+/// - tables: the tables generated by the patch.
+#[derive(Clone, Debug, Default)]
+pub struct PatchReport {
+    pub applied: usize,
+    pub conflicts: Vec<String>,
+    pub skipped: Vec<String>,
+    pub tables: Vec<Vec<String>>,
+}
+
+/// Implementation of `DBPatch`.
+impl DBPatch {
+
+    /// This function generates a patch with the differences between the tables of the provided PackedFiles and
+    /// the ones of the dependency database. If `with_deletes` is true, vanilla rows that are not in the tables
+    /// of the PackedFiles are deleted in the patch. Use it only for tables that replace the full vanilla table.
+    /// If `table` is provided, only that table is patched. It returns the patch and the tables that couldn't
+    /// be included in it, and why.
+    pub fn new(
+        packed_files: &[PackedFile],
+        dependency_database: &[PackedFile],
+        schema: &Schema,
+        table: Option<&str>,
+        with_deletes: bool,
+    ) -> Result<(Self, Vec<String>), Error> {
+        let table = table.map(|x| if x.ends_with("_tables") { x.to_owned() } else { format!("{}_tables", x) });
+
+        // Get the tables of the PackFile we want to patch, without duplicates.
+        let mut table_names: Vec<&str> = vec![];
+        for packed_file in packed_files.iter().filter(|x| x.path.len() >= 3 && x.path[0] == "db") {
+            if table.as_ref().map_or(true, |x| *x == packed_file.path[1]) && !table_names.contains(&&*packed_file.path[1]) {
+                table_names.push(&packed_file.path[1]);
+            }
+        }
+        if table_names.is_empty() {
+            return Err(PackedFileError::NoTablesToPatch.into());
+        }
+
+        let mut patch = Self::default();
+        let mut skipped_tables = vec![];
+        for table_name in table_names {
+            let modded = match decode_tables(packed_files, table_name, schema) {
+                Ok(tables) => tables,
+                Err(error) => {
+                    skipped_tables.push(format!("{}: {}", table_name, error));
+                    continue
+                }
+            };
+            let vanilla = decode_tables(dependency_database, table_name, schema).unwrap_or_else(|_| vec![]);
+            if vanilla.is_empty() {
+                skipped_tables.push(format!("{}: it's not in the Dependency Database, so there is nothing to patch.", table_name));
+                continue
+            }
+
+            match TablePatch::new(table_name, &modded, &vanilla, with_deletes) {
+                Ok(table_patch) => if !table_patch.operations.is_empty() { patch.tables.push(table_patch); },
+                Err(error) => skipped_tables.push(format!("{}: {}", table_name, error)),
+            }
+        }
+
+        Ok((patch, skipped_tables))
+    }
+
+    /// This function loads a patch from a file.
+    pub fn load(path: &PathBuf) -> Result<Self, Error> {
+        match File::open(path) {
+            Ok(file) => Ok(serde_json::from_reader(BufReader::new(file))?),
+            Err(_) => Err(PackedFileError::ReadFile { path: path.to_path_buf() }.into()),
+        }
+    }
+
+    /// This function saves the patch to a file, as JSON.
+    pub fn save(&self, path: &PathBuf) -> Result<(), Error> {
+        let json = serde_json::to_string_pretty(self)?;
+        match File::create(path) {
+            Ok(mut file) => match file.write_all(json.as_bytes()) {
+                Ok(_) => Ok(()),
+                Err(_) => Err(PackedFileError::WriteFile { path: path.to_path_buf() }.into()),
+            },
+            Err(_) => Err(PackedFileError::CreateFile { path: path.to_path_buf() }.into()),
+        }
+    }
+
+    /// This function applies the patch to the current tables of the dependency database, and puts the result
+    /// in the PackFile, at "db/<table>/<packed_file_name>", replacing the PackedFile if it already exists.
+    /// Only the rows changed by the patch are in the result, unless the patch deletes rows. In that case, the
+    /// result are the vanilla fragments without the deleted rows, with the same paths, so they replace them.
+    pub fn apply(
+        &self,
+        pack_file: &mut PackFile,
+        dependency_database: &[PackedFile],
+        schema: &Schema,
+        packed_file_name: &str,
+    ) -> Result<PatchReport, Error> {
+        let mut report = PatchReport::default();
+        let mut packed_files = vec![];
+        for table_patch in &self.tables {
+            let vanilla = match decode_tables(dependency_database, &table_patch.table, schema) {
+                Ok(vanilla) => vanilla,
+                Err(error) => {
+                    report.skipped.push(format!("{}: {}", table_patch.table, error));
+                    continue
+                }
+            };
+            if vanilla.is_empty() {
+                report.skipped.push(format!("{}: it's not in the Dependency Database, so the patch cannot be applied.", table_patch.table));
+                continue
+            }
+
+            let vanilla_paths = get_table_packed_files(dependency_database, &table_patch.table).iter().map(|x| x.path.to_vec()).collect::<Vec<Vec<String>>>();
+            for (fragment, db) in table_patch.apply(&vanilla, &mut report) {
+                let path = match fragment {
+                    Some(fragment) => vanilla_paths[fragment].to_vec(),
+                    None => vec!["db".to_owned(), table_patch.table.to_owned(), packed_file_name.to_owned()],
+                };
+                let data = db.save();
+                packed_files.push(PackedFile::read(data.len() as u32, path, data));
+            }
+        }
+
+        // Replace the PackedFiles that already exist, and add the new ones.
+        let mut new_packed_files = vec![];
+        report.tables = packed_files.iter().map(|x| x.path.to_vec()).collect::<Vec<Vec<String>>>();
+        for packed_file in packed_files {
+            match pack_file.data.packed_files.iter().position(|x| x.path == packed_file.path) {
+                Some(index) => pack_file.data.packed_files[index] = packed_file,
+                None => new_packed_files.push(packed_file),
+            }
+        }
+        pack_file.add_packedfiles(new_packed_files);
+
+        Ok(report)
+    }
+}
+
+/// Implementation of `TablePatch`.
+impl TablePatch {
+
+    /// This function generates the patch of a table, comparing the modded fragments of the table with the vanilla ones.
+    /// It fails if the table has no keys, as we need them to identify the rows.
+    pub fn new(table: &str, modded: &[DB], vanilla: &[DB], with_deletes: bool) -> Result<Self, Error> {
+        let table_definition = &modded[0].data.table_definition;
+        let key_columns = table_definition.fields.iter().filter(|x| x.field_is_key).map(|x| x.field_name.to_owned()).collect::<Vec<String>>();
+        if key_columns.is_empty() {
+            return Err(PackedFileError::TableWithoutKeys { table: table.to_owned() }.into());
+        }
+
+        // Index the vanilla rows by their keys. If a key is repeated, the first row wins, like in the game.
+        let vanilla_rows = vanilla.iter().flat_map(|x| get_named_rows(x)).collect::<Vec<BTreeMap<String, String>>>();
+        let mut vanilla_keys: HashMap<Vec<String>, usize> = HashMap::new();
+        for (index, row) in vanilla_rows.iter().enumerate() {
+            vanilla_keys.entry(get_key_values(row, &key_columns)).or_insert(index);
+        }
+
+        let mut operations = vec![];
+        let mut is_vanilla_row_found = vec![false; vanilla_rows.len()];
+        for row in modded.iter().flat_map(|x| get_named_rows(x)) {
+            match vanilla_keys.get(&get_key_values(&row, &key_columns)) {
+                Some(&index) => {
+                    is_vanilla_row_found[index] = true;
+                    let vanilla_row = &vanilla_rows[index];
+                    let changes = row.iter()
+                        .filter(|&(column, value)| vanilla_row.get(column) != Some(value))
+                        .map(|(column, value)| (column.to_owned(), CellChange { old: vanilla_row.get(column).cloned(), new: value.to_owned() }))
+                        .collect::<BTreeMap<String, CellChange>>();
+                    if !changes.is_empty() {
+                        operations.push(PatchOperation::Modify { key: get_key(&row, &key_columns), changes });
+                    }
+                }
+                None => operations.push(PatchOperation::Add { row }),
+            }
+        }
+
+        if with_deletes {
+            for (index, row) in vanilla_rows.iter().enumerate() {
+                if !is_vanilla_row_found[index] && vanilla_keys.get(&get_key_values(row, &key_columns)) == Some(&index) {
+                    operations.push(PatchOperation::Delete { key: get_key(row, &key_columns) });
+                }
+            }
+        }
+
+        Ok(Self {
+            table: table.to_owned(),
+            version: table_definition.version,
+            key_columns,
+            operations,
+        })
+    }
+
+    /// This function applies the patch to the vanilla fragments of the table, and returns the resulting tables, with
+    /// the position of the vanilla fragment they replace, if any. If the patch deletes rows, every vanilla fragment
+    /// is replaced without them, as otherwise the game would still load them. If not, there is only one new table
+    /// with the rows changed by the patch, or none if nothing changed. The results use the definition of the
+    /// first vanilla fragment.
+    pub fn apply(&self, vanilla: &[DB], report: &mut PatchReport) -> Vec<(Option<usize>, DB)> {
+        let table_definition = vanilla[0].data.table_definition.clone();
+        let version = vanilla[0].header.version;
+
+        // Get the positions of the key columns in the current version of the table.
+        let mut key_indexes = vec![];
+        for column in &self.key_columns {
+            match table_definition.fields.iter().position(|x| x.field_name == *column) {
+                Some(index) => key_indexes.push(index),
+                None => {
+                    report.skipped.push(format!("{}: the key column \"{}\" no longer exists, so the rows cannot be identified.", self.table, column));
+                    return vec![]
+                }
+            }
+        }
+
+        // Put all the vanilla rows together, with the current definition and the fragment they are from, and index them by their keys.
+        let mut rows = vec![];
+        let mut fragments = vec![];
+        for (fragment, db) in vanilla.iter().enumerate() {
+            rows.extend(db.data.entries.iter().map(|x| get_entry(&db.data.table_definition, x, &table_definition)));
+            fragments.extend(vec![fragment; db.data.entries.len()]);
+        }
+        let mut row_keys: HashMap<Vec<String>, usize> = HashMap::new();
+        for (index, row) in rows.iter().enumerate() {
+            row_keys.entry(key_indexes.iter().map(|x| row[*x].to_string()).collect()).or_insert(index);
+        }
+
+        let mut is_changed = vec![false; rows.len()];
+        let mut is_deleted = vec![false; rows.len()];
+        let mut has_deletes = false;
+        for operation in &self.operations {
+            match *operation {
+
+                // If the row already exists, we replace it with ours. Otherwise, we add it.
+                PatchOperation::Add { ref row } => {
+                    let row_name = format!("{}, row ({})", self.table, self.get_key_text(row));
+                    let mut entry = vec![];
+                    for field in &table_definition.fields {
+                        match row.get(&field.field_name) {
+                            Some(value) => match DecodedData::from_text(value, &field.field_type) {
+                                Ok(data) => entry.push(data),
+                                Err(_) => {
+                                    report.skipped.push(format!("{}: \"{}\" is not a valid value for the column \"{}\". The row has not been added.", row_name, value, field.field_name));
+                                    break
+                                }
+                            },
+                            None => {
+                                report.conflicts.push(format!("{}: the column \"{}\" is new, so it has his default value.", row_name, field.field_name));
+                                entry.push(DecodedData::default_for_field(field));
+                            }
+                        }
+                    }
+                    if entry.len() != table_definition.fields.len() { continue }
+                    for column in row.keys().filter(|x| !table_definition.fields.iter().any(|field| field.field_name == **x)) {
+                        report.conflicts.push(format!("{}: the column \"{}\" no longer exists, so his value has been lost.", row_name, column));
+                    }
+
+                    let key = key_indexes.iter().map(|x| entry[*x].to_string()).collect::<Vec<String>>();
+                    let existing_index = row_keys.get(&key).cloned();
+                    match existing_index {
+                        Some(index) if !is_deleted[index] => {
+                            report.conflicts.push(format!("{}: the row has been added to vanilla, so it has been replaced with ours.", row_name));
+                            rows[index] = entry;
+                            is_changed[index] = true;
+                        }
+                        _ => {
+                            row_keys.insert(key, rows.len());
+                            rows.push(entry);
+                            fragments.push(0);
+                            is_changed.push(true);
+                            is_deleted.push(false);
+                        }
+                    }
+                    report.applied += 1;
+                }
+
+                // Here we only change the cells of the patch, leaving the rest as they are now in vanilla.
+                PatchOperation::Modify { ref key, ref changes } => {
+                    let row_name = format!("{}, row ({})", self.table, self.get_key_text(key));
+                    let index = match row_keys.get(&get_key_values(key, &self.key_columns)) {
+                        Some(index) if !is_deleted[*index] => *index,
+                        _ => {
+                            report.skipped.push(format!("{}: the row no longer exists, so it cannot be modified.", row_name));
+                            continue
+                        }
+                    };
+
+                    // It only counts as applied if at least one of the cells has our value at the end.
+                    let mut is_applied = false;
+                    for (column, change) in changes {
+                        let column_index = match table_definition.fields.iter().position(|x| x.field_name == *column) {
+                            Some(column_index) => column_index,
+                            None => {
+                                report.skipped.push(format!("{}: the column \"{}\" no longer exists, so it cannot be changed to \"{}\".", row_name, column, change.new));
+                                continue
+                            }
+                        };
+
+                        let current_value = rows[index][column_index].to_string();
+                        if current_value == change.new {
+                            is_applied = true;
+                            continue
+                        }
+                        if change.old.as_ref() != Some(&current_value) {
+                            report.conflicts.push(format!(
+                                "{}: vanilla changed the column \"{}\" from \"{}\" to \"{}\". It has been changed to \"{}\".",
+                                row_name,
+                                column,
+                                change.old.as_ref().map_or("", |x| &**x),
+                                current_value,
+                                change.new
+                            ));
+                        }
+
+                        match DecodedData::from_text(&change.new, &table_definition.fields[column_index].field_type) {
+                            Ok(data) => {
+                                rows[index][column_index] = data;
+                                is_changed[index] = true;
+                                is_applied = true;
+                            }
+                            Err(_) => report.skipped.push(format!("{}: \"{}\" is not a valid value for the column \"{}\".", row_name, change.new, column)),
+                        }
+                    }
+                    if is_applied { report.applied += 1; }
+                }
+
+                PatchOperation::Delete { ref key } => {
+                    match row_keys.get(&get_key_values(key, &self.key_columns)) {
+                        Some(index) if !is_deleted[*index] => {
+                            is_deleted[*index] = true;
+                            has_deletes = true;
+                            report.applied += 1;
+                        }
+                        _ => report.skipped.push(format!("{}, row ({}): the row no longer exists, so there is nothing to delete.", self.table, self.get_key_text(key))),
+                    }
+                }
+            }
+        }
+
+        // Keep the rows we need. If we deleted something, we need all of them, in the fragment they are from.
+        // New rows go to the first fragment.
+        if has_deletes {
+            (0..vanilla.len()).map(|fragment| {
+                let entries = rows.iter().enumerate()
+                    .filter(|&(index, _)| !is_deleted[index] && fragments[index] == fragment)
+                    .map(|(_, row)| row.to_vec())
+                    .collect::<Vec<Vec<DecodedData>>>();
+                (Some(fragment), new_table(&self.table, version, &table_definition, entries))
+            }).collect()
+        }
+        else {
+            let entries = rows.into_iter().enumerate()
+                .filter(|&(index, _)| is_changed[index])
+                .map(|(_, row)| row)
+                .collect::<Vec<Vec<DecodedData>>>();
+            if entries.is_empty() { vec![] } else { vec![(None, new_table(&self.table, version, &table_definition, entries))] }
+        }
+    }
+
+    /// This function returns the keys of a row or key map as text, to identify the row in the reports.
+    fn get_key_text(&self, row: &BTreeMap<String, String>) -> String {
+        self.key_columns.iter().map(|x| format!("{}: {}", x, row.get(x).map_or("", |x| &**x))).collect::<Vec<String>>().join(", ")
+    }
+}
+
+/// This function returns the PackedFiles with the fragments of a table.
+fn get_table_packed_files<'a>(packed_files: &'a [PackedFile], table: &str) -> Vec<&'a PackedFile> {
+    packed_files.iter().filter(|x| x.path.len() >= 3 && x.path[0] == "db" && x.path[1] == table).collect()
+}
+
+/// This function decodes all the fragments of a table from the provided PackedFiles.
+fn decode_tables(packed_files: &[PackedFile], table: &str, schema: &Schema) -> Result<Vec<DB>, Error> {
+    let mut tables = vec![];
+    for packed_file in get_table_packed_files(packed_files, table) {
+        match DB::read(&packed_file.data, table, schema) {
+            Ok(db) => tables.push(db),
+            Err(_) => return Err(PackedFileError::UndecodeableTable { path: packed_file.path.to_vec() }.into()),
+        }
+    }
+    Ok(tables)
+}
+
+/// This function creates a table with the provided rows (without index), adding their index.
+fn new_table(table: &str, version: u32, table_definition: &TableDefinition, entries: Vec<Vec<DecodedData>>) -> DB {
+    let mut db = DB::new(table, version, table_definition.clone());
+    let count = entries.len().to_string().len() + 1;
+    db.data.entries = entries.into_iter().enumerate().map(|(index, mut entry)| {
+        entry.insert(0, DecodedData::Index(format!("{:0count$}", index + 1, count = count)));
+        entry
+    }).collect();
+    db
+}
+
+/// This function returns the rows of a table as "column name -> value" maps.
+fn get_named_rows(db: &DB) -> Vec<BTreeMap<String, String>> {
+    db.data.entries.iter().map(|entry| {
+        db.data.table_definition.fields.iter().enumerate().map(|(index, field)| (field.field_name.to_owned(), entry[index + 1].to_string())).collect()
+    }).collect()
+}
+
+/// This function returns the "column name -> value" map of the key columns of a row.
+fn get_key(row: &BTreeMap<String, String>, key_columns: &[String]) -> BTreeMap<String, String> {
+    key_columns.iter().map(|x| (x.to_owned(), row.get(x).cloned().unwrap_or_default())).collect()
+}
+
+/// This function returns the values of the key columns of a row, in the order of the key columns.
+fn get_key_values(row: &BTreeMap<String, String>, key_columns: &[String]) -> Vec<String> {
+    key_columns.iter().map(|x| row.get(x).cloned().unwrap_or_default()).collect()
+}
+
+/// Implementation of `Display` for `DBPatch`, so we can show what a patch does before applying it.
+impl fmt::Display for DBPatch {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.tables.is_empty() {
+            writeln!(f, "This patch doesn't change anything.")?;
+        }
+
+        for table_patch in &self.tables {
+            writeln!(f, "{} (version {}):", table_patch.table, table_patch.version)?;
+            for operation in &table_patch.operations {
+                match *operation {
+                    PatchOperation::Add { ref row } => writeln!(f, "  - Add ({}).", table_patch.get_key_text(row))?,
+                    PatchOperation::Modify { ref key, ref changes } => writeln!(
+                        f,
+                        "  - Modify ({}): {}.",
+                        table_patch.get_key_text(key),
+                        changes.iter().map(|(column, change)| format!("{}: \"{}\" -> \"{}\"", column, change.old.as_ref().map_or("", |x| &**x), change.new)).collect::<Vec<String>>().join(", ")
+                    )?,
+                    PatchOperation::Delete { ref key } => writeln!(f, "  - Delete ({}).", table_patch.get_key_text(key))?,
+                }
+            }
+            writeln!(f)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Implementation of `Display` for `PatchReport`, so we can show the result in the UI or print it in the terminal.
+impl fmt::Display for PatchReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "{} operations applied.", self.applied)?;
+        writeln!(f)?;
+
+        if !self.tables.is_empty() {
+            writeln!(f, "Tables generated:")?;
+            for path in &self.tables {
+                writeln!(f, "  - {}", path.join("/"))?;
+            }
+            writeln!(f)?;
+        }
+
+        if !self.conflicts.is_empty() {
+            writeln!(f, "Conflicts (applied, but check them):")?;
+            for conflict in &self.conflicts {
+                writeln!(f, "  - {}", conflict)?;
+            }
+            writeln!(f)?;
+        }
+
+        if !self.skipped.is_empty() {
+            writeln!(f, "Not applied:")?;
+            for skipped in &self.skipped {
+                writeln!(f, "  - {}", skipped)?;
+            }
+        }
+
+        Ok(())
+    }
+}
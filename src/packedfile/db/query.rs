@@ -0,0 +1,606 @@
+// In this file is the "Query" stuff for DB Tables. This allows us to search data in the tables using
+// queries like these ones:
+//
+// - land_units_tables where category = "inf_melee" and morale > 50
+// - land_units_tables join main_units_tables where caste = "lord" select land_units.key, main_units.unit
+// - unit_stats_land_tables where not (damage >= 30 or key contains "_cha_") select key, damage
+//
+// The syntax is: `<table> [join <table> [on <column>]]... [where <expression>] [select <column>, <column>...]`.
+// - Tables can be written with or without the "_tables" at the end.
+// - Joins are done following the references between the tables, in any direction. If there is more than
+//   one reference between the tables, use "on" with the name of the column to use. Joins only keep the
+//   rows that have a match in both tables.
+// - Columns can be written with the name of the table before them (land_units.key) or alone (key). If
+//   they are alone and more than one table has that column, the query fails, as we don't know which one to use.
+// - Expressions support "and", "or", "not", parenthesis and the following operators: =, !=, <, <=, >,
+//   >=, contains, starts_with and ends_with. If both sides of a comparison are finite numbers, the
+//   comparison is numeric. Otherwise, they are compared as text.
+//
+// The expressions alone are also used to filter the rows of the DB View.
+
+extern crate serde_json;
+
+use std::collections::HashMap;
+use std::cmp::Ordering;
+
+use failure::Error;
+
+use packfile::packfile::PackedFile;
+use packedfile::error::PackedFileError;
+use packedfile::serializers::decoded_data_to_json;
+use super::DB;
+use super::DecodedData;
+use super::schemas::*;
+
+/// `Query`: This struct holds a parsed query. It has:
+/// - table: the name of the main table, with "_tables" at the end.
+/// - joins: the tables we have to join to the main one, in order.
+/// - filter: the expression the rows must match, if any.
+/// - columns: the columns we have to return. If it's `None`, we return all of them.
+#[derive(Clone, Debug)]
+pub struct Query {
+    pub table: String,
+    pub joins: Vec<Join>,
+    pub filter: Option<Expression>,
+    pub columns: Option<Vec<String>>,
+}
+
+/// `Join`: This struct holds a join of a query. It has:
+/// - table: the name of the table to join, with "_tables" at the end.
+/// - column: the column of the reference to use for the join, if there are more than one.
+#[derive(Clone, Debug)]
+pub struct Join {
+    pub table: String,
+    pub column: Option<String>,
+}
+
+/// `Expression`: This enum holds a parsed expression. It can be:
+/// - And/Or: the two expressions must/can be true.
+/// - Not: the expression must be false.
+/// - Comparison: a comparison between two operands.
+#[derive(Clone, Debug)]
+pub enum Expression {
+    And(Box<Expression>, Box<Expression>),
+    Or(Box<Expression>, Box<Expression>),
+    Not(Box<Expression>),
+    Comparison(Operand, Operator, Operand),
+}
+
+/// `Operand`: This enum holds one of the sides of a comparison. It can be a column or a value.
+#[derive(Clone, Debug)]
+pub enum Operand {
+    Column(String),
+    Value(String),
+}
+
+/// `Operator`: This enum holds the operators we can use in a comparison.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Operator {
+    Equal,
+    NotEqual,
+    Less,
+    LessOrEqual,
+    Greater,
+    GreaterOrEqual,
+    Contains,
+    StartsWith,
+    EndsWith,
+}
+
+/// `QueryResult`: This struct holds the result of a query. It has:
+/// - columns: the names of the columns, in "table.column" format (table without "_tables").
+/// - rows: the rows that matched the query. Each row has one value for each column.
+#[derive(Clone, Debug)]
+pub struct QueryResult {
+    pub columns: Vec<String>,
+    pub rows: Vec<Vec<DecodedData>>,
+}
+
+/// `Token`: This enum holds the tokens of a query, so we don't have to parse text while building the query.
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    Word(String),
+    Text(String),
+    Symbol(String),
+    Comma,
+    OpenParenthesis,
+    CloseParenthesis,
+}
+
+/*
+--------------------------------------------------------
+                        Parsing
+--------------------------------------------------------
+*/
+
+/// Implementation of `Query`.
+impl Query {
+
+    /// This function parses a complete query.
+    pub fn parse(query: &str) -> Result<Self, Error> {
+        let tokens = tokenize(query)?;
+        let mut index = 0;
+
+        // First, the table.
+        let table = get_table_name(&expect_word(&tokens, &mut index)?);
+
+        // Then, the joins.
+        let mut joins = vec![];
+        while is_keyword(tokens.get(index), "join") {
+            index += 1;
+            let table = get_table_name(&expect_word(&tokens, &mut index)?);
+            let column = if is_keyword(tokens.get(index), "on") {
+                index += 1;
+                Some(expect_word(&tokens, &mut index)?)
+            } else { None };
+            joins.push(Join { table, column });
+        }
+
+        // Then, the filter.
+        let filter = if is_keyword(tokens.get(index), "where") {
+            index += 1;
+            Some(parse_or(&tokens, &mut index)?)
+        } else { None };
+
+        // And finally, the columns to return.
+        let columns = if is_keyword(tokens.get(index), "select") {
+            index += 1;
+            let mut columns = vec![expect_word(&tokens, &mut index)?];
+            while tokens.get(index) == Some(&Token::Comma) {
+                index += 1;
+                columns.push(expect_word(&tokens, &mut index)?);
+            }
+            if columns.len() == 1 && columns[0] == "*" { None } else { Some(columns) }
+        } else { None };
+
+        // If something is left, the query is wrong.
+        if let Some(token) = tokens.get(index) {
+            return Err(PackedFileError::InvalidExpression { message: format!("Unexpected {} in the query.", token) }.into());
+        }
+
+        Ok(Self {
+            table,
+            joins,
+            filter,
+            columns,
+        })
+    }
+}
+
+/// Implementation of `Expression`.
+impl Expression {
+
+    /// This function parses an expression alone, like the ones after the "where" of a query.
+    pub fn parse(expression: &str) -> Result<Self, Error> {
+        let tokens = tokenize(expression)?;
+        let mut index = 0;
+        let expression = parse_or(&tokens, &mut index)?;
+        match tokens.get(index) {
+            Some(token) => Err(PackedFileError::InvalidExpression { message: format!("Unexpected {} in the expression.", token) }.into()),
+            None => Ok(expression),
+        }
+    }
+
+    /// This function checks if a row matches the expression. It requires the names of the columns of
+    /// the row (in "table.column" or "column" format) and his values, in the same order.
+    pub fn evaluate(&self, columns: &[String], row: &[String]) -> Result<bool, Error> {
+        match *self {
+            Expression::And(ref left, ref right) => Ok(left.evaluate(columns, row)? && right.evaluate(columns, row)?),
+            Expression::Or(ref left, ref right) => Ok(left.evaluate(columns, row)? || right.evaluate(columns, row)?),
+            Expression::Not(ref expression) => Ok(!expression.evaluate(columns, row)?),
+            Expression::Comparison(ref left, ref operator, ref right) => {
+                let left = left.get_value(columns, row)?;
+                let right = right.get_value(columns, row)?;
+                Ok(match *operator {
+                    Operator::Equal => compare_values(left, right) == Ordering::Equal,
+                    Operator::NotEqual => compare_values(left, right) != Ordering::Equal,
+                    Operator::Less => compare_values(left, right) == Ordering::Less,
+                    Operator::LessOrEqual => compare_values(left, right) != Ordering::Greater,
+                    Operator::Greater => compare_values(left, right) == Ordering::Greater,
+                    Operator::GreaterOrEqual => compare_values(left, right) != Ordering::Less,
+                    Operator::Contains => left.contains(right),
+                    Operator::StartsWith => left.starts_with(right),
+                    Operator::EndsWith => left.ends_with(right),
+                })
+            }
+        }
+    }
+}
+
+/// Implementation of `Operand`.
+impl Operand {
+
+    /// This function returns the value of the operand for the provided row.
+    fn get_value<'a>(&'a self, columns: &[String], row: &'a [String]) -> Result<&'a str, Error> {
+        match *self {
+            Operand::Value(ref value) => Ok(value.as_str()),
+            Operand::Column(ref column) => Ok(row[get_column_index(columns, column)?].as_str()),
+        }
+    }
+}
+
+/// This function parses a list of expressions joined by "or".
+fn parse_or(tokens: &[Token], index: &mut usize) -> Result<Expression, Error> {
+    let mut expression = parse_and(tokens, index)?;
+    while is_keyword(tokens.get(*index), "or") {
+        *index += 1;
+        expression = Expression::Or(Box::new(expression), Box::new(parse_and(tokens, index)?));
+    }
+    Ok(expression)
+}
+
+/// This function parses a list of expressions joined by "and".
+fn parse_and(tokens: &[Token], index: &mut usize) -> Result<Expression, Error> {
+    let mut expression = parse_not(tokens, index)?;
+    while is_keyword(tokens.get(*index), "and") {
+        *index += 1;
+        expression = Expression::And(Box::new(expression), Box::new(parse_not(tokens, index)?));
+    }
+    Ok(expression)
+}
+
+/// This function parses a negated expression, an expression between parenthesis or a comparison.
+fn parse_not(tokens: &[Token], index: &mut usize) -> Result<Expression, Error> {
+    if is_keyword(tokens.get(*index), "not") {
+        *index += 1;
+        return Ok(Expression::Not(Box::new(parse_not(tokens, index)?)));
+    }
+
+    if tokens.get(*index) == Some(&Token::OpenParenthesis) {
+        *index += 1;
+        let expression = parse_or(tokens, index)?;
+        if tokens.get(*index) != Some(&Token::CloseParenthesis) {
+            return Err(PackedFileError::InvalidExpression { message: "Missing \")\" in the expression.".to_owned() }.into());
+        }
+        *index += 1;
+        return Ok(expression);
+    }
+
+    let left = parse_operand(tokens, index)?;
+    let operator = match tokens.get(*index) {
+        Some(&Token::Symbol(ref symbol)) => match &**symbol {
+            "=" | "==" => Operator::Equal,
+            "!=" | "<>" => Operator::NotEqual,
+            "<" => Operator::Less,
+            "<=" => Operator::LessOrEqual,
+            ">" => Operator::Greater,
+            ">=" => Operator::GreaterOrEqual,
+            _ => return Err(PackedFileError::InvalidExpression { message: format!("Unknown operator \"{}\".", symbol) }.into()),
+        },
+        Some(&Token::Word(ref word)) => match &*word.to_lowercase() {
+            "contains" => Operator::Contains,
+            "starts_with" => Operator::StartsWith,
+            "ends_with" => Operator::EndsWith,
+            _ => return Err(PackedFileError::InvalidExpression { message: format!("Unknown operator \"{}\".", word) }.into()),
+        },
+        Some(token) => return Err(PackedFileError::InvalidExpression { message: format!("Expected an operator, but found {}.", token) }.into()),
+        None => return Err(PackedFileError::InvalidExpression { message: "Expected an operator, but the expression ended.".to_owned() }.into()),
+    };
+    *index += 1;
+    let right = parse_operand(tokens, index)?;
+
+    Ok(Expression::Comparison(left, operator, right))
+}
+
+/// This function parses one of the sides of a comparison. Words starting with a number or a "-" and
+/// the words "true" and "false" are values. The rest of the words are columns.
+fn parse_operand(tokens: &[Token], index: &mut usize) -> Result<Operand, Error> {
+    let operand = match tokens.get(*index) {
+        Some(&Token::Text(ref text)) => Operand::Value(text.to_owned()),
+        Some(&Token::Word(ref word)) => {
+            if word.starts_with(|x: char| x.is_ascii_digit() || x == '-') || word == "true" || word == "false" {
+                Operand::Value(word.to_owned())
+            } else { Operand::Column(word.to_owned()) }
+        }
+        Some(token) => return Err(PackedFileError::InvalidExpression { message: format!("Expected a column or a value, but found {}.", token) }.into()),
+        None => return Err(PackedFileError::InvalidExpression { message: "Expected a column or a value, but the expression ended.".to_owned() }.into()),
+    };
+    *index += 1;
+    Ok(operand)
+}
+
+/// This function splits a query into tokens.
+fn tokenize(query: &str) -> Result<Vec<Token>, Error> {
+    let mut tokens = vec![];
+    let mut chars = query.chars().peekable();
+    while let Some(character) = chars.next() {
+        match character {
+            ' ' | '\t' | '\n' | '\r' => continue,
+            ',' => tokens.push(Token::Comma),
+            '(' => tokens.push(Token::OpenParenthesis),
+            ')' => tokens.push(Token::CloseParenthesis),
+
+            // Texts can be between double or single quotes, and use "\" to escape them.
+            '"' | '\'' => {
+                let mut text = String::new();
+                let mut closed = false;
+                while let Some(character_text) = chars.next() {
+                    if character_text == '\\' {
+                        if let Some(escaped) = chars.next() { text.push(escaped); }
+                    }
+                    else if character_text == character { closed = true; break; }
+                    else { text.push(character_text); }
+                }
+                if !closed { return Err(PackedFileError::InvalidExpression { message: "There is an unclosed text in the query.".to_owned() }.into()); }
+                tokens.push(Token::Text(text));
+            }
+
+            '=' | '!' | '<' | '>' => {
+                let mut symbol = character.to_string();
+                if let Some(&next) = chars.peek() {
+                    if next == '=' || (character == '<' && next == '>') {
+                        symbol.push(next);
+                        chars.next();
+                    }
+                }
+                tokens.push(Token::Symbol(symbol));
+            }
+
+            _ => {
+                if !is_word_character(character) { return Err(PackedFileError::InvalidExpression { message: format!("Unexpected character \"{}\" in the query.", character) }.into()); }
+                let mut word = character.to_string();
+                while let Some(&next) = chars.peek() {
+                    if is_word_character(next) { word.push(next); chars.next(); }
+                    else { break; }
+                }
+                tokens.push(Token::Word(word));
+            }
+        }
+    }
+    Ok(tokens)
+}
+
+/// This function returns if the character can be part of a word (table, column, number,...).
+fn is_word_character(character: char) -> bool {
+    character.is_alphanumeric() || character == '_' || character == '.' || character == '-' || character == '*'
+}
+
+/// This function returns if the token is the provided keyword. Keywords are case-insensitive.
+fn is_keyword(token: Option<&Token>, keyword: &str) -> bool {
+    match token {
+        Some(&Token::Word(ref word)) => word.to_lowercase() == keyword,
+        _ => false,
+    }
+}
+
+/// This function returns the word at `index` and advances the index, or an error if it's not a word.
+fn expect_word(tokens: &[Token], index: &mut usize) -> Result<String, Error> {
+    match tokens.get(*index) {
+        Some(&Token::Word(ref word)) => { *index += 1; Ok(word.to_owned()) }
+        Some(token) => Err(PackedFileError::InvalidExpression { message: format!("Expected a name, but found {}.", token) }.into()),
+        None => Err(PackedFileError::InvalidExpression { message: "Expected a name, but the query ended.".to_owned() }.into()),
+    }
+}
+
+/// Implementation of `Display` for `Token`, for the error messages.
+impl ::std::fmt::Display for Token {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+        match *self {
+            Token::Word(ref word) => write!(f, "\"{}\"", word),
+            Token::Text(ref text) => write!(f, "the text \"{}\"", text),
+            Token::Symbol(ref symbol) => write!(f, "\"{}\"", symbol),
+            Token::Comma => write!(f, "\",\""),
+            Token::OpenParenthesis => write!(f, "\"(\""),
+            Token::CloseParenthesis => write!(f, "\")\""),
+        }
+    }
+}
+
+/*
+--------------------------------------------------------
+                        Execution
+--------------------------------------------------------
+*/
+
+/// Implementation of `Query`.
+impl Query {
+
+    /// This function executes the query over the tables of the provided PackedFiles. If the same table is in
+    /// more than one PackedFile, his rows are put together, in the same order the PackedFiles are.
+    pub fn execute(&self, packed_files: &[PackedFile], schema: &Schema) -> Result<QueryResult, Error> {
+
+        // Get the main table.
+        let (definition, rows) = get_table_data(&self.table, packed_files, schema)?;
+        let mut tables = vec![(self.table.to_owned(), definition.to_owned())];
+        let mut result = QueryResult {
+            columns: get_column_names(&self.table, &definition),
+            rows,
+        };
+
+        // Do all the joins, one by one.
+        for join in &self.joins {
+            let (join_definition, join_rows) = get_table_data(&join.table, packed_files, schema)?;
+            let (left_column, right_column) = get_join_columns(&tables, &join.table, &join_definition, &join.column)?;
+            let left_index = get_column_index(&result.columns, &left_column)?;
+            let right_index = match join_definition.fields.iter().position(|x| x.field_name == right_column) {
+                Some(right_index) => right_index,
+                None => return Err(PackedFileError::ColumnNotFound { column: format!("{}.{}", get_short_table_name(&join.table), right_column) }.into()),
+            };
+
+            // Index the rows of the joined table by the value of the column, so we don't have to search them every time.
+            let mut join_index: HashMap<String, Vec<usize>> = HashMap::new();
+            for (index, row) in join_rows.iter().enumerate() {
+                join_index.entry(row[right_index].to_string()).or_insert_with(Vec::new).push(index);
+            }
+
+            let mut rows = vec![];
+            for row in &result.rows {
+                if let Some(matches) = join_index.get(&row[left_index].to_string()) {
+                    for index in matches {
+                        let mut new_row = row.to_vec();
+                        new_row.extend_from_slice(&join_rows[*index]);
+                        rows.push(new_row);
+                    }
+                }
+            }
+
+            result.columns.append(&mut get_column_names(&join.table, &join_definition));
+            result.rows = rows;
+            tables.push((join.table.to_owned(), join_definition));
+        }
+
+        // Filter the rows.
+        if let Some(ref filter) = self.filter {
+            let mut rows = vec![];
+            for row in result.rows {
+                let row_text = row.iter().map(|x| x.to_string()).collect::<Vec<String>>();
+                if filter.evaluate(&result.columns, &row_text)? { rows.push(row); }
+            }
+            result.rows = rows;
+        }
+
+        // And get only the columns we want.
+        if let Some(ref columns) = self.columns {
+            let mut indexes = vec![];
+            for column in columns {
+                indexes.push(get_column_index(&result.columns, column)?);
+            }
+
+            result.columns = indexes.iter().map(|x| result.columns[*x].to_owned()).collect();
+            result.rows = result.rows.iter().map(|row| indexes.iter().map(|x| row[*x].clone()).collect()).collect();
+        }
+
+        Ok(result)
+    }
+}
+
+/// Implementation of `QueryResult`.
+impl QueryResult {
+
+    /// This function returns the result as TSV, with the names of the columns in the first line.
+    pub fn to_tsv(&self) -> String {
+        let mut tsv = self.columns.join("\t");
+        tsv.push('\n');
+        for row in &self.rows {
+            tsv.push_str(&row.iter().map(|x| x.to_string().replace('\t', " ").replace('\n', " ")).collect::<Vec<String>>().join("\t"));
+            tsv.push('\n');
+        }
+        tsv
+    }
+
+    /// This function returns the result as a JSON array, with an object for each row.
+    pub fn to_json(&self) -> Result<String, Error> {
+        let rows = self.rows.iter().map(|row| {
+            let mut object = serde_json::Map::new();
+            for (column, value) in self.columns.iter().zip(row.iter()) {
+                object.insert(column.to_owned(), decoded_data_to_json(value));
+            }
+            serde_json::Value::Object(object)
+        }).collect::<Vec<serde_json::Value>>();
+
+        Ok(serde_json::to_string_pretty(&rows)?)
+    }
+}
+
+/// This function returns the definition and the rows (without the index) of a table from the provided PackedFiles.
+fn get_table_data(table: &str, packed_files: &[PackedFile], schema: &Schema) -> Result<(TableDefinition, Vec<Vec<DecodedData>>), Error> {
+    let mut definition: Option<TableDefinition> = None;
+    let mut rows = vec![];
+    for packed_file in packed_files.iter().filter(|x| x.path.len() >= 3 && x.path[0] == "db" && x.path[1] == table) {
+        let db = DB::read(&packed_file.data, table, schema)?;
+
+        // We cannot mix rows from different versions of the table, so we only accept the version of the first one.
+        if let Some(ref definition) = definition {
+            if definition.version != db.data.table_definition.version {
+                return Err(PackedFileError::MixedTableVersions { table: table.to_owned(), version: definition.version, other_version: db.data.table_definition.version }.into());
+            }
+        }
+        rows.extend(db.data.entries.iter().map(|x| x[1..].to_vec()));
+        definition = Some(db.data.table_definition);
+    }
+
+    match definition {
+        Some(definition) => Ok((definition, rows)),
+        None => Err(PackedFileError::TableNotFound { table: table.to_owned() }.into()),
+    }
+}
+
+/// This function returns the columns (left, right) we have to use to join a table to the tables we already have.
+/// The left column is returned in "table.column" format.
+fn get_join_columns(
+    tables: &[(String, TableDefinition)],
+    join_table: &str,
+    join_definition: &TableDefinition,
+    column: &Option<String>
+) -> Result<(String, String), Error> {
+
+    let mut candidates = vec![];
+    let join_short_name = get_short_table_name(join_table);
+    for &(ref table, ref definition) in tables {
+        let short_name = get_short_table_name(table);
+
+        // References from the tables we have to the new one... Schemas have references to columns that don't exist,
+        // so we ignore them.
+        for field in &definition.fields {
+            if let Some(ref reference) = field.field_is_reference {
+                if reference.0 == join_short_name && join_definition.fields.iter().any(|x| x.field_name == reference.1) {
+                    candidates.push((format!("{}.{}", short_name, field.field_name), reference.1.to_owned(), field.field_name.to_owned()));
+                }
+            }
+        }
+
+        // And references from the new one to the tables we have.
+        for field in &join_definition.fields {
+            if let Some(ref reference) = field.field_is_reference {
+                if reference.0 == short_name && definition.fields.iter().any(|x| x.field_name == reference.1) {
+                    candidates.push((format!("{}.{}", short_name, reference.1), field.field_name.to_owned(), field.field_name.to_owned()));
+                }
+            }
+        }
+    }
+
+    // If we have a column, only use the references on that column.
+    if let Some(ref column) = *column {
+        candidates.retain(|x| &x.2 == column || x.0.ends_with(&format!(".{}", column)));
+    }
+
+    match candidates.len() {
+        0 => Err(PackedFileError::NoJoinReference { table: join_table.to_owned() }.into()),
+        1 => Ok((candidates[0].0.to_owned(), candidates[0].1.to_owned())),
+        _ => Err(PackedFileError::AmbiguousJoinReference { table: join_table.to_owned(), columns: candidates.iter().map(|x| x.2.to_owned()).collect() }.into()),
+    }
+}
+
+/// This function returns the names of the columns of a table, in "table.column" format.
+fn get_column_names(table: &str, definition: &TableDefinition) -> Vec<String> {
+    let short_name = get_short_table_name(table);
+    definition.fields.iter().map(|x| format!("{}.{}", short_name, x.field_name)).collect()
+}
+
+/// This function returns the index of a column. The column can be in "table.column" or "column" format, and
+/// the table can be written with or without "_tables". The table and the column must match entirely, so "units.key"
+/// doesn't match "land_units.key". If the column is in "column" format and more than one table has it, it fails.
+pub fn get_column_index(columns: &[String], column: &str) -> Result<usize, Error> {
+    let matches = match column.find('.') {
+        Some(dot) => {
+            let name = format!("{}{}", get_short_table_name(&column[..dot]), &column[dot..]);
+            columns.iter().enumerate().filter(|&(_, x)| *x == name).map(|(index, _)| index).collect::<Vec<usize>>()
+        }
+        None => columns.iter().enumerate().filter(|&(_, x)| x.rsplit('.').next() == Some(column)).map(|(index, _)| index).collect::<Vec<usize>>(),
+    };
+
+    match matches.len() {
+        0 => Err(PackedFileError::ColumnNotFound { column: column.to_owned() }.into()),
+        1 => Ok(matches[0]),
+        _ => Err(PackedFileError::AmbiguousColumn { column: column.to_owned(), columns: matches.iter().map(|x| columns[*x].to_owned()).collect() }.into()),
+    }
+}
+
+/// This function returns the full name of a table, with "_tables" at the end.
+fn get_table_name(table: &str) -> String {
+    if table.ends_with("_tables") { table.to_owned() } else { format!("{}_tables", table) }
+}
+
+/// This function returns the short name of a table, without "_tables" at the end.
+fn get_short_table_name(table: &str) -> &str {
+    if table.ends_with("_tables") { &table[..table.len() - 7] } else { table }
+}
+
+/// This function compares two values. If both are finite numbers, they are compared as numbers. Otherwise, as
+/// text, so texts like "inf" or "nan" are not treated as numbers.
+fn compare_values(left: &str, right: &str) -> Ordering {
+    match (left.parse::<f64>(), right.parse::<f64>()) {
+        (Ok(left_number), Ok(right_number)) if left_number.is_finite() && right_number.is_finite() => left_number.partial_cmp(&right_number).unwrap_or(Ordering::Equal),
+        _ => left.cmp(right),
+    }
+}
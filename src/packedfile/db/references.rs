@@ -0,0 +1,192 @@
+// In this file we define the "Reference Report" for DB Tables. This report checks every cell that
+// references another table (the ones with `field_is_reference`) and reports the ones whose value
+// doesn't exist neither in the PackFile nor in the dependency database, as the game usually crashes
+// on start (or worse, in the middle of a campaign) with broken references.
+
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+use packfile::packfile::PackedFile;
+use super::DB;
+use super::DBData;
+use super::schemas::Schema;
+
+/// `ReferenceData`: This type holds all the values of each referenced column, indexed by (table_name, column_name).
+/// The table name is the complete one, with the "_tables" at the end.
+pub type ReferenceData = HashMap<(String, String), HashSet<String>>;
+
+/// `ReferenceReport`: This struct holds the result of checking the references of the tables of a
+/// PackFile. It has:
+/// - broken_references: the cells whose referenced value doesn't exist.
+/// - undecodeable_tables: tables we couldn't decode, so we couldn't check them.
+#[derive(Clone, Debug)]
+pub struct ReferenceReport {
+    pub broken_references: Vec<BrokenReference>,
+    pub undecodeable_tables: Vec<Vec<String>>,
+}
+
+/// `BrokenReference`: This struct holds the info of one of the broken cells of the report. It has:
+/// - path: the path of the table in the PackFile.
+/// - row: the row of the cell, starting from 0.
+/// - column: the name of the column of the cell.
+/// - value: the value of the cell.
+/// - reference: the (table, column) the cell is referencing, as it's in the schema.
+#[derive(Clone, Debug)]
+pub struct BrokenReference {
+    pub path: Vec<String>,
+    pub row: usize,
+    pub column: String,
+    pub value: String,
+    pub reference: (String, String),
+}
+
+/// Implementation of `ReferenceReport`.
+impl ReferenceReport {
+
+    /// This function checks the references of every table in the provided list of PackedFiles, using
+    /// both, the PackedFiles and the dependency database, as source of valid values.
+    pub fn new(
+        packed_files: &[PackedFile],
+        dependency_database: &[PackedFile],
+        schema: &Schema,
+    ) -> Self {
+
+        let mut broken_references = vec![];
+        let mut undecodeable_tables = vec![];
+
+        // First, decode all the tables of our PackFile.
+        let mut tables = vec![];
+        for packed_file in packed_files.iter().filter(|x| x.path.len() >= 3 && x.path[0] == "db") {
+            match DB::read(&packed_file.data, &packed_file.path[1], schema) {
+                Ok(db) => tables.push((packed_file.path.to_vec(), db)),
+                Err(_) => undecodeable_tables.push(packed_file.path.to_vec()),
+            }
+        }
+
+        // Get the list of all the references we need to check, and the values we can use for them.
+        let mut references = vec![];
+        for &(_, ref db) in &tables {
+            for reference in db.data.table_definition.fields.iter().filter_map(|x| x.field_is_reference.clone()) {
+                if !references.contains(&reference) { references.push(reference); }
+            }
+        }
+        let reference_data = get_reference_data(&references, packed_files, dependency_database, schema);
+
+        // Then, check every table against them.
+        for (path, db) in tables {
+            for (row, column) in get_broken_references(&db.data, &reference_data) {
+                let field = &db.data.table_definition.fields[column];
+                broken_references.push(BrokenReference {
+                    path: path.to_vec(),
+                    row,
+                    column: field.field_name.to_owned(),
+                    value: db.data.entries[row][column + 1].to_string(),
+                    reference: field.field_is_reference.clone().unwrap(),
+                });
+            }
+        }
+
+        Self {
+            broken_references,
+            undecodeable_tables,
+        }
+    }
+
+    /// This function returns true if there are no problems in the report.
+    pub fn is_empty(&self) -> bool {
+        self.broken_references.is_empty() && self.undecodeable_tables.is_empty()
+    }
+}
+
+/// This function gets all the values of the provided references, from the provided PackedFiles and the
+/// dependency database. The references must be in the same format as in the schema: (table without "_tables", column).
+pub fn get_reference_data(
+    references: &[(String, String)],
+    packed_files: &[PackedFile],
+    dependency_database: &[PackedFile],
+    schema: &Schema,
+) -> ReferenceData {
+
+    let mut reference_data: ReferenceData = HashMap::new();
+
+    // For each table in the database and in our PackedFiles...
+    for table in dependency_database.iter().chain(packed_files.iter()).filter(|x| x.path.len() >= 3 && x.path[0] == "db") {
+
+        // If it's one of the referenced tables, and we can decode it...
+        let columns = references.iter().filter(|x| format!("{}_tables", x.0) == table.path[1]).map(|x| x.1.to_owned()).collect::<Vec<String>>();
+        if columns.is_empty() { continue }
+        if let Ok(db) = DB::read(&table.data, &table.path[1], schema) {
+
+            // Get all the values of the referenced columns.
+            for (index, field) in db.data.table_definition.fields.iter().enumerate() {
+                if columns.contains(&field.field_name) {
+                    let values = reference_data.entry((table.path[1].to_owned(), field.field_name.to_owned())).or_insert_with(HashSet::new);
+                    for row in &db.data.entries {
+                        values.insert(row[index + 1].to_string());
+                    }
+                }
+            }
+        }
+    }
+
+    reference_data
+}
+
+/// This function returns the (row, column) of every cell in the provided table whose referenced value
+/// is not in the provided `ReferenceData`. The columns are the index of the field in the table definition.
+///
+/// Empty cells are not considered broken, as the game uses them as "no reference". Also, if we don't have
+/// data for a reference at all (the referenced table is not in the PackFile nor in the dependency database)
+/// we cannot say if the value is wrong, so these columns are not checked.
+pub fn get_broken_references(data: &DBData, reference_data: &ReferenceData) -> Vec<(usize, usize)> {
+    let mut broken_references = vec![];
+    for (column, field) in data.table_definition.fields.iter().enumerate() {
+        if let Some(ref reference) = field.field_is_reference {
+            if let Some(values) = reference_data.get(&(format!("{}_tables", reference.0), reference.1.to_owned())) {
+                for (row, entry) in data.entries.iter().enumerate() {
+                    let value = entry[column + 1].to_string();
+                    if !value.is_empty() && !values.contains(&value) {
+                        broken_references.push((row, column));
+                    }
+                }
+            }
+        }
+    }
+
+    broken_references.sort();
+    broken_references
+}
+
+/// Implementation of `Display` for `ReferenceReport`, so we can show it in the UI or print it in the terminal.
+impl fmt::Display for ReferenceReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+
+        if self.is_empty() {
+            return writeln!(f, "All the references in this PackFile are valid.");
+        }
+
+        if !self.broken_references.is_empty() {
+            writeln!(f, "Cells referencing values that don't exist:")?;
+            for reference in &self.broken_references {
+                writeln!(f, "  - {}, row {}, column \"{}\": \"{}\" not found in \"{}/{}\".",
+                    reference.path.join("/"),
+                    reference.row + 1,
+                    reference.column,
+                    reference.value,
+                    reference.reference.0,
+                    reference.reference.1
+                )?;
+            }
+            writeln!(f)?;
+        }
+
+        if !self.undecodeable_tables.is_empty() {
+            writeln!(f, "Tables that couldn't be decoded, so they haven't been checked:")?;
+            for path in &self.undecodeable_tables {
+                writeln!(f, "  - {}", path.join("/"))?;
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -2,13 +2,36 @@
 extern crate serde_json;
 extern crate failure;
 
+use std::fmt;
 use std::path::PathBuf;
-use std::fs::File;
+use std::fs::{self, File, DirBuilder};
 use std::io::Write;
 use std::io::BufReader;
 
 use self::failure::Error;
+use packedfile::error::PackedFileError;
 use super::schemas_importer;
+use settings::GameInfo;
+
+/// Columns of the Assembly Kit tables that are not in the final tables, but in the Locs, as "<table>_<column>_<key>".
+pub const LOCALISED_COLUMNS: &[&str] = &[
+    "localised_text",
+    "localised_name",
+    "localised_tooltip",
+    "description",
+    "objectives_team_1",
+    "objectives_team_2",
+    "short_description_text",
+    "historical_description_text",
+    "strengths_weaknesses_text",
+    "onscreen",
+    "onscreen_text",
+    "onscreen_name",
+    "onscreen_description",
+    "on_screen_name",
+    "on_screen_description",
+    "on_screen_target",
+];
 
 /// This struct holds the entire schema for the currently selected game (by "game" I mean the PackFile
 /// Type).
@@ -16,15 +39,21 @@ use super::schemas_importer;
 /// - game: the game for what the loaded definitions are intended.
 /// - version: custom variable to keep track of the updates to the schema.
 /// - tables_definition: the actual definitions.
-#[derive(Clone, Debug, Serialize, Deserialize)]
+/// - removed_versions: the versions of the tables (table, version) the user removed from the downloaded schema. Only
+///   used in the user's overlay, so the removed versions are not brought back from the downloaded schema when loading it.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct Schema {
     pub tables_definitions: Vec<TableDefinitions>,
+
+    // This is optional in the schema files, and only written if there is something, so the schemas stay the same.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub removed_versions: Vec<(String, u32)>,
 }
 
 /// This struct holds the definitions for a table. It has:
 /// - name: the name of the table.
 /// - versions: the different versions this table has.
-#[derive(Clone, Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct TableDefinitions {
     pub name: String,
     pub versions: Vec<TableDefinition>,
@@ -33,7 +62,7 @@ pub struct TableDefinitions {
 /// This struct holds the definitions for a version of a table. It has:
 /// - version: the version of the table these definitions are for.
 /// - fields: the different fields this table has.
-#[derive(Clone, Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct TableDefinition {
     pub version: u32,
     pub fields: Vec<Field>,
@@ -44,17 +73,41 @@ pub struct TableDefinition {
 /// - field_is_key: true if the field is a key field and his column needs to be put in the beginning of the TreeView.
 /// - field_is_reference: if this field is a reference of another, this has (table name, field name).
 /// - field_type: the type of the field.
-#[derive(Clone, Debug, Serialize, Deserialize)]
+/// - field_description: a description of what the field does, if we know it.
+/// - field_is_required: true if the field cannot be empty. Only used for validation.
+/// - field_max_length: the maximum amount of characters a String field can have, if it has a limit. Only used for validation.
+/// - field_default_value: the value new rows get in this field, as text. If there is none, they get the default of the type.
+/// - field_min_value: the minimum value a numeric field can have, if it has a limit.
+/// - field_max_value: the maximum value a numeric field can have, if it has a limit.
+/// - field_enum_values: the only values the field can have. If it's empty, the field can have any value.
+/// - field_is_filename: true if the field is a path to a file, so it cannot have characters not allowed in paths.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct Field {
     pub field_name: String,
     pub field_type: FieldType,
     pub field_is_key: bool,
     pub field_is_reference: Option<(String, String)>,
     pub field_description: String,
+
+    // These are optional in the schema files, so old schemas can still be loaded.
+    #[serde(default)]
+    pub field_is_required: bool,
+    #[serde(default)]
+    pub field_max_length: Option<u32>,
+    #[serde(default)]
+    pub field_default_value: Option<String>,
+    #[serde(default)]
+    pub field_min_value: Option<f64>,
+    #[serde(default)]
+    pub field_max_value: Option<f64>,
+    #[serde(default)]
+    pub field_enum_values: Vec<String>,
+    #[serde(default)]
+    pub field_is_filename: bool,
 }
 
 /// Enum FieldType: This enum is used to define the possible types of a field in the schema.
-#[derive(Clone, Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub enum FieldType {
     Boolean,
     Float,
@@ -66,6 +119,32 @@ pub enum FieldType {
     OptionalStringU16,
 }
 
+/// This struct holds the report of merging external definitions (like the ones from the Assembly Kit)
+/// into an `Schema`. Each entry is a line with the table, his version and, if needed, the reason. It has:
+/// - added: the tables or versions that didn't exist in the schema and have been added.
+/// - changed: the versions that already existed and have been updated (names, keys, references,...).
+/// - conflicts: the versions that already existed with a different structure, and have been left untouched.
+/// - errors: the definitions that couldn't be read or merged.
+#[derive(Clone, Debug, Default)]
+pub struct SchemaMergeReport {
+    pub added: Vec<String>,
+    pub changed: Vec<String>,
+    pub conflicts: Vec<String>,
+    pub errors: Vec<String>,
+}
+
+/// This struct holds the state of the user's overlay of an schema, compared with the base schema. Each entry
+/// is a line with the table and his version. It has:
+/// - identical: the definitions of the overlay that are now in the base schema, exactly as they are in the overlay.
+/// - different: the definitions of the overlay that are now in the base schema, but different.
+/// - only_in_overlay: the definitions of the overlay that are not in the base schema yet.
+#[derive(Clone, Debug, Default)]
+pub struct SchemaOverlayReport {
+    pub identical: Vec<String>,
+    pub different: Vec<String>,
+    pub only_in_overlay: Vec<String>,
+}
+
 /// Implementation of "Schema"
 impl Schema {
 
@@ -76,6 +155,7 @@ impl Schema {
 
         Schema {
             tables_definitions,
+            removed_versions: vec![],
         }
     }
 
@@ -112,35 +192,314 @@ impl Schema {
         None
     }
 
-    /// This function takes an schema file and reads it into a "Schema" object.
-    pub fn load(rpfm_path: &PathBuf, schema_file: &str) -> Result<Schema, Error> {
+    /// This function merges the provided definition of a table into the schema, keeping what we already
+    /// know about it, and writes what happened into the report:
+    /// - If the table or the version doesn't exist in the schema, it's added.
+    /// - If the version exists and has the same structure (same amount of fields, with the same types), the
+    ///   names, keys, references and constraints of the fields are updated. The descriptions are only
+    ///   updated if we didn't have one.
+    /// - If the version exists with a different structure, we keep ours, as it's the one that decodes the
+    ///   table, and report it as a conflict.
+    /// Versions that are not in the new definitions (for example, manually decoded ones) are never touched.
+    pub fn merge_table_definition(&mut self, table_name: &str, table_definition: TableDefinition, report: &mut SchemaMergeReport) {
+        let version = table_definition.version;
+        let index = match self.get_table_definitions(table_name) {
+            Some(index) => index,
+            None => {
+                self.add_table_definitions(TableDefinitions::new(table_name));
+                self.tables_definitions.len() - 1
+            }
+        };
+
+        let table_definitions = &mut self.tables_definitions[index];
+        let version_index = match table_definitions.get_table_version(version) {
+            Some(version_index) => version_index,
+            None => {
+                table_definitions.add_table_definition(table_definition);
+                report.added.push(format!("{} (v{})", table_name, version));
+                return
+            }
+        };
 
-        let mut schemas_path = rpfm_path.clone();
-        schemas_path.push("schemas");
+        // If the structure is different, we keep our version.
+        let current_definition = &mut table_definitions.versions[version_index];
+        if current_definition.fields.len() != table_definition.fields.len() {
+            report.conflicts.push(format!("{} (v{}): {} fields in the schema, {} in the new definition.", table_name, version, current_definition.fields.len(), table_definition.fields.len()));
+            return
+        }
 
-        // We load the provided schema file.
-        let schema_file = BufReader::new(File::open(PathBuf::from(format!("{}/{}", schemas_path.to_string_lossy(), schema_file)))?);
+        if let Some((position, (current_field, new_field))) = current_definition.fields.iter().zip(table_definition.fields.iter()).enumerate().find(|&(_, (current_field, new_field))| current_field.field_type != new_field.field_type) {
+            report.conflicts.push(format!("{} (v{}): the field {} is \"{}\" ({:?}) in the schema, but \"{}\" ({:?}) in the new definition.", table_name, version, position + 1, current_field.field_name, current_field.field_type, new_field.field_name, new_field.field_type));
+            return
+        }
 
-        let schema = serde_json::from_reader(schema_file)?;
-        Ok(schema)
+        // Same structure, so we update the fields, keeping our descriptions.
+        let mut changes = vec![];
+        for (current_field, new_field) in current_definition.fields.iter_mut().zip(table_definition.fields.into_iter()) {
+            if current_field.field_name != new_field.field_name {
+                changes.push(format!("\"{}\" renamed to \"{}\"", current_field.field_name, new_field.field_name));
+                current_field.field_name = new_field.field_name.to_owned();
+            }
+            if current_field.field_is_key != new_field.field_is_key {
+                changes.push(format!("\"{}\" key changed", current_field.field_name));
+                current_field.field_is_key = new_field.field_is_key;
+            }
+            if current_field.field_is_reference != new_field.field_is_reference {
+                changes.push(format!("\"{}\" reference changed", current_field.field_name));
+                current_field.field_is_reference = new_field.field_is_reference;
+            }
+            if current_field.field_is_required != new_field.field_is_required || current_field.field_max_length != new_field.field_max_length {
+                changes.push(format!("\"{}\" constraints changed", current_field.field_name));
+                current_field.field_is_required = new_field.field_is_required;
+                current_field.field_max_length = new_field.field_max_length;
+            }
+
+            // The rest of the constraints can be set by hand, so we only replace them if the new definition has them.
+            if new_field.field_default_value.is_some() && current_field.field_default_value != new_field.field_default_value {
+                changes.push(format!("\"{}\" default value changed", current_field.field_name));
+                current_field.field_default_value = new_field.field_default_value.to_owned();
+            }
+            if new_field.field_is_filename && !current_field.field_is_filename {
+                changes.push(format!("\"{}\" is now a path", current_field.field_name));
+                current_field.field_is_filename = true;
+            }
+            if current_field.field_description.is_empty() && !new_field.field_description.is_empty() {
+                changes.push(format!("\"{}\" description added", current_field.field_name));
+                current_field.field_description = new_field.field_description;
+            }
+        }
+
+        if !changes.is_empty() {
+            report.changed.push(format!("{} (v{}): {}.", table_name, version, changes.join(", ")));
+        }
+    }
+
+    /// This function loads the schema of a game, keyed by his `folder_name` (the `game` of `GameSelected`).
+    /// The schema we get is built in layers, each one replacing the versions of the tables of the previous one:
+    /// - The shared base schema of the game, if it has one (for example, both Warhammer games share one).
+    /// - The schema file of the game itself.
+    /// - The user's overlay (the definitions the user has added or changed) of the schema file of the game.
+    /// If some of the layers don't exist, we just skip them. If none of them exist, we return an error. If the overlay
+    /// is broken (for example, the user edited it by hand), we use the downloaded schema alone, and return the error
+    /// of the overlay with it, so the caller can warn the user about it.
+    pub fn load(rpfm_path: &PathBuf, supported_games: &[GameInfo], game: &str) -> Result<(Schema, Option<PackedFileError>), Error> {
+        let game_info = Schema::get_game_info(supported_games, game)?;
+        let (overlay, warning) = match Schema::load_overlay(rpfm_path, &game_info.schema) {
+            Ok(overlay) => (overlay, None),
+            Err(error) => (Schema::new(), Some(PackedFileError::BrokenSchemaOverlay { path: Schema::get_overlay_path(rpfm_path, &game_info.schema), error: error.to_string() })),
+        };
+        let mut schema = match Schema::load_upstream(rpfm_path, supported_games, game) {
+            Ok(schema) => schema,
+            Err(error) => if overlay.tables_definitions.is_empty() { return Err(error) } else { Schema::new() },
+        };
+
+        schema.merge(overlay);
+        Ok((schema, warning))
+    }
+
+    /// This function loads the schema of a game as we download it with the updates: the shared base schema
+    /// with the schema file of the game over it, without the user's overlay.
+    pub fn load_upstream(rpfm_path: &PathBuf, supported_games: &[GameInfo], game: &str) -> Result<Schema, Error> {
+        let game_info = Schema::get_game_info(supported_games, game)?;
+        let schema = Schema::load_base(rpfm_path, &game_info.schema);
+        match game_info.schema_base {
+            Some(ref schema_base) => match (Schema::load_base(rpfm_path, schema_base), schema) {
+                (Ok(mut base), Ok(schema)) => {
+                    base.merge(schema);
+                    Ok(base)
+                }
+                (Ok(base), Err(_)) => Ok(base),
+                (Err(_), schema) => schema,
+            },
+            None => schema,
+        }
+    }
+
+    /// This function reads a single schema file, as we download it with the updates.
+    pub fn load_base(rpfm_path: &PathBuf, schema_file: &str) -> Result<Schema, Error> {
+        let mut schema_path = rpfm_path.clone();
+        schema_path.push("schemas");
+        schema_path.push(schema_file);
+        Schema::load_from_path(&schema_path)
+    }
+
+    /// This function reads the user's overlay of a schema file. If there is no overlay, we get an empty schema.
+    pub fn load_overlay(rpfm_path: &PathBuf, schema_file: &str) -> Result<Schema, Error> {
+        let schema_path = Schema::get_overlay_path(rpfm_path, schema_file);
+        if schema_path.is_file() { Schema::load_from_path(&schema_path) }
+        else { Ok(Schema::new()) }
+    }
+
+    /// This function takes an "Schema" object and saves it as the schema of a game. The downloaded schema files
+    /// are never touched, as they are overwritten with every update. Instead, every definition that is not exactly
+    /// the same in the downloaded schema of the game is saved into the user's overlay, so it's not lost when the
+    /// schemas are updated. The versions of the downloaded schema that are not in the schema (because the user
+    /// removed them) are saved into the overlay too, so they are still removed the next time we load the schema.
+    /// If the current overlay is broken, it's kept as "<schema_file>.broken" before saving the new one, so the
+    /// definitions in it are not lost.
+    pub fn save(schema: &Schema, rpfm_path: &PathBuf, supported_games: &[GameInfo], game: &str) -> Result<(), Error> {
+        let game_info = Schema::get_game_info(supported_games, game)?;
+        let upstream = Schema::load_upstream(rpfm_path, supported_games, game).unwrap_or_else(|_| Schema::new());
+        let mut overlay = schema.get_definitions_not_in(&upstream);
+        overlay.removed_versions = schema.get_versions_removed_from(&upstream);
+
+        let mut schema_path = Schema::get_overlay_path(rpfm_path, &game_info.schema);
+        if Schema::load_overlay(rpfm_path, &game_info.schema).is_err() {
+            let mut broken_path = schema_path.to_path_buf();
+            broken_path.set_file_name(format!("{}.broken", game_info.schema));
+            fs::rename(&schema_path, &broken_path)?;
+        }
+
+        schema_path.pop();
+        if DirBuilder::new().recursive(true).create(&schema_path).is_err() {
+            return Err(PackedFileError::CreateSchemaFolder { path: schema_path.to_path_buf() }.into())
+        }
+        schema_path.push(&game_info.schema);
+        Schema::save_to_path(&overlay, &schema_path)
+    }
+
+    /// This function compares the user's overlay of the schema of a game with the downloaded schema, so we can
+    /// know what definitions of the overlay are already in the downloaded schema, and what definitions are not.
+    pub fn check_overlay(rpfm_path: &PathBuf, supported_games: &[GameInfo], game: &str) -> Result<SchemaOverlayReport, Error> {
+        let game_info = Schema::get_game_info(supported_games, game)?;
+        let upstream = Schema::load_upstream(rpfm_path, supported_games, game).unwrap_or_else(|_| Schema::new());
+        let overlay = Schema::load_overlay(rpfm_path, &game_info.schema)?;
+
+        let mut report = SchemaOverlayReport::default();
+        for table_definitions in &overlay.tables_definitions {
+            let upstream_table_definitions = upstream.get_table_definitions(&table_definitions.name).map(|x| &upstream.tables_definitions[x]);
+            for table_definition in &table_definitions.versions {
+                let entry = format!("{} (v{})", table_definitions.name, table_definition.version);
+                match upstream_table_definitions.and_then(|x| x.get_table_version(table_definition.version).map(|y| &x.versions[y])) {
+                    Some(upstream_table_definition) => if upstream_table_definition == table_definition { report.identical.push(entry) } else { report.different.push(entry) },
+                    None => report.only_in_overlay.push(entry),
+                }
+            }
+        }
+
+        Ok(report)
+    }
+
+    /// This function removes from the user's overlay of the schema of a game the definitions that are already in the
+    /// downloaded schema, exactly as they are in the overlay. It returns how many definitions have been removed.
+    pub fn prune_overlay(rpfm_path: &PathBuf, supported_games: &[GameInfo], game: &str) -> Result<usize, Error> {
+        let report = Schema::check_overlay(rpfm_path, supported_games, game)?;
+        if !report.identical.is_empty() {
+
+            // Saving the schema already leaves out of the overlay what is in the downloaded schema.
+            let (schema, _) = Schema::load(rpfm_path, supported_games, game)?;
+            Schema::save(&schema, rpfm_path, supported_games, game)?;
+        }
+        Ok(report.identical.len())
+    }
+
+    /// This function exports the definitions of the user's overlay of the schema of a game that are not in the
+    /// downloaded schema (or are different there) to a file, in the same format as the schemas, so they can be
+    /// contributed upstream. It returns how many definitions have been exported.
+    pub fn export_overlay(rpfm_path: &PathBuf, supported_games: &[GameInfo], game: &str, destination_path: &PathBuf) -> Result<usize, Error> {
+        let game_info = Schema::get_game_info(supported_games, game)?;
+        let upstream = Schema::load_upstream(rpfm_path, supported_games, game).unwrap_or_else(|_| Schema::new());
+        let overlay = Schema::load_overlay(rpfm_path, &game_info.schema)?.get_definitions_not_in(&upstream);
+
+        if overlay.tables_definitions.is_empty() {
+            return Err(PackedFileError::NothingToContribute.into())
+        }
+
+        Schema::save_to_path(&overlay, destination_path)?;
+        Ok(overlay.tables_definitions.iter().map(|x| x.versions.len()).sum())
+    }
+
+    /// This function merges another schema over this one. The versions of the tables of the other schema
+    /// replace the ones of this schema with the same version, and the versions removed in the other schema
+    /// are removed from this one.
+    fn merge(&mut self, other: Schema) {
+        for &(ref table_name, version) in &other.removed_versions {
+            if let Some(index) = self.get_table_definitions(table_name) {
+                if let Some(version_index) = self.tables_definitions[index].get_table_version(version) {
+                    self.tables_definitions[index].versions.remove(version_index);
+                }
+            }
+        }
+
+        for table_definitions in other.tables_definitions {
+            match self.get_table_definitions(&table_definitions.name) {
+                Some(index) => for table_definition in table_definitions.versions {
+                    self.tables_definitions[index].add_table_definition(table_definition);
+                },
+                None => self.add_table_definitions(table_definitions),
+            }
+        }
+    }
+
+    /// This function returns the definitions of this schema that are not exactly the same in the other schema.
+    fn get_definitions_not_in(&self, other: &Schema) -> Schema {
+        let mut schema = Schema::new();
+        for table_definitions in &self.tables_definitions {
+            let other_table_definitions = other.get_table_definitions(&table_definitions.name).map(|x| &other.tables_definitions[x]);
+            let versions = table_definitions.versions.iter()
+                .filter(|x| !other_table_definitions.map(|y| y.versions.contains(*x)).unwrap_or(false))
+                .cloned()
+                .collect::<Vec<TableDefinition>>();
+
+            if !versions.is_empty() {
+                schema.add_table_definitions(TableDefinitions {
+                    name: table_definitions.name.to_owned(),
+                    versions,
+                });
+            }
+        }
+        schema
+    }
+
+    /// This function returns the versions of the tables (table, version) of the other schema that are not in this one.
+    fn get_versions_removed_from(&self, other: &Schema) -> Vec<(String, u32)> {
+        let mut removed_versions = vec![];
+        for other_table_definitions in &other.tables_definitions {
+            let table_definitions = self.get_table_definitions(&other_table_definitions.name).map(|x| &self.tables_definitions[x]);
+            for other_table_definition in &other_table_definitions.versions {
+                if table_definitions.and_then(|x| x.get_table_version(other_table_definition.version)).is_none() {
+                    removed_versions.push((other_table_definitions.name.to_owned(), other_table_definition.version));
+                }
+            }
+        }
+        removed_versions
     }
 
-    /// This function takes an "Schema" object and saves it into a schema file.
-    pub fn save(schema: &Schema, rpfm_path: &PathBuf, schema_file: &str) -> Result<(), Error> {
+    /// This function returns the `GameInfo` of the provided game, or an error if the game is not supported.
+    fn get_game_info<'a>(supported_games: &'a [GameInfo], game: &str) -> Result<&'a GameInfo, Error> {
+        match supported_games.iter().find(|x| x.folder_name == game) {
+            Some(game_info) => Ok(game_info),
+            None => Err(PackedFileError::GameNotSupported { game: game.to_owned() }.into()),
+        }
+    }
 
-        let schema_json = serde_json::to_string_pretty(schema);
+    /// This function returns the path of the user's overlay of an schema.
+    fn get_overlay_path(rpfm_path: &PathBuf, schema_file: &str) -> PathBuf {
         let mut schema_path = rpfm_path.clone();
         schema_path.push("schemas");
+        schema_path.push("user");
         schema_path.push(schema_file);
+        schema_path
+    }
 
+    /// This function reads an schema from the provided path.
+    pub fn load_from_path(schema_path: &PathBuf) -> Result<Schema, Error> {
+        let schema_file = BufReader::new(File::open(schema_path)?);
+        let schema = serde_json::from_reader(schema_file)?;
+        Ok(schema)
+    }
+
+    /// This function writes an schema to the provided path.
+    fn save_to_path(schema: &Schema, schema_path: &PathBuf) -> Result<(), Error> {
+        let schema_json = serde_json::to_string_pretty(schema)?;
         match File::create(schema_path) {
             Ok(mut file) => {
-                match file.write_all(schema_json.unwrap().as_bytes()) {
+                match file.write_all(schema_json.as_bytes()) {
                     Ok(_) => Ok(()),
-                    Err(_) => Err(format_err!("Error while trying to write the schema file.")),
+                    Err(_) => Err(PackedFileError::WriteSchema { path: schema_path.to_path_buf() }.into()),
                 }
             },
-            Err(_) => Err(format_err!("Error while trying prepare the schema file to be written."))
+            Err(_) => Err(PackedFileError::CreateSchema { path: schema_path.to_path_buf() }.into())
         }
     }
 }
@@ -215,30 +574,15 @@ impl TableDefinition {
             // First, we need to disable a number of known fields that are not in the final tables. We
             // check if the current field is one of them, and ignore it if it's.
             if field.name == "game_expansion_key" || // This one exists in one of the advices tables.
-                field.name == "localised_text" ||
-                field.name == "localised_name" ||
-                field.name == "localised_tooltip" ||
-                field.name == "description" ||
-                field.name == "objectives_team_1" ||
-                field.name == "objectives_team_2" ||
-                field.name == "short_description_text" ||
-                field.name == "historical_description_text" ||
-                field.name == "strengths_weaknesses_text" ||
-                field.name == "onscreen" ||
-                field.name == "onscreen_text" ||
-                field.name == "onscreen_name" ||
-                field.name == "onscreen_description" ||
-                field.name == "on_screen_name" ||
-                field.name == "on_screen_description" ||
-                field.name == "on_screen_target" {
+                LOCALISED_COLUMNS.contains(&&*field.name) {
                 continue;
             }
             let field_name = field.name.to_owned();
             let field_is_key = field.primary_key == "1";
-            let field_is_reference = if field.column_source_table != None {
-                Some((field.column_source_table.clone().unwrap().to_owned(), field.column_source_column.clone().unwrap()[0].to_owned()))
-            }
-            else {None};
+            let field_is_reference = match (&field.column_source_table, &field.column_source_column) {
+                (&Some(ref table), &Some(ref columns)) if !columns.is_empty() => Some((table.to_owned(), columns[0].to_owned())),
+                _ => None,
+            };
 
             let field_type = match &*field.field_type {
                 "yesno" => FieldType::Boolean,
@@ -292,13 +636,22 @@ impl TableDefinition {
                 None => String::new(),
             };
 
-            let new_field = Field::new(
+            let mut new_field = Field::new(
                 field_name,
                 field_type,
                 field_is_key,
                 field_is_reference,
                 field_description
             );
+
+            // Keep the constraints of the field, so we can validate the tables later.
+            new_field.field_is_required = field_is_key || field.required == "1";
+            new_field.field_max_length = match field.max_length {
+                Some(ref max_length) => max_length.parse::<u32>().ok().filter(|x| *x > 0),
+                None => None,
+            };
+            new_field.field_default_value = field.default_value.as_ref().filter(|x| !x.is_empty()).map(|x| x.to_owned());
+            new_field.field_is_filename = field.is_filename.as_ref().map(|x| x == "1").unwrap_or(false);
             fields.push(new_field);
         }
 
@@ -321,7 +674,132 @@ impl Field {
             field_type,
             field_is_key,
             field_is_reference,
-            field_description
+            field_description,
+            field_is_required: false,
+            field_max_length: None,
+            field_default_value: None,
+            field_min_value: None,
+            field_max_value: None,
+            field_enum_values: vec![],
+            field_is_filename: false,
         }
     }
+
+    /// This function returns the description of the field, followed by his constraints, one per line. If we
+    /// know nothing about the field, it returns an empty `String`. It's used for the tooltips of the DB View
+    /// and for the documentation of the schemas.
+    pub fn get_notes(&self) -> String {
+        let mut notes = vec![];
+
+        if !self.field_description.is_empty() { notes.push(self.field_description.to_owned()); }
+        if let Some(ref default_value) = self.field_default_value { notes.push(format!("Default: {}", default_value)); }
+        match (self.field_min_value, self.field_max_value) {
+            (Some(min), Some(max)) => notes.push(format!("Range: {} to {}", min, max)),
+            (Some(min), None) => notes.push(format!("Minimum: {}", min)),
+            (None, Some(max)) => notes.push(format!("Maximum: {}", max)),
+            (None, None) => {},
+        }
+        if !self.field_enum_values.is_empty() { notes.push(format!("Allowed values: {}", self.field_enum_values.join(", "))); }
+        if let Some(max_length) = self.field_max_length { notes.push(format!("Maximum length: {}", max_length)); }
+        if self.field_is_required { notes.push("Required".to_owned()); }
+        if self.field_is_filename { notes.push("Path to a file".to_owned()); }
+
+        notes.join("\n")
+    }
+
+    /// This function returns the constraints of the field in the format used by the Decoder to edit them:
+    /// "required; max_length=32; default=5; min=0; max=100; values=a|b|c; filename", without the ones the field doesn't have.
+    pub fn get_constraints(&self) -> String {
+        let mut constraints = vec![];
+        if self.field_is_required { constraints.push("required".to_owned()); }
+        if let Some(max_length) = self.field_max_length { constraints.push(format!("max_length={}", max_length)); }
+        if let Some(ref default_value) = self.field_default_value { constraints.push(format!("default={}", default_value)); }
+        if let Some(min) = self.field_min_value { constraints.push(format!("min={}", min)); }
+        if let Some(max) = self.field_max_value { constraints.push(format!("max={}", max)); }
+        if !self.field_enum_values.is_empty() { constraints.push(format!("values={}", self.field_enum_values.join("|"))); }
+        if self.field_is_filename { constraints.push("filename".to_owned()); }
+        constraints.join("; ")
+    }
+
+    /// This function replaces the constraints of the field with the ones in the provided text, in the format of
+    /// `get_constraints`. If the text is not valid, it returns an error and the field is left untouched.
+    pub fn set_constraints(&mut self, constraints: &str) -> Result<(), Error> {
+        let mut field = Field::new(self.field_name.to_owned(), self.field_type.clone(), self.field_is_key, self.field_is_reference.clone(), self.field_description.to_owned());
+        for constraint in constraints.split(';').map(|x| x.trim()).filter(|x| !x.is_empty()) {
+            let mut constraint_parts = constraint.splitn(2, '=');
+            let name = constraint_parts.next().unwrap_or("").trim();
+            let value = constraint_parts.next().map(|x| x.trim());
+            let invalid = || PackedFileError::InvalidConstraint { constraint: constraint.to_owned() };
+            match (name, value) {
+                ("required", None) => field.field_is_required = true,
+                ("filename", None) => field.field_is_filename = true,
+                ("max_length", Some(value)) => field.field_max_length = Some(value.parse::<u32>().map_err(|_| invalid())?),
+                ("default", Some(value)) => field.field_default_value = Some(value.to_owned()),
+                ("min", Some(value)) => field.field_min_value = Some(value.parse::<f64>().map_err(|_| invalid())?),
+                ("max", Some(value)) => field.field_max_value = Some(value.parse::<f64>().map_err(|_| invalid())?),
+                ("values", Some(value)) => field.field_enum_values = value.split('|').map(|x| x.trim().to_owned()).filter(|x| !x.is_empty()).collect(),
+                _ => return Err(invalid().into()),
+            }
+        }
+
+        *self = field;
+        Ok(())
+    }
+}
+
+/// Implementation of `Display` for `SchemaMergeReport`, so we can show it in the UI or print it in the terminal.
+impl fmt::Display for SchemaMergeReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+
+        if self.added.is_empty() && self.changed.is_empty() && self.conflicts.is_empty() && self.errors.is_empty() {
+            return writeln!(f, "The schema is already up to date. Nothing has been changed.");
+        }
+
+        let sections = [
+            ("Added tables and versions:", &self.added),
+            ("Updated versions:", &self.changed),
+            ("Conflicts (the version in the schema has been kept):", &self.conflicts),
+            ("Definitions that couldn't be imported:", &self.errors),
+        ];
+
+        for &(title, entries) in &sections {
+            if !entries.is_empty() {
+                writeln!(f, "{}", title)?;
+                for entry in entries {
+                    writeln!(f, "  - {}", entry)?;
+                }
+                writeln!(f)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Implementation of `Display` for `SchemaOverlayReport`, so we can show it in the UI or print it in the terminal.
+impl fmt::Display for SchemaOverlayReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+
+        if self.identical.is_empty() && self.different.is_empty() && self.only_in_overlay.is_empty() {
+            return writeln!(f, "There are no definitions in your schema. Everything you use comes from the downloaded schema.");
+        }
+
+        let sections = [
+            ("Your definitions that are now in the downloaded schema, exactly as yours:", &self.identical),
+            ("Your definitions that are now in the downloaded schema, but different (yours are being used):", &self.different),
+            ("Your definitions that are not in the downloaded schema yet:", &self.only_in_overlay),
+        ];
+
+        for &(title, entries) in &sections {
+            if !entries.is_empty() {
+                writeln!(f, "{}", title)?;
+                for entry in entries {
+                    writeln!(f, "  - {}", entry)?;
+                }
+                writeln!(f)?;
+            }
+        }
+
+        Ok(())
+    }
 }
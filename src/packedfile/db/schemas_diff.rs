@@ -0,0 +1,318 @@
+// In this file is all the stuff needed to compare two schemas and get what changed between them, so we
+// can tell the user what a schema update or an import actually did. The result can be written as Markdown,
+// to be read by people, or as JSON, to be read by other programs.
+
+extern crate serde_json;
+extern crate failure;
+
+use failure::Error;
+
+use super::schemas::*;
+
+/// This struct holds the differences between two schemas. It has:
+/// - tables_added: the tables that are only in the new schema.
+/// - tables_removed: the tables that are only in the old schema.
+/// - tables_changed: the tables that are in both schemas, but with different definitions.
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct SchemaDiff {
+    pub tables_added: Vec<String>,
+    pub tables_removed: Vec<String>,
+    pub tables_changed: Vec<TableDiff>,
+}
+
+/// This struct holds the differences between the definitions of a table in two schemas. It has:
+/// - name: the name of the table.
+/// - versions_added: the versions that are only in the new schema.
+/// - versions_removed: the versions that are only in the old schema.
+/// - versions_changed: the changes of each version. For new versions, the changes are against the previous version of the table.
+#[derive(Clone, Debug, Serialize)]
+pub struct TableDiff {
+    pub name: String,
+    pub versions_added: Vec<u32>,
+    pub versions_removed: Vec<u32>,
+    pub versions_changed: Vec<VersionDiff>,
+}
+
+/// This struct holds the differences between two definitions of a table. It has:
+/// - version: the version of the new definition.
+/// - compared_with: the version of the old definition we compared it with, if there was one.
+/// - changes: the list of changes in the fields.
+#[derive(Clone, Debug, Serialize)]
+pub struct VersionDiff {
+    pub version: u32,
+    pub compared_with: Option<u32>,
+    pub changes: Vec<FieldChange>,
+}
+
+/// This enum represents the possible changes in a field between two definitions. Positions start at 1.
+#[derive(Clone, Debug, Serialize)]
+pub enum FieldChange {
+    Added { name: String, position: usize, field_type: FieldType },
+    Removed { name: String, position: usize },
+    Renamed { position: usize, old_name: String, new_name: String },
+    Retyped { name: String, old_type: FieldType, new_type: FieldType },
+    Moved { name: String, old_position: usize, new_position: usize },
+    KeyChanged { name: String, is_key: bool },
+    ReferenceChanged { name: String, old_reference: Option<(String, String)>, new_reference: Option<(String, String)> },
+}
+
+/// Implementation of `SchemaDiff`.
+impl SchemaDiff {
+
+    /// This function compares two schemas and returns the differences between them.
+    pub fn new(old_schema: &Schema, new_schema: &Schema) -> Self {
+        let mut diff = Self::default();
+
+        for new_table in &new_schema.tables_definitions {
+            match old_schema.get_table_definitions(&new_table.name) {
+                Some(index) => {
+                    let table_diff = TableDiff::new(&old_schema.tables_definitions[index], new_table);
+                    if !table_diff.is_empty() { diff.tables_changed.push(table_diff); }
+                }
+                None => diff.tables_added.push(new_table.name.to_owned()),
+            }
+        }
+
+        diff.tables_removed = old_schema.tables_definitions.iter()
+            .filter(|x| new_schema.get_table_definitions(&x.name).is_none())
+            .map(|x| x.name.to_owned())
+            .collect();
+
+        diff.tables_added.sort();
+        diff.tables_removed.sort();
+        diff.tables_changed.sort_by(|x, y| x.name.cmp(&y.name));
+        diff
+    }
+
+    /// This function returns if there are no differences between the schemas.
+    pub fn is_empty(&self) -> bool {
+        self.tables_added.is_empty() && self.tables_removed.is_empty() && self.tables_changed.is_empty()
+    }
+
+    /// This function returns the differences as a Markdown document.
+    pub fn to_markdown(&self) -> String {
+        let mut markdown = String::new();
+        if self.is_empty() {
+            markdown.push_str("No changes.\n");
+            return markdown;
+        }
+
+        if !self.tables_added.is_empty() {
+            markdown.push_str("## Tables added\n\n");
+            for table in &self.tables_added { markdown.push_str(&format!("- `{}`\n", table)); }
+            markdown.push('\n');
+        }
+
+        if !self.tables_removed.is_empty() {
+            markdown.push_str("## Tables removed\n\n");
+            for table in &self.tables_removed { markdown.push_str(&format!("- `{}`\n", table)); }
+            markdown.push('\n');
+        }
+
+        if !self.tables_changed.is_empty() {
+            markdown.push_str("## Tables changed\n\n");
+            for table in &self.tables_changed {
+                markdown.push_str(&format!("### `{}`\n\n", table.name));
+                if !table.versions_added.is_empty() {
+                    markdown.push_str(&format!("- New versions: {}\n", table.versions_added.iter().map(|x| x.to_string()).collect::<Vec<String>>().join(", ")));
+                }
+                if !table.versions_removed.is_empty() {
+                    markdown.push_str(&format!("- Removed versions: {}\n", table.versions_removed.iter().map(|x| x.to_string()).collect::<Vec<String>>().join(", ")));
+                }
+                for version in &table.versions_changed {
+                    match version.compared_with {
+                        Some(compared_with) if compared_with != version.version => markdown.push_str(&format!("- Version {} (against version {}):\n", version.version, compared_with)),
+                        _ => markdown.push_str(&format!("- Version {}:\n", version.version)),
+                    }
+                    for change in &version.changes {
+                        markdown.push_str(&format!("    - {}\n", change.to_markdown()));
+                    }
+                }
+                markdown.push('\n');
+            }
+        }
+
+        markdown
+    }
+
+    /// This function returns the differences as a JSON document.
+    pub fn to_json(&self) -> Result<String, Error> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+}
+
+/// Implementation of `TableDiff`.
+impl TableDiff {
+
+    /// This function compares two definitions of the same table and returns the differences between them.
+    pub fn new(old_table: &TableDefinitions, new_table: &TableDefinitions) -> Self {
+        let mut versions_added = vec![];
+        let mut versions_changed = vec![];
+
+        let mut new_versions = new_table.versions.iter().collect::<Vec<&TableDefinition>>();
+        new_versions.sort_by_key(|x| x.version);
+        for new_version in new_versions {
+            match old_table.get_table_version(new_version.version) {
+                Some(index) => {
+                    let changes = get_field_changes(&old_table.versions[index].fields, &new_version.fields);
+                    if !changes.is_empty() {
+                        versions_changed.push(VersionDiff {
+                            version: new_version.version,
+                            compared_with: Some(new_version.version),
+                            changes,
+                        });
+                    }
+                }
+
+                // For new versions, we compare them with the newest older version we had, if any.
+                None => {
+                    versions_added.push(new_version.version);
+                    if let Some(previous_version) = old_table.versions.iter().filter(|x| x.version < new_version.version).max_by_key(|x| x.version) {
+                        let changes = get_field_changes(&previous_version.fields, &new_version.fields);
+                        if !changes.is_empty() {
+                            versions_changed.push(VersionDiff {
+                                version: new_version.version,
+                                compared_with: Some(previous_version.version),
+                                changes,
+                            });
+                        }
+                    }
+                }
+            }
+        }
+
+        let mut versions_removed = old_table.versions.iter()
+            .filter(|x| new_table.get_table_version(x.version).is_none())
+            .map(|x| x.version)
+            .collect::<Vec<u32>>();
+        versions_removed.sort_unstable();
+
+        Self {
+            name: new_table.name.to_owned(),
+            versions_added,
+            versions_removed,
+            versions_changed,
+        }
+    }
+
+    /// This function returns if there are no differences between the definitions.
+    pub fn is_empty(&self) -> bool {
+        self.versions_added.is_empty() && self.versions_removed.is_empty() && self.versions_changed.is_empty()
+    }
+}
+
+/// Implementation of `FieldChange`.
+impl FieldChange {
+
+    /// This function returns the change as a line of Markdown.
+    pub fn to_markdown(&self) -> String {
+        match *self {
+            FieldChange::Added { ref name, position, ref field_type } => format!("Added `{}` ({:?}) at position {}.", name, field_type, position),
+            FieldChange::Removed { ref name, position } => format!("Removed `{}` from position {}.", name, position),
+            FieldChange::Renamed { position, ref old_name, ref new_name } => format!("Renamed `{}` to `{}` (position {}).", old_name, new_name, position),
+            FieldChange::Retyped { ref name, ref old_type, ref new_type } => format!("Changed the type of `{}` from {:?} to {:?}.", name, old_type, new_type),
+            FieldChange::Moved { ref name, old_position, new_position } => format!("Moved `{}` from position {} to {}.", name, old_position, new_position),
+            FieldChange::KeyChanged { ref name, is_key } => if is_key { format!("`{}` is now a key.", name) } else { format!("`{}` is no longer a key.", name) },
+            FieldChange::ReferenceChanged { ref name, ref old_reference, ref new_reference } => format!(
+                "Changed the reference of `{}` from {} to {}.",
+                name,
+                old_reference.as_ref().map(|x| format!("`{}/{}`", x.0, x.1)).unwrap_or_else(|| "nothing".to_owned()),
+                new_reference.as_ref().map(|x| format!("`{}/{}`", x.0, x.1)).unwrap_or_else(|| "nothing".to_owned()),
+            ),
+        }
+    }
+}
+
+/// This function returns the changes between two lists of fields. Fields are matched by name. The ones that
+/// don't match with any other are considered renamed if there is another unmatched one of the same type between
+/// the same matched fields, no matter their positions, so adding a field before a renamed one doesn't hide the rename.
+fn get_field_changes(old_fields: &[Field], new_fields: &[Field]) -> Vec<FieldChange> {
+    let mut changes = vec![];
+
+    // Pairs of (old position, new position) of the fields that are in both lists.
+    let mut pairs = vec![];
+    for (new_position, new_field) in new_fields.iter().enumerate() {
+        if let Some(old_position) = old_fields.iter().position(|x| x.field_name == new_field.field_name) {
+            pairs.push((old_position, new_position));
+        }
+    }
+
+    // The unmatched fields are grouped by the matched field before them (in the old list), so we only compare
+    // fields that are in the same place between the fields that didn't change.
+    let unmatched_old = (0..old_fields.len()).filter(|x| !pairs.iter().any(|y| y.0 == *x)).collect::<Vec<usize>>();
+    let unmatched_new = (0..new_fields.len()).filter(|x| !pairs.iter().any(|y| y.1 == *x)).collect::<Vec<usize>>();
+    let old_anchors = unmatched_old.iter().map(|x| pairs.iter().filter(|y| y.0 < *x).map(|y| y.0).max()).collect::<Vec<Option<usize>>>();
+    let new_anchors = unmatched_new.iter().map(|x| pairs.iter().filter(|y| y.1 < *x).max_by_key(|y| y.1).map(|y| y.0)).collect::<Vec<Option<usize>>>();
+
+    // An unmatched new field is the first unused unmatched old field in the same group with the same type, renamed.
+    let mut renamed_old = vec![];
+    for (index, position) in unmatched_new.iter().enumerate() {
+        let new_field = &new_fields[*position];
+        let renamed = unmatched_old.iter().enumerate().find(|&(old_index, old_position)|
+            old_anchors[old_index] == new_anchors[index] &&
+            !renamed_old.contains(old_position) &&
+            old_fields[*old_position].field_type == new_field.field_type
+        ).map(|(_, old_position)| *old_position);
+
+        match renamed {
+            Some(old_position) => {
+                changes.push(FieldChange::Renamed { position: position + 1, old_name: old_fields[old_position].field_name.to_owned(), new_name: new_field.field_name.to_owned() });
+                renamed_old.push(old_position);
+                pairs.push((old_position, *position));
+            }
+            None => changes.push(FieldChange::Added { name: new_field.field_name.to_owned(), position: position + 1, field_type: new_field.field_type.clone() }),
+        }
+    }
+    for position in unmatched_old.iter().filter(|x| !renamed_old.contains(x)) {
+        changes.push(FieldChange::Removed { name: old_fields[*position].field_name.to_owned(), position: position + 1 });
+    }
+
+    // A field has been moved if it's not part of the longest list of fields that kept their order. Otherwise,
+    // moving one field would report all the fields between his old and new position as moved too.
+    pairs.sort_by_key(|x| x.1);
+    let kept_order = get_longest_increasing_subsequence(&pairs.iter().map(|x| x.0).collect::<Vec<usize>>());
+    for (index, &(old_position, new_position)) in pairs.iter().enumerate() {
+        let old_field = &old_fields[old_position];
+        let new_field = &new_fields[new_position];
+
+        if !kept_order.contains(&index) {
+            changes.push(FieldChange::Moved { name: new_field.field_name.to_owned(), old_position: old_position + 1, new_position: new_position + 1 });
+        }
+        if old_field.field_type != new_field.field_type {
+            changes.push(FieldChange::Retyped { name: new_field.field_name.to_owned(), old_type: old_field.field_type.clone(), new_type: new_field.field_type.clone() });
+        }
+        if old_field.field_is_key != new_field.field_is_key {
+            changes.push(FieldChange::KeyChanged { name: new_field.field_name.to_owned(), is_key: new_field.field_is_key });
+        }
+        if old_field.field_is_reference != new_field.field_is_reference {
+            changes.push(FieldChange::ReferenceChanged { name: new_field.field_name.to_owned(), old_reference: old_field.field_is_reference.clone(), new_reference: new_field.field_is_reference.clone() });
+        }
+    }
+
+    changes
+}
+
+/// This function returns the indexes of the longest subsequence of the provided values that is in increasing order.
+fn get_longest_increasing_subsequence(values: &[usize]) -> Vec<usize> {
+
+    // For each value, the length of the longest subsequence ending in it, and the previous value of that subsequence.
+    let mut lengths = vec![1; values.len()];
+    let mut previous: Vec<Option<usize>> = vec![None; values.len()];
+    for (index, value) in values.iter().enumerate() {
+        for (previous_index, previous_value) in values[..index].iter().enumerate() {
+            if previous_value < value && lengths[previous_index] + 1 > lengths[index] {
+                lengths[index] = lengths[previous_index] + 1;
+                previous[index] = Some(previous_index);
+            }
+        }
+    }
+
+    let mut subsequence = vec![];
+    let mut current = (0..values.len()).max_by_key(|x| lengths[*x]);
+    while let Some(index) = current {
+        subsequence.push(index);
+        current = previous[index];
+    }
+    subsequence.reverse();
+    subsequence
+}
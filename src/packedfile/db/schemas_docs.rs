@@ -0,0 +1,230 @@
+// In this file is all the stuff needed to generate the documentation of the tables of a schema. The
+// documentation is a folder with an index, one page per table (with every version we know, his fields
+// and the references from and to the table) and a graph of the relations between tables in DOT format,
+// so it can be rendered with Graphviz (`dot -Tsvg relations.dot -o relations.svg`).
+
+extern crate failure;
+
+use std::collections::BTreeMap;
+use std::fs::{File, DirBuilder};
+use std::io::Write;
+use std::path::PathBuf;
+
+use failure::Error;
+
+use packedfile::error::PackedFileError;
+use super::assembly_kit::escape_xml;
+use super::schemas::*;
+
+/// This enum represents the formats we can generate the documentation in.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum DocsFormat {
+    Markdown,
+    Html,
+}
+
+/// This struct holds a reference between two columns of two tables. It has:
+/// - table: the table with the column that references the other one.
+/// - column: the column that references the other one.
+/// - referenced_table: the referenced table, with the "_tables" at the end.
+/// - referenced_column: the referenced column.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+struct Reference {
+    table: String,
+    column: String,
+    referenced_table: String,
+    referenced_column: String,
+}
+
+/// Implementation of `DocsFormat`.
+impl DocsFormat {
+
+    /// This function returns the extension of the pages of this format.
+    fn get_extension(self) -> &'static str {
+        match self {
+            DocsFormat::Markdown => "md",
+            DocsFormat::Html => "html",
+        }
+    }
+}
+
+/// This function generates the documentation of every table in the schema into the provided folder, and
+/// returns the amount of pages (tables) generated. Existing files with the same names are overwritten.
+pub fn generate_docs(schema: &Schema, folder_path: &PathBuf, format: DocsFormat) -> Result<usize, Error> {
+    DirBuilder::new().recursive(true).create(folder_path)?;
+
+    // Get all the references of the schema, from every version of every table.
+    let mut references = vec![];
+    for table_definitions in &schema.tables_definitions {
+        for table_definition in &table_definitions.versions {
+            for field in &table_definition.fields {
+                if let Some((ref referenced_table, ref referenced_column)) = field.field_is_reference {
+                    let reference = Reference {
+                        table: table_definitions.name.to_owned(),
+                        column: field.field_name.to_owned(),
+                        referenced_table: format!("{}_tables", referenced_table),
+                        referenced_column: referenced_column.to_owned(),
+                    };
+                    if !references.contains(&reference) { references.push(reference); }
+                }
+            }
+        }
+    }
+    references.sort();
+
+    let mut tables = schema.tables_definitions.iter().collect::<Vec<&TableDefinitions>>();
+    tables.sort_by(|x, y| x.name.cmp(&y.name));
+
+    // One page per table.
+    for table_definitions in &tables {
+        let outgoing = references.iter().filter(|x| x.table == table_definitions.name).collect::<Vec<&Reference>>();
+        let incoming = references.iter().filter(|x| x.referenced_table == table_definitions.name).collect::<Vec<&Reference>>();
+        let page = match format {
+            DocsFormat::Markdown => get_table_page_markdown(table_definitions, &outgoing, &incoming),
+            DocsFormat::Html => get_table_page_html(table_definitions, &outgoing, &incoming),
+        };
+
+        let mut page_path = folder_path.to_path_buf();
+        page_path.push(format!("{}.{}", table_definitions.name, format.get_extension()));
+        write_file(&page_path, &page)?;
+    }
+
+    // The index, with every table and his amount of versions and references.
+    let mut index = match format {
+        DocsFormat::Markdown => String::from("# Tables\n\nThe relations between tables are in `relations.dot`.\n\n| Table | Versions | References | Referenced by |\n|---|---|---|---|\n"),
+        DocsFormat::Html => String::from("<!DOCTYPE html>\n<html>\n<head><meta charset=\"utf-8\"><title>Tables</title></head>\n<body>\n<h1>Tables</h1>\n<p>The relations between tables are in <a href=\"relations.dot\">relations.dot</a>.</p>\n<table border=\"1\">\n<tr><th>Table</th><th>Versions</th><th>References</th><th>Referenced by</th></tr>\n"),
+    };
+    for table_definitions in &tables {
+        let versions = get_versions_text(table_definitions);
+        let outgoing = references.iter().filter(|x| x.table == table_definitions.name).count();
+        let incoming = references.iter().filter(|x| x.referenced_table == table_definitions.name).count();
+        match format {
+            DocsFormat::Markdown => index.push_str(&format!("| [{0}]({0}.md) | {1} | {2} | {3} |\n", table_definitions.name, versions, outgoing, incoming)),
+            DocsFormat::Html => index.push_str(&format!("<tr><td><a href=\"{0}.html\">{0}</a></td><td>{1}</td><td>{2}</td><td>{3}</td></tr>\n", escape_xml(&table_definitions.name), versions, outgoing, incoming)),
+        }
+    }
+    if format == DocsFormat::Html { index.push_str("</table>\n</body>\n</html>\n"); }
+
+    let mut index_path = folder_path.to_path_buf();
+    index_path.push(format!("index.{}", format.get_extension()));
+    write_file(&index_path, &index)?;
+
+    // And the graph of relations.
+    let mut dot_path = folder_path.to_path_buf();
+    dot_path.push("relations.dot");
+    write_file(&dot_path, &get_relations_graph(&references))?;
+
+    Ok(tables.len())
+}
+
+/// This function returns the page of a table in Markdown.
+fn get_table_page_markdown(table_definitions: &TableDefinitions, outgoing: &[&Reference], incoming: &[&Reference]) -> String {
+    let mut page = format!("# {}\n\n[Back to the index](index.md)\n\n", table_definitions.name);
+
+    for table_definition in get_sorted_versions(table_definitions) {
+        page.push_str(&format!("## Version {}\n\n| # | Field | Type | Key | Reference | Description |\n|---|---|---|---|---|---|\n", table_definition.version));
+        for (index, field) in table_definition.fields.iter().enumerate() {
+            page.push_str(&format!(
+                "| {} | `{}` | {:?} | {} | {} | {} |\n",
+                index + 1,
+                field.field_name,
+                field.field_type,
+                if field.field_is_key { "Yes" } else { "" },
+                field.field_is_reference.as_ref().map(|x| format!("[{0}_tables]({0}_tables.md) / `{1}`", x.0, x.1)).unwrap_or_default(),
+                field.get_notes().replace('|', "\\|").replace('\n', " "),
+            ));
+        }
+        page.push('\n');
+    }
+
+    page.push_str("## References\n\n");
+    if outgoing.is_empty() { page.push_str("This table doesn't reference other tables.\n"); }
+    for reference in outgoing {
+        page.push_str(&format!("- `{0}` references [{1}]({1}.md) / `{2}`\n", reference.column, reference.referenced_table, reference.referenced_column));
+    }
+
+    page.push_str("\n## Referenced by\n\n");
+    if incoming.is_empty() { page.push_str("No other table references this table.\n"); }
+    for reference in incoming {
+        page.push_str(&format!("- [{0}]({0}.md) / `{1}` references `{2}`\n", reference.table, reference.column, reference.referenced_column));
+    }
+
+    page
+}
+
+/// This function returns the page of a table in HTML.
+fn get_table_page_html(table_definitions: &TableDefinitions, outgoing: &[&Reference], incoming: &[&Reference]) -> String {
+    let name = escape_xml(&table_definitions.name);
+    let mut page = format!("<!DOCTYPE html>\n<html>\n<head><meta charset=\"utf-8\"><title>{0}</title></head>\n<body>\n<h1>{0}</h1>\n<p><a href=\"index.html\">Back to the index</a></p>\n", name);
+
+    for table_definition in get_sorted_versions(table_definitions) {
+        page.push_str(&format!("<h2>Version {}</h2>\n<table border=\"1\">\n<tr><th>#</th><th>Field</th><th>Type</th><th>Key</th><th>Reference</th><th>Description</th></tr>\n", table_definition.version));
+        for (index, field) in table_definition.fields.iter().enumerate() {
+            page.push_str(&format!(
+                "<tr><td>{}</td><td><code>{}</code></td><td>{:?}</td><td>{}</td><td>{}</td><td>{}</td></tr>\n",
+                index + 1,
+                escape_xml(&field.field_name),
+                field.field_type,
+                if field.field_is_key { "Yes" } else { "" },
+                field.field_is_reference.as_ref().map(|x| format!("<a href=\"{0}_tables.html\">{0}_tables</a> / <code>{1}</code>", escape_xml(&x.0), escape_xml(&x.1))).unwrap_or_default(),
+                escape_xml(&field.get_notes()).replace('\n', "<br>"),
+            ));
+        }
+        page.push_str("</table>\n");
+    }
+
+    page.push_str("<h2>References</h2>\n<ul>\n");
+    if outgoing.is_empty() { page.push_str("<li>This table doesn't reference other tables.</li>\n"); }
+    for reference in outgoing {
+        page.push_str(&format!("<li><code>{}</code> references <a href=\"{1}.html\">{1}</a> / <code>{2}</code></li>\n", escape_xml(&reference.column), escape_xml(&reference.referenced_table), escape_xml(&reference.referenced_column)));
+    }
+
+    page.push_str("</ul>\n<h2>Referenced by</h2>\n<ul>\n");
+    if incoming.is_empty() { page.push_str("<li>No other table references this table.</li>\n"); }
+    for reference in incoming {
+        page.push_str(&format!("<li><a href=\"{0}.html\">{0}</a> / <code>{1}</code> references <code>{2}</code></li>\n", escape_xml(&reference.table), escape_xml(&reference.column), escape_xml(&reference.referenced_column)));
+    }
+    page.push_str("</ul>\n</body>\n</html>\n");
+
+    page
+}
+
+/// This function returns the graph of the relations between tables in DOT format. If a table references
+/// another one through more than one column, all of them are in the label of the same edge.
+fn get_relations_graph(references: &[Reference]) -> String {
+    let mut edges: BTreeMap<(&str, &str), Vec<&str>> = BTreeMap::new();
+    for reference in references {
+        let columns = edges.entry((&reference.table, &reference.referenced_table)).or_insert_with(Vec::new);
+        if !columns.contains(&&*reference.column) { columns.push(&reference.column); }
+    }
+
+    let mut graph = String::from("digraph tables {\n    rankdir=LR;\n    node [shape=box];\n");
+    for (&(table, referenced_table), columns) in &edges {
+        graph.push_str(&format!("    \"{}\" -> \"{}\" [label=\"{}\"];\n", table, referenced_table, columns.join(", ")));
+    }
+    graph.push_str("}\n");
+    graph
+}
+
+/// This function returns the versions of a table, from the newest to the oldest one.
+fn get_sorted_versions(table_definitions: &TableDefinitions) -> Vec<&TableDefinition> {
+    let mut versions = table_definitions.versions.iter().collect::<Vec<&TableDefinition>>();
+    versions.sort_by(|x, y| y.version.cmp(&x.version));
+    versions
+}
+
+/// This function returns the versions of a table as text, from the newest to the oldest one.
+fn get_versions_text(table_definitions: &TableDefinitions) -> String {
+    get_sorted_versions(table_definitions).iter().map(|x| x.version.to_string()).collect::<Vec<String>>().join(", ")
+}
+
+/// This function writes a text file to disk.
+fn write_file(path: &PathBuf, text: &str) -> Result<(), Error> {
+    match File::create(path) {
+        Ok(mut file) => match file.write_all(text.as_bytes()) {
+            Ok(_) => Ok(()),
+            Err(_) => Err(PackedFileError::WriteFile { path: path.to_path_buf() }.into()),
+        },
+        Err(_) => Err(PackedFileError::CreateFile { path: path.to_path_buf() }.into()),
+    }
+}
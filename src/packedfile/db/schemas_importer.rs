@@ -1,16 +1,19 @@
-// This is just a helper to get the schemas from the assembly kit. This is NOT INTENDED to work in
-// runtime, so we just wired up when we need to create a new schema from scratch.
+// In this file is all the stuff needed to import the definitions of the tables from the Assembly Kit
+// into an existing schema. The definitions are in the "TWaD_table_name.xml" files, in the folder
+// "assembly_kit/raw_data/db". As they don't have the version of the table, we take it from the tables
+// of the game, so we can only import the definitions of the tables the game actually has.
 
 extern crate serde_xml_rs;
 extern crate failure;
 
 use std::fs::File;
-use std::io::Read;
 use std::path::PathBuf;
 
 use self::failure::Error;
 use self::serde_xml_rs::deserialize;
-use super::DBHeader;
+use packfile::packfile::PackedFile;
+use packedfile::error::PackedFileError;
+use packedfile::get_game_table_version;
 use super::schemas::*;
 use ::common;
 
@@ -30,77 +33,59 @@ pub struct field {
     pub field_type: String,
     pub required: String,
     pub max_length: Option<String>,
+    pub default_value: Option<String>,
+    pub is_filename: Option<String>,
     // There can be multiple source_columns, but we just take the first one.
     pub column_source_column: Option<Vec<String>>,
     pub column_source_table: Option<String>,
     pub field_description: Option<String>,
 }
 
-/// This function creates an schema, and decodes all the tables from the folder we say it into it.
-/// This is intended to use just after compilation, providing the needed folders as const from the
-/// main.rs file. The paths are:
-/// - assembly_kit_schemas_path: this is the path with the TWaD_*****.xml syntax. They are usually in GameFolder/assembly_kit/raw_data/db/.
-/// - testing_tables_path: this is a path containing all the tables extracted from the game we want the schemas. It should have xxx_table/table.
+/// This function merges the definitions of the tables from the Assembly Kit into the provided schema, and
+/// returns a report with the tables added, changed and in conflict. The schema is not saved here, that's
+/// up to the caller. The arguments are:
+/// - schema: the schema of the game we want to update.
+/// - assembly_kit_schemas_path: the folder with the "TWaD_*.xml" files. It's usually "assembly_kit/raw_data/db".
+/// - dependency_database: the tables of the game, so we can know the version of each table.
+///
+/// What we already have in the schema takes priority: manually decoded versions are kept, and so are the
+/// descriptions of the fields. Check `Schema::merge_table_definition` for the details.
 pub fn import_schema(
+    schema: &mut Schema,
     assembly_kit_schemas_path: &PathBuf,
-    testing_tables_path: &PathBuf,
-    rpfm_path: &PathBuf,
-) -> Result<(), Error> {
+    dependency_database: &[PackedFile],
+) -> Result<SchemaMergeReport, Error> {
 
-    // We get the new schema.
-    let mut schema = Schema::new();
-
-    // Then we get all the schema files. We unwrap it, as we want it to crash oon error.
-    let assembly_kit_schemas = common::get_assembly_kit_schemas(assembly_kit_schemas_path).unwrap();
+    // Get all the definition files, and stop if there are none, as it's probably the wrong folder.
+    let assembly_kit_schemas = common::get_assembly_kit_schemas(assembly_kit_schemas_path)?;
+    if assembly_kit_schemas.is_empty() {
+        return Err(PackedFileError::NoAssemblyKitDefinitions { path: assembly_kit_schemas_path.to_path_buf() }.into());
+    }
 
-    // For each file...
+    let mut report = SchemaMergeReport::default();
     for path in &assembly_kit_schemas {
 
-        // We just do this in Debug builds, so we use a print to check when a table throws an error.
-        println!("{:?}", path);
-
-        // We read the file and deserialize it...
-        let file = File::open(&path).expect("Couldn't open file");
-        let imported_table_definition: root = deserialize(file).unwrap();
-
-        // Then we create a new table_definitions, a new imported table definition, and add it to the schema.
-        let mut file_name = path.file_stem().unwrap().to_str().unwrap().to_string();
-        let table_name = format!("{}_tables", file_name.split_off(5));
-
-        // We need it's version too, so... We only add it if his table actually exists.
-        let mut testing_tables_path = testing_tables_path.clone();
-        testing_tables_path.push(table_name.to_owned());
-        match common::get_files_from_subdir(&testing_tables_path) {
-            Ok(db_file_path) => {
-
-                // If we found something...
-                if !db_file_path.is_empty() {
-                    match File::open(&db_file_path[0]) {
-                        Ok(ref mut file) => {
-
-                            // Read the table...
-                            let mut pack_file_buffered = vec![];
-                            file.read_to_end(&mut pack_file_buffered).expect("Error reading file.");
-
-                            // Get it's version...
-                            let header = DBHeader::read(&pack_file_buffered, &mut 0).unwrap();
-                            let version = header.version;
-
-                            // And add it to the schema.
-                            schema.add_table_definitions(TableDefinitions::new(&table_name));
-                            let index = schema.get_table_definitions(&table_name).unwrap();
-                            schema.tables_definitions[index].add_table_definition(TableDefinition::new_from_assembly_kit(&imported_table_definition, version, &table_name));
-
-                        }
-                        Err(_) => continue,
-                    }
-                }
-            }
-            Err(_) => continue,
-        }
+        // The name of the table is the name of the file, without the "TWaD_".
+        let file_name = path.file_stem().map(|x| x.to_string_lossy().to_string()).unwrap_or_default();
+        let table_name = format!("{}_tables", &file_name[5..]);
+
+        // We need the version the game uses. If the game doesn't have the table, we cannot use the definition.
+        let version = match get_game_table_version(&table_name, dependency_database) {
+            Some(version) => version,
+            None => { report.errors.push(format!("{}: the table is not in the game files, so his version is unknown.", table_name)); continue }
+        };
+
+        let imported_table_definition: root = match File::open(&path) {
+            Ok(file) => match deserialize(file) {
+                Ok(imported_table_definition) => imported_table_definition,
+                Err(error) => { report.errors.push(format!("{}: the definition file couldn't be read ({}).", table_name, error)); continue }
+            },
+            Err(_) => { report.errors.push(format!("{}: the definition file couldn't be opened.", table_name)); continue }
+        };
+
+        let table_definition = TableDefinition::new_from_assembly_kit(&imported_table_definition, version, &table_name);
+        schema.merge_table_definition(&table_name, table_definition, &mut report);
     }
 
-    Schema::save(&schema, rpfm_path, "PFH5")?;
-
-    Ok(())
+    Ok(report)
 }
@@ -0,0 +1,203 @@
+// In this file is all the stuff needed to convert the schemas of Pack File Manager (the "master_schema.xml"
+// file) to our `Schema` and back. PFM uses one file for all the games, with this structure:
+//
+// <?xml version="1.0" encoding="utf-8"?>
+// <schema>
+//   <table table_name="land_units_tables" table_version="17">
+//     <field name="key" type="string_ascii" pk="true" />
+//     <field name="category" type="string_ascii" fkey="unit_category.key" />
+//     <field name="morale" type="integer" />
+//     ...
+//   </table>
+//   ...
+// </schema>
+//
+// Tables without "table_version" are version 0, and the references are "table.column", with or without
+// the "_tables" at the end of the table. PFM doesn't have constraints for the fields, so we write ours in a
+// "constraints" attribute, in the same format the Decoder uses ("required; max_length=32; values=a|b"), and
+// read them back if they are there.
+
+extern crate failure;
+extern crate xml;
+
+use std::fs::File;
+use std::io::{BufReader, Write};
+use std::path::PathBuf;
+
+use failure::Error;
+use self::xml::reader::{EventReader, XmlEvent};
+
+use packfile::packfile::PackedFile;
+use packedfile::error::PackedFileError;
+use super::assembly_kit::escape_xml;
+use super::schemas::*;
+
+/// This function reads a PFM schema file and returns the definitions in it as an `Schema`, and the list of
+/// definitions that couldn't be converted (for example, because they have a type we don't know).
+pub fn read_pfm_schema(pfm_schema_path: &PathBuf) -> Result<(Schema, Vec<String>), Error> {
+
+    let file = match File::open(pfm_schema_path) {
+        Ok(file) => file,
+        Err(_) => return Err(PackedFileError::ReadFile { path: pfm_schema_path.to_path_buf() }.into()),
+    };
+
+    let mut schema = Schema::new();
+    let mut errors = vec![];
+
+    // The table we're reading, and the reason why it's not valid, if we found one.
+    let mut current_table: Option<(String, TableDefinition, Option<String>)> = None;
+    for event in EventReader::new(BufReader::new(file)) {
+        match event? {
+            XmlEvent::StartElement { name, attributes, .. } => {
+                let get_attribute = |attribute_name: &str| attributes.iter().find(|x| x.name.local_name == attribute_name).map(|x| x.value.to_owned());
+
+                if name.local_name == "table" {
+                    let table_name = get_attribute("table_name").unwrap_or_default();
+                    let version = get_attribute("table_version").unwrap_or_else(|| "0".to_owned());
+                    current_table = match version.parse::<u32>() {
+                        Ok(version) => Some((table_name, TableDefinition::new(version), None)),
+                        Err(_) => Some((table_name, TableDefinition::new(0), Some(format!("invalid version \"{}\"", version)))),
+                    };
+                }
+
+                else if name.local_name == "field" {
+                    if let Some((_, ref mut table_definition, ref mut error)) = current_table {
+                        let field_name = get_attribute("name").unwrap_or_default();
+                        let field_type = get_attribute("type").unwrap_or_default();
+                        match get_field_type_from_pfm(&field_type) {
+                            Some(field_type) => {
+                                let field_is_key = get_attribute("pk").map(|x| x == "true" || x == "1" || x == "yes").unwrap_or(false);
+                                let field_is_reference = get_attribute("fkey").and_then(|x| {
+                                    let mut reference = x.splitn(2, '.');
+                                    match (reference.next(), reference.next()) {
+                                        (Some(table), Some(column)) if !table.is_empty() && !column.is_empty() => Some((table.trim_right_matches("_tables").to_owned(), column.to_owned())),
+                                        _ => None,
+                                    }
+                                });
+                                let field_description = get_attribute("description").unwrap_or_default();
+                                let mut field = Field::new(field_name, field_type, field_is_key, field_is_reference, field_description);
+                                if let Some(constraints) = get_attribute("constraints") {
+                                    if field.set_constraints(&constraints).is_err() && error.is_none() {
+                                        *error = Some(format!("the field \"{}\" has invalid constraints \"{}\"", field.field_name, constraints));
+                                    }
+                                }
+                                table_definition.fields.push(field);
+                            }
+                            None => if error.is_none() { *error = Some(format!("the field \"{}\" has an unknown type \"{}\"", field_name, field_type)); },
+                        }
+                    }
+                }
+            }
+
+            XmlEvent::EndElement { name } => {
+                if name.local_name == "table" {
+                    if let Some((table_name, table_definition, error)) = current_table.take() {
+                        if table_name.is_empty() { errors.push("A table without name has been ignored.".to_owned()); }
+                        else if let Some(error) = error { errors.push(format!("{} (v{}): {}.", table_name, table_definition.version, error)); }
+                        else {
+                            if schema.get_table_definitions(&table_name).is_none() {
+                                schema.add_table_definitions(TableDefinitions::new(&table_name));
+                            }
+                            let index = schema.get_table_definitions(&table_name).unwrap();
+                            schema.tables_definitions[index].add_table_definition(table_definition);
+                        }
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    Ok((schema, errors))
+}
+
+/// This function merges the definitions of a PFM schema file into the provided schema, and returns a report
+/// with the tables added, changed and in conflict. The schema is not saved here, that's up to the caller.
+///
+/// As PFM uses the same file for all the games, if we have the tables of the game (`game_tables`) we only
+/// merge the definitions of the tables the game has. Otherwise, every definition is merged.
+pub fn import_pfm_schema(schema: &mut Schema, pfm_schema_path: &PathBuf, game_tables: Option<&[PackedFile]>) -> Result<SchemaMergeReport, Error> {
+    let (pfm_schema, errors) = read_pfm_schema(pfm_schema_path)?;
+
+    let mut report = SchemaMergeReport {
+        errors,
+        ..Default::default()
+    };
+    for table_definitions in pfm_schema.tables_definitions {
+        if let Some(game_tables) = game_tables {
+            if !game_tables.iter().any(|x| x.path.len() >= 3 && x.path[0] == "db" && x.path[1] == table_definitions.name) { continue }
+        }
+
+        for table_definition in table_definitions.versions {
+            schema.merge_table_definition(&table_definitions.name, table_definition, &mut report);
+        }
+    }
+
+    Ok(report)
+}
+
+/// This function writes the provided schema to a file in the format of PFM's schemas.
+pub fn export_pfm_schema(schema: &Schema, pfm_schema_path: &PathBuf) -> Result<(), Error> {
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"utf-8\"?>\n<schema>\n");
+
+    for table_definitions in &schema.tables_definitions {
+        for table_definition in &table_definitions.versions {
+            xml.push_str(&format!("  <table table_name=\"{}\" table_version=\"{}\">\n", escape_xml(&table_definitions.name), table_definition.version));
+            for field in &table_definition.fields {
+                xml.push_str(&format!("    <field name=\"{}\" type=\"{}\"", escape_xml(&field.field_name), get_pfm_field_type(&field.field_type)));
+                if field.field_is_key { xml.push_str(" pk=\"true\""); }
+                if let Some((ref table, ref column)) = field.field_is_reference {
+                    xml.push_str(&format!(" fkey=\"{}.{}\"", escape_xml(table), escape_xml(column)));
+                }
+                if !field.field_description.is_empty() {
+                    xml.push_str(&format!(" description=\"{}\"", escape_xml(&field.field_description)));
+                }
+                let constraints = field.get_constraints();
+                if !constraints.is_empty() {
+                    xml.push_str(&format!(" constraints=\"{}\"", escape_xml(&constraints)));
+                }
+                xml.push_str(" />\n");
+            }
+            xml.push_str("  </table>\n");
+        }
+    }
+    xml.push_str("</schema>\n");
+
+    match File::create(pfm_schema_path) {
+        Ok(mut file) => match file.write_all(xml.as_bytes()) {
+            Ok(_) => Ok(()),
+            Err(_) => Err(PackedFileError::WriteFile { path: pfm_schema_path.to_path_buf() }.into()),
+        },
+        Err(_) => Err(PackedFileError::CreateFile { path: pfm_schema_path.to_path_buf() }.into()),
+    }
+}
+
+/// This function returns the `FieldType` of a PFM type, or `None` if we don't know it. In PFM, the strings
+/// without "_ascii" are the UTF-16 ones.
+fn get_field_type_from_pfm(field_type: &str) -> Option<FieldType> {
+    match field_type {
+        "boolean" | "yesno" => Some(FieldType::Boolean),
+        "single" | "float" | "decimal" | "double" => Some(FieldType::Float),
+        "integer" | "int" => Some(FieldType::Integer),
+        "autonumber" | "long" => Some(FieldType::LongInteger),
+        "string_ascii" => Some(FieldType::StringU8),
+        "string" => Some(FieldType::StringU16),
+        "optstring_ascii" => Some(FieldType::OptionalStringU8),
+        "optstring" => Some(FieldType::OptionalStringU16),
+        _ => None,
+    }
+}
+
+/// This function returns the name PFM uses for the provided `FieldType`.
+fn get_pfm_field_type(field_type: &FieldType) -> &'static str {
+    match *field_type {
+        FieldType::Boolean => "boolean",
+        FieldType::Float => "single",
+        FieldType::Integer => "integer",
+        FieldType::LongInteger => "autonumber",
+        FieldType::StringU8 => "string_ascii",
+        FieldType::StringU16 => "string",
+        FieldType::OptionalStringU8 => "optstring_ascii",
+        FieldType::OptionalStringU16 => "optstring",
+    }
+}
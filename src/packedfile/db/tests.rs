@@ -0,0 +1,540 @@
+// This module contain tests to make sure we don't break the parsers and the logic of the DB Tables
+// stuff (queries, bulk edits, patches,...) in a update, as a bug in them can silently break the
+// tables of a mod. Like in the common tests, this is the only place where ".unwrap()" will be allowed.
+
+use std::collections::{BTreeMap, HashSet};
+use std::env;
+use std::fs::{File, create_dir_all, remove_dir_all, remove_file};
+use std::io::Write;
+use serde_json;
+
+use packedfile::error::PackedFileError;
+use packedfile::db::{DB, DecodedData};
+use packedfile::db::schemas::*;
+use packedfile::db::query::*;
+use packedfile::db::key_rename::get_loc_key_match;
+use packedfile::db::patches::*;
+use packedfile::db::bulk_edit::*;
+use packedfile::loc::{Loc, LocEntry};
+use packedfile::normalize::{sort_db_rows, sort_loc_rows};
+use packedfile::serializers::SerializableWithHeaders;
+use packfile::packfile::PackedFile;
+use settings::GameInfo;
+
+/*
+--------------------------------------------------------
+                        Queries
+--------------------------------------------------------
+*/
+
+/// This function returns the columns and a row to test the expressions with.
+fn get_query_test_row() -> (Vec<String>, Vec<String>) {
+    let columns = vec!["land_units.key".to_owned(), "land_units.morale".to_owned(), "units.key".to_owned()];
+    let row = vec!["wh_main_emp_inf_swordsmen".to_owned(), "45".to_owned(), "swordsmen".to_owned()];
+    (columns, row)
+}
+
+/// Test to make sure a complete query (`Query::parse()`) is parsed properly.
+#[test]
+fn test_query_parse() {
+    let query = Query::parse("land_units join main_units_tables on unit where morale > 50 select land_units.key, caste").unwrap();
+    assert_eq!(query.table, "land_units_tables");
+    assert_eq!(query.joins.len(), 1);
+    assert_eq!(query.joins[0].table, "main_units_tables");
+    assert_eq!(query.joins[0].column, Some("unit".to_owned()));
+    assert_eq!(query.filter.is_some(), true);
+    assert_eq!(query.columns, Some(vec!["land_units.key".to_owned(), "caste".to_owned()]));
+
+    // "select *" is the same as not selecting columns.
+    assert_eq!(Query::parse("land_units select *").unwrap().columns, None);
+
+    // Anything left after the query is an error.
+    assert_eq!(Query::parse("land_units where morale > 50 morale").is_err(), true);
+    assert_eq!(Query::parse("land_units select").is_err(), true);
+}
+
+/// Test to make sure "and" has more precedence than "or", and parenthesis and "not" work.
+#[test]
+fn test_expression_precedence() {
+    let (columns, row) = get_query_test_row();
+
+    // This is "true or (false and false)". If "or" went first, it would be false.
+    assert_eq!(Expression::parse("morale = 45 or morale = 1 and morale = 2").unwrap().evaluate(&columns, &row).unwrap(), true);
+
+    // This is "(true or false) and false".
+    assert_eq!(Expression::parse("(morale = 45 or morale = 1) and morale = 2").unwrap().evaluate(&columns, &row).unwrap(), false);
+
+    // "not" only negates the comparison after it.
+    assert_eq!(Expression::parse("not morale = 1 and morale = 45").unwrap().evaluate(&columns, &row).unwrap(), true);
+    assert_eq!(Expression::parse("not (morale = 45 or morale = 1)").unwrap().evaluate(&columns, &row).unwrap(), false);
+
+    // Keywords are case-insensitive.
+    assert_eq!(Expression::parse("morale = 1 OR morale = 45").unwrap().evaluate(&columns, &row).unwrap(), true);
+}
+
+/// Test to make sure the texts of the expressions are quoted and escaped properly.
+#[test]
+fn test_expression_quoting() {
+    let (columns, row) = get_query_test_row();
+
+    // Both quotes work, and spaces and keywords inside them are part of the text.
+    assert_eq!(Expression::parse("land_units.key contains \"_inf_\"").unwrap().evaluate(&columns, &row).unwrap(), true);
+    assert_eq!(Expression::parse("land_units.key starts_with 'wh_main'").unwrap().evaluate(&columns, &row).unwrap(), true);
+    assert_eq!(Expression::parse("land_units.key = \"a and b\"").unwrap().evaluate(&columns, &row).unwrap(), false);
+
+    // Escaped quotes are part of the text.
+    match Expression::parse("units.key = \"sword\\\"smen\"").unwrap() {
+        Expression::Comparison(_, _, Operand::Value(ref value)) => assert_eq!(value, "sword\"smen"),
+        _ => panic!("The expression is not a comparison with a value."),
+    }
+
+    // Numbers are compared as numbers, but only if they are finite.
+    assert_eq!(Expression::parse("morale > 5").unwrap().evaluate(&columns, &row).unwrap(), true);
+    assert_eq!(Expression::parse("morale < \"inf\"").unwrap().evaluate(&columns, &row).unwrap(), true);
+    assert_eq!(Expression::parse("morale = \"nan\"").unwrap().evaluate(&columns, &row).unwrap(), false);
+}
+
+/// Test to make sure the wrong expressions return an error.
+#[test]
+fn test_expression_errors() {
+    assert_eq!(Expression::parse("key = \"unclosed").is_err(), true);
+    assert_eq!(Expression::parse("key ~ 5").is_err(), true);
+    assert_eq!(Expression::parse("key is 5").is_err(), true);
+    assert_eq!(Expression::parse("(key = 5").is_err(), true);
+    assert_eq!(Expression::parse("key = 5)").is_err(), true);
+    assert_eq!(Expression::parse("key =").is_err(), true);
+    assert_eq!(Expression::parse("key = 5 and").is_err(), true);
+    assert_eq!(Expression::parse("").is_err(), true);
+}
+
+/// Test to make sure the columns (`get_column_index()`) are found only when the table and the column match entirely.
+#[test]
+fn test_get_column_index() {
+    let (columns, _) = get_query_test_row();
+
+    // Full names, with or without "_tables".
+    assert_eq!(get_column_index(&columns, "land_units.key").unwrap(), 0);
+    assert_eq!(get_column_index(&columns, "land_units_tables.key").unwrap(), 0);
+    assert_eq!(get_column_index(&columns, "units.key").unwrap(), 2);
+
+    // Only the column, if only one table has it.
+    assert_eq!(get_column_index(&columns, "morale").unwrap(), 1);
+
+    // Part of a table is not a table.
+    match get_column_index(&columns, "nits.key").unwrap_err().downcast_ref::<PackedFileError>() {
+        Some(&PackedFileError::ColumnNotFound { .. }) => {},
+        _ => panic!("Part of the name of a table matched a column."),
+    }
+
+    // Only the column, if more than one table has it.
+    match get_column_index(&columns, "key").unwrap_err().downcast_ref::<PackedFileError>() {
+        Some(&PackedFileError::AmbiguousColumn { ref columns, .. }) => assert_eq!(columns.len(), 2),
+        _ => panic!("An ambiguous column has been matched."),
+    }
+}
+
+/// This function adds a table with one row to the schema and the PackedFiles, to test the queries with.
+fn add_query_test_table(table: &str, fields: Vec<Field>, row: &[&str], schema: &mut Schema, packed_files: &mut Vec<PackedFile>) {
+    let table_definition = TableDefinition { version: 1, fields };
+    let mut table_definitions = TableDefinitions::new(table);
+    table_definitions.add_table_definition(table_definition.clone());
+    schema.add_table_definitions(table_definitions);
+
+    let mut db = DB::new(table, 1, table_definition);
+    let mut entry = vec![DecodedData::Index("1".to_owned())];
+    entry.extend(row.iter().map(|x| DecodedData::StringU8((*x).to_owned())));
+    db.data.entries.push(entry);
+
+    let data = db.save();
+    packed_files.push(PackedFile::read(data.len() as u32, vec!["db".to_owned(), table.to_owned(), "test".to_owned()], data));
+}
+
+/// Test to make sure a join (`Query::execute()`) ignores the references to columns that don't exist, instead of crashing.
+#[test]
+fn test_query_join_missing_reference_column() {
+    let mut schema = Schema::new();
+    let mut packed_files = vec![];
+    add_query_test_table("abilities_tables", vec![
+        Field::new("id".to_owned(), FieldType::StringU8, true, None, String::new()),
+    ], &["charge"], &mut schema, &mut packed_files);
+    add_query_test_table("junctions_tables", vec![
+        Field::new("faction".to_owned(), FieldType::StringU8, true, None, String::new()),
+        Field::new("ability".to_owned(), FieldType::StringU8, true, Some(("abilities".to_owned(), "key".to_owned())), String::new()),
+    ], &["wh_main_emp", "charge"], &mut schema, &mut packed_files);
+    add_query_test_table("units_tables", vec![
+        Field::new("unit".to_owned(), FieldType::StringU8, true, None, String::new()),
+        Field::new("ability".to_owned(), FieldType::StringU8, false, Some(("abilities".to_owned(), "id".to_owned())), String::new()),
+    ], &["swordsmen", "charge"], &mut schema, &mut packed_files);
+
+    // "abilities" has no "key" column, so there is nothing to join with.
+    match Query::parse("junctions join abilities").unwrap().execute(&packed_files, &schema).unwrap_err().downcast_ref::<PackedFileError>() {
+        Some(&PackedFileError::NoJoinReference { .. }) => {},
+        _ => panic!("A reference to a column that doesn't exist has been used to join the tables."),
+    }
+
+    // References to existing columns still work.
+    let result = Query::parse("units join abilities").unwrap().execute(&packed_files, &schema).unwrap();
+    assert_eq!(result.columns.len(), 3);
+    assert_eq!(result.rows.len(), 1);
+}
+
+/*
+--------------------------------------------------------
+                    Loc Keys of Rows
+--------------------------------------------------------
+*/
+
+/// Test to make sure the Loc keys (`get_loc_key_match()`) of a row are found without taking the ones of other rows.
+#[test]
+fn test_get_loc_key_match() {
+    let table_keys = vec!["swordsmen".to_owned(), "emp_swordsmen".to_owned()].into_iter().collect::<HashSet<String>>();
+
+    // Known columns are sure matches, unknown ones are only guesses.
+    assert_eq!(get_loc_key_match("land_units_onscreen_name_swordsmen", "land_units", "swordsmen", &table_keys), Some(false));
+    assert_eq!(get_loc_key_match("land_units_weird_column_swordsmen", "land_units", "swordsmen", &table_keys), Some(true));
+
+    // Keys of other rows ending like the key are not matches.
+    assert_eq!(get_loc_key_match("land_units_onscreen_name_emp_swordsmen", "land_units", "swordsmen", &table_keys), None);
+    assert_eq!(get_loc_key_match("land_units_onscreen_name_emp_swordsmen", "land_units", "emp_swordsmen", &table_keys), Some(false));
+
+    // Other tables and keys without column are not matches.
+    assert_eq!(get_loc_key_match("main_units_onscreen_name_swordsmen", "land_units", "swordsmen", &table_keys), None);
+    assert_eq!(get_loc_key_match("land_units_swordsmen", "land_units", "swordsmen", &table_keys), None);
+}
+
+/*
+--------------------------------------------------------
+                        Patches
+--------------------------------------------------------
+*/
+
+/// This function returns a table with a key column and a value column, with the provided rows.
+fn get_patch_test_table(rows: &[(&str, &str)]) -> DB {
+    let table_definition = TableDefinition {
+        version: 1,
+        fields: vec![
+            Field::new("key".to_owned(), FieldType::StringU8, true, None, String::new()),
+            Field::new("value".to_owned(), FieldType::StringU8, false, None, String::new()),
+        ],
+    };
+
+    let mut db = DB::new("patch_tests_tables", 1, table_definition);
+    db.data.entries = rows.iter().enumerate().map(|(index, &(key, value))| vec![
+        DecodedData::Index(format!("{}", index + 1)),
+        DecodedData::StringU8(key.to_owned()),
+        DecodedData::StringU8(value.to_owned()),
+    ]).collect();
+    db
+}
+
+/// This function returns the rows of a table, without index.
+fn get_patch_test_rows(db: &DB) -> Vec<(String, String)> {
+    db.data.entries.iter().map(|x| (x[1].to_string(), x[2].to_string())).collect()
+}
+
+/// Test to make sure a patch (`TablePatch::new()`) applied (`TablePatch::apply()`) to the same vanilla table
+/// gives us only the rows we changed.
+#[test]
+fn test_table_patch_round_trip() {
+    let vanilla = vec![get_patch_test_table(&[("a", "1"), ("b", "2"), ("c", "3")])];
+    let modded = vec![get_patch_test_table(&[("a", "1"), ("b", "20"), ("d", "4")])];
+
+    let table_patch = TablePatch::new("patch_tests_tables", &modded, &vanilla, false).unwrap();
+    assert_eq!(table_patch.operations.len(), 2);
+
+    let mut report = PatchReport::default();
+    let tables = table_patch.apply(&vanilla, &mut report);
+    assert_eq!(report.applied, 2);
+    assert_eq!(report.conflicts.is_empty() && report.skipped.is_empty(), true);
+    assert_eq!(tables.len(), 1);
+    assert_eq!(tables[0].0, None);
+    assert_eq!(get_patch_test_rows(&tables[0].1), vec![("b".to_owned(), "20".to_owned()), ("d".to_owned(), "4".to_owned())]);
+}
+
+/// Test to make sure a patch with deletes replaces every vanilla fragment, without the deleted rows.
+#[test]
+fn test_table_patch_deletes() {
+    let vanilla = vec![get_patch_test_table(&[("a", "1"), ("b", "2")]), get_patch_test_table(&[("c", "3")])];
+    let modded = vec![get_patch_test_table(&[("a", "1"), ("b", "20"), ("d", "4")])];
+
+    let table_patch = TablePatch::new("patch_tests_tables", &modded, &vanilla, true).unwrap();
+    assert_eq!(table_patch.operations.len(), 3);
+
+    let mut report = PatchReport::default();
+    let tables = table_patch.apply(&vanilla, &mut report);
+    assert_eq!(report.applied, 3);
+    assert_eq!(tables.len(), 2);
+    assert_eq!(tables[0].0, Some(0));
+    assert_eq!(get_patch_test_rows(&tables[0].1), vec![("a".to_owned(), "1".to_owned()), ("b".to_owned(), "20".to_owned()), ("d".to_owned(), "4".to_owned())]);
+    assert_eq!(tables[1].0, Some(1));
+    assert_eq!(tables[1].1.data.entries.is_empty(), true);
+}
+
+/// Test to make sure the changes of vanilla are reported, and the operations that cannot be applied are not counted.
+#[test]
+fn test_table_patch_conflicts() {
+    let vanilla = vec![get_patch_test_table(&[("a", "1"), ("b", "2"), ("c", "3")])];
+    let modded = vec![get_patch_test_table(&[("a", "10"), ("b", "20"), ("c", "30")])];
+    let mut table_patch = TablePatch::new("patch_tests_tables", &modded, &vanilla, false).unwrap();
+
+    // A change to a column that doesn't exist.
+    let mut key = BTreeMap::new();
+    key.insert("key".to_owned(), "a".to_owned());
+    let mut changes = BTreeMap::new();
+    changes.insert("missing".to_owned(), CellChange { old: None, new: "5".to_owned() });
+    table_patch.operations.push(PatchOperation::Modify { key, changes });
+
+    // Vanilla changed "b" and deleted "c".
+    let new_vanilla = vec![get_patch_test_table(&[("a", "1"), ("b", "5")])];
+    let mut report = PatchReport::default();
+    let tables = table_patch.apply(&new_vanilla, &mut report);
+
+    assert_eq!(report.applied, 2);
+    assert_eq!(report.conflicts.len(), 1);
+    assert_eq!(report.skipped.len(), 2);
+    assert_eq!(get_patch_test_rows(&tables[0].1), vec![("a".to_owned(), "10".to_owned()), ("b".to_owned(), "20".to_owned())]);
+}
+
+/*
+--------------------------------------------------------
+                        Bulk Edits
+--------------------------------------------------------
+*/
+
+/// This function returns the columns and a row to test the value expressions with.
+fn get_bulk_edit_test_row() -> (Vec<String>, Vec<DecodedData>) {
+    let columns = vec!["land_units.key".to_owned(), "land_units.cost".to_owned(), "land_units.category".to_owned()];
+    let row = vec![DecodedData::StringU8("10".to_owned()), DecodedData::Integer(100), DecodedData::StringU8("inf_melee".to_owned())];
+    (columns, row)
+}
+
+/// This function parses and evaluates a value expression for the test row.
+fn evaluate_bulk_edit(expression: &str) -> Value {
+    let (columns, row) = get_bulk_edit_test_row();
+    ValueExpression::parse(expression).unwrap().evaluate(&columns, &row).unwrap()
+}
+
+/// Test to make sure "*" and "/" have more precedence than "+" and "-", and these more than "&".
+#[test]
+fn test_bulk_edit_precedence() {
+    assert_eq!(evaluate_bulk_edit("1 + 2 * 3"), Value::Number(7.0));
+    assert_eq!(evaluate_bulk_edit("(1 + 2) * 3"), Value::Number(9.0));
+    assert_eq!(evaluate_bulk_edit("10 - 2 - 3"), Value::Number(5.0));
+    assert_eq!(evaluate_bulk_edit("-2 * 3"), Value::Number(-6.0));
+    assert_eq!(evaluate_bulk_edit("1 + 2 & 3"), Value::Text("33".to_owned()));
+    assert_eq!(evaluate_bulk_edit("round(cost * 1.15)"), Value::Number(115.0));
+    assert_eq!(evaluate_bulk_edit("max(1, cost / 4, 3)"), Value::Number(25.0));
+}
+
+/// Test to make sure "+" adds numbers and concatenates texts, even if the texts are numbers.
+#[test]
+fn test_bulk_edit_types() {
+    assert_eq!(evaluate_bulk_edit("cost + 2"), Value::Number(102.0));
+    assert_eq!(evaluate_bulk_edit("key + 2"), Value::Text("102".to_owned()));
+    assert_eq!(evaluate_bulk_edit("key & cost"), Value::Text("10100".to_owned()));
+    assert_eq!(evaluate_bulk_edit("key * 2"), Value::Number(20.0));
+
+    // The result is converted to the type of the column.
+    let mut data = get_patch_test_table(&[("a", "10")]).data;
+    let report = BulkEdit::new("value", "value + 2", None).unwrap().apply("patch_tests_tables", &mut data).unwrap();
+    assert_eq!(report.changes, vec![(0, "10".to_owned(), "102".to_owned())]);
+}
+
+/// Test to make sure the texts are quoted and escaped properly, and their templates filled.
+#[test]
+fn test_bulk_edit_quoting() {
+    assert_eq!(evaluate_bulk_edit("'it\\'s'"), Value::Text("it's".to_owned()));
+    assert_eq!(evaluate_bulk_edit("\"a, (b) + c\""), Value::Text("a, (b) + c".to_owned()));
+    assert_eq!(evaluate_bulk_edit("\"{key}_name\""), Value::Text("10_name".to_owned()));
+    assert_eq!(evaluate_bulk_edit("\"{land_units.category}\""), Value::Text("inf_melee".to_owned()));
+
+    // Parts that are not columns are kept.
+    assert_eq!(evaluate_bulk_edit("\"{unknown}_{}_{key\""), Value::Text("{unknown}_{}_{key".to_owned()));
+}
+
+/// Test to make sure the wrong expressions return an error.
+#[test]
+fn test_bulk_edit_errors() {
+    assert_eq!(ValueExpression::parse("1 +").is_err(), true);
+    assert_eq!(ValueExpression::parse("(1").is_err(), true);
+    assert_eq!(ValueExpression::parse("1)").is_err(), true);
+    assert_eq!(ValueExpression::parse("1 & ").is_err(), true);
+    assert_eq!(ValueExpression::parse("'unclosed").is_err(), true);
+    assert_eq!(ValueExpression::parse("1 # 2").is_err(), true);
+    assert_eq!(ValueExpression::parse("foo(1)").is_err(), true);
+    assert_eq!(ValueExpression::parse("round()").is_err(), true);
+    assert_eq!(ValueExpression::parse("round(1, 2, 3)").is_err(), true);
+    assert_eq!(ValueExpression::parse("").is_err(), true);
+
+    // These are valid, but cannot be evaluated.
+    let (columns, row) = get_bulk_edit_test_row();
+    assert_eq!(ValueExpression::parse("cost / 0").unwrap().evaluate(&columns, &row).is_err(), true);
+    assert_eq!(ValueExpression::parse("missing + 1").unwrap().evaluate(&columns, &row).is_err(), true);
+    assert_eq!(ValueExpression::parse("category * 2").unwrap().evaluate(&columns, &row).is_err(), true);
+}
+
+/*
+--------------------------------------------------------
+                    Normalization
+--------------------------------------------------------
+*/
+
+/// Test to make sure the rows of a table are sorted only by their keys, keeping the order of the duplicated ones.
+#[test]
+fn test_sort_db_rows() {
+    let mut db = get_patch_test_table(&[("b", "first"), ("a", "x"), ("b", "second"), ("c", "y"), ("b", "third")]);
+    assert_eq!(sort_db_rows(&mut db), vec!["b".to_owned()]);
+    assert_eq!(get_patch_test_rows(&db), vec![
+        ("a".to_owned(), "x".to_owned()),
+        ("b".to_owned(), "first".to_owned()),
+        ("b".to_owned(), "second".to_owned()),
+        ("b".to_owned(), "third".to_owned()),
+        ("c".to_owned(), "y".to_owned()),
+    ]);
+
+    // The indexes are regenerated in the new order.
+    assert_eq!(db.data.entries.iter().map(|x| x[0].to_string()).collect::<Vec<String>>(), vec!["01", "02", "03", "04", "05"]);
+
+    // Without keys, all the columns are the keys.
+    let mut db = get_patch_test_table(&[("b", "2"), ("b", "1"), ("a", "3"), ("b", "1")]);
+    db.data.table_definition.fields[0].field_is_key = false;
+    assert_eq!(sort_db_rows(&mut db), vec!["b, 1".to_owned()]);
+    assert_eq!(get_patch_test_rows(&db), vec![
+        ("a".to_owned(), "3".to_owned()),
+        ("b".to_owned(), "1".to_owned()),
+        ("b".to_owned(), "1".to_owned()),
+        ("b".to_owned(), "2".to_owned()),
+    ]);
+}
+
+/// Test to make sure the rows of a Loc are sorted only by their keys, keeping the order of the duplicated ones.
+#[test]
+fn test_sort_loc_rows() {
+    let mut loc = Loc::new();
+    loc.data.entries = vec![
+        LocEntry::new("b".to_owned(), "z".to_owned(), false),
+        LocEntry::new("a".to_owned(), "y".to_owned(), false),
+        LocEntry::new("b".to_owned(), "x".to_owned(), true),
+    ];
+
+    assert_eq!(sort_loc_rows(&mut loc), vec!["b".to_owned()]);
+    assert_eq!(loc.data.entries.iter().map(|x| (&*x.key, &*x.text)).collect::<Vec<(&str, &str)>>(), vec![("a", "y"), ("b", "z"), ("b", "x")]);
+}
+
+/*
+--------------------------------------------------------
+                    Truncated Data
+--------------------------------------------------------
+*/
+
+/// Test to make sure a table with a truncated string returns `TruncatedData`, with the field that couldn't be decoded.
+#[test]
+fn test_read_truncated_string() {
+    let db = get_patch_test_table(&[("a", "1"), ("bb", "22")]);
+    let mut table_definitions = TableDefinitions::new("patch_tests_tables");
+    table_definitions.add_table_definition(db.data.table_definition.clone());
+    let mut schema = Schema::new();
+    schema.add_table_definitions(table_definitions);
+
+    let mut data = db.save();
+    assert_eq!(DB::read(&data, "patch_tests_tables", &schema).unwrap().data.entries.len(), 2);
+
+    // The size of the last string says it has two bytes, but we only have one.
+    let offset = data.len() - 4;
+    data.pop();
+    match DB::read(&data, "patch_tests_tables", &schema).unwrap_err().downcast_ref::<PackedFileError>() {
+        Some(&PackedFileError::TruncatedData { ref field_type, ref column, row, offset: error_offset }) => {
+            assert_eq!(*field_type, FieldType::StringU8);
+            assert_eq!(column, "value");
+            assert_eq!(row, 1);
+            assert_eq!(error_offset, offset);
+        }
+        _ => panic!("A truncated string has not been reported as truncated data."),
+    }
+}
+
+/*
+--------------------------------------------------------
+                  Imports with Headers
+--------------------------------------------------------
+*/
+
+/// Test to make sure a Loc CSV file without the "tooltip" column (`LocData::import_with_headers()`) is imported
+/// with the default tooltip, and an invalid tooltip is still an error.
+#[test]
+fn test_loc_import_with_headers_missing_tooltip() {
+    let path = env::temp_dir().join("rpfm_test_loc_import_with_headers_missing_tooltip.csv");
+    let mut loc = Loc::new();
+
+    File::create(&path).unwrap().write_all(b"key,text\nswordsmen,Swordsmen\n").unwrap();
+    let report = loc.data.import_with_headers(&path).unwrap();
+    assert_eq!(report.missing_columns, vec!["tooltip".to_owned()]);
+    assert_eq!(loc.data.entries.iter().map(|x| (&*x.key, &*x.text, x.tooltip)).collect::<Vec<(&str, &str, bool)>>(), vec![("swordsmen", "Swordsmen", false)]);
+
+    File::create(&path).unwrap().write_all(b"key,text,tooltip\nswordsmen,Swordsmen,maybe\n").unwrap();
+    match loc.data.import_with_headers(&path).unwrap_err().downcast_ref::<PackedFileError>() {
+        Some(&PackedFileError::InvalidImportValue { ref column, row, .. }) => {
+            assert_eq!(column, "tooltip");
+            assert_eq!(row, 0);
+        }
+        _ => panic!("An invalid tooltip has been imported."),
+    }
+
+    remove_file(&path).unwrap();
+}
+
+/*
+--------------------------------------------------------
+                    Schema Overlays
+--------------------------------------------------------
+*/
+
+/// This function returns the versions of a table in a schema, sorted.
+fn get_schema_test_versions(schema: &Schema, table: &str) -> Vec<u32> {
+    let mut versions = schema.tables_definitions[schema.get_table_definitions(table).unwrap()].versions.iter().map(|x| x.version).collect::<Vec<u32>>();
+    versions.sort();
+    versions
+}
+
+/// Test to make sure a version removed from the downloaded schema (`Schema::save()`) is still removed when loading
+/// the schema again, and a broken overlay is returned as a warning, loading the downloaded schema alone.
+#[test]
+fn test_schema_overlay_removed_versions() {
+    let rpfm_path = env::temp_dir().join("rpfm_test_schema_overlay_removed_versions");
+    let _ = remove_dir_all(&rpfm_path);
+    create_dir_all(rpfm_path.join("schemas")).unwrap();
+    let supported_games = vec![GameInfo {
+        display_name: "Test".to_owned(),
+        folder_name: "test".to_owned(),
+        id: "PFH5".to_owned(),
+        dependency_pack: "test.pack".to_owned(),
+        schema: "schema_test.json".to_owned(),
+        schema_base: None,
+    }];
+
+    let mut table_definitions = TableDefinitions::new("patch_tests_tables");
+    table_definitions.add_table_definition(TableDefinition::new(1));
+    table_definitions.add_table_definition(TableDefinition::new(2));
+    let mut upstream = Schema::new();
+    upstream.add_table_definitions(table_definitions);
+    File::create(rpfm_path.join("schemas/schema_test.json")).unwrap().write_all(serde_json::to_string(&upstream).unwrap().as_bytes()).unwrap();
+
+    let (mut schema, warning) = Schema::load(&rpfm_path, &supported_games, "test").unwrap();
+    assert!(warning.is_none());
+    DB::remove_table_version("patch_tests_tables", 1, &mut schema).unwrap();
+    Schema::save(&schema, &rpfm_path, &supported_games, "test").unwrap();
+
+    let (schema, warning) = Schema::load(&rpfm_path, &supported_games, "test").unwrap();
+    assert!(warning.is_none());
+    assert_eq!(get_schema_test_versions(&schema, "patch_tests_tables"), vec![2]);
+
+    File::create(rpfm_path.join("schemas/user/schema_test.json")).unwrap().write_all(b"{").unwrap();
+    let (schema, warning) = Schema::load(&rpfm_path, &supported_games, "test").unwrap();
+    match warning {
+        Some(PackedFileError::BrokenSchemaOverlay { ref path, .. }) => assert_eq!(*path, rpfm_path.join("schemas/user/schema_test.json")),
+        _ => panic!("A broken schema overlay has not been reported."),
+    }
+    assert_eq!(get_schema_test_versions(&schema, "patch_tests_tables"), vec![1, 2]);
+
+    remove_dir_all(&rpfm_path).unwrap();
+}
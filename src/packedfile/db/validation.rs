@@ -0,0 +1,222 @@
+// In this file we define the "Validation Report" for DB Tables. This report checks the data of the
+// tables against the constraints we have in the schema (keys, required fields, max lengths, ranges,
+// allowed values and paths), so we can catch the errors before the game does it for us.
+
+use std::collections::HashMap;
+use std::fmt;
+
+use packfile::packfile::PackedFile;
+use super::DB;
+use super::DBData;
+use super::schemas::{Schema, Field, FieldType};
+
+/// `ValidationReport`: This struct holds the result of validating the tables of a PackFile. It has:
+/// - errors: the errors found in the tables, in the order we found them.
+/// - undecodeable_tables: tables we couldn't decode, so we couldn't check them.
+#[derive(Clone, Debug)]
+pub struct ValidationReport {
+    pub errors: Vec<ValidationError>,
+    pub undecodeable_tables: Vec<Vec<String>>,
+}
+
+/// `ValidationError`: This struct holds the info of one of the errors of the report. It has:
+/// - path: the path of the table in the PackFile.
+/// - row: the row with the error, starting from 0.
+/// - error_type: the error itself.
+#[derive(Clone, Debug)]
+pub struct ValidationError {
+    pub path: Vec<String>,
+    pub row: usize,
+    pub error_type: ValidationErrorType,
+}
+
+/// `ValidationErrorType`: This enum holds the different errors we can find in a row:
+/// - DuplicatedKey: the key of the row is already used by another row. It has the key and the row that used it first.
+/// - EmptyRequiredField: the field with this name is empty, but it cannot be.
+/// - FieldTooLong: the field with this name has more characters than allowed. It has (field name, length, max length).
+/// - OutOfRange: the field with this name has a value out of his range. It has (field name, value, min, max).
+/// - NotAllowedValue: the field with this name has a value that is not one of his allowed values. It has (field name, value).
+/// - InvalidPath: the field with this name is a path, but it has characters not allowed in paths. It has (field name, value).
+#[derive(Clone, Debug)]
+pub enum ValidationErrorType {
+    DuplicatedKey(String, usize),
+    EmptyRequiredField(String),
+    FieldTooLong(String, usize, u32),
+    OutOfRange(String, String, Option<f64>, Option<f64>),
+    NotAllowedValue(String, String),
+    InvalidPath(String, String),
+}
+
+/// Implementation of `ValidationReport`.
+impl ValidationReport {
+
+    /// This function validates every table in the provided list of PackedFiles and returns the report of the errors found.
+    pub fn new(packed_files: &[PackedFile], schema: &Schema) -> Self {
+
+        let mut errors = vec![];
+        let mut undecodeable_tables = vec![];
+
+        for packed_file in packed_files.iter().filter(|x| x.path.len() >= 3 && x.path[0] == "db") {
+            match DB::read(&packed_file.data, &packed_file.path[1], schema) {
+                Ok(db) => errors.append(&mut validate_table(&db.data, &packed_file.path)),
+                Err(_) => undecodeable_tables.push(packed_file.path.to_vec()),
+            }
+        }
+
+        Self {
+            errors,
+            undecodeable_tables,
+        }
+    }
+
+    /// This function returns true if there are no errors in the report. Tables we couldn't decode are
+    /// not considered errors here, as they are not our problem, but the schema's problem.
+    pub fn is_empty(&self) -> bool {
+        self.errors.is_empty()
+    }
+}
+
+/// This function validates the data of a table and returns the errors found, ordered by row.
+pub fn validate_table(data: &DBData, path: &[String]) -> Vec<ValidationError> {
+
+    let mut errors = vec![];
+    let fields = &data.table_definition.fields;
+    let key_columns = fields.iter().enumerate().filter(|x| x.1.field_is_key).map(|x| x.0).collect::<Vec<usize>>();
+    let mut keys: HashMap<String, usize> = HashMap::new();
+
+    for (row, entry) in data.entries.iter().enumerate() {
+
+        // If the table has keys, check that nobody has used this one before. Keys with multiple columns are joined.
+        if !key_columns.is_empty() {
+            let key = key_columns.iter().map(|x| entry[x + 1].to_string()).collect::<Vec<String>>().join("|");
+            match keys.get(&key) {
+                Some(first_row) => errors.push(ValidationError {
+                    path: path.to_vec(),
+                    row,
+                    error_type: ValidationErrorType::DuplicatedKey(key.to_owned(), *first_row),
+                }),
+                None => { keys.insert(key, row); }
+            }
+        }
+
+        // Then check the constraints of every field.
+        for (column, field) in fields.iter().enumerate() {
+            for error_type in validate_value(field, &entry[column + 1].to_string()) {
+                errors.push(ValidationError {
+                    path: path.to_vec(),
+                    row,
+                    error_type,
+                });
+            }
+        }
+    }
+
+    errors
+}
+
+/// This function checks a value (as text) against the constraints of his field, and returns the errors
+/// found. It's used by the validation of entire tables and, through `validate_edited_value`, by the DB View.
+pub fn validate_value(field: &Field, value: &str) -> Vec<ValidationErrorType> {
+    let mut errors = vec![];
+    match field.field_type {
+        FieldType::StringU8 |
+        FieldType::StringU16 |
+        FieldType::OptionalStringU8 |
+        FieldType::OptionalStringU16 => {
+            let length = value.chars().count();
+
+            if field.field_is_required && length == 0 {
+                errors.push(ValidationErrorType::EmptyRequiredField(field.field_name.to_owned()));
+            }
+
+            if let Some(max_length) = field.field_max_length {
+                if length > max_length as usize {
+                    errors.push(ValidationErrorType::FieldTooLong(field.field_name.to_owned(), length, max_length));
+                }
+            }
+
+            if field.field_is_filename && value.chars().any(|x| x.is_control() || "<>:\"|?*".contains(x)) {
+                errors.push(ValidationErrorType::InvalidPath(field.field_name.to_owned(), value.to_owned()));
+            }
+        }
+
+        FieldType::Float |
+        FieldType::Integer |
+        FieldType::LongInteger => {
+            if let Ok(number) = value.parse::<f64>() {
+                if field.field_min_value.map(|x| number < x).unwrap_or(false) || field.field_max_value.map(|x| number > x).unwrap_or(false) {
+                    errors.push(ValidationErrorType::OutOfRange(field.field_name.to_owned(), value.to_owned(), field.field_min_value, field.field_max_value));
+                }
+            }
+        }
+        FieldType::Boolean => {},
+    }
+
+    // Empty optional values are allowed, even if they are not one of the allowed values.
+    let is_empty_optional = value.is_empty() && !field.field_is_required;
+    if !field.field_enum_values.is_empty() && !is_empty_optional && !field.field_enum_values.iter().any(|x| x == value) {
+        errors.push(ValidationErrorType::NotAllowedValue(field.field_name.to_owned(), value.to_owned()));
+    }
+
+    errors
+}
+
+/// This function checks a value the user is writing in a cell of the DB View against the constraints of his field.
+/// It's like `validate_value`, but empty values are always allowed, as clearing a cell (even a key one) is a common
+/// step while editing a table. They are still reported when validating the entire table.
+pub fn validate_edited_value(field: &Field, value: &str) -> Vec<ValidationErrorType> {
+    if value.is_empty() { vec![] } else { validate_value(field, value) }
+}
+
+/// Implementation of `Display` for `ValidationError`. Rows are shown starting from 1, like in the Index column of the DB View.
+impl fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}, row {}: {}", self.path.join("/"), self.row + 1, self.error_type)
+    }
+}
+
+/// Implementation of `Display` for `ValidationErrorType`.
+impl fmt::Display for ValidationErrorType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ValidationErrorType::DuplicatedKey(ref key, first_row) => write!(f, "the key \"{}\" is already used in row {}.", key, first_row + 1),
+            ValidationErrorType::EmptyRequiredField(ref field) => write!(f, "the field \"{}\" cannot be empty.", field),
+            ValidationErrorType::FieldTooLong(ref field, length, max_length) => write!(f, "the field \"{}\" has {} characters, but the maximum is {}.", field, length, max_length),
+            ValidationErrorType::OutOfRange(ref field, ref value, min, max) => match (min, max) {
+                (Some(min), Some(max)) => write!(f, "the field \"{}\" is {}, but it must be between {} and {}.", field, value, min, max),
+                (Some(min), None) => write!(f, "the field \"{}\" is {}, but the minimum is {}.", field, value, min),
+                (None, Some(max)) => write!(f, "the field \"{}\" is {}, but the maximum is {}.", field, value, max),
+                (None, None) => write!(f, "the field \"{}\" is {}, which is out of his range.", field, value),
+            },
+            ValidationErrorType::NotAllowedValue(ref field, ref value) => write!(f, "the field \"{}\" cannot be \"{}\".", field, value),
+            ValidationErrorType::InvalidPath(ref field, ref value) => write!(f, "the field \"{}\" is a path, but \"{}\" has characters not allowed in paths.", field, value),
+        }
+    }
+}
+
+/// Implementation of `Display` for `ValidationReport`, so we can show it in the UI or print it in the terminal.
+impl fmt::Display for ValidationReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+
+        if self.is_empty() && self.undecodeable_tables.is_empty() {
+            return writeln!(f, "No errors found in the tables of this PackFile.");
+        }
+
+        if !self.errors.is_empty() {
+            writeln!(f, "Errors found in the tables:")?;
+            for error in &self.errors {
+                writeln!(f, "  - {}", error)?;
+            }
+            writeln!(f)?;
+        }
+
+        if !self.undecodeable_tables.is_empty() {
+            writeln!(f, "Tables that couldn't be decoded, so they haven't been checked:")?;
+            for path in &self.undecodeable_tables {
+                writeln!(f, "  - {}", path.join("/"))?;
+            }
+        }
+
+        Ok(())
+    }
+}
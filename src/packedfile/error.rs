@@ -0,0 +1,240 @@
+// In this file are the errors of the PackedFile logic (DB Tables, Locs, schemas, imports, exports,...). Each one keeps
+// the context of the error (paths, tables, versions, rows, offsets,...), so the callers can know what went wrong
+// without parsing the messages, which are the same we always showed.
+
+extern crate failure;
+
+use std::fmt;
+use std::path::PathBuf;
+
+use failure::Fail;
+
+use packedfile::db::schemas::FieldType;
+
+/// `PackedFileError`: This enum holds all the errors we can get while working with PackedFiles. The rows are the
+/// index of the row in the table, starting at 0, but the messages show them starting at 1, like the UI does.
+///
+/// Files:
+/// - ReadFile, ReadTSVFile: the file couldn't be opened or read.
+/// - CreateFile, WriteFile: the file couldn't be created or written to disk.
+/// - ImportTSV, ImportTSVWrongStructure: a TSV file couldn't be imported into a table.
+/// - TSVWrongHeader, TSVWrongTable, TSVMissingTableInfo, TSVNotLoc, TSVNoHeader: the first line of a TSV file is not valid.
+/// - ImportErrors: some of the TSV files of a mass-import couldn't be imported.
+/// - InvalidImportValue: a value of an imported file is not valid for his column.
+/// - InvalidRowValue: a value of an imported table (SQLite, Assembly Kit) is not valid for his column.
+/// - InvalidBoolean: a text is not a valid Boolean.
+/// - JSONNoRows, JSONRowNotObject: a JSON file doesn't have the structure we expect.
+/// - JSONWrongTable: a JSON file is from another table.
+/// - InvalidConstraint: a constraint of a field, written in the Decoder, is not valid.
+/// - SQLiteDatabaseNotFound, SQLiteBlobNotSupported: a SQLite database couldn't be imported.
+///
+/// Schemas:
+/// - DependencyDatabaseNotFound, SchemaNotFound: we need the Dependency Database or the schema, but there is none.
+/// - TableDefinitionNotFound: there is no definition in the schema for the version of the table we are decoding.
+/// - NewTableDefinitionNotFound: there is no definition in the schema for the version of the table we want to create.
+/// - DefinitionNotInSchema: there is no definition in the schema for the version of the table we need.
+/// - DefinitionVersionNotFound, TableNotInSchema: the definition we want to remove is not in the schema.
+/// - CreateSchemaFolder, CreateSchema, WriteSchema: the schema couldn't be saved.
+/// - BrokenSchemaOverlay: the user's overlay of the schema couldn't be loaded, so the schema has been loaded without it.
+/// - NothingToContribute: the user's schema has nothing the upstream one doesn't have.
+/// - GameNotSupported: the game has no schema.
+/// - NoAssemblyKitDefinitions: the folder doesn't have any Assembly Kit definition.
+///
+/// DB Tables:
+/// - UnsupportedTableVersion: the version of the table uses a type we cannot decode yet.
+/// - TruncatedData: the data of the table ended in the middle of a field.
+/// - NoTablesToPatch, TableWithoutKeys: we cannot generate a patch for the table.
+/// - UndecodeableTable, UnmergeableTable: a table couldn't be decoded.
+/// - NothingToMerge: the table doesn't have enough fragments to merge.
+/// - NotADBTable: the PackedFile is not a DB Table.
+/// - PackedFileNotFound, PackedFileNoLongerExists, PackedFileAlreadyExists: the PackedFile is not (or is) where we expect.
+/// - ColumnNoLongerExists, RowNoLongerExists, RowChanged: the table has changed since we checked it.
+/// - EmptyKey, SameKey, KeyAlreadyUsed, ColumnNotInTables, UndoFromAnotherPackFile: a key cannot be renamed (or the
+///   rename cannot be undone).
+/// - InvalidKeyPattern, KeyNotFound: a row cannot be deep-cloned.
+///
+/// Queries and bulk edits:
+/// - ColumnNotFound: the column doesn't exist in the table.
+/// - AmbiguousColumn: the column exists in more than one of the tables, so it needs the name of the table.
+/// - InvalidRow: the expression failed in one of the rows.
+/// - InvalidExpression: the expression or the query has a syntax error. It has the description of the error.
+/// - UnknownFunction, DivisionByZero, NotANumber, NumberOutOfRange: the expression couldn't be evaluated.
+/// - MixedTableVersions, TableNotFound, NoJoinReference, AmbiguousJoinReference: the tables cannot be queried.
+///
+/// RigidModels:
+/// - NotARigidModel: the PackedFile doesn't have the signature of a RigidModel.
+/// - UnsupportedRigidModel: the RigidModel is from a version we cannot decode yet.
+#[derive(Clone, Debug, PartialEq)]
+pub enum PackedFileError {
+    ReadFile { path: PathBuf },
+    ReadTSVFile { path: PathBuf },
+    CreateFile { path: PathBuf },
+    WriteFile { path: PathBuf },
+    ImportTSV { path: PathBuf },
+    ImportTSVWrongStructure { path: PathBuf },
+    TSVWrongHeader,
+    TSVWrongTable { table: String, version: u32 },
+    TSVMissingTableInfo,
+    TSVNotLoc,
+    TSVNoHeader,
+    ImportErrors { paths: Vec<PathBuf> },
+    InvalidImportValue { path: PathBuf, column: String, row: usize, field_type: FieldType },
+    InvalidRowValue { table: String, column: String, row: usize },
+    InvalidBoolean { value: String },
+    JSONNoRows,
+    JSONRowNotObject,
+    JSONWrongTable { path: PathBuf, table: String, expected_table: String },
+    InvalidConstraint { constraint: String },
+    SQLiteDatabaseNotFound { path: PathBuf },
+    SQLiteBlobNotSupported,
+
+    DependencyDatabaseNotFound,
+    SchemaNotFound,
+    TableDefinitionNotFound { table: String, version: u32 },
+    NewTableDefinitionNotFound { table: String, version: u32 },
+    DefinitionNotInSchema { table: String, version: u32 },
+    DefinitionVersionNotFound { table: String, version: u32 },
+    TableNotInSchema { table: String, version: u32 },
+    CreateSchemaFolder { path: PathBuf },
+    CreateSchema { path: PathBuf },
+    WriteSchema { path: PathBuf },
+    BrokenSchemaOverlay { path: PathBuf, error: String },
+    NothingToContribute,
+    GameNotSupported { game: String },
+    NoAssemblyKitDefinitions { path: PathBuf },
+
+    UnsupportedTableVersion { table: String, version: u32 },
+    TruncatedData { field_type: FieldType, column: String, row: usize, offset: usize },
+    NoTablesToPatch,
+    TableWithoutKeys { table: String },
+    UndecodeableTable { path: Vec<String> },
+    UnmergeableTable { path: Vec<String> },
+    NothingToMerge { table: String },
+    NotADBTable { path: Vec<String> },
+    PackedFileNotFound { path: Vec<String> },
+    PackedFileNoLongerExists { path: Vec<String> },
+    PackedFileAlreadyExists { path: Vec<String> },
+    ColumnNoLongerExists { path: Vec<String>, column: String },
+    RowNoLongerExists { path: Vec<String>, row: usize },
+    RowChanged { path: Vec<String>, row: usize },
+    EmptyKey,
+    SameKey,
+    KeyAlreadyUsed { key: String, path: Vec<String>, row: usize },
+    ColumnNotInTables { table: String, column: String },
+    UndoFromAnotherPackFile { old_key: String, new_key: String },
+    InvalidKeyPattern,
+    KeyNotFound { table: String, column: String, key: String },
+
+    ColumnNotFound { column: String },
+    AmbiguousColumn { column: String, columns: Vec<String> },
+    InvalidRow { row: usize, message: String },
+    InvalidExpression { message: String },
+    UnknownFunction { name: String },
+    DivisionByZero,
+    NotANumber { value: String },
+    NumberOutOfRange { value: f64, field_type: FieldType },
+    MixedTableVersions { table: String, version: u32, other_version: u32 },
+    TableNotFound { table: String },
+    NoJoinReference { table: String },
+    AmbiguousJoinReference { table: String, columns: Vec<String> },
+
+    NotARigidModel { signature: String },
+    UnsupportedRigidModel { version: u32 },
+}
+
+/// Implementation of `Display` for `PackedFileError`, with the messages we show to the user.
+impl fmt::Display for PackedFileError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            PackedFileError::ReadFile { ref path } => write!(f, "Error while trying to read the following file:\n{}", path.display()),
+            PackedFileError::ReadTSVFile { ref path } => write!(f, "Error while trying to read the TSV file:\n{}.", path.display()),
+            PackedFileError::CreateFile { ref path } => write!(f, "Error while trying to write the following file to disk:\n{}", path.display()),
+            PackedFileError::WriteFile { ref path } => write!(f, "Error while writing the following file to disk:\n{}", path.display()),
+            PackedFileError::ImportTSV { ref path } => write!(f, "Error while trying import the TSV file:\n{}", path.display()),
+            PackedFileError::ImportTSVWrongStructure { ref path } => write!(f, "Error while trying import the TSV file:\n{}\n\nIf you see this message, you probably tried to import a .tsv file into a table with different structure.", path.display()),
+            PackedFileError::TSVWrongHeader => write!(f, "This TSV file's first line is incorrect."),
+            PackedFileError::TSVWrongTable { .. } => write!(f, "This TSV file belongs to another table/version."),
+            PackedFileError::TSVMissingTableInfo => write!(f, "This TSV file's first row doesn't contain the name of the table and his version."),
+            PackedFileError::TSVNotLoc => write!(f, "This TSV file it's not from a Loc PackedFile."),
+            PackedFileError::TSVNoHeader => write!(f, "This TSV file's doesn't have a header."),
+            PackedFileError::ImportErrors { ref paths } => write!(f, "The following files returned error when trying to import them:\n\n{:#?}", paths),
+            PackedFileError::InvalidImportValue { ref path, ref column, row, ref field_type } => write!(f, "Error while trying import the file:\n{}\n\nThe value of \"{}\" in the row {} is not a valid {:?}.", path.display(), column, row + 1, field_type),
+            PackedFileError::InvalidRowValue { ref table, ref column, row } => write!(f, "Error importing the table \"{}\": the row {} has an invalid value in the column \"{}\".", table, row + 1, column),
+            PackedFileError::InvalidBoolean { ref value } => write!(f, "\"{}\" is not a valid Boolean.", value),
+            PackedFileError::JSONNoRows => write!(f, "This JSON file doesn't have a list of rows."),
+            PackedFileError::JSONRowNotObject => write!(f, "This JSON file has rows that are not objects."),
+            PackedFileError::JSONWrongTable { ref table, ref expected_table, .. } => write!(f, "This JSON file is from the table \"{}\", not from \"{}\".", table, expected_table),
+            PackedFileError::InvalidConstraint { ref constraint } => write!(f, "\"{}\" is not a valid constraint. The valid ones are: required, max_length=<number>, default=<value>, min=<number>, max=<number>, values=<value>|<value>... and filename.", constraint),
+            PackedFileError::SQLiteDatabaseNotFound { ref path } => write!(f, "The following SQLite database doesn't exist:\n{}", path.display()),
+            PackedFileError::SQLiteBlobNotSupported => write!(f, "Blobs are not supported."),
+
+            PackedFileError::DependencyDatabaseNotFound => write!(f, "To be able to create a DB Table we need first a Dependency Database created for that game. Create one and try again."),
+            PackedFileError::SchemaNotFound => write!(f, "There is no schema loaded for this game."),
+            PackedFileError::TableDefinitionNotFound { .. } => write!(f, "Schema for this DB Table not found"),
+            PackedFileError::NewTableDefinitionNotFound { .. } => write!(f, "We don't have a table definition for this table/version of the table, so we can neither decode it nor create it."),
+            PackedFileError::DefinitionNotInSchema { ref table, version } => write!(f, "There is no definition for the version {} of \"{}\" in the schema.", version, table),
+            PackedFileError::DefinitionVersionNotFound { ref table, version } => write!(f, "Error while deleting the definition for version {} of table {}:\nThis table doesn't have this version decoded.", version, table),
+            PackedFileError::TableNotInSchema { ref table, version } => write!(f, "Error while deleting the definition for version {} of table {}:\nThis table is not in the currently loaded Schema.", version, table),
+            PackedFileError::CreateSchemaFolder { .. } => write!(f, "Error while trying to create the folder for the user's schemas."),
+            PackedFileError::CreateSchema { .. } => write!(f, "Error while trying prepare the schema file to be written."),
+            PackedFileError::WriteSchema { .. } => write!(f, "Error while trying to write the schema file."),
+            PackedFileError::BrokenSchemaOverlay { ref path, ref error } => write!(f, "The schema overlay \"{}\" couldn't be loaded, so it has been ignored: {}", path.display(), error),
+            PackedFileError::NothingToContribute => write!(f, "There are no definitions in your schema to contribute. Everything you have is already in the schema."),
+            PackedFileError::GameNotSupported { ref game } => write!(f, "The game \"{}\" is not supported.", game),
+            PackedFileError::NoAssemblyKitDefinitions { ref path } => write!(f, "There are no Assembly Kit definition files (TWaD_*.xml) in the following folder:\n{}", path.display()),
+
+            PackedFileError::UnsupportedTableVersion { .. } => write!(f, "This specific table version use an unimplemented type (List), so is undecodeable, for now."),
+            PackedFileError::TruncatedData { ref field_type, .. } => match *field_type {
+                FieldType::Boolean => write!(f, "Error: trying to decode a bool without a byte."),
+                FieldType::Float => write!(f, "Error: trying to decode a Float without enough bytes."),
+                FieldType::Integer => write!(f, "Error: trying to decode a signed Integer without enough bytes."),
+                FieldType::LongInteger => write!(f, "Error: trying to decode a signed Long Integer without enough bytes."),
+                FieldType::StringU8 => write!(f, "Error: trying to decode a StringU8 without enough bytes."),
+                FieldType::StringU16 => write!(f, "Error: trying to decode a StringU16 without enough bytes."),
+                FieldType::OptionalStringU8 => write!(f, "Error: trying to decode an OptionalStringU8 without enough bytes."),
+                FieldType::OptionalStringU16 => write!(f, "Error: trying to decode an OptionalStringU16 without enough bytes."),
+            },
+            PackedFileError::NoTablesToPatch => write!(f, "There are no tables to patch in this PackFile."),
+            PackedFileError::TableWithoutKeys { .. } => write!(f, "This table has no key columns, so its rows cannot be identified in a patch."),
+            PackedFileError::UndecodeableTable { ref path } => write!(f, "{} couldn't be decoded.", path.join("/")),
+            PackedFileError::UnmergeableTable { ref path } => write!(f, "{} couldn't be decoded, so the table cannot be merged.", path.join("/")),
+            PackedFileError::NothingToMerge { ref table } => write!(f, "The table \"{}\" has less than two fragments in this PackFile, so there is nothing to merge.", table),
+            PackedFileError::NotADBTable { ref path } => write!(f, "{} is not a DB Table.", path.join("/")),
+            PackedFileError::PackedFileNotFound { ref path } => write!(f, "{} is not in the PackFile.", path.join("/")),
+            PackedFileError::PackedFileNoLongerExists { ref path } => write!(f, "The following PackedFile no longer exists:\n{}", path.join("/")),
+            PackedFileError::PackedFileAlreadyExists { ref path } => write!(f, "{} already exists in the PackFile.", path.join("/")),
+            PackedFileError::ColumnNoLongerExists { ref path, ref column } => write!(f, "The column \"{}\" no longer exists in {}.", column, path.join("/")),
+            PackedFileError::RowNoLongerExists { ref path, row } => write!(f, "The row {} of {} no longer exists. Please, try again.", row + 1, path.join("/")),
+            PackedFileError::RowChanged { ref path, row } => write!(f, "The row {} of {} has changed since the preview. Please, try again.", row + 1, path.join("/")),
+            PackedFileError::EmptyKey => write!(f, "The new key cannot be empty."),
+            PackedFileError::SameKey => write!(f, "The new key is the same as the current one."),
+            PackedFileError::KeyAlreadyUsed { ref key, ref path, row } => write!(f, "The key \"{}\" is already used in {}, row {}.", key, path.join("/"), row + 1),
+            PackedFileError::ColumnNotInTables { ref table, ref column } => write!(f, "There is no column \"{}\" in any \"{}\" table of this PackFile.", column, table),
+            PackedFileError::UndoFromAnotherPackFile { ref old_key, ref new_key } => write!(f, "The rename of \"{}\" to \"{}\" was done in another PackFile, so it cannot be undone here.", old_key, new_key),
+            PackedFileError::InvalidKeyPattern => write!(f, "The pattern for the new keys must contain \"{{key}}\" and something else, like \"{{key}}_copy\"."),
+            PackedFileError::KeyNotFound { ref table, ref column, ref key } => write!(f, "There is no row with \"{}\" in the column \"{}\" of any \"{}\" table, neither in the PackFile nor in the Dependency Database.", key, column, table),
+
+            PackedFileError::ColumnNotFound { ref column } => write!(f, "The column \"{}\" doesn't exist.", column),
+            PackedFileError::AmbiguousColumn { ref column, ref columns } => write!(f, "There is more than one column called \"{}\". Use the name of the table to choose one of them: {}.", column, columns.join(", ")),
+            PackedFileError::InvalidRow { row, ref message } => write!(f, "Error in the row {}: {}", row + 1, message),
+            PackedFileError::InvalidExpression { ref message } => write!(f, "{}", message),
+            PackedFileError::UnknownFunction { ref name } => write!(f, "Unknown function \"{}\".", name),
+            PackedFileError::DivisionByZero => write!(f, "Division by zero."),
+            PackedFileError::NotANumber { ref value } => write!(f, "\"{}\" is not a number.", value),
+            PackedFileError::NumberOutOfRange { value, ref field_type } => match *field_type {
+                FieldType::Integer => write!(f, "{} is too big for an Integer column.", value),
+                _ => write!(f, "{} is too big for a {:?} column.", value, field_type),
+            },
+            PackedFileError::MixedTableVersions { ref table, version, other_version } => write!(f, "The table \"{}\" has rows in more than one version ({} and {}), so it cannot be queried.", table, version, other_version),
+            PackedFileError::TableNotFound { ref table } => write!(f, "The table \"{}\" doesn't exist.", table),
+            PackedFileError::NoJoinReference { ref table } => write!(f, "There are no references between \"{}\" and the previous tables.", table),
+            PackedFileError::AmbiguousJoinReference { ref table, ref columns } => write!(f, "There is more than one reference between \"{}\" and the previous tables. Use \"on <column>\" to choose one of them: {}.", table, columns.join(", ")),
+
+            PackedFileError::NotARigidModel { .. } => write!(f, "This is not a RMV2 RigidModel."),
+            PackedFileError::UnsupportedRigidModel { .. } => write!(f, "RigidModel model not yet decodeable."),
+        }
+    }
+}
+
+/// Implementation of `Fail` for `PackedFileError`, so it can be used as a `failure::Error`.
+impl Fail for PackedFileError {}
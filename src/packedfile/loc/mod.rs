@@ -12,6 +12,7 @@ use self::csv::{ ReaderBuilder, WriterBuilder, QuoteStyle };
 
 use common::coding_helpers::*;
 use super::SerializableToTSV;
+use packedfile::error::PackedFileError;
 
 /// `Loc`: This stores the data of a decoded Localisation PackedFile in memory.
 /// It stores the PackedFile divided in 2 parts:
@@ -241,12 +242,12 @@ impl SerializableToTSV for LocData {
 
                         // If it's not of type "Loc PackedFile" or not over 9000, it's not Goku.
                         if tsv_type != packed_file_type || its_over_9000 != 9001 {
-                            return Err(format_err!("This TSV file it's not from a Loc PackedFile."));
+                            return Err(PackedFileError::TSVNotLoc.into());
                         }
                     }
 
                     // If it fails, return error.
-                    Err(_) => return Err(format_err!("This TSV file's doesn't have a header.")),
+                    Err(_) => return Err(PackedFileError::TSVNoHeader.into()),
                 }
 
                 // Then we add the new entries to the decoded entry list, or return error if any of the entries is invalid.
@@ -256,7 +257,7 @@ impl SerializableToTSV for LocData {
                     if index > 0 {
                         match reader_entry {
                             Ok(entry) => packed_file_data.push(entry),
-                            Err(_) => return Err(format_err!("Error while trying import the TSV file:\n{}", &tsv_file_path.display()))
+                            Err(_) => return Err(PackedFileError::ImportTSV { path: tsv_file_path.to_path_buf() }.into())
                         }
                     }
                 }
@@ -269,7 +270,7 @@ impl SerializableToTSV for LocData {
             }
 
             // If we couldn't read the TSV file, return error.
-            Err(_) => Err(format_err!("Error while trying to read the TSV file:\n{}.", &tsv_file_path.display()))
+            Err(_) => Err(PackedFileError::ReadTSVFile { path: tsv_file_path.to_path_buf() }.into())
         }
     }
 
@@ -300,10 +301,10 @@ impl SerializableToTSV for LocData {
             Ok(mut file) => {
                 match file.write_all(String::from_utf8(writer.into_inner()?)?.as_bytes()) {
                     Ok(_) => Ok(format!("DB PackedFile successfully exported:\n{}", packed_file_path.display())),
-                    Err(_) => Err(format_err!("Error while writing the following file to disk:\n{}", packed_file_path.display()))
+                    Err(_) => Err(PackedFileError::WriteFile { path: packed_file_path.to_path_buf() }.into())
                 }
             }
-            Err(_) => Err(format_err!("Error while trying to write the following file to disk:\n{}", packed_file_path.display()))
+            Err(_) => Err(PackedFileError::CreateFile { path: packed_file_path.to_path_buf() }.into())
         }
     }
 }
@@ -8,15 +8,21 @@ use std::fs::File;
 use std::path::PathBuf;
 
 use common::coding_helpers::*;
+use common::progress::Progress;
 use packfile::packfile::PackFile;
 use packfile::packfile::PackedFile;
 use packedfile::loc::*;
 use packedfile::db::*;
 use packedfile::db::schemas::*;
+use packedfile::error::PackedFileError;
 
 pub mod loc;
 pub mod db;
 pub mod rigidmodel;
+pub mod sqlite;
+pub mod serializers;
+pub mod normalize;
+pub mod error;
 
 /// This enum specifies the PackedFile types we can create.
 #[derive(Clone, Debug)]
@@ -82,45 +88,22 @@ pub fn create_packed_file(
             path = vec!["db".to_owned(), db_type.to_owned(), name.to_owned()];
 
             // If there is a dependency_database, use it to get the version of the table currently in use by the game. Otherwise, return error.
+            // If the table is not in the dependency_database, we use 9999. This'll cause the schema to not be found and properly return error.
             let version = match dependency_database {
-                Some(ref dependency_database) => {
-
-                    // Initialize version to 9999. If it fails, this'll cause the schema to not be found and properly return error.
-                    let mut version = 9999;
-
-                    // For each table in our dependency_database...
-                    for table in dependency_database.iter() {
-
-                        // If it's a table...
-                        if table.path[0] == "db" {
-
-                            // And the one we're searching for...
-                            if table.path[1] == db_type {
-
-                                // Get his version...
-                                version = DBHeader::read(&table.data, &mut 0).unwrap().version
-                            }
-                        }
-                    }
-
-                    // Return the version. If None has been found, return 0.
-                    version
-                }
-
-                // If there is None, return error.
-                None => return Err(format_err!("To be able to create a DB Table we need first a Dependency Database created for that game. Create one and try again."))
+                Some(ref dependency_database) => get_game_table_version(db_type, dependency_database).unwrap_or(9999),
+                None => return Err(PackedFileError::DependencyDatabaseNotFound.into())
             };
 
             // Try to get his table definition.
             let table_definition = match schema {
                 Some(schema) => DB::get_schema(&db_type, version, &schema),
-                None => return Err(format_err!("There is no schema loaded for this game."))
+                None => return Err(PackedFileError::SchemaNotFound.into())
             };
 
             // If there is a table definition, create the new table. Otherwise, return error.
             match table_definition {
                 Some(table_definition) => DB::new(&db_type, version, table_definition).save(),
-                None => return Err(format_err!("We don't have a table definition for this table/version of the table, so we can neither decode it nor create it."))
+                None => return Err(PackedFileError::NewTableDefinitionNotFound { table: db_type.to_owned(), version }.into())
             }
         }
 
@@ -159,13 +142,29 @@ pub fn create_packed_file(
     Ok(path)
 }
 
+/// This function returns the version of the table `db_type` the game is currently using, taken from
+/// the dependency database. If the table is not in the dependency database or his header is broken,
+/// it returns `None`.
+pub fn get_game_table_version(db_type: &str, dependency_database: &[PackedFile]) -> Option<u32> {
+
+    // For each table in our dependency_database, if it's the one we're searching for, get his version.
+    dependency_database.iter()
+        .filter(|table| table.path.len() >= 3 && table.path[0] == "db" && table.path[1] == db_type)
+        .filter_map(|table| DBHeader::read(&table.data, &mut 0).ok())
+        .map(|header| header.version)
+        .last()
+}
+
 /// This function is used to Mass-Import TSV files into a PackFile. Note that this will OVERWRITE any
 /// existing PackedFile that has a name conflict with the TSV files provided.
+///
+/// The progress is reported by file to `progress`. If it's cancelled, the PackFile is left untouched.
 pub fn tsv_mass_import(
     tsv_paths: &[PathBuf],
     name: &str,
     schema: &Option<Schema>,
-    pack_file: &mut PackFile
+    pack_file: &mut PackFile,
+    progress: &Progress,
 ) -> Result<(Vec<Vec<String>>, Vec<Vec<String>>), Error> {
 
     // Create a list of PackedFiles succesfully imported, and another for the ones that didn't work.
@@ -175,6 +174,7 @@ pub fn tsv_mass_import(
 
     // For each TSV File we have...
     for (index, path) in tsv_paths.iter().enumerate() {
+        progress.update("Importing TSV files...", index, tsv_paths.len())?;
 
         // We open it and read it to a string.
         let mut tsv = String::new();
@@ -227,9 +227,12 @@ pub fn tsv_mass_import(
                 // Otherwise, it's a table or an invalid TSV.
                 else {
 
-                    // Get the type and the version of the table.
+                    // Get the type and the version of the table. If the version is not a number, add it to the error list.
                     let table_type = tsv_info[0];
-                    let table_version = tsv_info[1].parse::<u32>().unwrap();
+                    let table_version = match tsv_info[1].parse::<u32>() {
+                        Ok(table_version) => table_version,
+                        Err(_) => { error_files.push(path); continue }
+                    };
 
                     // If we managed to find it in the schema...
                     let table_definition = if let Some(ref schema) = schema {
@@ -283,7 +286,7 @@ pub fn tsv_mass_import(
 
     // If any of the files returned error, return error.
     if !error_files.is_empty() {
-        return Err(format_err!("The following files returned error when trying to import them:\n\n{:#?}", error_files))
+        return Err(PackedFileError::ImportErrors { paths: error_files.iter().map(|x| x.to_path_buf()).collect() }.into())
     }
 
     // Get the "TreePath" of the new PackFiles to return them.
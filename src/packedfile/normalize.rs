@@ -0,0 +1,178 @@
+// In this file is the stuff to normalize the DB and Loc PackedFiles of a PackFile. The rows are saved in the same
+// order the TreeView has them, so sorting a column or doing a trivial edit can change the entire file, and that
+// makes a mess of the diffs when the mods are in a git repo.
+//
+// Normalizing sorts the rows of the DB Tables by their key columns (or by all of them, if the table has no keys)
+// and the rows of the Locs by key, so the same data always gets saved in the same order. The sort is stable, so
+// rows with duplicated keys keep their order (the first one is the one the game uses), and they are reported.
+// Optionally, it also replaces the random GUIDs of the DB Tables with ones generated from their path.
+//
+// Both the UI and the CLI normalize the PackFiles before saving them, if it's enabled in the settings.
+
+use std::cmp::Ordering;
+use std::fmt;
+
+use packfile::packfile::{PackFile, PackedFile};
+use packedfile::db::{DB, DBHeader, DecodedData};
+use packedfile::db::schemas::Schema;
+use packedfile::loc::Loc;
+use settings::Settings;
+
+/// `NormalizeReport`: This struct holds the result of normalizing the PackedFiles of a PackFile. It has:
+/// - changed: the PackedFiles whose data changed.
+/// - undecodeable: the DB Tables we couldn't decode, so they were left as they are.
+/// - duplicated_keys: the keys used by more than one row, with the path of their PackedFile.
+#[derive(Clone, Debug, Default)]
+pub struct NormalizeReport {
+    pub changed: Vec<Vec<String>>,
+    pub undecodeable: Vec<Vec<String>>,
+    pub duplicated_keys: Vec<(Vec<String>, String)>,
+}
+
+/// This function normalizes all the DB Tables and Locs of a PackFile. If `sort_rows` is true, their rows are sorted.
+/// If `deterministic_guids` is true, the GUIDs of the DB Tables are replaced with the ones generated from their path.
+/// Only the PackedFiles that change are encoded again.
+pub fn normalize_packed_files(
+    pack_file: &mut PackFile,
+    schema: &Schema,
+    sort_rows: bool,
+    deterministic_guids: bool,
+) -> NormalizeReport {
+    let mut report = NormalizeReport::default();
+    for packed_file in &mut pack_file.data.packed_files {
+        let data = if packed_file.path.len() == 3 && packed_file.path[0] == "db" {
+            match DB::read(&packed_file.data, &packed_file.path[1], schema) {
+                Ok(mut db) => {
+                    if sort_rows {
+                        let path = &packed_file.path;
+                        report.duplicated_keys.extend(sort_db_rows(&mut db).into_iter().map(|x| (path.to_vec(), x)));
+                    }
+                    if deterministic_guids { db.header.guid = DBHeader::get_deterministic_guid(&packed_file.path); }
+                    db.save()
+                }
+                Err(_) => {
+                    report.undecodeable.push(packed_file.path.to_vec());
+                    continue
+                }
+            }
+        }
+        else if sort_rows && packed_file.path.last().map_or(false, |x| x.ends_with(".loc")) {
+            match Loc::read(&packed_file.data) {
+                Ok(mut loc) => {
+                    let path = &packed_file.path;
+                    report.duplicated_keys.extend(sort_loc_rows(&mut loc).into_iter().map(|x| (path.to_vec(), x)));
+                    loc.save()
+                }
+                Err(_) => continue,
+            }
+        }
+        else { continue };
+
+        if data != packed_file.data {
+            report.changed.push(packed_file.path.to_vec());
+            *packed_file = PackedFile::read(data.len() as u32, packed_file.path.to_vec(), data);
+        }
+    }
+    report
+}
+
+/// This function normalizes the PackFile before saving it, if it's enabled in the settings. Rows are only sorted
+/// if sorting on save is enabled for this PackFile, and GUIDs are only replaced if deterministic GUIDs are enabled.
+pub fn normalize_packed_files_before_save(pack_file: &mut PackFile, schema: &Schema, settings: &Settings) -> NormalizeReport {
+    let sort_rows = settings.is_sort_rows_on_save_enabled(&pack_file.extra_data.file_name);
+    if !sort_rows && !settings.deterministic_guids { return NormalizeReport::default() }
+    normalize_packed_files(pack_file, schema, sort_rows, settings.deterministic_guids)
+}
+
+/// This function sorts the rows of a DB Table by their key columns, in order. If the table has no keys, all the
+/// columns are used as keys. The sort is stable, so rows with the same keys keep their order. It returns the keys
+/// used by more than one row, as text.
+pub fn sort_db_rows(db: &mut DB) -> Vec<String> {
+    let mut key_columns = db.data.table_definition.fields.iter().enumerate().filter(|x| x.1.field_is_key).map(|x| x.0 + 1).collect::<Vec<usize>>();
+    if key_columns.is_empty() { key_columns = (0..db.data.table_definition.fields.len()).map(|x| x + 1).collect(); }
+    db.data.entries.sort_by(|x, y| compare_keys(&key_columns, x, y));
+
+    let mut duplicated_keys = vec![];
+    for (index, entry) in db.data.entries.iter().enumerate().skip(1) {
+        let is_first_duplicate = index < 2 || compare_keys(&key_columns, &db.data.entries[index - 2], entry) != Ordering::Equal;
+        if compare_keys(&key_columns, &db.data.entries[index - 1], entry) == Ordering::Equal && is_first_duplicate {
+            duplicated_keys.push(key_columns.iter().map(|x| entry[*x].to_string()).collect::<Vec<String>>().join(", "));
+        }
+    }
+
+    // Sorting leaves the indexes in the old order, so we regenerate them.
+    let count = db.data.entries.len().to_string().len() + 1;
+    for (index, entry) in db.data.entries.iter_mut().enumerate() {
+        entry[0] = DecodedData::Index(format!("{:0count$}", index + 1, count = count));
+    }
+    duplicated_keys
+}
+
+/// This function sorts the rows of a Loc by key. The sort is stable, so rows with the same key keep their order.
+/// It returns the keys used by more than one row.
+pub fn sort_loc_rows(loc: &mut Loc) -> Vec<String> {
+    loc.data.entries.sort_by(|x, y| x.key.cmp(&y.key));
+
+    let mut duplicated_keys: Vec<String> = vec![];
+    for (index, entry) in loc.data.entries.iter().enumerate().skip(1) {
+        if loc.data.entries[index - 1].key == entry.key && duplicated_keys.last() != Some(&entry.key) {
+            duplicated_keys.push(entry.key.to_owned());
+        }
+    }
+    duplicated_keys
+}
+
+/// This function compares two rows of the same table by the provided columns, in order.
+fn compare_keys(key_columns: &[usize], left: &[DecodedData], right: &[DecodedData]) -> Ordering {
+    key_columns.iter().map(|column| compare_cells(&left[*column], &right[*column])).find(|x| *x != Ordering::Equal).unwrap_or(Ordering::Equal)
+}
+
+/// This function compares two cells of the same column. Numbers are compared as numbers, and the rest as text.
+fn compare_cells(left: &DecodedData, right: &DecodedData) -> Ordering {
+    match (left, right) {
+        (&DecodedData::Boolean(left), &DecodedData::Boolean(right)) => left.cmp(&right),
+        (&DecodedData::Float(left), &DecodedData::Float(right)) => left.partial_cmp(&right).unwrap_or(Ordering::Equal),
+        (&DecodedData::Integer(left), &DecodedData::Integer(right)) => left.cmp(&right),
+        (&DecodedData::LongInteger(left), &DecodedData::LongInteger(right)) => left.cmp(&right),
+        _ => left.to_string().cmp(&right.to_string()),
+    }
+}
+
+/// Implementation of `NormalizeReport`.
+impl NormalizeReport {
+
+    /// This function checks if the normalization didn't change anything.
+    pub fn is_empty(&self) -> bool {
+        self.changed.is_empty()
+    }
+}
+
+/// Implementation of `Display` for `NormalizeReport`, so we can show it in the UI or print it in the terminal.
+impl fmt::Display for NormalizeReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.changed.is_empty() { writeln!(f, "All the PackedFiles are already normalized.")?; }
+        else {
+            writeln!(f, "Normalized PackedFiles:")?;
+            for path in &self.changed {
+                writeln!(f, "  - {}", path.join("/"))?;
+            }
+        }
+
+        if !self.duplicated_keys.is_empty() {
+            writeln!(f)?;
+            writeln!(f, "Keys used by more than one row. Their rows have been left in the same order between them:")?;
+            for &(ref path, ref key) in &self.duplicated_keys {
+                writeln!(f, "  - {}: {}", path.join("/"), key)?;
+            }
+        }
+
+        if !self.undecodeable.is_empty() {
+            writeln!(f)?;
+            writeln!(f, "Tables that couldn't be decoded, so they have been left as they are:")?;
+            for path in &self.undecodeable {
+                writeln!(f, "  - {}", path.join("/"))?;
+            }
+        }
+        Ok(())
+    }
+}
@@ -6,6 +6,7 @@ extern crate failure;
 
 use common::coding_helpers;
 use self::failure::Error;
+use packedfile::error::PackedFileError;
 
 /// Struct "RigidModel". For more info about this, check the comment at the start of "packedfile/
 /// rigidmodel/mod.rs".
@@ -107,7 +108,7 @@ impl RigidModelHeader {
         // We check this, just in case we try to read some malformed file with a string in the first
         // four bytes (which is not uncommon).
         if packed_file_header.packed_file_header_signature != "RMV2" {
-            return Err(format_err!("This is not a RMV2 RigidModel."))
+            return Err(PackedFileError::NotARigidModel { signature: packed_file_header.packed_file_header_signature }.into())
         }
 
         match coding_helpers::decode_integer_u32(&packed_file_data[4..8]) {
@@ -156,7 +157,7 @@ impl RigidModelData {
         let offset: usize = match *packed_file_header_model_type {
             6 => 20, // Attila
             7 => 28, // Warhammer 1&2
-            _ => return Err(format_err!("RigidModel model not yet decodeable."))
+            _ => return Err(PackedFileError::UnsupportedRigidModel { version: *packed_file_header_model_type }.into())
         };
 
         // We get the "headers" of every lod.
@@ -0,0 +1,377 @@
+// In this file are the importers and exporters for JSON and CSV/TSV files with headers. Unlike our own
+// TSV format, these files have the names of the columns in them, so we can map the columns by name
+// instead of by position. This means:
+// - Reordered columns are imported in their right place.
+// - Files exported from other versions of a table can be imported, as long as the columns still exist.
+//   Columns missing in the file get their default value, and columns that no longer exist are ignored.
+//   Both are reported after the import, so the user knows what happened.
+//
+// The JSON files have this structure (the "rows" array alone is also accepted on import):
+// {
+//   "table": "land_units_tables",
+//   "version": 12,
+//   "rows": [ { "key": "wh_main_emp_inf_swordsmen", "morale": 45, ... }, ... ]
+// }
+//
+// When importing a DB Table, the "table" must be the same as the one we import into. If the "version" is
+// different, the rows are still imported (mapping the columns by name), and the change is reported. The Loc
+// PackedFiles have no table or version, so they are not written and ignored on import.
+//
+// The CSV/TSV files have the names of the columns in the first line, and nothing else. Values with delimiters,
+// quotes or line breaks in them are quoted, like in any CSV file.
+
+extern crate failure;
+extern crate csv;
+extern crate serde_json;
+
+use std::fmt;
+use std::fs::File;
+use std::io::{BufReader, Write};
+use std::path::PathBuf;
+
+use failure::Error;
+use self::csv::{ReaderBuilder, WriterBuilder, QuoteStyle};
+
+use packedfile::db::{DB, DecodedData};
+use packedfile::loc::{LocData, LocEntry};
+use packedfile::db::schemas::FieldType;
+use packedfile::error::PackedFileError;
+
+/// Names of the columns of the Loc PackedFiles.
+const LOC_COLUMNS: [&str; 3] = ["key", "text", "tooltip"];
+
+/// `FileFormat`: This enum holds the formats we can import from/export to.
+#[derive(Clone, Debug, PartialEq)]
+pub enum FileFormat {
+    Json,
+    Csv,
+    Tsv,
+}
+
+/// `ImportReport`: This struct holds what happened with the columns of an imported file. It has:
+/// - rows: the amount of rows imported.
+/// - missing_columns: columns of the table not found in the file. They got their default value.
+/// - extra_columns: columns of the file not found in the table. They were ignored.
+/// - version_change: the version of the table in the file and the version it was imported into, if they are different.
+#[derive(Clone, Debug)]
+pub struct ImportReport {
+    pub rows: usize,
+    pub missing_columns: Vec<String>,
+    pub extra_columns: Vec<String>,
+    pub version_change: Option<(u32, u32)>,
+}
+
+/// `FileData`: This struct holds the contents of an imported file, as text. It has:
+/// - columns: the names of the columns of the file.
+/// - rows: the rows of the file, with one value for each column.
+/// - table: the name of the table the file was exported from, if the file has it (only JSON files).
+/// - version: the version of the table the file was exported from, if the file has it (only JSON files).
+struct FileData {
+    columns: Vec<String>,
+    rows: Vec<Vec<String>>,
+    table: Option<String>,
+    version: Option<u32>,
+}
+
+/// Trait `SerializableWithHeaders`: This trait needs to be implemented by all the structs that can be
+/// imported from/exported to JSON and CSV/TSV files with the names of the columns in them.
+pub trait SerializableWithHeaders {
+
+    /// `import_with_headers`: Requires `&mut self` and the path of the file. The format is taken from the
+    /// extension of the file. Returns what happened with the columns, or an error.
+    fn import_with_headers(&mut self, file_path: &PathBuf) -> Result<ImportReport, Error>;
+
+    /// `export_with_headers`: Requires `&self` and the destination path for the file. The format is taken from
+    /// the extension of the file. Returns a success message, or an error.
+    fn export_with_headers(&self, file_path: &PathBuf) -> Result<String, Error>;
+}
+
+/// Implementation of `FileFormat`.
+impl FileFormat {
+
+    /// This function returns the format of a file from his extension. Files without a known extension are
+    /// considered TSV files.
+    pub fn from_path(file_path: &PathBuf) -> Self {
+        match file_path.extension().and_then(|x| x.to_str()).map(|x| x.to_lowercase()) {
+            Some(ref extension) if extension == "json" => FileFormat::Json,
+            Some(ref extension) if extension == "csv" => FileFormat::Csv,
+            _ => FileFormat::Tsv,
+        }
+    }
+
+    /// This function returns the delimiter of the format, if it has one.
+    fn get_delimiter(&self) -> u8 {
+        match *self {
+            FileFormat::Csv => b',',
+            _ => b'\t',
+        }
+    }
+}
+
+/// Implementation of `SerializableWithHeaders` for `DB`. It's for the entire DB, and not just his data, as we
+/// need the name and version of the table for the JSON files.
+impl SerializableWithHeaders for DB {
+
+    /// This function imports a JSON/CSV/TSV file with headers into a DB Table, mapping the columns by name.
+    fn import_with_headers(&mut self, file_path: &PathBuf) -> Result<ImportReport, Error> {
+        let FileData { columns, rows, table, version } = read_file(file_path)?;
+
+        // If the file says from what table it is, it has to be this one.
+        if let Some(table) = table {
+            if table != self.db_type {
+                return Err(PackedFileError::JSONWrongTable { path: file_path.to_path_buf(), table, expected_table: self.db_type.to_owned() }.into());
+            }
+        }
+
+        let table_columns = self.data.table_definition.fields.iter().map(|x| x.field_name.to_owned()).collect::<Vec<String>>();
+        let (indexes, mut report) = map_columns(&columns, &table_columns, rows.len());
+        report.version_change = match version {
+            Some(version) if version != self.header.version => Some((version, self.header.version)),
+            _ => None,
+        };
+
+        // Decode every row. We only replace our data if all of them are valid.
+        let mut entries = vec![];
+        for (row_index, row) in rows.iter().enumerate() {
+            let mut entry = vec![DecodedData::Index(format!("{:0count$}", row_index + 1, count = (rows.len().to_string().len() + 1)))];
+            for (field, index) in self.data.table_definition.fields.iter().zip(indexes.iter()) {
+                entry.push(match *index {
+                    Some(index) => match DecodedData::from_text(row.get(index).map(|x| x.as_str()).unwrap_or(""), &field.field_type) {
+                        Ok(data) => data,
+                        Err(_) => return Err(PackedFileError::InvalidImportValue { path: file_path.to_path_buf(), column: field.field_name.to_owned(), row: row_index, field_type: field.field_type.clone() }.into()),
+                    },
+                    None => DecodedData::default_for_field(field),
+                });
+            }
+            entries.push(entry);
+        }
+
+        self.data.entries = entries;
+        Ok(report)
+    }
+
+    /// This function exports a DB Table to a JSON/CSV/TSV file with headers.
+    fn export_with_headers(&self, file_path: &PathBuf) -> Result<String, Error> {
+        let columns = self.data.table_definition.fields.iter().map(|x| x.field_name.to_owned()).collect::<Vec<String>>();
+        let rows = self.data.entries.iter().map(|x| x[1..].to_vec()).collect::<Vec<Vec<DecodedData>>>();
+        write_file(file_path, &columns, &rows, Some((self.db_type.as_str(), self.header.version)))?;
+        Ok(format!("DB PackedFile successfully exported:\n{}", file_path.display()))
+    }
+}
+
+/// Implementation of `SerializableWithHeaders` for `LocData`.
+impl SerializableWithHeaders for LocData {
+
+    /// This function imports a JSON/CSV/TSV file with headers into a Loc PackedFile, mapping the columns by name.
+    fn import_with_headers(&mut self, file_path: &PathBuf) -> Result<ImportReport, Error> {
+        let FileData { columns, rows, .. } = read_file(file_path)?;
+        let loc_columns = LOC_COLUMNS.iter().map(|x| x.to_string()).collect::<Vec<String>>();
+        let (indexes, report) = map_columns(&columns, &loc_columns, rows.len());
+
+        let mut entries = vec![];
+        for (row_index, row) in rows.iter().enumerate() {
+            let values = indexes.iter().map(|x| x.and_then(|x| row.get(x)).map(|x| x.to_owned()).unwrap_or_else(String::new)).collect::<Vec<String>>();
+
+            // If the file has no tooltip column, we use his default value. It's already in the report as missing.
+            let tooltip = match indexes[2] {
+                Some(_) => match DecodedData::from_text(&values[2], &FieldType::Boolean) {
+                    Ok(DecodedData::Boolean(tooltip)) => tooltip,
+                    _ => return Err(PackedFileError::InvalidImportValue { path: file_path.to_path_buf(), column: "tooltip".to_owned(), row: row_index, field_type: FieldType::Boolean }.into()),
+                },
+                None => false,
+            };
+            entries.push(LocEntry::new(values[0].to_owned(), values[1].to_owned(), tooltip));
+        }
+
+        self.entries = entries;
+        Ok(report)
+    }
+
+    /// This function exports a Loc PackedFile to a JSON/CSV/TSV file with headers.
+    fn export_with_headers(&self, file_path: &PathBuf) -> Result<String, Error> {
+        let columns = LOC_COLUMNS.iter().map(|x| x.to_string()).collect::<Vec<String>>();
+        let rows = self.entries.iter().map(|x| vec![
+            DecodedData::StringU16(x.key.to_owned()),
+            DecodedData::StringU16(x.text.to_owned()),
+            DecodedData::Boolean(x.tooltip),
+        ]).collect::<Vec<Vec<DecodedData>>>();
+        write_file(file_path, &columns, &rows, None)?;
+        Ok(format!("Loc PackedFile successfully exported:\n{}", file_path.display()))
+    }
+}
+
+/// This function returns the value of a `DecodedData` as a JSON value, keeping his type.
+pub fn decoded_data_to_json(data: &DecodedData) -> serde_json::Value {
+    match *data {
+        DecodedData::Boolean(data) => serde_json::Value::from(data),
+
+        // We pass floats through text, so we get "0.1" instead of "0.10000000149011612".
+        DecodedData::Float(data) => serde_json::Value::from(data.to_string().parse::<f64>().unwrap_or_else(|_| f64::from(data))),
+        DecodedData::Integer(data) => serde_json::Value::from(data),
+        DecodedData::LongInteger(data) => serde_json::Value::from(data),
+        _ => serde_json::Value::from(data.to_string()),
+    }
+}
+
+/// This function reads a JSON/CSV/TSV file and returns the names of his columns and his rows, as text, and
+/// the name and version of his table, if the file has them.
+fn read_file(file_path: &PathBuf) -> Result<FileData, Error> {
+    match FileFormat::from_path(file_path) {
+        FileFormat::Json => {
+            let json: serde_json::Value = match File::open(file_path) {
+                Ok(file) => serde_json::from_reader(BufReader::new(file))?,
+                Err(_) => return Err(PackedFileError::ReadFile { path: file_path.to_path_buf() }.into()),
+            };
+
+            // Accept both, the complete file and the rows alone.
+            let rows = match json.get("rows").unwrap_or(&json).as_array() {
+                Some(rows) => rows.to_vec(),
+                None => return Err(PackedFileError::JSONNoRows.into()),
+            };
+
+            // The columns are all the names used in the rows, in the order we find them.
+            let mut columns: Vec<String> = vec![];
+            for row in &rows {
+                match row.as_object() {
+                    Some(row) => for column in row.keys() {
+                        if !columns.contains(column) { columns.push(column.to_owned()); }
+                    },
+                    None => return Err(PackedFileError::JSONRowNotObject.into()),
+                }
+            }
+
+            let rows = rows.iter().map(|row| columns.iter().map(|column| match row.get(column) {
+                Some(&serde_json::Value::String(ref value)) => value.to_owned(),
+                Some(&serde_json::Value::Null) | None => String::new(),
+                Some(value) => value.to_string(),
+            }).collect()).collect();
+
+            Ok(FileData {
+                columns,
+                rows,
+                table: json.get("table").and_then(|x| x.as_str()).map(|x| x.to_owned()),
+                version: json.get("version").and_then(|x| x.as_u64()).map(|x| x as u32),
+            })
+        }
+
+        // CSV and TSV files are the same, but with a different delimiter.
+        format => {
+            let mut reader = match ReaderBuilder::new()
+                .delimiter(format.get_delimiter())
+                .has_headers(true)
+                .flexible(true)
+                .from_path(file_path) {
+                Ok(reader) => reader,
+                Err(_) => return Err(PackedFileError::ReadFile { path: file_path.to_path_buf() }.into()),
+            };
+
+            let columns = reader.headers()?.iter().map(|x| x.to_owned()).collect();
+            let mut rows = vec![];
+            for record in reader.records() {
+                rows.push(record?.iter().map(|x| x.to_owned()).collect());
+            }
+            Ok(FileData {
+                columns,
+                rows,
+                table: None,
+                version: None,
+            })
+        }
+    }
+}
+
+/// This function writes the provided rows to a JSON/CSV/TSV file, with the names of the columns.
+fn write_file(
+    file_path: &PathBuf,
+    columns: &[String],
+    rows: &[Vec<DecodedData>],
+    table_info: Option<(&str, u32)>
+) -> Result<(), Error> {
+
+    let data = match FileFormat::from_path(file_path) {
+        FileFormat::Json => {
+            let rows = rows.iter().map(|row| {
+                let mut object = serde_json::Map::new();
+                for (column, value) in columns.iter().zip(row.iter()) {
+                    object.insert(column.to_owned(), decoded_data_to_json(value));
+                }
+                serde_json::Value::Object(object)
+            }).collect::<Vec<serde_json::Value>>();
+
+            let mut json = serde_json::Map::new();
+            if let Some(table_info) = table_info {
+                json.insert("table".to_owned(), serde_json::Value::from(table_info.0));
+                json.insert("version".to_owned(), serde_json::Value::from(table_info.1));
+            }
+            json.insert("rows".to_owned(), serde_json::Value::Array(rows));
+            serde_json::to_string_pretty(&json)?.into_bytes()
+        }
+
+        format => {
+            let mut writer = WriterBuilder::new()
+                .delimiter(format.get_delimiter())
+                .quote_style(QuoteStyle::Necessary)
+                .from_writer(vec![]);
+
+            writer.write_record(columns)?;
+            for row in rows {
+                writer.write_record(row.iter().map(|x| x.to_string()))?;
+            }
+            writer.into_inner()?
+        }
+    };
+
+    match File::create(&file_path) {
+        Ok(mut file) => match file.write_all(&data) {
+            Ok(_) => Ok(()),
+            Err(_) => Err(PackedFileError::WriteFile { path: file_path.to_path_buf() }.into())
+        },
+        Err(_) => Err(PackedFileError::CreateFile { path: file_path.to_path_buf() }.into())
+    }
+}
+
+/// This function maps the columns of a file to the columns we expect, by name. It returns, for each column
+/// we expect, the index of his column in the file (if any), and the report of the missing and extra columns.
+fn map_columns(file_columns: &[String], columns: &[String], rows: usize) -> (Vec<Option<usize>>, ImportReport) {
+    let indexes = columns.iter().map(|x| file_columns.iter().position(|y| y == x)).collect::<Vec<Option<usize>>>();
+    let report = ImportReport {
+        rows,
+        missing_columns: columns.iter().zip(indexes.iter()).filter(|x| x.1.is_none()).map(|x| x.0.to_owned()).collect(),
+        extra_columns: file_columns.iter().filter(|x| !columns.contains(x)).cloned().collect(),
+        version_change: None,
+    };
+    (indexes, report)
+}
+
+/// Implementation of `ImportReport`.
+impl ImportReport {
+
+    /// This function returns if something happened with the columns (missing or extra columns, or a different version),
+    /// so we know if the report is worth showing when importing a lot of files.
+    pub fn has_column_changes(&self) -> bool {
+        !self.missing_columns.is_empty() || !self.extra_columns.is_empty() || self.version_change.is_some()
+    }
+}
+
+/// Implementation of `Display` for `ImportReport`, so we can show it to the user after an import.
+impl fmt::Display for ImportReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "{} rows imported.", self.rows)?;
+        if let Some((file_version, table_version)) = self.version_change {
+            writeln!(f, "\nThe file is from the version {} of the table, and it has been imported into the version {}. The columns have been mapped by name.", file_version, table_version)?;
+        }
+        if !self.missing_columns.is_empty() {
+            writeln!(f, "\nColumns not found in the file (filled with default values):")?;
+            for column in &self.missing_columns {
+                writeln!(f, "  - {}", column)?;
+            }
+        }
+        if !self.extra_columns.is_empty() {
+            writeln!(f, "\nColumns of the file not found in the table (ignored):")?;
+            for column in &self.extra_columns {
+                writeln!(f, "  - {}", column)?;
+            }
+        }
+        Ok(())
+    }
+}
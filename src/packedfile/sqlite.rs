@@ -0,0 +1,374 @@
+// In this file is all the stuff needed to export the tables and Locs of a PackFile to a SQLite database,
+// and to import them back after editing them with SQL.
+//
+// The database has the following structure:
+// - One table for each type of DB Table ("land_units_tables", "unit_stats_land_tables",...), with his
+//   columns typed from the schema, a primary key over his key fields and a foreign key for each of his
+//   reference fields.
+// - One table named "loc" with all the Loc PackedFiles, with his key as primary key.
+// - One table named "_rpfm_packed_files" with the info of every PackedFile exported (path, table, version,
+//   the rest of his header and if it comes from the dependency database or not), so we know where each row
+//   has to go on import, and we can write the PackedFiles back with the same header.
+// - One table named "_rpfm_references" with the references between columns of the schema, for the tools
+//   that don't show the foreign keys.
+//
+// Every table has an extra "_rpfm_source" column, with the path of the PackedFile each row comes from, and an
+// extra "_rpfm_duplicate" column, with how many rows with the same keys are before it in his PackedFile. Both
+// are part of the primary key, so the same key can be in more than one PackedFile, and rows with duplicated
+// keys are kept and exported back as they were. Rows inserted with SQL get 0, so they cannot duplicate keys.
+//
+// SQLite only checks the foreign keys with "PRAGMA foreign_keys = ON". Don't enable it, as most of the referenced
+// columns are not unique, and broken references are very common in mods.
+//
+// PackedFiles of the dependency database with the same path as one of our PackFile are not exported, as the game
+// ignores them.
+
+extern crate failure;
+extern crate rusqlite;
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use failure::Error;
+use self::rusqlite::Connection;
+use self::rusqlite::types::{ToSql, Value};
+
+use packfile::packfile::{PackFile, PackedFile};
+use packedfile::db::*;
+use packedfile::db::schemas::*;
+use packedfile::loc::*;
+use packedfile::error::PackedFileError;
+
+/// Name of the table with the info of the exported PackedFiles.
+const PACKED_FILES_TABLE: &str = "_rpfm_packed_files";
+
+/// Name of the column with the path of the PackedFile each row comes from.
+const SOURCE_COLUMN: &str = "_rpfm_source";
+
+/// Name of the column with the amount of rows with the same keys before each row in his PackedFile.
+const DUPLICATE_COLUMN: &str = "_rpfm_duplicate";
+
+/// Name of the table with all the Loc PackedFiles.
+const LOC_TABLE: &str = "loc";
+
+/// Name of the table with the references between columns.
+const REFERENCES_TABLE: &str = "_rpfm_references";
+
+/// This function exports all the DB Tables and Locs from the provided PackedFiles (and from the dependency
+/// database, if provided) to a new SQLite database in `sqlite_path`. If the database already exists, it's
+/// overwritten. It returns the paths of the PackedFiles we couldn't decode, and so, we couldn't export.
+pub fn export_to_sqlite(
+    packed_files: &[PackedFile],
+    dependency_database: Option<&[PackedFile]>,
+    schema: &Schema,
+    sqlite_path: &PathBuf,
+) -> Result<Vec<Vec<String>>, Error> {
+
+    // We want an empty database, so remove the old one if it exists.
+    if sqlite_path.is_file() { ::std::fs::remove_file(sqlite_path)?; }
+    let mut connection = Connection::open(sqlite_path)?;
+    let transaction = connection.transaction()?;
+
+    transaction.execute_batch(&format!(
+        "CREATE TABLE {0} (path TEXT PRIMARY KEY, table_name TEXT NOT NULL, version INTEGER NOT NULL, is_dependency INTEGER NOT NULL, guid TEXT NOT NULL, version_marker INTEGER NOT NULL, mysterious_byte INTEGER NOT NULL);
+        CREATE TABLE {1} (key TEXT NOT NULL, text TEXT NOT NULL, tooltip INTEGER NOT NULL, {2} TEXT NOT NULL, {3} INTEGER NOT NULL DEFAULT 0, PRIMARY KEY (key, {2}, {3}));
+        CREATE TABLE {4} (table_name TEXT NOT NULL, column_name TEXT NOT NULL, referenced_table TEXT NOT NULL, referenced_column TEXT NOT NULL, PRIMARY KEY (table_name, column_name));",
+        quote(PACKED_FILES_TABLE), quote(LOC_TABLE), quote(SOURCE_COLUMN), quote(DUPLICATE_COLUMN), quote(REFERENCES_TABLE)
+    ))?;
+
+    // We export first the dependency database, skipping the PackedFiles our PackFile overwrites.
+    let mut sources = vec![];
+    if let Some(dependency_database) = dependency_database {
+        sources.extend(dependency_database.iter()
+            .filter(|x| !packed_files.iter().any(|y| y.path == x.path))
+            .map(|x| (x, true)));
+    }
+    sources.extend(packed_files.iter().map(|x| (x, false)));
+
+    let mut created_tables: Vec<String> = vec![];
+    let mut error_files = vec![];
+    for (packed_file, is_dependency) in sources {
+        let path = packed_file.path.join("/");
+
+        // If it's a DB Table...
+        if packed_file.path.len() >= 3 && packed_file.path[0] == "db" {
+            let table_name = &packed_file.path[1];
+            let db = match DB::read(&packed_file.data, table_name, schema) {
+                Ok(db) => db,
+                Err(_) => { error_files.push(packed_file.path.to_vec()); continue }
+            };
+
+            // Create his SQL table if it's the first time we find this table.
+            if !created_tables.contains(table_name) {
+                transaction.execute_batch(&get_create_table_statement(table_name, &db.data.table_definition))?;
+                for field in &db.data.table_definition.fields {
+                    if let Some(ref reference) = field.field_is_reference {
+                        transaction.execute(
+                            &format!("INSERT OR REPLACE INTO {} VALUES (?, ?, ?, ?)", quote(REFERENCES_TABLE)),
+                            &[table_name, &field.field_name, &format!("{}_tables", reference.0), &reference.1]
+                        )?;
+                    }
+                }
+                created_tables.push(table_name.to_owned());
+            }
+
+            // If the table already exists with different columns (we got two versions of the same table), we cannot export it.
+            let columns = db.data.table_definition.fields.iter().map(|x| quote(&x.field_name)).collect::<Vec<String>>();
+            let mut statement = match transaction.prepare(&format!(
+                "INSERT INTO {} ({}, {}, {}) VALUES ({}?, ?)",
+                quote(table_name),
+                columns.join(", "),
+                quote(SOURCE_COLUMN),
+                quote(DUPLICATE_COLUMN),
+                "?, ".repeat(columns.len())
+            )) {
+                Ok(statement) => statement,
+                Err(_) => { error_files.push(packed_file.path.to_vec()); continue }
+            };
+
+            let key_columns = db.data.table_definition.fields.iter().enumerate().filter(|x| x.1.field_is_key).map(|x| x.0 + 1).collect::<Vec<usize>>();
+            let mut duplicates: HashMap<Vec<String>, i64> = HashMap::new();
+            for row in &db.data.entries {
+                let duplicate = duplicates.entry(key_columns.iter().map(|x| row[*x].to_string()).collect()).or_insert(-1);
+                *duplicate += 1;
+
+                let mut values = row[1..].iter().map(|x| decoded_data_to_value(x)).collect::<Vec<Value>>();
+                values.push(Value::Text(path.to_owned()));
+                values.push(Value::Integer(*duplicate));
+                let params = values.iter().map(|x| x as &ToSql).collect::<Vec<&ToSql>>();
+                statement.execute(&params)?;
+            }
+
+            transaction.execute(
+                &format!("INSERT OR REPLACE INTO {} VALUES (?, ?, ?, ?, ?, ?, ?)", quote(PACKED_FILES_TABLE)),
+                &[&path, table_name, &i64::from(db.header.version), &is_dependency, &db.header.guid, &db.header.version_marker, &i64::from(db.header.mysterious_byte)]
+            )?;
+        }
+
+        // If it's a Loc PackedFile...
+        else if packed_file.path.last().map_or(false, |x| x.ends_with(".loc")) {
+            let loc = match Loc::read(&packed_file.data) {
+                Ok(loc) => loc,
+                Err(_) => { error_files.push(packed_file.path.to_vec()); continue }
+            };
+
+            {
+                let mut statement = transaction.prepare(&format!("INSERT INTO {} VALUES (?, ?, ?, ?, ?)", quote(LOC_TABLE)))?;
+                let mut duplicates: HashMap<&str, i64> = HashMap::new();
+                for entry in &loc.data.entries {
+                    let duplicate = duplicates.entry(entry.key.as_str()).or_insert(-1);
+                    *duplicate += 1;
+                    statement.execute(&[&entry.key, &entry.text, &entry.tooltip, &path, &*duplicate])?;
+                }
+            }
+
+            transaction.execute(
+                &format!("INSERT OR REPLACE INTO {} VALUES (?, ?, ?, ?, ?, ?, ?)", quote(PACKED_FILES_TABLE)),
+                &[&path, &LOC_TABLE.to_owned(), &0i64, &is_dependency, &String::new(), &false, &0i64]
+            )?;
+        }
+    }
+
+    transaction.commit()?;
+    Ok(error_files)
+}
+
+/// This function imports back the DB Tables and Locs of our PackFile from a SQLite database created with
+/// `export_to_sqlite`. PackedFiles from the dependency database are ignored. It returns the paths of the
+/// PackedFiles added or updated.
+///
+/// NOTE: Rows are returned in the same order they were inserted. Rows inserted with SQL are added at the
+/// end of their PackedFile, so remember to set the "_rpfm_source" column when inserting them.
+pub fn import_from_sqlite(
+    pack_file: &mut PackFile,
+    schema: &Schema,
+    sqlite_path: &PathBuf,
+) -> Result<Vec<Vec<String>>, Error> {
+
+    if !sqlite_path.is_file() { return Err(PackedFileError::SQLiteDatabaseNotFound { path: sqlite_path.to_path_buf() }.into()) }
+    let connection = Connection::open(sqlite_path)?;
+
+    // Get the list of PackedFiles we have to import.
+    let mut packed_files_info = vec![];
+    {
+        let mut statement = connection.prepare(&format!("SELECT path, table_name, version, guid, version_marker, mysterious_byte FROM {} WHERE is_dependency = 0", quote(PACKED_FILES_TABLE)))?;
+        let mut rows = statement.query(&[])?;
+        while let Some(row) = rows.next() {
+            let row = row?;
+            let path: String = row.get_checked(0)?;
+            let table_name: String = row.get_checked(1)?;
+            let version: i64 = row.get_checked(2)?;
+            let guid: String = row.get_checked(3)?;
+            let version_marker: bool = row.get_checked(4)?;
+            let mysterious_byte: i64 = row.get_checked(5)?;
+            packed_files_info.push((path, table_name, version as u32, guid, version_marker, mysterious_byte as u8));
+        }
+    }
+
+    let mut packed_files = vec![];
+    for (path, table_name, version, guid, version_marker, mysterious_byte) in packed_files_info {
+
+        // Loc PackedFiles.
+        let data = if table_name == LOC_TABLE {
+            let mut loc = Loc::new();
+            let mut statement = connection.prepare(&format!("SELECT key, text, tooltip FROM {} WHERE {} = ? ORDER BY rowid", quote(LOC_TABLE), quote(SOURCE_COLUMN)))?;
+            let mut rows = statement.query(&[&path])?;
+            while let Some(row) = rows.next() {
+                let row = row?;
+                loc.data.entries.push(LocEntry::new(row.get_checked(0)?, row.get_checked(1)?, row.get_checked(2)?));
+            }
+            loc.save()
+        }
+
+        // DB Tables.
+        else {
+            let table_definition = match DB::get_schema(&table_name, version, schema) {
+                Some(table_definition) => table_definition,
+                None => return Err(PackedFileError::DefinitionNotInSchema { table: table_name.to_owned(), version }.into()),
+            };
+
+            let columns = table_definition.fields.iter().map(|x| quote(&x.field_name)).collect::<Vec<String>>();
+            let mut statement = connection.prepare(&format!("SELECT {} FROM {} WHERE {} = ? ORDER BY rowid", columns.join(", "), quote(&table_name), quote(SOURCE_COLUMN)))?;
+            let mut rows = statement.query(&[&path])?;
+
+            let mut entries = vec![];
+            while let Some(row) = rows.next() {
+                let row = row?;
+                let mut entry = vec![];
+                for (column, field) in table_definition.fields.iter().enumerate() {
+                    let value: Value = row.get_checked(column as i32)?;
+                    entry.push(value_to_decoded_data(value, &field.field_type).map_err(|_|
+                        PackedFileError::InvalidRowValue { table: path.to_owned(), column: field.field_name.to_owned(), row: entries.len() }
+                    )?);
+                }
+                entries.push(entry);
+            }
+
+            // Add the index column to the rows, like the decoder does.
+            let entry_count = entries.len();
+            for (index, entry) in entries.iter_mut().enumerate() {
+                entry.insert(0, DecodedData::Index(format!("{:0count$}", index + 1, count = (entry_count.to_string().len() + 1))));
+            }
+
+            // Use the header the table had when it was exported.
+            let mut db = DB::new(&table_name, version, table_definition);
+            if !guid.is_empty() { db.header.guid = guid; }
+            db.header.version_marker = version_marker;
+            db.header.mysterious_byte = mysterious_byte;
+            db.data.entries = entries;
+            db.save()
+        };
+
+        let path = path.split('/').map(|x| x.to_owned()).collect::<Vec<String>>();
+        packed_files.push(PackedFile::read(data.len() as u32, path, data));
+    }
+
+    // Replace the PackedFiles that already exist, and add the new ones.
+    let mut new_packed_files = vec![];
+    let paths = packed_files.iter().map(|x| x.path.to_vec()).collect::<Vec<Vec<String>>>();
+    for packed_file in packed_files {
+        match pack_file.data.packed_files.iter().position(|x| x.path == packed_file.path) {
+            Some(index) => pack_file.data.packed_files[index] = packed_file,
+            None => new_packed_files.push(packed_file),
+        }
+    }
+    pack_file.add_packedfiles(new_packed_files);
+
+    Ok(paths)
+}
+
+/// This function returns the "CREATE TABLE" statement for the provided table, with his primary and foreign keys.
+fn get_create_table_statement(table_name: &str, table_definition: &TableDefinition) -> String {
+    let mut columns = vec![];
+    let mut keys = vec![];
+    let mut references = vec![];
+
+    for field in &table_definition.fields {
+        let field_type = match field.field_type {
+            FieldType::Boolean | FieldType::Integer | FieldType::LongInteger => "INTEGER",
+            FieldType::Float => "REAL",
+            FieldType::StringU8 | FieldType::StringU16 | FieldType::OptionalStringU8 | FieldType::OptionalStringU16 => "TEXT",
+        };
+        columns.push(format!("{} {} NOT NULL", quote(&field.field_name), field_type));
+
+        if field.field_is_key { keys.push(quote(&field.field_name)); }
+        if let Some(ref reference) = field.field_is_reference {
+            references.push(format!("FOREIGN KEY ({}) REFERENCES {}({})", quote(&field.field_name), quote(&format!("{}_tables", reference.0)), quote(&reference.1)));
+        }
+    }
+
+    columns.push(format!("{} TEXT NOT NULL", quote(SOURCE_COLUMN)));
+    columns.push(format!("{} INTEGER NOT NULL DEFAULT 0", quote(DUPLICATE_COLUMN)));
+
+    // The same keys can be in more than one PackedFile, or duplicated in the same one, so they are not enough for the primary key.
+    if !keys.is_empty() {
+        keys.push(quote(SOURCE_COLUMN));
+        keys.push(quote(DUPLICATE_COLUMN));
+        columns.push(format!("PRIMARY KEY ({})", keys.join(", ")));
+    }
+    columns.append(&mut references);
+
+    format!("CREATE TABLE {} (\n{}\n);", quote(table_name), columns.join(",\n"))
+}
+
+/// This function returns the name of a table or a column quoted for SQL, escaping the quotes in it.
+fn quote(name: &str) -> String {
+    format!("\"{}\"", name.replace('"', "\"\""))
+}
+
+/// This function turns a `DecodedData` into a SQLite value.
+fn decoded_data_to_value(data: &DecodedData) -> Value {
+    match *data {
+        DecodedData::Boolean(data) => Value::Integer(data as i64),
+        DecodedData::Float(data) => Value::Real(f64::from(data)),
+        DecodedData::Integer(data) => Value::Integer(i64::from(data)),
+        DecodedData::LongInteger(data) => Value::Integer(data),
+        DecodedData::Index(ref data) |
+        DecodedData::StringU8(ref data) |
+        DecodedData::StringU16(ref data) |
+        DecodedData::OptionalStringU8(ref data) |
+        DecodedData::OptionalStringU16(ref data) => Value::Text(data.to_owned()),
+    }
+}
+
+/// This function turns a SQLite value into a `DecodedData` of the provided type. As SQLite doesn't really
+/// enforce the types of the columns, we try to convert the value if it's not of the type we expect.
+fn value_to_decoded_data(value: Value, field_type: &FieldType) -> Result<DecodedData, Error> {
+
+    // Get it as text, so we can parse it if it's not of the type we expected.
+    let text = match value {
+        Value::Null => String::new(),
+        Value::Integer(data) => data.to_string(),
+        Value::Real(data) => data.to_string(),
+        Value::Text(ref data) => data.to_owned(),
+        Value::Blob(_) => return Err(PackedFileError::SQLiteBlobNotSupported.into()),
+    };
+
+    Ok(match *field_type {
+        FieldType::Boolean => match value {
+            Value::Integer(data) => DecodedData::Boolean(data != 0),
+            _ => DecodedData::Boolean(text.to_lowercase() == "true" || text == "1"),
+        },
+        FieldType::Float => match value {
+            Value::Real(data) => DecodedData::Float(data as f32),
+            _ => DecodedData::Float(text.parse::<f32>()?),
+        },
+        FieldType::Integer => match value {
+            Value::Integer(data) => {
+                if data < i64::from(i32::min_value()) || data > i64::from(i32::max_value()) {
+                    return Err(PackedFileError::NumberOutOfRange { value: data as f64, field_type: FieldType::Integer }.into())
+                }
+                DecodedData::Integer(data as i32)
+            }
+            _ => DecodedData::Integer(text.parse::<i32>()?),
+        },
+        FieldType::LongInteger => match value {
+            Value::Integer(data) => DecodedData::LongInteger(data),
+            _ => DecodedData::LongInteger(text.parse::<i64>()?),
+        },
+        FieldType::StringU8 => DecodedData::StringU8(text),
+        FieldType::StringU16 => DecodedData::StringU16(text),
+        FieldType::OptionalStringU8 => DecodedData::OptionalStringU8(text),
+        FieldType::OptionalStringU16 => DecodedData::OptionalStringU16(text),
+    })
+}
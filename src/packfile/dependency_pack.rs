@@ -0,0 +1,230 @@
+// In this file is the stuff to generate the Dependency PackFile of a game. The Dependency PackFile holds the DB Tables
+// and Locs of all the official PackFiles of the game (not only "data.pack", as the DLCs and patches have their own
+// tables and locs), so we can check references, versions,... without opening the game's PackFiles every time.
+//
+// Next to the Dependency PackFile we save a little JSON file with the PackFiles used to build it. If any of them
+// changes (a game update) or the game gets new ones (a new DLC), the Dependency PackFile is outdated.
+
+extern crate failure;
+extern crate serde_json;
+
+use std::collections::HashMap;
+use std::fs::{DirBuilder, File, read_dir};
+use std::io::{BufRead, BufReader, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+use failure::Error;
+
+use common::progress::Progress;
+use super::error::PackFileError;
+use super::packfile::{PackFile, PackFileHeader, PackedFile};
+
+/// `DependencyPackSource`: This struct holds the info of one of the PackFiles used to build a Dependency PackFile. It has:
+/// - name: the name of the PackFile, like "data.pack".
+/// - pack_file_type: the type of the PackFile (Boot, Release or Patch).
+/// - size: the size of the PackFile in bytes.
+/// - modified: the modification time of the PackFile, in seconds.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct DependencyPackSource {
+    pub name: String,
+    pub pack_file_type: u32,
+    pub size: u64,
+    pub modified: u64,
+}
+
+/// `DependencyPackInfo`: This struct holds the metadata of a Dependency PackFile. It has:
+/// - game: the game the Dependency PackFile is for.
+/// - sources: the official PackFiles used to build it, in load order.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct DependencyPackInfo {
+    pub game: String,
+    pub sources: Vec<DependencyPackSource>,
+}
+
+/// Implementation of `DependencyPackInfo`.
+impl DependencyPackInfo {
+
+    /// This function loads the metadata of the Dependency PackFile in the provided path.
+    pub fn load(dependency_pack_file_path: &Path) -> Result<Self, Error> {
+        let file = BufReader::new(File::open(get_info_path(dependency_pack_file_path))?);
+        Ok(serde_json::from_reader(file)?)
+    }
+
+    /// This function saves the metadata of the Dependency PackFile in the provided path.
+    pub fn save(&self, dependency_pack_file_path: &Path) -> Result<(), Error> {
+        let mut file = File::create(get_info_path(dependency_pack_file_path))?;
+        file.write_all(serde_json::to_string_pretty(self)?.as_bytes())?;
+        Ok(())
+    }
+
+    /// This function returns the names of the official PackFiles that have been added, removed or changed since
+    /// the Dependency PackFile was built. If it's empty, the Dependency PackFile is up to date.
+    pub fn get_changed_sources(&self, game_data_path: &Path) -> Result<Vec<String>, Error> {
+        let sources = get_official_pack_files(game_data_path)?.into_iter().map(|x| x.1).collect::<Vec<DependencyPackSource>>();
+        let mut changed = sources.iter().filter(|x| !self.sources.contains(x)).map(|x| x.name.to_owned()).collect::<Vec<String>>();
+        for source in &self.sources {
+            if !sources.iter().any(|x| x.name == source.name) { changed.push(source.name.to_owned()); }
+        }
+        Ok(changed)
+    }
+}
+
+/// This function checks if the Dependency PackFile in the provided path is outdated. A Dependency PackFile without
+/// metadata (generated by an older version of RPFM, from "data.pack" only) is always outdated. If there is no
+/// Dependency PackFile at all, there is nothing outdated.
+pub fn is_dependency_pack_file_outdated(dependency_pack_file_path: &Path, game_data_path: &Path) -> bool {
+    if !dependency_pack_file_path.is_file() { return false }
+    match DependencyPackInfo::load(dependency_pack_file_path) {
+        Ok(info) => info.get_changed_sources(game_data_path).map(|x| !x.is_empty()).unwrap_or(false),
+        Err(_) => true,
+    }
+}
+
+/// This function returns the official PackFiles of a game, in the order the game loads them. If the data folder has a
+/// "manifest.txt", only the PackFiles in it are considered. Otherwise, we check all the PackFiles in the folder. Either
+/// way, only the Boot, Release and Patch PackFiles are official, so mods and movies are ignored. Loc PackFiles of
+/// languages other than the one the game uses are ignored too.
+///
+/// With a manifest, the PackFiles keep the order of the manifest. Otherwise, they are sorted by type and then by name.
+/// Files later in the list overwrite the ones before.
+pub fn get_official_pack_files(game_data_path: &Path) -> Result<Vec<(PathBuf, DependencyPackSource)>, Error> {
+    let language = get_game_language(game_data_path);
+    let manifest_path = game_data_path.join("manifest.txt");
+    let has_manifest = manifest_path.is_file();
+    let paths = if has_manifest {
+        let mut paths = vec![];
+        for line in BufReader::new(File::open(&manifest_path)?).lines() {
+            let line = line?;
+            if let Some(name) = line.split('\t').next().map(|x| x.trim()) {
+                let path = game_data_path.join(name);
+                if name.ends_with(".pack") && path.is_file() { paths.push(path); }
+            }
+        }
+        paths
+    }
+    else {
+        let mut paths = vec![];
+        for entry in read_dir(game_data_path)? {
+            let path = entry?.path();
+            if path.is_file() && path.extension().map_or(false, |x| x == "pack") { paths.push(path); }
+        }
+        paths
+    };
+
+    let mut pack_files = vec![];
+    for path in paths {
+        let name = path.file_name().map(|x| x.to_string_lossy().to_string()).unwrap_or_default();
+        if is_loc_pack_file_of_other_language(&name, &language) { continue }
+
+        let header = match PackFileHeader::read(&mut BufReader::new(File::open(&path)?)) {
+            Ok(header) => header,
+            Err(_) => continue,
+        };
+
+        if header.pack_file_type <= 2 {
+            let metadata = path.metadata()?;
+            let source = DependencyPackSource {
+                name,
+                pack_file_type: header.pack_file_type,
+                size: metadata.len(),
+                modified: metadata.modified().ok().and_then(|x| x.duration_since(UNIX_EPOCH).ok()).map_or(0, |x| x.as_secs()),
+            };
+            pack_files.push((path, source));
+        }
+    }
+
+    if !has_manifest { pack_files.sort_by(|x, y| (x.1.pack_file_type, &x.1.name).cmp(&(y.1.pack_file_type, &y.1.name))); }
+    Ok(pack_files)
+}
+
+/// This function returns the language the game uses, in lowercase, from the "language.txt" file of his data folder.
+/// If there is no "language.txt", the game uses English.
+pub fn get_game_language(game_data_path: &Path) -> String {
+    let mut language = String::new();
+    if let Ok(mut file) = File::open(game_data_path.join("language.txt")) {
+        if file.read_to_string(&mut language).is_err() { language.clear(); }
+    }
+
+    let language = language.trim_matches(|x: char| x.is_whitespace() || x == '\u{feff}').to_lowercase();
+    if language.is_empty() { "en".to_owned() } else { language }
+}
+
+/// This function checks if a PackFile is a Loc PackFile ("local_en.pack", "local_en_patch.pack",...) of a language
+/// other than the provided one.
+fn is_loc_pack_file_of_other_language(name: &str, language: &str) -> bool {
+    let name = name.to_lowercase();
+    if !name.starts_with("local_") { return false }
+    name["local_".len()..].split(|x: char| x == '_' || x == '.').next().map_or(true, |x| x != language)
+}
+
+/// This function generates the Dependency PackFile of a game from all his official PackFiles, with all their DB Tables
+/// and Locs, and saves it with his metadata. The official PackFiles are opened as Read-Only, so we only load to memory
+/// the PackedFiles we need. It returns the metadata of the new Dependency PackFile.
+///
+/// The progress is reported by PackFile to `progress`, and it can be cancelled through it until we start saving.
+pub fn generate_dependency_pack_file(
+    game: &str,
+    game_data_path: &Path,
+    dependency_pack_file_path: &Path,
+    progress: &Progress,
+) -> Result<DependencyPackInfo, Error> {
+    let official_pack_files = get_official_pack_files(game_data_path)?;
+    if official_pack_files.is_empty() { return Err(PackFileError::NoOfficialPackFiles { path: game_data_path.to_path_buf() }.into()) }
+
+    // PackedFiles from later PackFiles replace the ones with the same path from earlier PackFiles.
+    let mut packed_files: Vec<PackedFile> = vec![];
+    let mut packed_files_indexes: HashMap<Vec<String>, usize> = HashMap::new();
+    let mut pack_file_id = String::new();
+    for (pack_file_index, &(ref path, ref source)) in official_pack_files.iter().enumerate() {
+        progress.update(&format!("Reading {}...", source.name), pack_file_index, official_pack_files.len())?;
+        let (pack_file, mut buffer) = super::open_packfile_with_bufreader(path.to_path_buf())?;
+        if pack_file_id.is_empty() { pack_file_id = pack_file.header.id.to_owned(); }
+
+        for (index, packed_file) in pack_file.data.packed_files.iter().enumerate() {
+            if is_dependency_packed_file(&packed_file.path) {
+                let mut data = vec![0; packed_file.size as usize];
+                buffer.seek(SeekFrom::Start(pack_file.packed_file_indexes[index]))?;
+                buffer.read_exact(&mut data)?;
+
+                let packed_file = PackedFile::read(data.len() as u32, packed_file.path.to_vec(), data);
+                let existing_index = packed_files_indexes.get(&packed_file.path).cloned();
+                match existing_index {
+                    Some(index) => packed_files[index] = packed_file,
+                    None => {
+                        packed_files_indexes.insert(packed_file.path.to_vec(), packed_files.len());
+                        packed_files.push(packed_file);
+                    }
+                }
+            }
+        }
+    }
+
+    // Just in case the folder doesn't exists, we try to create it.
+    if let Some(folder) = dependency_pack_file_path.parent() { DirBuilder::new().recursive(true).create(folder)?; }
+
+    let file_name = dependency_pack_file_path.file_name().map(|x| x.to_string_lossy().to_string()).unwrap_or_default();
+    let mut dependency_pack_file = PackFile::new_with_name(file_name, &pack_file_id);
+    dependency_pack_file.header.pack_file_type = 1;
+    dependency_pack_file.add_packedfiles(packed_files);
+    progress.update("Saving the Dependency PackFile...", official_pack_files.len(), official_pack_files.len())?;
+    super::save_packfile(&mut dependency_pack_file, Some(dependency_pack_file_path.to_path_buf()))?;
+
+    let info = DependencyPackInfo {
+        game: game.to_owned(),
+        sources: official_pack_files.into_iter().map(|x| x.1).collect(),
+    };
+    info.save(dependency_pack_file_path)?;
+    Ok(info)
+}
+
+/// This function checks if a PackedFile should be in the Dependency PackFile: DB Tables and Locs from the "text" folder.
+fn is_dependency_packed_file(path: &[String]) -> bool {
+    (path.len() >= 3 && path[0] == "db") ||
+    (path.len() >= 2 && path[0] == "text" && path.last().map_or(false, |x| x.ends_with(".loc")))
+}
+
+/// This function returns the path of the metadata file of a Dependency PackFile, like "warhammer_2.json" for "warhammer_2.pack".
+fn get_info_path(dependency_pack_file_path: &Path) -> PathBuf {
+    dependency_pack_file_path.with_extension("json")
+}
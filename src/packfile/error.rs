@@ -0,0 +1,102 @@
+// In this file are the errors of the PackFile logic. Each one keeps the context of the error (paths, names,...),
+// so the callers can know what went wrong without parsing the messages, which are the same we always showed.
+
+extern crate failure;
+
+use std::fmt;
+use std::path::PathBuf;
+
+use failure::Fail;
+
+/// `PackFileError`: This enum holds all the errors we can get while working with PackFiles:
+/// - InvalidFileName: the file doesn't end in ".pack".
+/// - UnsupportedPackFile: the file has an id we don't support, so it's not a PackFile we can read.
+/// - IncompleteHeader: the file is too small to have a complete header. It has the amount of bytes we could read.
+/// - ReadHeader: the header couldn't be read from the disk.
+/// - EmptyPath: we tried to save a PackFile without a path.
+/// - PackedFileAlreadyExists: there is already a PackedFile with that path in the PackFile.
+/// - FilesAlreadyExist: one of the PackedFiles we are importing already exists in the destination PackFile.
+/// - DestinationAlreadyExists: the PackedFile we are importing already exists in the destination PackFile.
+/// - NothingSelected: we tried to import without selecting the source or the destination.
+/// - UnexpectedTreePath: the selected path is of a type that shouldn't be possible there.
+/// - NonExistentFile: we tried to delete or extract something that doesn't exist.
+/// - WriteFile: a file couldn't be written to disk.
+/// - ExtractErrors: some of the files couldn't be extracted.
+/// - SameName, EmptyName, NameWithSpaces: the new name of a PackedFile or folder is not valid.
+/// - FileNameInUse, FolderNameInUse: the new name is already used in that path.
+/// - EmptyPackFile: the PackFile has no PackedFiles.
+/// - NothingToPatch: the PackFile has no PackedFiles to patch.
+/// - RigidModelFromWarhammer, UnknownRigidModelGame: the RigidModel cannot be patched.
+/// - MapFolderNotFound, TerryFileNotFound, LayerNotFound, LayerIdNotFound: the stuff needed for a prefab is missing.
+/// - NoOfficialPackFiles: there are no official PackFiles in the data folder of the game.
+/// - Unknown: something that should never happen happened.
+#[derive(Clone, Debug, PartialEq)]
+pub enum PackFileError {
+    InvalidFileName { path: PathBuf },
+    UnsupportedPackFile { id: String },
+    IncompleteHeader { bytes_read: usize },
+    ReadHeader,
+    EmptyPath,
+    PackedFileAlreadyExists { path: Vec<String> },
+    FilesAlreadyExist { path: Vec<String> },
+    DestinationAlreadyExists { path: Vec<String> },
+    NothingSelected,
+    UnexpectedTreePath,
+    NonExistentFile { path: Vec<String> },
+    WriteFile { path: PathBuf },
+    ExtractErrors { paths: Vec<String> },
+    SameName { name: String },
+    EmptyName,
+    NameWithSpaces { name: String },
+    FileNameInUse { path: Vec<String> },
+    FolderNameInUse { path: Vec<String> },
+    EmptyPackFile,
+    NothingToPatch,
+    RigidModelFromWarhammer,
+    UnknownRigidModelGame { version: u32 },
+    MapFolderNotFound { path: PathBuf },
+    TerryFileNotFound { path: PathBuf },
+    LayerNotFound { catchment: String, path: PathBuf },
+    LayerIdNotFound { catchment: String, path: PathBuf },
+    NoOfficialPackFiles { path: PathBuf },
+    Unknown,
+}
+
+/// Implementation of `Display` for `PackFileError`, with the messages we show to the user.
+impl fmt::Display for PackFileError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            PackFileError::InvalidFileName { .. } => write!(f, "A valid PackFile name needs to end in \".pack\". Otherwise, RPFM will not open it."),
+            PackFileError::UnsupportedPackFile { .. } => write!(f, "The file is not a supported PackFile.\n\nFor now, we only support:\n - Warhammer 2.\n - Warhammer.\n - Attila."),
+            PackFileError::IncompleteHeader { .. } => write!(f, "The file doesn't even have a full header."),
+            PackFileError::ReadHeader => write!(f, "Error while trying to read the header of the PackFile from the disk."),
+            PackFileError::EmptyPath => write!(f, "Saving a PackFile with an empty path is almost as bad as dividing by 0. Almost."),
+            PackFileError::PackedFileAlreadyExists { ref path } => write!(f, "The PackedFile \"{}\" already exist. Ignored.", path.last().map_or("", |x| &**x)),
+            PackFileError::FilesAlreadyExist { .. } => write!(f, "One or more of the files we want to add already exists in the Destination PackFile. Aborted."),
+            PackFileError::DestinationAlreadyExists { .. } => write!(f, "A PackedFile with the same name already exist in the destination. Aborted."),
+            PackFileError::NothingSelected => write!(f, "You need to select what and where you want to import BEFORE pressing the button."),
+            PackFileError::UnexpectedTreePath => write!(f, "This situation shouldn't happen, but the compiler will complain otherwise."),
+            PackFileError::NonExistentFile { .. } => write!(f, "How the hell did you managed to try to delete a non-existent file?"),
+            PackFileError::WriteFile { ref path } => write!(f, "Error while writing the following file to disk:\n{}", path.display()),
+            PackFileError::ExtractErrors { ref paths } => write!(f, "There has been a problem extracting the following files: {:#?}", paths),
+            PackFileError::SameName { .. } => write!(f, "New name is the same as old name."),
+            PackFileError::EmptyName => write!(f, "Only my hearth can be empty."),
+            PackFileError::NameWithSpaces { .. } => write!(f, "Spaces are not valid characters."),
+            PackFileError::FileNameInUse { .. } => write!(f, "This name is already being used by another file in this path."),
+            PackFileError::FolderNameInUse { .. } => write!(f, "This name is already being used by another folder in this path."),
+            PackFileError::EmptyPackFile => write!(f, "This packfile is empty, so we can't patch it."),
+            PackFileError::NothingToPatch => write!(f, "There are not files in this Packfile that could be patched/deleted."),
+            PackFileError::RigidModelFromWarhammer => write!(f, "This is not an Attila's RigidModel, but a Warhammer one."),
+            PackFileError::UnknownRigidModelGame { .. } => write!(f, "I don't even know from what game is this RigidModel."),
+            PackFileError::MapFolderNotFound { ref path } => write!(f, "The following map's original folder couldn't be found:\n{:?}", path),
+            PackFileError::TerryFileNotFound { ref path } => write!(f, "The following map's .terry file couldn't be found:\n{:?}", path),
+            PackFileError::LayerNotFound { ref catchment, ref path } => write!(f, "The layer of \"{}\" couldn't be found in the following map's .terry file:\n{:?}", catchment, path),
+            PackFileError::LayerIdNotFound { ref catchment, ref path } => write!(f, "The id of the layer of \"{}\" couldn't be found in the following map's .terry file:\n{:?}", catchment, path),
+            PackFileError::NoOfficialPackFiles { .. } => write!(f, "There are no official PackFiles in the data folder of the game."),
+            PackFileError::Unknown => write!(f, "This should never happen."),
+        }
+    }
+}
+
+/// Implementation of `Fail` for `PackFileError`, so it can be used as a `failure::Error`.
+impl Fail for PackFileError {}
@@ -21,11 +21,15 @@ use std::io::SeekFrom;
 use failure::Error;
 
 use common::*;
+use common::progress::Progress;
 use packedfile::loc::Loc;
 use packedfile::db::DB;
 use packedfile::rigidmodel::RigidModel;
+use self::error::PackFileError;
 
 pub mod packfile;
+pub mod dependency_pack;
+pub mod error;
 
 /*
 --------------------------------------------------------
@@ -42,6 +46,12 @@ pub fn new_packfile(file_name: String, packfile_id: &str) -> packfile::PackFile
 /// This function is used to open the PackFiles. It requires the path of the PackFile to open, and
 /// it returns the PackFile decoded (if success) or an error message (if error).
 pub fn open_packfile(pack_file_path: PathBuf) -> Result<packfile::PackFile, Error> {
+    open_packfile_with_progress(pack_file_path, &Progress::none())
+}
+
+/// This function is the same as `open_packfile`, but it reports his progress to `progress`, and it can be
+/// cancelled through it. Use it for PackFiles that may take a while to open, like the game's ones.
+pub fn open_packfile_with_progress(pack_file_path: PathBuf, progress: &Progress) -> Result<packfile::PackFile, Error> {
 
     // First, we get his name.
     let pack_file_name = pack_file_path.file_name().unwrap().to_str().unwrap().to_string();
@@ -53,11 +63,11 @@ pub fn open_packfile(pack_file_path: PathBuf) -> Result<packfile::PackFile, Erro
         let mut pack_file = BufReader::new(File::open(&pack_file_path)?);
 
         // And then we try to read it into a PackFile.
-        packfile::PackFile::read(&mut pack_file, pack_file_name, pack_file_path, false)
+        packfile::PackFile::read(&mut pack_file, pack_file_name, pack_file_path, false, progress)
     }
 
     // Otherwise, return an error.
-    else { Err(format_err!("A valid PackFile name needs to end in \".pack\". Otherwise, RPFM will not open it.")) }
+    else { Err(PackFileError::InvalidFileName { path: pack_file_path }.into()) }
 }
 
 /// This function is used to open the PackFiles. It requires the path of the PackFile to open, and
@@ -74,11 +84,11 @@ pub fn open_packfile_with_bufreader(pack_file_path: PathBuf) -> Result<(packfile
         let mut pack_file = BufReader::new(File::open(&pack_file_path)?);
 
         // And then we try to read it into a PackFile.
-        packfile::PackFile::read(&mut pack_file, pack_file_name, pack_file_path, true).map(|result| (result, pack_file))
+        packfile::PackFile::read(&mut pack_file, pack_file_name, pack_file_path, true, &Progress::none()).map(|result| (result, pack_file))
     }
 
     // Otherwise, return an error.
-    else { Err(format_err!("A valid PackFile name needs to end in \".pack\". Otherwise, RPFM will not open it.")) }
+    else { Err(PackFileError::InvalidFileName { path: pack_file_path }.into()) }
 }
 
 /// This function is used to take an open PackFile, encode it and save it into the disk. We return
@@ -91,6 +101,15 @@ pub fn save_packfile(
     pack_file: &mut packfile::PackFile,
     new_path: Option<PathBuf>
 ) -> Result<(), Error> {
+    save_packfile_with_progress(pack_file, new_path, &Progress::none())
+}
+
+/// This function is the same as `save_packfile`, but it reports his progress to `progress`.
+pub fn save_packfile_with_progress(
+    pack_file: &mut packfile::PackFile,
+    new_path: Option<PathBuf>,
+    progress: &Progress,
+) -> Result<(), Error> {
 
     // If we haven't received a new_path, we assume the path is the original path of the file.
     // If that one is empty too (should never happen), we panic and cry.
@@ -114,7 +133,7 @@ pub fn save_packfile(
             }
 
             // Otherwise, return error.
-            else { return Err( format_err!("Saving a PackFile with an empty path is almost as bad as dividing by 0. Almost.")) }
+            else { return Err(PackFileError::EmptyPath.into()) }
         }
     };
 
@@ -122,7 +141,7 @@ pub fn save_packfile(
     let mut file = BufWriter::new(File::create(&pack_file_path)?);
 
     // And we try to save it.
-    packfile::PackFile::save(&pack_file, &mut file)
+    packfile::PackFile::save(&pack_file, &mut file, progress)
 }
 
 
@@ -152,7 +171,7 @@ pub fn add_file_to_packfile(
         pack_file.add_packedfiles(packed_files);
         Ok(format!("File added."))
     }
-    else { Err(format_err!("The PackedFile \"{}\" already exist. Ignored.", tree_path.last().unwrap())) }
+    else { Err(PackFileError::PackedFileAlreadyExists { path: tree_path }.into()) }
 }
 
 
@@ -212,7 +231,7 @@ pub fn add_packedfile_to_packfile(
                         // Here we check for duplicates before adding the files
                         for packed_file in &new_packed_files {
                             if pack_file_destination.data.packedfile_exists(&packed_file.path) {
-                                return Err(format_err!("One or more of the files we want to add already exists in the Destination PackFile. Aborted."))
+                                return Err(PackFileError::FilesAlreadyExist { path: packed_file.path.to_vec() }.into())
                             }
                         }
 
@@ -238,7 +257,7 @@ pub fn add_packedfile_to_packfile(
 
                         // Here we check for duplicates before adding the file.
                         if pack_file_destination.data.packedfile_exists(&new_packed_file.path) {
-                            return Err(format_err!("A PackedFile with the same name already exist in the destination. Aborted."))
+                            return Err(PackFileError::DestinationAlreadyExists { path: new_packed_file.path.to_vec() }.into())
                         }
 
                         // If they passed the checks, add them and return success.
@@ -277,7 +296,7 @@ pub fn add_packedfile_to_packfile(
                         // Here we check for duplicates before adding the files
                         for packed_file in &new_packed_files {
                             if pack_file_destination.data.packedfile_exists(&packed_file.path) {
-                                return Err(format_err!("One or more of the files we want to add already exists in the Destination PackFile. Aborted."))
+                                return Err(PackFileError::FilesAlreadyExist { path: packed_file.path.to_vec() }.into())
                             }
                         }
 
@@ -287,7 +306,7 @@ pub fn add_packedfile_to_packfile(
                     },
 
                     // If the source is not selected (this should really never happen).
-                    _ => Err(format_err!("This situation shouldn't happen, but the compiler will complain otherwise.")),
+                    _ => Err(PackFileError::UnexpectedTreePath.into()),
                 }
             }
 
@@ -313,7 +332,7 @@ pub fn add_packedfile_to_packfile(
                         // Here we check for duplicates before adding the files
                         for packed_file in &new_packed_files {
                             if pack_file_destination.data.packedfile_exists(&packed_file.path) {
-                                return Err(format_err!("One or more of the files we want to add already exists in the Destination PackFile. Aborted."))
+                                return Err(PackFileError::FilesAlreadyExist { path: packed_file.path.to_vec() }.into())
                             }
                         }
 
@@ -354,7 +373,7 @@ pub fn add_packedfile_to_packfile(
                         // Here we check for duplicates before adding the files
                         for packed_file in &new_packed_files {
                             if pack_file_destination.data.packedfile_exists(&packed_file.path) {
-                                return Err(format_err!("One or more of the files we want to add already exists in the Destination PackFile. Aborted."))
+                                return Err(PackFileError::FilesAlreadyExist { path: packed_file.path.to_vec() }.into())
                             }
                         }
 
@@ -381,7 +400,7 @@ pub fn add_packedfile_to_packfile(
 
                         // Here we check for duplicates before adding the file.
                         if pack_file_destination.data.packedfile_exists(&new_packed_file.path) {
-                            return Err(format_err!("A PackedFile with the same name already exist in the destination. Aborted."))
+                            return Err(PackFileError::DestinationAlreadyExists { path: new_packed_file.path.to_vec() }.into())
                         }
 
                         // If they passed the checks, add them and return success.
@@ -390,15 +409,15 @@ pub fn add_packedfile_to_packfile(
                     }
 
                     // If the source is not selected (this should really never happen).
-                    _ => Err(format_err!("This situation shouldn't happen, but the compiler will complain otherwise.")),
+                    _ => Err(PackFileError::UnexpectedTreePath.into()),
 
                 }
             }
             // If the destination is not selected, or it's a file (this should really never happen).
-            _ => Err(format_err!("This situation shouldn't happen, but the compiler will complain otherwise.")),
+            _ => Err(PackFileError::UnexpectedTreePath.into()),
         }
     }
-    else { Err(format_err!("You need to select what and where you want to import BEFORE pressing the button.")) }
+    else { Err(PackFileError::NothingSelected.into()) }
 }
 
 
@@ -438,7 +457,7 @@ pub fn delete_from_packfile(
 
         // If it's a PackFile, easy job. For non-existant files, return an error.
         TreePathType::PackFile => pack_file.remove_all_packedfiles(),
-        TreePathType::None => return Err(format_err!("How the hell did you managed to try to delete a non-existent file?")),
+        TreePathType::None => return Err(PackFileError::NonExistentFile { path: tree_path.to_vec() }.into()),
     }
     Ok(())
 }
@@ -467,7 +486,7 @@ pub fn extract_from_packfile(
             // And try to write it.
             match file.write_all(&pack_file.data.packed_files[packed_file_data.1].data){
                 Ok(_) => Ok(format!("File extracted successfully:\n{}", extracted_path.display())),
-                Err(_) => Err(format_err!("Error while writing the following file to disk:\n{}", extracted_path.display()))
+                Err(_) => Err(PackFileError::WriteFile { path: extracted_path.to_path_buf() }.into())
             }
         },
 
@@ -512,7 +531,7 @@ pub fn extract_from_packfile(
             }
 
             // If there is any error in the list, report it.
-            if !error_files.is_empty() { return Err(format_err!("There has been a problem extracting the following files: {:#?}", error_files)) }
+            if !error_files.is_empty() { return Err(PackFileError::ExtractErrors { paths: error_files }.into()) }
 
             // If we reach this, return success.
             Ok(format!("{} files extracted. No errors detected.", files_extracted))
@@ -554,14 +573,14 @@ pub fn extract_from_packfile(
             }
 
             // If there is any error in the list, report it.
-            if !error_files.is_empty() { return Err(format_err!("There has been a problem extracting the following files: {:#?}", error_files)) }
+            if !error_files.is_empty() { return Err(PackFileError::ExtractErrors { paths: error_files }.into()) }
 
             // If we reach this, return success.
             Ok(format!("{} files extracted. No errors detected.", files_extracted))
         }
 
         // If it doesn't exist, there has been a bug somewhere else. Otherwise, this situation will never happen.
-        TreePathType::None => Err(format_err!("How the hell did you managed to try to delete a non-existant file?")),
+        TreePathType::None => Err(PackFileError::NonExistentFile { path: tree_path.to_vec() }.into()),
     }
 }
 
@@ -579,13 +598,13 @@ pub fn rename_packed_file(
 
     // First we check if the name is valid, and return an error if the new name is invalid.
     if new_name == tree_path.last().unwrap() {
-        Err(format_err!("New name is the same as old name."))
+        Err(PackFileError::SameName { name: new_name.to_owned() }.into())
     }
     else if new_name.is_empty() {
-        Err(format_err!("Only my hearth can be empty."))
+        Err(PackFileError::EmptyName.into())
     }
     else if new_name.contains(' ') {
-        Err(format_err!("Spaces are not valid characters."))
+        Err(PackFileError::NameWithSpaces { name: new_name.to_owned() }.into())
     }
 
     // If we reach this point, we can rename the file/folder.
@@ -605,7 +624,7 @@ pub fn rename_packed_file(
                     Ok(())
                 }
                 else {
-                    Err(format_err!("This name is already being used by another file in this path."))
+                    Err(PackFileError::FileNameInUse { path: new_tree_path }.into())
                 }
             }
             TreePathType::Folder(tree_path) => {
@@ -626,11 +645,11 @@ pub fn rename_packed_file(
                     Ok(())
                 }
                 else {
-                    Err(format_err!("This name is already being used by another folder in this path."))
+                    Err(PackFileError::FolderNameInUse { path: new_tree_path }.into())
                 }
             }
             TreePathType::PackFile |
-            TreePathType::None => Err(format_err!("This should never happen.")),
+            TreePathType::None => Err(PackFileError::Unknown.into()),
         }
     }
 }
@@ -799,10 +818,10 @@ pub fn patch_siege_ai (
 
     // And now we return success or error depending on what happened during the patching process.
     if packfile_is_empty {
-        Err(format_err!("This packfile is empty, so we can't patch it."))
+        Err(PackFileError::EmptyPackFile.into())
     }
     else if files_patched == 0 && files_deleted == 0 {
-        Err(format_err!("There are not files in this Packfile that could be patched/deleted."))
+        Err(PackFileError::NothingToPatch.into())
     }
     else if files_patched >= 0 || files_deleted >= 0 {
         if files_patched == 0 {
@@ -840,7 +859,7 @@ pub fn patch_siege_ai (
         }
     }
     else {
-        Err(format_err!("This should never happen."))
+        Err(PackFileError::Unknown.into())
     }
 }
 
@@ -870,8 +889,8 @@ pub fn patch_rigid_model_attila_to_warhammer (
             }
             Ok(format!("RigidModel patched succesfully."))
         },
-        7 => Err(format_err!("This is not an Attila's RigidModel, but a Warhammer one.")),
-        _ => Err(format_err!("I don't even know from what game is this RigidModel.")),
+        7 => Err(PackFileError::RigidModelFromWarhammer.into()),
+        version => Err(PackFileError::UnknownRigidModelGame { version }.into()),
     }
 }
 
@@ -908,13 +927,13 @@ pub fn create_prefab_from_catchment(
         terry_map_path.push(&pack_file.borrow().data.packed_files[*prefab.0].path[4]);
 
         // If the map folder doesn't exist, return error.
-        if !terry_map_path.is_dir() { return Err(format_err!("The following map's original folder couldn't be found:\n{:?}", terry_map_path)) }
+        if !terry_map_path.is_dir() { return Err(PackFileError::MapFolderNotFound { path: terry_map_path }.into()) }
 
         // Get the ".terry" file of the map.
         let terry_file = get_files_from_subdir(&terry_map_path).unwrap().iter().filter(|x| x.file_name().unwrap().to_string_lossy().as_ref().to_owned().ends_with(".terry")).cloned().collect::<Vec<PathBuf>>();
 
         // If the terry file wasn't found, return error.
-        if terry_file.is_empty() { return Err(format_err!("The following map's .terry file couldn't be found:\n{:?}", terry_map_path)) }
+        if terry_file.is_empty() { return Err(PackFileError::TerryFileNotFound { path: terry_map_path }.into()) }
 
         // Read it to a String so we can examine it properly.
         let mut file = BufReader::new(File::open(&terry_file[0])?);
@@ -926,13 +945,13 @@ pub fn create_prefab_from_catchment(
 
         let line = match terry_file_string.find(&format!("bmd_export_type=\"{}\"/>", catchment_name)) {
             Some(line) => line,
-            None => return Err(format_err!("The layer of \"{}\" couldn't be found in the following map's .terry file:\n{:?}", catchment_name, terry_map_path)),
+            None => return Err(PackFileError::LayerNotFound { catchment: catchment_name.to_owned(), path: terry_map_path }.into()),
         };
         terry_file_string.truncate(line);
 
         let id_index = match terry_file_string.rfind(" id=\"") {
             Some(id_index) => id_index,
-            None => return Err(format_err!("The id of the layer of \"{}\" couldn't be found in the following map's .terry file:\n{:?}", catchment_name, terry_map_path))
+            None => return Err(PackFileError::LayerIdNotFound { catchment: catchment_name.to_owned(), path: terry_map_path }.into())
         };
         let id_layer = &terry_file_string[(id_index + 5)..(id_index + 20)];
 
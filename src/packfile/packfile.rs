@@ -12,7 +12,9 @@ use std::fs::File;
 use failure::Error;
 
 use common::coding_helpers::*;
+use common::progress::Progress;
 use settings::*;
+use super::error::PackFileError;
 
 /// `PackFile`: This stores the data of the entire PackFile in memory ('cause fuck lazy-loading),
 /// along with some extra data needed to manipulate the PackFile.
@@ -157,11 +159,13 @@ impl PackFile {
     /// - file_name: a String with the name of the PackFile.
     /// - file_path: a PathBuf with the path of the PackFile.
     /// - is_read_only: if yes, don't load to memory his data. Instead, just get his indexes.
+    /// - progress: where we report the PackedFiles loaded so far. Loading can be cancelled through it.
     pub fn read(
         pack_file: &mut BufReader<File>,
         file_name: String,
         file_path: PathBuf,
-        is_read_only: bool
+        is_read_only: bool,
+        progress: &Progress,
     ) -> Result<Self, Error> {
 
         // We try to decode the header of the PackFile.
@@ -207,7 +211,7 @@ impl PackFile {
                         else {
 
                             // We try to load his data to memory.
-                            match data.read_data(pack_file) {
+                            match data.read_data(pack_file, progress) {
                                 Ok(_) => {
 
                                     // We return a fully decoded PackFile.
@@ -235,8 +239,9 @@ impl PackFile {
         }
     }
 
-    /// This function takes a decoded &mut PackFile, and tries to encode it and write it on disk.
-    pub fn save(&self, mut file: &mut BufWriter<File>) -> Result<(), Error> {
+    /// This function takes a decoded &mut PackFile, and tries to encode it and write it on disk. The progress
+    /// of the writing is reported to `progress`, but it cannot be cancelled, as it'll leave a broken file.
+    pub fn save(&self, mut file: &mut BufWriter<File>, progress: &Progress) -> Result<(), Error> {
 
         // First, we encode the indexes, as we need their final size to encode complete the header.
         let indexes = self.data.save_indexes(&self.header);
@@ -249,7 +254,7 @@ impl PackFile {
         file.write(&indexes.1)?;
 
         // After all that, we try to write all the PackFiles to the file.
-        self.data.save_data(&mut file)?;
+        self.data.save_data(&mut file, progress)?;
 
         // If nothing has failed, return success.
         Ok(())
@@ -304,7 +309,7 @@ impl PackFileHeader {
     }
 
     /// This function reads the Header of a PackFile and decode it into a PackFileHeader.
-    fn read(header: &mut BufReader<File>) -> Result<Self, Error> {
+    pub fn read(header: &mut BufReader<File>) -> Result<Self, Error> {
 
         // Create a new default header.
         let mut pack_file_header = Self::new("PFH5");
@@ -330,7 +335,7 @@ impl PackFileHeader {
 
                             // If we reach this point, the file is not valid.
                             else {
-                                return Err(format_err!("The file is not a supported PackFile.\n\nFor now, we only support:\n - Warhammer 2.\n - Warhammer.\n - Attila."))
+                                return Err(PackFileError::UnsupportedPackFile { id }.into())
                             }
                         }
 
@@ -340,11 +345,11 @@ impl PackFileHeader {
                 }
 
                 // Otherwise, return an error.
-                else { return Err(format_err!("The file doesn't even have a full header.")) }
+                else { return Err(PackFileError::IncompleteHeader { bytes_read: bytes }.into()) }
             }
 
             // If we couldn't read the header, return the error.
-            Err(_) => return Err(format_err!("Error while trying to read the header of the PackFile from the disk.")),
+            Err(_) => return Err(PackFileError::ReadHeader.into()),
         }
 
         // Fill the default header with the current PackFile values.
@@ -562,13 +567,17 @@ impl PackFileData {
     /// It requires:
     /// - data: the raw data or the PackFile.
     /// - header: the header of the PackFile.
+    /// - progress: where we report the PackedFiles loaded so far.
     fn read_data(
         &mut self,
         data: &mut BufReader<File>,
+        progress: &Progress,
     ) -> Result<(), Error> {
 
         // Now, we get the raw data from the PackedFiles, and get it into the corresponding PackedFile.
-        for packed_file in &mut self.packed_files {
+        let total = self.packed_files.len();
+        for (index, packed_file) in self.packed_files.iter_mut().enumerate() {
+            progress.update("Loading PackedFiles...", index, total)?;
 
             // Prepare his buffer.
             packed_file.data = vec![0; packed_file.size as usize];
@@ -628,10 +637,12 @@ impl PackFileData {
     }
 
     /// This function writes all the PackedFile's data at the end of the provided file.
-    fn save_data(&self, file: &mut BufWriter<File>) -> Result<(), Error> {
+    fn save_data(&self, file: &mut BufWriter<File>, progress: &Progress) -> Result<(), Error> {
 
         // For each PackedFile, just try to write his data to the disk.
-        for packed_file in &self.packed_files {
+        let total = self.packed_files.len();
+        for (index, packed_file) in self.packed_files.iter().enumerate() {
+            progress.report("Saving PackedFiles...", index, total);
             file.write(&packed_file.data)?;
         }
 
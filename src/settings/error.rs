@@ -0,0 +1,37 @@
+// In this file are the errors of the settings. They keep the path of the file we were working with, so the
+// callers can know what went wrong without parsing the messages.
+
+extern crate failure;
+
+use std::fmt;
+use std::path::PathBuf;
+
+use failure::Fail;
+
+/// `SettingsError`: This enum holds all the errors we can get while loading or saving the settings:
+/// - ReadSettings: the settings file couldn't be opened (usually, because it doesn't exist yet).
+/// - ParseSettings: the settings file is not a valid settings file. It has the error of the parser.
+/// - CreateSettings: the settings file couldn't be created.
+/// - WriteSettings: the settings couldn't be written into the settings file.
+#[derive(Clone, Debug, PartialEq)]
+pub enum SettingsError {
+    ReadSettings { path: PathBuf },
+    ParseSettings { path: PathBuf, message: String },
+    CreateSettings { path: PathBuf },
+    WriteSettings { path: PathBuf },
+}
+
+/// Implementation of `Display` for `SettingsError`, with the messages we show to the user.
+impl fmt::Display for SettingsError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            SettingsError::ReadSettings { ref path } => write!(f, "Error while trying to read the following settings file:\n{}", path.display()),
+            SettingsError::ParseSettings { ref path, ref message } => write!(f, "The following settings file is broken:\n{}\n\n{}", path.display(), message),
+            SettingsError::CreateSettings { .. } => write!(f, "Error while trying prepare the \"settings.json\" file to be written."),
+            SettingsError::WriteSettings { .. } => write!(f, "Error while trying to write the \"settings.json\" file."),
+        }
+    }
+}
+
+/// Implementation of `Fail` for `SettingsError`, so it can be used as a `failure::Error`.
+impl Fail for SettingsError {}
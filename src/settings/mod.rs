@@ -9,12 +9,19 @@ use std::io::BufReader;
 
 use failure::Error;
 
+use self::error::SettingsError;
+
+pub mod error;
+
 /// `GameInfo`: This struct holds all the info needed for a game to be "supported" by RPFM features.
 /// It's stores the following data:
 /// - `display_name`: This is the name it'll show up in the UI. For example, in a dropdown.
 /// - `folder_name`: This name is the name used for any internal operation. For example, for the MyMod stuff.
 /// - `id`: This is the ID used at the start of every PackFile for that game.
 /// - `dependency_pack`: The name of the "Dependency" PackFile for that game.
+/// - `schema`: The name of the schema file of that game.
+/// - `schema_base`: The name of the schema file this game's schema inherits from, if any. The definitions in
+///   `schema` replace the ones of this file with the same version, so games can share most of their definitions.
 #[derive(Clone, Debug)]
 pub struct GameInfo {
     pub display_name: String,
@@ -22,6 +29,7 @@ pub struct GameInfo {
     pub id: String,
     pub dependency_pack: String,
     pub schema: String,
+    pub schema_base: Option<String>,
 }
 
 /// This struct hold every setting of the program, and it's the one that we are going to serialize.
@@ -34,6 +42,18 @@ pub struct Settings {
     pub allow_edition_of_ca_packfiles: bool,
     pub check_updates_on_start: bool,
     pub check_schema_updates_on_start: bool,
+
+    // These are optional in the settings file, so older settings files can still be loaded.
+    #[serde(default)]
+    pub validate_tables_on_save: bool,
+    #[serde(default)]
+    pub block_save_on_validation_errors: bool,
+    #[serde(default)]
+    pub sort_rows_on_save: bool,
+    #[serde(default)]
+    pub sort_rows_on_save_packfiles: Vec<String>,
+    #[serde(default)]
+    pub deterministic_guids: bool,
 }
 
 /// This struct should hold any path we need to store in the settings.
@@ -77,7 +97,8 @@ impl GameInfo {
             folder_name: "warhammer_2".to_owned(),
             id: "PFH5".to_owned(),
             dependency_pack: "wh2.pack".to_owned(),
-            schema: "schema_wh.json".to_owned(),
+            schema: "schema_wh2.json".to_owned(),
+            schema_base: Some("schema_wh.json".to_owned()),
         };
 
         supported_games.push(game_info);
@@ -88,7 +109,8 @@ impl GameInfo {
             folder_name: "warhammer".to_owned(),
             id: "PFH4".to_owned(),
             dependency_pack: "wh.pack".to_owned(),
-            schema: "schema_wh.json".to_owned(),
+            schema: "schema_wh1.json".to_owned(),
+            schema_base: Some("schema_wh.json".to_owned()),
         };
 
         supported_games.push(game_info);
@@ -100,6 +122,7 @@ impl GameInfo {
             id: "PFH4".to_owned(),
             dependency_pack: "att.pack".to_owned(),
             schema: "schema_att.json".to_owned(),
+            schema_base: None,
         };
 
         supported_games.push(game_info);
@@ -133,14 +156,25 @@ impl Settings {
             allow_edition_of_ca_packfiles: false,
             check_updates_on_start: true,
             check_schema_updates_on_start: true,
+            validate_tables_on_save: false,
+            block_save_on_validation_errors: false,
+            sort_rows_on_save: false,
+            sort_rows_on_save_packfiles: vec![],
+            deterministic_guids: false,
         }
     }
 
+    /// This function checks if we have to sort the rows of the tables of a PackFile when saving it. That's if it's
+    /// enabled for every PackFile, or only for the one with the provided name.
+    pub fn is_sort_rows_on_save_enabled(&self, pack_file_name: &str) -> bool {
+        self.sort_rows_on_save || self.sort_rows_on_save_packfiles.iter().any(|x| x == pack_file_name)
+    }
+
     /// This function takes a settings.json file and reads it into a "Settings" object.
     pub fn load(path: &PathBuf, supported_games: &[GameInfo]) -> Result<Self, Error> {
         let settings_path = path.to_path_buf().join(PathBuf::from("settings.json"));
-        let settings_file = BufReader::new(File::open(settings_path)?);
-        let mut settings: Self = serde_json::from_reader(settings_file)?;
+        let settings_file = BufReader::new(File::open(&settings_path).map_err(|_| SettingsError::ReadSettings { path: settings_path.to_path_buf() })?);
+        let mut settings: Self = serde_json::from_reader(settings_file).map_err(|error| SettingsError::ParseSettings { path: settings_path.to_path_buf(), message: error.to_string() })?;
 
         // We need to make sure here that we have entries in `game_paths` for every supported game.
         // Otherwise, it'll crash when trying to open the "Preferences" window.
@@ -174,14 +208,14 @@ impl Settings {
         settings_path.push("settings.json");
 
         let settings_json = serde_json::to_string_pretty(self);
-        match File::create(settings_path) {
+        match File::create(&settings_path) {
             Ok(mut file) => {
                 match file.write_all(settings_json.unwrap().as_bytes()) {
                     Ok(_) => Ok(()),
-                    Err(_) => Err(format_err!("Error while trying to write the \"settings.json\" file.")),
+                    Err(_) => Err(SettingsError::WriteSettings { path: settings_path.to_path_buf() }.into()),
                 }
             },
-            Err(_) => Err(format_err!("Error while trying prepare the \"settings.json\" file to be written."))
+            Err(_) => Err(SettingsError::CreateSettings { path: settings_path.to_path_buf() }.into())
         }
     }
 }
@@ -15,19 +15,29 @@ use gtk::{
     Grid, Statusbar, MessageType, ButtonsType, DialogFlags, ApplicationWindow, ResponseType, ComboBoxText,
     AboutDialog, License, WindowPosition, TreeIter, Application, Paned, Orientation, CellRendererMode,
     TreeViewColumn, CellRendererText, ScrolledWindow, ButtonBox, Button, Entry, ButtonBoxStyle,
-    FileChooserAction, ReliefStyle
+    FileChooserAction, ReliefStyle, Dialog, TextView, WrapMode, ProgressBar, events_pending, main_iteration
 };
 use std::cmp::Ordering;
 use std::path::{Path, PathBuf};
 use std::fmt::Display;
+use std::mem;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::atomic;
+use std::sync::mpsc::{channel, TryRecvError};
+use std::thread;
+use std::time::Duration;
+use failure::Error;
 
 use common::*;
+use common::progress::{Progress, is_cancelled_error};
 use packedfile::*;
 use packedfile::db::*;
 use packedfile::loc::*;
 use packedfile::db::schemas::Schema;
+use packfile;
 use packfile::packfile::PackFile;
 use packfile::packfile::PackedFile;
+use settings::{GameInfo, GameSelected};
 use AppUI;
 
 pub mod packedfile_db;
@@ -280,9 +290,13 @@ pub fn show_create_packed_file_window(
     if let PackedFileType::DB = packed_file_type {
 
         // Only if there is a dependency_database we populate this.
+        // As the database has tables from multiple PackFiles, we skip his Locs and the repeated tables.
         if let Some(ref dependency_database) = *dependency_database.borrow() {
-            for table in dependency_database.iter() {
-                table_combo.append(Some(&*table.path[1]), &*table.path[1]);
+            let mut tables = dependency_database.iter().filter(|x| x.path.len() >= 3 && x.path[0] == "db").map(|x| x.path[1].to_owned()).collect::<Vec<String>>();
+            tables.sort();
+            tables.dedup();
+            for table in &tables {
+                table_combo.append(Some(&**table), &**table);
             }
         }
     }
@@ -717,11 +731,19 @@ pub fn show_tsv_mass_import_window(
         entry,
         app_ui => move |_,_| {
 
-            // Try to mass-import all the provided TSV files.
-            let tree_paths = match tsv_mass_import(&tsv_paths.borrow(), &entry.get_text().unwrap(), &schema.borrow(), &mut pack_file.borrow_mut()) {
+            // Try to mass-import all the provided TSV files in another thread. The PackFile is moved there and back.
+            let paths = tsv_paths.borrow().to_vec();
+            let name = entry.get_text().unwrap();
+            let schema = schema.borrow().clone();
+            let result = run_with_progress_on_pack_file(&window, "Importing TSV files...", true, &pack_file, move |pack_file, progress| {
+                tsv_mass_import(&paths, &name, &schema, pack_file, progress)
+            });
+
+            // If the user cancelled the import, the PackFile is untouched and there is nothing to report.
+            let tree_paths = match result {
                 Ok(tree_path) => tree_path,
                 Err(error) => {
-                    show_dialog(&app_ui.window, false, error.cause());
+                    if !is_cancelled_error(&error) { show_dialog(&app_ui.window, false, error.cause()); }
                     return Inhibit(false)
                 }
             };
@@ -852,6 +874,350 @@ pub fn show_dialog<T: Display>(parent_window: &ApplicationWindow, is_success: bo
     dialog.destroy();
 }
 
+/// This function shows a long, read-only text (like the result of a check) in a scrollable dialog.
+/// It requires:
+/// - parent_window: a reference to the `Window` that'll act as "parent" of the dialog.
+/// - title: the title of the dialog.
+/// - text: something that implements the trait "Display", with the text we want to show.
+pub fn show_report<T: Display>(parent_window: &ApplicationWindow, title: &str, text: T) {
+
+    // Create the dialog...
+    let dialog = Dialog::new_with_buttons(
+        Some(title),
+        Some(parent_window),
+        DialogFlags::from_bits(1).unwrap(),
+        &[("Close", -7)]
+    );
+    dialog.set_default_size(700, 500);
+
+    // Put the text in a monospaced, non-editable TextView, so the reports keep their format.
+    let text_view = TextView::new();
+    text_view.set_editable(false);
+    text_view.set_monospace(true);
+    text_view.set_wrap_mode(WrapMode::Word);
+    text_view.get_buffer().unwrap().set_text(&text.to_string());
+
+    let scroll = ScrolledWindow::new(None, None);
+    scroll.set_hexpand(true);
+    scroll.set_vexpand(true);
+    scroll.add(&text_view);
+    dialog.get_content_area().pack_start(&scroll, true, true, 0);
+
+    // Run & Destroy the Dialog.
+    dialog.show_all();
+    dialog.run();
+    dialog.destroy();
+}
+
+/// This function shows a "Yes/No" question to the user, and returns true if the answer was "Yes".
+/// It requires:
+/// - parent_window: a reference to the `Window` that'll act as "parent" of the dialog.
+/// - text: something that implements the trait "Display", with the question we want to ask.
+pub fn show_question<T: Display>(parent_window: &ApplicationWindow, text: T) -> bool {
+
+    // Create the dialog...
+    let dialog = MessageDialog::new(
+        Some(parent_window),
+        DialogFlags::from_bits(1).unwrap(),
+        MessageType::Question,
+        ButtonsType::YesNo,
+        "Question"
+    );
+
+    // Set the title and secondary text.
+    dialog.set_title("Question");
+    dialog.set_property_secondary_text(Some(&text.to_string()));
+
+    // Run & Destroy the Dialog, returning the answer.
+    let response_yes: i32 = ResponseType::Yes.into();
+    let answer = dialog.run() == response_yes;
+    dialog.destroy();
+    answer
+}
+
+/// This function asks the user for the row to clone with his dependencies, and the pattern for the new keys.
+/// It returns the table, column, key and pattern written by the user, or None if the user cancelled.
+/// It requires:
+/// - parent_window: a reference to the `Window` that'll act as "parent" of the dialog.
+pub fn show_clone_with_dependencies_dialog(parent_window: &ApplicationWindow) -> Option<(String, String, String, String)> {
+
+    // Create the dialog...
+    let response_cancel: i32 = ResponseType::Cancel.into();
+    let response_accept: i32 = ResponseType::Accept.into();
+    let dialog = Dialog::new_with_buttons(
+        Some("Clone with Dependencies"),
+        Some(parent_window),
+        DialogFlags::from_bits(1).unwrap(),
+        &[("Cancel", response_cancel), ("Clone", response_accept)]
+    );
+
+    // Create the grid with the labels and entries.
+    let grid = Grid::new();
+    grid.set_border_width(6);
+    grid.set_row_spacing(3);
+    grid.set_column_spacing(3);
+
+    let labels = ["Table:", "Column:", "Key:", "Pattern for the new keys:"];
+    let defaults = ["", "key", "", "{key}_copy"];
+    let placeholders = ["land_units_tables", "key", "Key of the row to clone", "\"{key}\" is replaced with the old keys"];
+    let mut entries = vec![];
+    for (index, label_text) in labels.iter().enumerate() {
+        let label = Label::new(Some(*label_text));
+        label.set_xalign(0.0);
+        label.set_yalign(0.5);
+
+        let entry = Entry::new();
+        entry.set_size_request(300, 0);
+        entry.set_hexpand(true);
+        entry.set_text(defaults[index]);
+        entry.set_placeholder_text(placeholders[index]);
+
+        grid.attach(&label, 0, index as i32, 1, 1);
+        grid.attach(&entry, 1, index as i32, 1, 1);
+        entries.push(entry);
+    }
+    dialog.get_content_area().pack_start(&grid, true, true, 0);
+
+    // Run & Destroy the Dialog, returning the values if we hit "Clone".
+    dialog.show_all();
+    let result = if dialog.run() == response_accept {
+        let values = entries.iter().map(|x| x.get_text().unwrap_or_default()).collect::<Vec<String>>();
+        Some((values[0].to_owned(), values[1].to_owned(), values[2].to_owned(), values[3].to_owned()))
+    } else { None };
+    dialog.destroy();
+    result
+}
+
+/// This function asks the user for the column, expression and filter of a bulk edit. It returns them,
+/// or None if the user cancelled. It requires:
+/// - parent_window: a reference to the `Window` that'll act as "parent" of the dialog.
+/// - columns: the names of the columns of the table.
+/// - default_column: the position of the column selected by default.
+/// - default_filter: the filter to put by default in the dialog.
+pub fn show_bulk_edit_dialog(
+    parent_window: &ApplicationWindow,
+    columns: &[String],
+    default_column: usize,
+    default_filter: &str,
+) -> Option<(String, String, String)> {
+
+    // Create the dialog...
+    let response_cancel: i32 = ResponseType::Cancel.into();
+    let response_accept: i32 = ResponseType::Accept.into();
+    let dialog = Dialog::new_with_buttons(
+        Some("Bulk Edit"),
+        Some(parent_window),
+        DialogFlags::from_bits(1).unwrap(),
+        &[("Cancel", response_cancel), ("Apply", response_accept)]
+    );
+
+    // Create the grid with the column selector and the entries.
+    let grid = Grid::new();
+    grid.set_border_width(6);
+    grid.set_row_spacing(3);
+    grid.set_column_spacing(3);
+
+    let column_label = Label::new(Some("Column:"));
+    column_label.set_xalign(0.0);
+    column_label.set_yalign(0.5);
+
+    let column_combo = ComboBoxText::new();
+    for column in columns {
+        column_combo.append(Some(&**column), &**column);
+    }
+    column_combo.set_active(default_column as i32);
+    column_combo.set_hexpand(true);
+
+    grid.attach(&column_label, 0, 0, 1, 1);
+    grid.attach(&column_combo, 1, 0, 1, 1);
+
+    let labels = ["New value:", "Only in rows where:"];
+    let defaults = ["", default_filter];
+    let placeholders = ["round(cost * 1.15), true or \"{key}_name\"", "key starts_with \"wh2_dlc\". Empty for all the rows"];
+    let mut entries = vec![];
+    for (index, label_text) in labels.iter().enumerate() {
+        let label = Label::new(Some(*label_text));
+        label.set_xalign(0.0);
+        label.set_yalign(0.5);
+
+        let entry = Entry::new();
+        entry.set_size_request(300, 0);
+        entry.set_hexpand(true);
+        entry.set_text(defaults[index]);
+        entry.set_placeholder_text(placeholders[index]);
+
+        grid.attach(&label, 0, index as i32 + 1, 1, 1);
+        grid.attach(&entry, 1, index as i32 + 1, 1, 1);
+        entries.push(entry);
+    }
+    dialog.get_content_area().pack_start(&grid, true, true, 0);
+
+    // Run & Destroy the Dialog, returning the values if we hit "Apply".
+    dialog.show_all();
+    let result = if dialog.run() == response_accept {
+        match column_combo.get_active_text() {
+            Some(column) => Some((column, entries[0].get_text().unwrap_or_default(), entries[1].get_text().unwrap_or_default())),
+            None => None,
+        }
+    } else { None };
+    dialog.destroy();
+    result
+}
+
+/// This function runs an operation in another thread, showing a dialog with his progress until it finishes. Meanwhile,
+/// we keep processing the events of GTK, so the window doesn't freeze. It requires:
+/// - parent_window: a reference to the `Window` that'll act as "parent" of the dialog.
+/// - title: the title of the dialog.
+/// - is_cancellable: if true, the dialog has a "Cancel" button, that cancels the operation through his `Progress`.
+/// - operation: the operation to run. Everything it needs must be moved into it.
+///
+/// It returns what the operation returned, or an error if the thread crashed before finishing. If the operation is
+/// cancelled, what it returns is up to it, but the ones using `Progress::update` return `Cancelled`, which the callers
+/// should check with `is_cancelled_error` to not report the cancellation as an error.
+pub fn run_with_progress<T, F>(
+    parent_window: &ApplicationWindow,
+    title: &str,
+    is_cancellable: bool,
+    operation: F,
+) -> Result<T, Error>
+where T: Send + 'static, F: FnOnce(&Progress) -> T + Send + 'static {
+
+    // The operation sends his progress through one channel, and his result through the other one.
+    let (progress_sender, progress_receiver) = channel();
+    let (result_sender, result_receiver) = channel();
+    let progress = Progress::new(move |status| { let _ = progress_sender.send(status.clone()); });
+    let cancel_flag = progress.get_cancel_flag();
+    thread::spawn(move || { let _ = result_sender.send(operation(&progress)); });
+
+    // Create the dialog...
+    let response_cancel: i32 = ResponseType::Cancel.into();
+    let dialog = Dialog::new_with_buttons(
+        Some(title),
+        Some(parent_window),
+        DialogFlags::from_bits(1).unwrap(),
+        &[("Cancel", response_cancel)]
+    );
+    dialog.set_deletable(false);
+    dialog.set_response_sensitive(response_cancel, is_cancellable);
+
+    let label = Label::new(Some(title));
+    label.set_xalign(0.0);
+    label.set_yalign(0.5);
+
+    let progress_bar = ProgressBar::new();
+    progress_bar.set_size_request(400, 0);
+    progress_bar.set_show_text(true);
+
+    let grid = Grid::new();
+    grid.set_border_width(6);
+    grid.set_row_spacing(3);
+    grid.attach(&label, 0, 0, 1, 1);
+    grid.attach(&progress_bar, 0, 1, 1, 1);
+    dialog.get_content_area().pack_start(&grid, true, true, 0);
+
+    // If we hit "Cancel", we tell the operation to stop. It'll stop the next time it reports his progress.
+    dialog.connect_response(move |dialog, response| {
+        if is_cancellable && response == response_cancel {
+            cancel_flag.store(true, atomic::Ordering::SeqCst);
+            dialog.set_response_sensitive(response_cancel, false);
+        }
+    });
+    dialog.connect_delete_event(|_,_| Inhibit(true));
+    dialog.show_all();
+
+    // Until the operation finishes, we process the pending events and show his last status.
+    let result = loop {
+        while events_pending() { main_iteration(); }
+
+        if let Some(status) = progress_receiver.try_iter().last() {
+            label.set_text(&status.message);
+            if status.total == 0 { progress_bar.pulse(); }
+            else {
+                progress_bar.set_fraction(status.get_fraction());
+                progress_bar.set_text(Some(&*format!("{} / {}", status.current, status.total)));
+            }
+        }
+
+        match result_receiver.try_recv() {
+            Ok(result) => break Ok(result),
+            Err(TryRecvError::Disconnected) => break Err(format_err!("The operation crashed before finishing.")),
+            Err(TryRecvError::Empty) => thread::sleep(Duration::from_millis(20)),
+        }
+    };
+
+    dialog.destroy();
+    result
+}
+
+/// This function runs an operation over a PackFile in another thread, like `run_with_progress`. While it runs, the
+/// PackFile is moved out of his `Rc<RefCell<>>`, and it's always put back once it's done, even if the operation panics.
+/// The dialog is modal, so nothing can touch the PackFile meanwhile.
+pub fn run_with_progress_on_pack_file<T, F>(
+    parent_window: &ApplicationWindow,
+    title: &str,
+    is_cancellable: bool,
+    pack_file: &Rc<RefCell<PackFile>>,
+    operation: F,
+) -> Result<T, Error>
+where T: Send + 'static, F: FnOnce(&mut PackFile, &Progress) -> Result<T, Error> + Send + 'static {
+    let mut pack_file_to_use = mem::replace(&mut *pack_file.borrow_mut(), PackFile::new());
+    let result = run_with_progress(parent_window, title, is_cancellable, move |progress| {
+        let result = panic::catch_unwind(AssertUnwindSafe(|| operation(&mut pack_file_to_use, progress)));
+        (pack_file_to_use, result)
+    });
+
+    match result {
+        Ok((pack_file_used, result)) => {
+            *pack_file.borrow_mut() = pack_file_used;
+            match result {
+                Ok(result) => result,
+                Err(_) => Err(format_err!("The operation crashed before finishing.")),
+            }
+        }
+        Err(error) => Err(error),
+    }
+}
+
+/// This function saves a PackFile in another thread, showing his progress.
+pub fn save_packfile_with_progress_dialog(
+    parent_window: &ApplicationWindow,
+    pack_file: &Rc<RefCell<PackFile>>,
+    new_path: Option<PathBuf>,
+) -> Result<(), Error> {
+    run_with_progress_on_pack_file(parent_window, "Saving PackFile...", false, pack_file, move |pack_file, progress| {
+        packfile::save_packfile_with_progress(pack_file, new_path, progress)
+    })
+}
+
+/// This function loads the schema of a game. If the user's overlay of the schema is broken, the schema is loaded
+/// without it, and we warn the user about it with a dialog. If there is no schema for the game, we get None.
+pub fn load_schema(
+    parent_window: &ApplicationWindow,
+    rpfm_path: &PathBuf,
+    supported_games: &[GameInfo],
+    game: &str,
+) -> Option<Schema> {
+    match Schema::load(rpfm_path, supported_games, game) {
+        Ok((schema, warning)) => {
+            if let Some(warning) = warning { show_dialog(parent_window, false, warning); }
+            Some(schema)
+        }
+        Err(_) => None,
+    }
+}
+
+/// This function loads the Dependency Database of the game selected, if it isn't already loaded. We don't load it
+/// until something needs it, as it's big and most of the time the Dependency Cache is enough. If there is no
+/// Dependency PackFile for the game selected, the Dependency Database stays as None.
+pub fn load_dependency_database(
+    dependency_database: &Rc<RefCell<Option<Vec<PackedFile>>>>,
+    game_selected: &GameSelected,
+) {
+    if dependency_database.borrow().is_none() {
+        *dependency_database.borrow_mut() = packfile::open_packfile(game_selected.game_dependency_packfile_path.to_path_buf()).ok().map(|x| x.data.packed_files);
+    }
+}
+
 /// This function shows a message in the Statusbar. For notification of common errors and low
 /// importance stuff. It requires:
 /// - status_bar: a reference to the `Statusbar` where to show the message.
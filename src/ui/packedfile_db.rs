@@ -6,8 +6,16 @@ extern crate glib;
 extern crate failure;
 
 use std::cell::RefCell;
+use std::collections::HashSet;
 use std::rc::Rc;
 use packedfile::db::schemas::*;
+use packedfile::db::references::get_reference_data;
+use packedfile::db::query::Expression;
+use packedfile::db::validation::validate_edited_value;
+use packedfile::db::key_rename::*;
+use packedfile::db::inbound_references::*;
+use packedfile::db::bulk_edit::BulkEdit;
+use packedfile::db::dependency_cache::DependencyCache;
 use packfile::packfile::PackedFile;
 use settings::*;
 use common::coding_helpers;
@@ -19,11 +27,13 @@ use gtk::{
     TreeView, ListStore, ScrolledWindow, Button, Orientation, TextView, Label, Entry, FileChooserNative,
     CellRendererText, TreeViewColumn, CellRendererToggle, Type, Frame, CellRendererCombo, CssProvider,
     TextTag, Popover, ModelButton, Paned, Switch, Separator, Grid, ButtonBox, ButtonBoxStyle, FileChooserAction,
-    StyleContext, TreeViewGridLines, TreeViewColumnSizing, EntryIconPosition, TreeIter
+    StyleContext, TreeViewGridLines, TreeViewColumnSizing, EntryIconPosition, TreeIter, TreeModel,
+    TreeModelFilter, TreePath, ApplicationWindow
 };
 
 use super::*;
 use packedfile::SerializableToTSV;
+use packedfile::serializers::SerializableWithHeaders;
 use AppUI;
 use packfile::update_packed_file_data_db;
 
@@ -42,6 +52,9 @@ pub struct PackedFileDBTreeView {
     pub list_cell_reference: Vec<CellRendererCombo>,
     pub context_menu: Popover,
     pub add_rows_entry: Entry,
+    pub rename_key_entry: Entry,
+    pub filter_entry: Entry,
+    pub filter_model: Rc<RefCell<Option<TreeModelFilter>>>,
 }
 
 /// Struct PackedFileDBDecoder: contains all the stuff we need to return to be able to decode DB PackedFiles.
@@ -84,6 +97,7 @@ pub struct PackedFileDBDecoder {
     pub fields_tree_view_cell_combo: CellRendererCombo,
     pub fields_tree_view_cell_combo_list_store: ListStore,
     pub fields_tree_view_cell_string: Vec<CellRendererText>,
+    pub fields_tree_view_cell_constraints: CellRendererText,
     pub delete_all_fields_button: Button,
     pub decoder_grid_scroll: ScrolledWindow,
     pub context_menu: Popover,
@@ -100,9 +114,11 @@ pub fn create_db_view(
     is_packedfile_opened: &Rc<RefCell<bool>>,
     schema: &Rc<RefCell<Option<Schema>>>,
     dependency_database: &Rc<RefCell<Option<Vec<PackedFile>>>>,
+    dependency_cache: &Rc<RefCell<Option<DependencyCache>>>,
     game_selected: &Rc<RefCell<GameSelected>>,
     supported_games: &Rc<RefCell<Vec<GameInfo>>>,
     settings: &Settings,
+    key_rename_undo: &Rc<RefCell<Option<KeyRenameUndo>>>,
 ) -> Result<(), Error> {
 
     // Get the data of the PackedFile and his name.
@@ -147,6 +163,7 @@ pub fn create_db_view(
         application,
         app_ui,
         schema,
+        dependency_cache,
         rpfm_path,
         game_selected,
         supported_games => move |_,_| {
@@ -169,6 +186,7 @@ pub fn create_db_view(
                 table_name.to_owned(),
                 packed_file_encoded.to_vec(),
                 &schema,
+                &dependency_cache,
             ) {
                 show_dialog(&app_ui.window, false, error.cause())
             };
@@ -184,6 +202,11 @@ pub fn create_db_view(
             // Get the decoded PackedFile in a `Rc<RefCell<>>` so we can pass it to the closures.
             let packed_file_decoded = Rc::new(RefCell::new(packed_file_decoded));
 
+            // Make sure the cache of the dependency database is loaded and up to date before using it for the references.
+            if let Some(ref schema) = *schema.borrow() {
+                update_dependency_cache(rpfm_path, dependency_database, dependency_cache, &game_selected.borrow(), schema);
+            }
+
             // Try to create the `TreeView`.
             if let Err(error) = PackedFileDBTreeView::create_tree_view(
                 &application,
@@ -191,9 +214,12 @@ pub fn create_db_view(
                 &pack_file,
                 &packed_file_decoded,
                 packed_file_decoded_index,
-                &dependency_database.borrow(),
+                dependency_database,
+                dependency_cache,
+                game_selected,
                 &schema.borrow().clone().unwrap(),
                 &settings,
+                key_rename_undo,
             ) { return Err(error) };
 
             // Return success.
@@ -205,6 +231,33 @@ pub fn create_db_view(
     }
 }
 
+/// This function loads the cache of the dependency database of the game selected, or builds it if there is none
+/// or it's outdated. The dependency database is only loaded if the cache has to be built. If there is no dependency
+/// database, the cache is cleared. Changes in the schema are not checked here, so remember to clear the cache every
+/// time the schema changes.
+pub fn update_dependency_cache(
+    rpfm_path: &PathBuf,
+    dependency_database: &Rc<RefCell<Option<Vec<PackedFile>>>>,
+    dependency_cache: &Rc<RefCell<Option<DependencyCache>>>,
+    game_selected: &GameSelected,
+    schema: &Schema,
+) {
+    let is_up_to_date = match *dependency_cache.borrow() {
+        Some(ref cache) => cache.is_up_to_date(&game_selected.game, &game_selected.game_dependency_packfile_path),
+        None => false,
+    };
+
+    if !is_up_to_date {
+        let mut cache = DependencyCache::load_if_valid(rpfm_path, &game_selected.game, &game_selected.game_dependency_packfile_path, schema);
+        if cache.is_none() {
+            load_dependency_database(dependency_database, game_selected);
+            if let Some(ref dependency_database) = *dependency_database.borrow() {
+                cache = DependencyCache::build(rpfm_path, &game_selected.game, &game_selected.game_dependency_packfile_path, dependency_database, schema);
+            }
+        }
+        *dependency_cache.borrow_mut() = cache;
+    }
+}
 
 /// Implementation of `PackedFileDBTreeView`.
 impl PackedFileDBTreeView{
@@ -217,9 +270,12 @@ impl PackedFileDBTreeView{
         pack_file: &Rc<RefCell<PackFile>>,
         packed_file_decoded: &Rc<RefCell<DB>>,
         packed_file_decoded_index: &usize,
-        dependency_database: &Option<Vec<PackedFile>>,
+        dependency_database: &Rc<RefCell<Option<Vec<PackedFile>>>>,
+        dependency_cache: &Rc<RefCell<Option<DependencyCache>>>,
+        game_selected: &Rc<RefCell<GameSelected>>,
         master_schema: &Schema,
         settings: &Settings,
+        key_rename_undo: &Rc<RefCell<Option<KeyRenameUndo>>>,
     ) -> Result<(), Error> {
 
         // Here we define the `Accept` response for GTK, as it seems Restson causes it to fail to compile
@@ -435,43 +491,11 @@ impl PackedFileDBTreeView{
                             let mut origin_combo_data = vec![];
 
                             // If we have a database PackFile to check for refs...
-                            if let Some(ref dependency_database) = *dependency_database {
-
-                                // For each table in the database...
-                                for table in dependency_database {
-
-                                    // If it's actually a table.
-                                    if table.path.len() >= 3 {
-
-                                        // If it's our original table...
-                                        if table.path[0] == "db" && table.path[1] == format!("{}_tables", origin.0) {
-
-                                            // If we could decode it...
-                                            if let Ok(db) = DB::read(&table.data, &*table.path[1], master_schema) {
-
-                                                // For each column in our original table...
-                                                for (index, original_field) in db.data.table_definition.fields.iter().enumerate() {
+                            if let Some(ref dependency_cache) = *dependency_cache.borrow() {
 
-                                                    // If it's our column...
-                                                    if original_field.field_name == origin.1 {
-
-                                                        // For each row...
-                                                        for row in &db.data.entries {
-
-                                                            // Check what's in our column in that row...
-                                                            match row[index + 1] {
-
-                                                                // And if it's a `String`, get his value.
-                                                                DecodedData::StringU8(ref data) | DecodedData::StringU16(ref data) => origin_combo_data.push(data.to_owned()),
-                                                                _ => {},
-                                                            };
-
-                                                        }
-                                                    }
-                                                }
-                                            }
-                                        }
-                                    }
+                                // Get the values of our original column from the cache of the database...
+                                if let Some(values) = dependency_cache.get_values(&format!("{}_tables", origin.0), &origin.1) {
+                                    origin_combo_data.extend_from_slice(values);
                                 }
 
                                 // For each table in our mod...
@@ -626,43 +650,11 @@ impl PackedFileDBTreeView{
                             let mut origin_combo_data = vec![];
 
                             // If we have a database PackFile to check for refs...
-                            if let Some(ref dependency_database) = *dependency_database {
+                            if let Some(ref dependency_cache) = *dependency_cache.borrow() {
 
-                                // For each table in the database...
-                                for table in dependency_database {
-
-                                    // If it's actually a table.
-                                    if table.path.len() >= 3 {
-
-                                        // If it's our original table...
-                                        if table.path[0] == "db" && table.path[1] == format!("{}_tables", origin.0) {
-
-                                            // If we could decode it...
-                                            if let Ok(db) = DB::read(&table.data, &*table.path[1], master_schema) {
-
-                                                // For each column in our original table...
-                                                for (index, original_field) in db.data.table_definition.fields.iter().enumerate() {
-
-                                                    // If it's our column...
-                                                    if original_field.field_name == origin.1 {
-
-                                                        // For each row...
-                                                        for row in &db.data.entries {
-
-                                                            // Check what's in our column in that row...
-                                                            match row[index + 1] {
-
-                                                                // And if it's a `String`, get his value.
-                                                                DecodedData::OptionalStringU8(ref data) | DecodedData::OptionalStringU16(ref data) => origin_combo_data.push(data.to_owned()),
-                                                                _ => {},
-                                                            };
-
-                                                        }
-                                                    }
-                                                }
-                                            }
-                                        }
-                                    }
+                                // Get the values of our original column from the cache of the database...
+                                if let Some(values) = dependency_cache.get_values(&format!("{}_tables", origin.0), &origin.1) {
+                                    origin_combo_data.extend_from_slice(values);
                                 }
 
                                 // For each table in our mod...
@@ -808,6 +800,18 @@ impl PackedFileDBTreeView{
             }
         }
 
+        // Put the description and the constraints of each field as tooltip of his column's header. Here the
+        // columns are still in the same order as the fields, after the index column.
+        for (column, field) in tree_view.get_columns().iter().skip(1).zip(table_definition.fields.iter()) {
+            let tooltip = field.get_notes();
+            if !tooltip.is_empty() {
+                let header = Label::new(Some(&*clean_column_names(&field.field_name)));
+                header.set_tooltip_text(Some(&*tooltip));
+                header.show();
+                column.set_widget(Some(&header));
+            }
+        }
+
         // // We create the cell and the column that's going to serve as "filler" column at the end.
         let cell_fill = CellRendererText::new();
         let column_fill = TreeViewColumn::new();
@@ -934,6 +938,55 @@ impl PackedFileDBTreeView{
         export_tsv_button.set_property_text(Some("Export to TSV"));
         export_tsv_button.set_action_name("app.packedfile_db_export_tsv");
 
+        // Create the "Import from JSON/CSV" button.
+        let import_headered_button = ModelButton::new();
+        import_headered_button.set_property_text(Some("Import from JSON/CSV"));
+        import_headered_button.set_action_name("app.packedfile_db_import_headered");
+
+        // Create the "Export to JSON/CSV" button.
+        let export_headered_button = ModelButton::new();
+        export_headered_button.set_property_text(Some("Export to JSON/CSV"));
+        export_headered_button.set_action_name("app.packedfile_db_export_headered");
+
+        // Create the separator between the "Rename key" buttons and the rest.
+        let separator_3 = Separator::new(Orientation::Vertical);
+
+        // Create the "Rename key" button.
+        let rename_key_button = ModelButton::new();
+        rename_key_button.set_property_text(Some("Rename key to:"));
+        rename_key_button.set_action_name("app.packedfile_db_rename_key");
+
+        // Create the entry to specify the new key.
+        let rename_key_entry = Entry::new();
+        rename_key_entry.set_width_chars(16);
+        rename_key_entry.set_has_frame(false);
+
+        // Create the "Undo key rename" button.
+        let undo_key_rename_button = ModelButton::new();
+        undo_key_rename_button.set_property_text(Some("Undo key rename"));
+        undo_key_rename_button.set_action_name("app.packedfile_db_undo_key_rename");
+
+        // Create the separator between the "References" buttons and the rest.
+        let separator_4 = Separator::new(Orientation::Vertical);
+
+        // Create the "Find references" button.
+        let find_references_button = ModelButton::new();
+        find_references_button.set_property_text(Some("Find references to rows"));
+        find_references_button.set_action_name("app.packedfile_db_find_references");
+
+        // Create the "Delete with dependent rows" button.
+        let delete_rows_cascade_button = ModelButton::new();
+        delete_rows_cascade_button.set_property_text(Some("Delete rows with dependent rows"));
+        delete_rows_cascade_button.set_action_name("app.packedfile_db_delete_rows_cascade");
+
+        // Create the separator between the "Bulk Edit" button and the rest.
+        let separator_5 = Separator::new(Orientation::Vertical);
+
+        // Create the "Bulk Edit" button.
+        let bulk_edit_button = ModelButton::new();
+        bulk_edit_button.set_property_text(Some("Bulk edit column..."));
+        bulk_edit_button.set_action_name("app.packedfile_db_bulk_edit");
+
         // Right-click menu actions.
         let add_rows = SimpleAction::new("packedfile_db_add_rows", None);
         let delete_rows = SimpleAction::new("packedfile_db_delete_rows", None);
@@ -946,6 +999,13 @@ impl PackedFileDBTreeView{
         let paste_columns = SimpleAction::new("packedfile_db_paste_columns", None);
         let import_tsv = SimpleAction::new("packedfile_db_import_tsv", None);
         let export_tsv = SimpleAction::new("packedfile_db_export_tsv", None);
+        let import_headered = SimpleAction::new("packedfile_db_import_headered", None);
+        let export_headered = SimpleAction::new("packedfile_db_export_headered", None);
+        let rename_key = SimpleAction::new("packedfile_db_rename_key", None);
+        let undo_key_rename = SimpleAction::new("packedfile_db_undo_key_rename", None);
+        let find_references = SimpleAction::new("packedfile_db_find_references", None);
+        let delete_rows_cascade = SimpleAction::new("packedfile_db_delete_rows_cascade", None);
+        let bulk_edit = SimpleAction::new("packedfile_db_bulk_edit", None);
 
         application.add_action(&add_rows);
         application.add_action(&delete_rows);
@@ -958,6 +1018,13 @@ impl PackedFileDBTreeView{
         application.add_action(&paste_columns);
         application.add_action(&import_tsv);
         application.add_action(&export_tsv);
+        application.add_action(&import_headered);
+        application.add_action(&export_headered);
+        application.add_action(&rename_key);
+        application.add_action(&undo_key_rename);
+        application.add_action(&find_references);
+        application.add_action(&delete_rows_cascade);
+        application.add_action(&bulk_edit);
 
         // Accels for popovers need to be specified here. Don't know why, but otherwise they do not work.
         application.set_accels_for_action("app.packedfile_db_add_rows", &["<Primary><Shift>a"]);
@@ -981,6 +1048,10 @@ impl PackedFileDBTreeView{
         paste_cell.set_enabled(false);
         paste_rows.set_enabled(true);
         paste_columns.set_enabled(true);
+        rename_key.set_enabled(false);
+        undo_key_rename.set_enabled(key_rename_undo.borrow().is_some());
+        find_references.set_enabled(false);
+        delete_rows_cascade.set_enabled(false);
 
         // Attach all the stuff to the Context Menu `Grid`.
         context_menu_grid.attach(&add_rows_button, 0, 0, 1, 1);
@@ -997,6 +1068,17 @@ impl PackedFileDBTreeView{
         context_menu_grid.attach(&separator_2, 0, 10, 2, 1);
         context_menu_grid.attach(&import_tsv_button, 0, 11, 2, 1);
         context_menu_grid.attach(&export_tsv_button, 0, 12, 2, 1);
+        context_menu_grid.attach(&import_headered_button, 0, 13, 2, 1);
+        context_menu_grid.attach(&export_headered_button, 0, 14, 2, 1);
+        context_menu_grid.attach(&separator_3, 0, 15, 2, 1);
+        context_menu_grid.attach(&rename_key_button, 0, 16, 1, 1);
+        context_menu_grid.attach(&rename_key_entry, 1, 16, 1, 1);
+        context_menu_grid.attach(&undo_key_rename_button, 0, 17, 2, 1);
+        context_menu_grid.attach(&separator_4, 0, 18, 2, 1);
+        context_menu_grid.attach(&find_references_button, 0, 19, 2, 1);
+        context_menu_grid.attach(&delete_rows_cascade_button, 0, 20, 2, 1);
+        context_menu_grid.attach(&separator_5, 0, 21, 2, 1);
+        context_menu_grid.attach(&bulk_edit_button, 0, 22, 2, 1);
 
         // Add the `Grid` to the Context Menu and show it.
         context_menu.add(&context_menu_grid);
@@ -1007,9 +1089,16 @@ impl PackedFileDBTreeView{
         packed_file_data_scroll.set_hexpand(true);
         packed_file_data_scroll.set_vexpand(true);
 
-        // Add the `TreeView` to the `ScrolledWindow`, the `ScrolledWindow` to the main `Grid`, and show it.
+        // Create the `Entry` to filter the rows of the table.
+        let filter_entry = Entry::new();
+        filter_entry.set_hexpand(true);
+        filter_entry.set_placeholder_text("Filter rows (Enter to apply), like: category = \"inf_melee\" and morale > 50");
+        filter_entry.set_icon_from_icon_name(EntryIconPosition::Primary, "edit-find");
+
+        // Add the `TreeView` to the `ScrolledWindow`, the `ScrolledWindow` and the filter to the main `Grid`, and show them.
         packed_file_data_scroll.add(&tree_view);
         app_ui.packed_file_data_display.attach(&packed_file_data_scroll, 0, 1, 1, 1);
+        app_ui.packed_file_data_display.attach(&filter_entry, 0, 2, 1, 1);
         app_ui.packed_file_data_display.show_all();
 
         // Hide the Context Menu by default.
@@ -1028,6 +1117,9 @@ impl PackedFileDBTreeView{
             list_cell_reference,
             context_menu,
             add_rows_entry,
+            rename_key_entry,
+            filter_entry,
+            filter_model: Rc::new(RefCell::new(None)),
         };
 
         // Try to load the data from the table to the `TreeView`.
@@ -1038,8 +1130,81 @@ impl PackedFileDBTreeView{
             return Err(error);
         }
 
+        // If we have a dependency database, we highlight the cells referencing values that don't exist,
+        // so the user can see them at first sight. The values of the database come from his cache.
+        if let Some(ref dependency_cache) = *dependency_cache.borrow() {
+            let references = table_definition.fields.iter().filter_map(|x| x.field_is_reference.clone()).collect::<Vec<(String, String)>>();
+            let mut reference_data = get_reference_data(&references, &pack_file.borrow().data.packed_files, &[], master_schema);
+            for reference in &references {
+                let table = format!("{}_tables", reference.0);
+                if let Some(values) = dependency_cache.get_values(&table, &reference.1) {
+                    reference_data.entry((table, reference.1.to_owned())).or_insert_with(HashSet::new).extend(values.iter().cloned());
+                }
+            }
+
+            // For each column with a reference we have data for...
+            for (index, field) in table_definition.fields.iter().enumerate() {
+                if let Some(ref reference) = field.field_is_reference {
+                    if let Some(values) = reference_data.get(&(format!("{}_tables", reference.0), reference.1.to_owned())) {
+                        // We search the column by his ID, as the key columns are not in their original position.
+                        if let Some(column) = table.tree_view.get_columns().into_iter().find(|x| x.get_sort_column_id() == (index + 1) as i32) {
+                            if let Some(cell) = column.get_cells().first() {
+
+                                // Every time the cell is drawn, we check his current value, so the highlight follows the edits.
+                                let values = values.clone();
+                                let field_type = field.field_type.clone();
+                                column.set_cell_data_func(cell, Some(Box::new(move |_, cell, model, iter| {
+                                    let value = get_cell_text(model, iter, (index + 1) as i32, &field_type);
+
+                                    if !value.is_empty() && !values.contains(&value) { cell.set_property_cell_background(Some("#B22222")); }
+                                    else { cell.set_property_cell_background(None); }
+                                })));
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
         // Events for the DB Table.
 
+        // When we hit "Enter" in the filter, we filter the rows of the table with the expression in it.
+        table.filter_entry.connect_activate(clone!(
+            app_ui,
+            table_definition,
+            packed_file_decoded,
+            table => move |filter_entry| {
+                let text = filter_entry.get_buffer().get_text();
+
+                // If the filter is empty, we go back to the full table.
+                if text.trim().is_empty() { table.set_filter(None); }
+
+                else {
+
+                    // The columns can be written alone or with the name of the table before them, like in the queries.
+                    let table_name = packed_file_decoded.borrow().db_type.trim_right_matches("_tables").to_owned();
+                    let columns = table_definition.fields.iter().map(|x| format!("{}.{}", table_name, x.field_name)).collect::<Vec<String>>();
+
+                    // An expression with non-existant columns fails with any row, so we check it with an empty one.
+                    match Expression::parse(&text).and_then(|expression| expression.evaluate(&columns, &vec![String::new(); columns.len()]).map(|_| expression)) {
+                        Ok(expression) => {
+                            let filter_model = TreeModelFilter::new(&table.list_store, None);
+                            filter_model.set_visible_func(clone!(
+                                table_definition => move |model, iter| {
+                                    let row = table_definition.fields.iter().enumerate().map(|(index, field)| get_cell_text(model, iter, (index + 1) as i32, &field.field_type)).collect::<Vec<String>>();
+                                    expression.evaluate(&columns, &row).unwrap_or(false)
+                                }
+                            ));
+                            table.set_filter(Some(filter_model));
+                        }
+
+                        // If the filter is not valid, we keep the table as it is and tell the user why.
+                        Err(error) => show_dialog(&app_ui.window, false, error.cause()),
+                    }
+                }
+            }
+        ));
+
         // When a tooltip gets triggered...
         table.tree_view.connect_query_tooltip(clone!(
             table_definition => move |tree_view, x, y,_, tooltip| {
@@ -1163,11 +1328,15 @@ impl PackedFileDBTreeView{
             // We we change the selection, we enable or disable the different actions of the Contextual Menu.
             table.tree_view.connect_cursor_changed(clone!(
                 app_ui,
+                table_definition,
+                rename_key,
                 copy_cell,
                 copy_rows,
                 copy_columns,
                 clone_rows,
                 paste_cell,
+                find_references,
+                delete_rows_cascade,
                 delete_rows => move |tree_view| {
 
                     // If we have something selected...
@@ -1179,6 +1348,8 @@ impl PackedFileDBTreeView{
                         copy_columns.set_enabled(true);
                         clone_rows.set_enabled(true);
                         delete_rows.set_enabled(true);
+                        find_references.set_enabled(true);
+                        delete_rows_cascade.set_enabled(true);
                     }
 
                     // Otherwise, disable them.
@@ -1188,8 +1359,17 @@ impl PackedFileDBTreeView{
                         copy_columns.set_enabled(false);
                         clone_rows.set_enabled(false);
                         delete_rows.set_enabled(false);
+                        find_references.set_enabled(false);
+                        delete_rows_cascade.set_enabled(false);
                     }
 
+                    // We can only rename keys, and only one at a time.
+                    let is_key_selected = tree_view.get_selection().count_selected_rows() == 1 && match tree_view.get_cursor().1 {
+                        Some(column) => column.get_sort_column_id() > 0 && table_definition.fields[column.get_sort_column_id() as usize - 1].field_is_key,
+                        None => false,
+                    };
+                    rename_key.set_enabled(is_key_selected);
+
                     // If we got text in the `Clipboard`...
                     if app_ui.clipboard.wait_for_text().is_some() {
 
@@ -1248,16 +1428,19 @@ impl PackedFileDBTreeView{
                                     let new_row = table.list_store.append();
                                     table.list_store.set_value(&new_row, 0, &"New".to_value());
 
-                                    // For each column we have...
-                                    for column in 1..(table_definition.fields.len() + 1) {
-
-                                        match table_definition.fields[column - 1].field_type {
-                                            FieldType::Boolean => table.list_store.set_value(&new_row, column as u32, &false.to_value()),
-                                            FieldType::Float => table.list_store.set_value(&new_row, column as u32, &0.0f32.to_string().to_value()),
-                                            FieldType::Integer | FieldType::LongInteger => table.list_store.set_value(&new_row, column as u32, &0.to_value()),
-                                            FieldType::StringU8 | FieldType::StringU16 | FieldType::OptionalStringU8 | FieldType::OptionalStringU16 => {
-                                                table.list_store.set_value(&new_row, column as u32, &String::new().to_value());
-                                            }
+                                    // For each column we have, fill it with the default value of his field.
+                                    for (column, field) in table_definition.fields.iter().enumerate() {
+                                        let column = (column + 1) as u32;
+                                        match DecodedData::default_for_field(field) {
+                                            DecodedData::Boolean(data) => table.list_store.set_value(&new_row, column, &data.to_value()),
+                                            DecodedData::Float(data) => table.list_store.set_value(&new_row, column, &format!("{}", data).to_value()),
+                                            DecodedData::Integer(data) => table.list_store.set_value(&new_row, column, &data.to_value()),
+                                            DecodedData::LongInteger(data) => table.list_store.set_value(&new_row, column, &data.to_value()),
+                                            DecodedData::Index(data) |
+                                            DecodedData::StringU8(data) |
+                                            DecodedData::StringU16(data) |
+                                            DecodedData::OptionalStringU8(data) |
+                                            DecodedData::OptionalStringU16(data) => table.list_store.set_value(&new_row, column, &data.to_value()),
                                         }
                                     }
                                 }
@@ -1296,7 +1479,7 @@ impl PackedFileDBTreeView{
                             for row in (0..selected_rows.len()).rev() {
 
                                 // Remove it.
-                                table.list_store.remove(&table.list_store.get_iter(&selected_rows[row]).unwrap());
+                                table.list_store.remove(&table.get_iter_from_view_path(&selected_rows[row]).unwrap());
                             }
 
                             // Try to save the new data from the `TreeView`.
@@ -1352,7 +1535,7 @@ impl PackedFileDBTreeView{
                             if let Some(column) = focused_cell.1 {
 
                                 // Get his `TreeIter`.
-                                let row = table.list_store.get_iter(&tree_path).unwrap();
+                                let row = table.get_iter_from_view_path(&tree_path).unwrap();
 
                                 // Get his column ID.
                                 let column = column.get_sort_column_id();
@@ -1428,7 +1611,7 @@ impl PackedFileDBTreeView{
                                 if let Some(data) = app_ui.clipboard.wait_for_text() {
 
                                     // Get his `TreeIter`.
-                                    let row = table.list_store.get_iter(&tree_path).unwrap();
+                                    let row = table.get_iter_from_view_path(&tree_path).unwrap();
 
                                     // Get his column ID.
                                     let column = column.get_sort_column_id() as u32;
@@ -1532,7 +1715,7 @@ impl PackedFileDBTreeView{
                             for tree_path in &selected_rows {
 
                                 // We get the old `TreeIter` and create a new one.
-                                let old_row = table.list_store.get_iter(tree_path).unwrap();
+                                let old_row = table.get_iter_from_view_path(tree_path).unwrap();
                                 let new_row = table.list_store.append();
 
                                 // For each column...
@@ -1593,7 +1776,7 @@ impl PackedFileDBTreeView{
                         let selected_rows = table.tree_view.get_selection().get_selected_rows().0;
 
                         // Get the list of `TreeIter`s we want to copy.
-                        let tree_iter_list = selected_rows.iter().map(|row| table.list_store.get_iter(row).unwrap()).collect::<Vec<TreeIter>>();
+                        let tree_iter_list = selected_rows.iter().map(|row| table.get_iter_from_view_path(row).unwrap()).collect::<Vec<TreeIter>>();
 
                         // Create the `String` that will copy the row that will bring that shit of TLJ down.
                         let mut copy_string = String::new();
@@ -1656,6 +1839,11 @@ impl PackedFileDBTreeView{
                     // Hide the context menu.
                     table.context_menu.popdown();
 
+                    // Pasting goes through consecutive rows, including the hidden ones, so we don't allow it with a filter active.
+                    if table.filter_model.borrow().is_some() {
+                        return show_dialog(&app_ui.window, false, "You cannot paste while the table is filtered. Clear the filter and try again.");
+                    }
+
                     // We only do something in case the focus is in the TreeView. This should stop problems with
                     // the accels working everywhere.
                     if table.tree_view.has_focus() {
@@ -1684,7 +1872,7 @@ impl PackedFileDBTreeView{
 
                                 // If there is at least one line selected, use it as "base" to paste.
                                 let mut tree_iter = if !selected_row.is_empty() {
-                                    table.list_store.get_iter(&selected_row[0]).unwrap()
+                                    table.get_iter_from_view_path(&selected_row[0]).unwrap()
                                 }
 
                                 // Otherwise, append a new `TreeIter` to the `TreeView`, and use it.
@@ -1791,7 +1979,7 @@ impl PackedFileDBTreeView{
                                 column_number += 1;
 
                                 // Get the list of `TreeIter`s we want to copy.
-                                let tree_iter_list = selected_rows.iter().map(|row| table.list_store.get_iter(row).unwrap()).collect::<Vec<TreeIter>>();
+                                let tree_iter_list = selected_rows.iter().map(|row| table.get_iter_from_view_path(row).unwrap()).collect::<Vec<TreeIter>>();
 
                                 // Create the `String` that will copy the row that will bring that shit of TLJ down.
                                 let mut copy_string = String::new();
@@ -1849,6 +2037,11 @@ impl PackedFileDBTreeView{
                     // Hide the context menu.
                     table.context_menu.popdown();
 
+                    // Pasting goes through consecutive rows, including the hidden ones, so we don't allow it with a filter active.
+                    if table.filter_model.borrow().is_some() {
+                        return show_dialog(&app_ui.window, false, "You cannot paste while the table is filtered. Clear the filter and try again.");
+                    }
+
                     // We only do something in case the focus is in the TreeView. This should stop problems with
                     // the accels working everywhere.
                     if table.tree_view.has_focus() {
@@ -1888,9 +2081,9 @@ impl PackedFileDBTreeView{
 
                                             // Get the selected row.
                                             let tree_iter = if !selected_rows.is_empty() {
-                                                table.list_store.get_iter(&selected_rows[0]).unwrap()
+                                                table.get_iter_from_view_path(&selected_rows[0]).unwrap()
                                             }
-                                            else { table.list_store.get_iter(&tree_path).unwrap() };
+                                            else { table.get_iter_from_view_path(&tree_path).unwrap() };
 
                                             // For each line to paste...
                                             for field in &fields_data {
@@ -2031,6 +2224,7 @@ impl PackedFileDBTreeView{
             // When we hit the "Export to TSV" button.
             export_tsv.connect_activate(clone!(
                 app_ui,
+                tree_path,
                 packed_file_decoded,
                 table => move |_,_| {
 
@@ -2070,6 +2264,409 @@ impl PackedFileDBTreeView{
                     }
                 }
             ));
+
+            // When we hit the "Import from JSON/CSV" button.
+            import_headered.connect_activate(clone!(
+                app_ui,
+                pack_file,
+                packed_file_decoded,
+                packed_file_decoded_index,
+                table => move |_,_| {
+
+                    // We hide the context menu first.
+                    table.context_menu.popdown();
+
+                    // We only do something in case the focus is in the TreeView. This should stop problems with
+                    // the accels working everywhere.
+                    if table.tree_view.has_focus() {
+
+                        // Create the `FileChooser`, with a filter for each format.
+                        let file_chooser = FileChooserNative::new(
+                            "Select JSON/CSV/TSV File to Import...",
+                            &app_ui.window,
+                            FileChooserAction::Open,
+                            "Import",
+                            "Cancel"
+                        );
+                        file_chooser_filter_packfile(&file_chooser, "*.json");
+                        file_chooser_filter_packfile(&file_chooser, "*.csv");
+                        file_chooser_filter_packfile(&file_chooser, "*.tsv");
+
+                        // If we have selected a file to import...
+                        if file_chooser.run() == gtk_response_accept {
+
+                            // Same as with the TSV, we keep a copy of the table, so we can restore it if something fails.
+                            let packed_file_data_copy = packed_file_decoded.borrow().data.clone();
+
+                            // The columns are mapped by name, so here we get what happened with them.
+                            let report = match packed_file_decoded.borrow_mut().import_with_headers(&file_chooser.get_filename().unwrap()) {
+                                Ok(report) => report,
+                                Err(error) => return show_dialog(&app_ui.window, false, error.cause()),
+                            };
+
+                            // Try to load the data into the `TreeView` and save it to the PackFile.
+                            let result = PackedFileDBTreeView::load_data_to_tree_view(&packed_file_decoded.borrow().data, &table.list_store)
+                                .and_then(|_| update_packed_file_data_db(&*packed_file_decoded.borrow(), &mut *pack_file.borrow_mut(), packed_file_decoded_index));
+
+                            match result {
+                                Ok(_) => {
+                                    set_modified(true, &app_ui.window, &mut *pack_file.borrow_mut());
+                                    show_dialog(&app_ui.window, true, report);
+                                }
+
+                                // If the import broke somewhere along the way, restore the old copy and report the error.
+                                Err(error) => {
+                                    packed_file_decoded.borrow_mut().data = packed_file_data_copy;
+                                    if let Err(error) = PackedFileDBTreeView::load_data_to_tree_view(&packed_file_decoded.borrow().data, &table.list_store) {
+                                        show_dialog(&app_ui.window, false, error.cause());
+                                    }
+                                    show_dialog(&app_ui.window, false, error.cause());
+                                }
+                            }
+                        }
+                    }
+                }
+            ));
+
+            // When we hit the "Export to JSON/CSV" button.
+            export_headered.connect_activate(clone!(
+                app_ui,
+                tree_path,
+                packed_file_decoded,
+                table => move |_,_| {
+
+                    // We hide the context menu first.
+                    table.context_menu.popdown();
+
+                    // We only do something in case the focus is in the TreeView. This should stop problems with
+                    // the accels working everywhere.
+                    if table.tree_view.has_focus() {
+
+                        let file_chooser = FileChooserNative::new(
+                            "Export JSON/CSV/TSV File...",
+                            &app_ui.window,
+                            FileChooserAction::Save,
+                            "Save",
+                            "Cancel"
+                        );
+
+                        // We want to ask before overwriting files. Just in case. Otherwise, there can be an accident.
+                        file_chooser.set_do_overwrite_confirmation(true);
+
+                        // The format depends on the extension. By default, we use JSON.
+                        file_chooser.set_current_name(format!("{}-{}.json", &tree_path[1], &tree_path[2]));
+
+                        // If we hit "Save"...
+                        if file_chooser.run() == gtk_response_accept {
+                            match packed_file_decoded.borrow().export_with_headers(&file_chooser.get_filename().unwrap()) {
+                                Ok(result) => show_dialog(&app_ui.window, true, result),
+                                Err(error) => show_dialog(&app_ui.window, false, error.cause()),
+                            }
+                        }
+                    }
+                }
+            ));
+
+            // When we hit the "Rename key" button.
+            rename_key.connect_activate(clone!(
+                app_ui,
+                table_definition,
+                pack_file,
+                packed_file_decoded,
+                packed_file_decoded_index,
+                master_schema,
+                key_rename_undo,
+                undo_key_rename,
+                table => move |_,_| {
+
+                    // We hide the context menu first.
+                    table.context_menu.popdown();
+
+                    // We only do something in case the focus is in the TreeView. This should stop problems with
+                    // the accels working everywhere.
+                    if table.tree_view.has_focus() {
+
+                        // Get the focused cell, and check it's a key.
+                        let focused_cell = table.tree_view.get_cursor();
+                        if let (Some(tree_path), Some(column)) = (focused_cell.0, focused_cell.1) {
+                            let column = column.get_sort_column_id();
+                            if column <= 0 || !table_definition.fields[column as usize - 1].field_is_key { return }
+                            let field = &table_definition.fields[column as usize - 1];
+
+                            // Get the current key, the same way we do when copying it.
+                            let row = table.get_iter_from_view_path(&tree_path).unwrap();
+                            let old_key = match field.field_type {
+                                FieldType::Boolean => table.list_store.get_value(&row, column).get::<bool>().unwrap().to_string(),
+                                FieldType::Integer => table.list_store.get_value(&row, column).get::<i32>().unwrap().to_string(),
+                                FieldType::LongInteger => table.list_store.get_value(&row, column).get::<i64>().unwrap().to_string(),
+                                _ => table.list_store.get_value(&row, column).get::<String>().unwrap(),
+                            };
+                            let new_key = table.rename_key_entry.get_buffer().get_text();
+                            let table_name = packed_file_decoded.borrow().db_type.to_owned();
+
+                            // Get everything we have to change and show it to the user before changing anything.
+                            let key_rename = match KeyRename::new(&pack_file.borrow().data.packed_files, &master_schema, &table_name, &field.field_name, &old_key, &new_key) {
+                                Ok(key_rename) => key_rename,
+                                Err(error) => return show_dialog(&app_ui.window, false, error.cause()),
+                            };
+                            show_report(&app_ui.window, "Rename Key", &key_rename);
+                            if key_rename.is_empty() || !show_question(&app_ui.window, format!(
+                                "Do you want to rename \"{}\" to \"{}\"? This will change {} cells and {} Loc keys.\n\nYou can undo it later with \"Undo key rename\".",
+                                old_key,
+                                new_key,
+                                key_rename.cells.len(),
+                                key_rename.loc_keys.len()
+                            )) { return }
+
+                            let result = key_rename.apply(&mut pack_file.borrow_mut(), &master_schema);
+                            match result {
+                                Ok(undo) => {
+                                    *key_rename_undo.borrow_mut() = Some(undo);
+                                    undo_key_rename.set_enabled(true);
+                                    set_modified(true, &app_ui.window, &mut *pack_file.borrow_mut());
+
+                                    // This table has changed too, so we need to reload it.
+                                    if let Err(error) = table.reload_data(&pack_file.borrow(), &packed_file_decoded, packed_file_decoded_index, &master_schema) {
+                                        show_dialog(&app_ui.window, false, error.cause());
+                                    }
+                                    show_message_in_statusbar(&app_ui.status_bar, format!("\"{}\" renamed to \"{}\".", old_key, new_key));
+                                }
+                                Err(error) => show_dialog(&app_ui.window, false, error.cause()),
+                            }
+                        }
+                    }
+                }
+            ));
+
+            // When we hit the "Undo key rename" button.
+            undo_key_rename.connect_activate(clone!(
+                app_ui,
+                pack_file,
+                packed_file_decoded,
+                packed_file_decoded_index,
+                master_schema,
+                key_rename_undo,
+                table => move |undo_key_rename,_| {
+
+                    // We hide the context menu first.
+                    table.context_menu.popdown();
+
+                    let undo = key_rename_undo.borrow().clone();
+                    if let Some(undo) = undo {
+                        if !show_question(&app_ui.window, format!(
+                            "Do you want to undo the rename of \"{}\" to \"{}\"?\n\nAny change done after it to the {} PackedFiles it changed will be lost.",
+                            undo.old_key,
+                            undo.new_key,
+                            undo.packed_files.len()
+                        )) { return }
+
+                        let result = undo.undo(&mut pack_file.borrow_mut());
+                        match result {
+                            Ok(missing_paths) => {
+                                *key_rename_undo.borrow_mut() = None;
+                                undo_key_rename.set_enabled(false);
+                                set_modified(true, &app_ui.window, &mut *pack_file.borrow_mut());
+
+                                // This table may have changed too, so we need to reload it.
+                                if let Err(error) = table.reload_data(&pack_file.borrow(), &packed_file_decoded, packed_file_decoded_index, &master_schema) {
+                                    show_dialog(&app_ui.window, false, error.cause());
+                                }
+
+                                if !missing_paths.is_empty() {
+                                    show_dialog(&app_ui.window, false, format!(
+                                        "The following PackedFiles no longer exist, so they have not been restored:\n{}",
+                                        missing_paths.iter().map(|x| x.join("/")).collect::<Vec<String>>().join("\n")
+                                    ));
+                                }
+                                else { show_message_in_statusbar(&app_ui.status_bar, format!("Rename of \"{}\" to \"{}\" undone.", undo.old_key, undo.new_key)); }
+                            }
+                            Err(error) => show_dialog(&app_ui.window, false, error.cause()),
+                        }
+                    }
+                }
+            ));
+
+            // When we hit the "Find references to rows" button.
+            find_references.connect_activate(clone!(
+                app_ui,
+                table_definition,
+                pack_file,
+                packed_file_decoded,
+                packed_file_decoded_index,
+                dependency_database,
+                game_selected,
+                master_schema,
+                table => move |_,_| {
+
+                    // We hide the context menu first.
+                    table.context_menu.popdown();
+
+                    // We only do something in case the focus is in the TreeView. This should stop problems with
+                    // the accels working everywhere.
+                    if table.tree_view.has_focus() {
+
+                        // This doesn't change anything, so we take the rows from the `TreeView` instead of saving them.
+                        let rows = table.get_selected_rows();
+                        if rows.is_empty() { return }
+                        let entries = match PackedFileDBTreeView::return_data_from_tree_view(&table_definition, &table.list_store) {
+                            Ok(entries) => entries,
+                            Err(error) => return show_dialog(&app_ui.window, false, error.cause()),
+                        };
+
+                        let path = pack_file.borrow().data.packed_files[packed_file_decoded_index].path.to_vec();
+                        load_dependency_database(&dependency_database, &game_selected.borrow());
+                        let index = match *dependency_database.borrow() {
+                            Some(ref dependency_database) => ReferenceIndex::new(&pack_file.borrow().data.packed_files, dependency_database, &master_schema),
+                            None => ReferenceIndex::new(&pack_file.borrow().data.packed_files, &[], &master_schema),
+                        };
+
+                        let mut report = String::new();
+                        for row in &rows {
+                            let references = index.get_references_to_entry(&path[1], &table_definition, &entries[*row]);
+                            report.push_str(&format!("Row {}:\n", row + 1));
+                            if references.is_empty() { report.push_str("  - No rows reference this row.\n"); }
+                            for reference in &references {
+                                report.push_str(&format!("  - {}\n", reference));
+                            }
+                            report.push('\n');
+                        }
+
+                        if !index.undecodeable_tables.is_empty() {
+                            report.push_str("Tables of the PackFile that couldn't be decoded, so any reference in them is not here:\n");
+                            for path in &index.undecodeable_tables {
+                                report.push_str(&format!("  - {}\n", path.join("/")));
+                            }
+                        }
+
+                        show_report(&app_ui.window, "References to Rows", &report);
+                    }
+                }
+            ));
+
+            // When we hit the "Delete rows with dependent rows" button.
+            delete_rows_cascade.connect_activate(clone!(
+                app_ui,
+                table_definition,
+                pack_file,
+                packed_file_decoded,
+                packed_file_decoded_index,
+                dependency_database,
+                game_selected,
+                master_schema,
+                table => move |_,_| {
+
+                    // We hide the context menu first.
+                    table.context_menu.popdown();
+
+                    // We only do something in case the focus is in the TreeView. This should stop problems with
+                    // the accels working everywhere.
+                    if table.tree_view.has_focus() {
+                        let rows = match table.get_selected_rows_synced(&app_ui.window, &table_definition, &pack_file, &packed_file_decoded, packed_file_decoded_index) {
+                            Ok(rows) => rows,
+                            Err(error) => return show_dialog(&app_ui.window, false, error.cause()),
+                        };
+                        if rows.is_empty() { return }
+
+                        // Get everything we have to delete and show it to the user before deleting anything.
+                        let path = pack_file.borrow().data.packed_files[packed_file_decoded_index].path.to_vec();
+                        load_dependency_database(&dependency_database, &game_selected.borrow());
+                        let index = match *dependency_database.borrow() {
+                            Some(ref dependency_database) => ReferenceIndex::new(&pack_file.borrow().data.packed_files, dependency_database, &master_schema),
+                            None => ReferenceIndex::new(&pack_file.borrow().data.packed_files, &[], &master_schema),
+                        };
+                        let cascade_delete = match CascadeDelete::new(&index, &path, &rows) {
+                            Ok(cascade_delete) => cascade_delete,
+                            Err(error) => return show_dialog(&app_ui.window, false, error.cause()),
+                        };
+                        show_report(&app_ui.window, "Delete Rows with Dependent Rows", &cascade_delete);
+                        if !show_question(&app_ui.window, format!(
+                            "Do you want to delete these {} rows and the {} rows depending on them?",
+                            cascade_delete.rows.len(),
+                            cascade_delete.dependent_rows.len()
+                        )) { return }
+
+                        let result = cascade_delete.apply(&mut pack_file.borrow_mut(), &master_schema);
+                        match result {
+                            Ok(_) => {
+                                set_modified(true, &app_ui.window, &mut *pack_file.borrow_mut());
+
+                                // This table has changed, so we need to reload it.
+                                if let Err(error) = table.reload_data(&pack_file.borrow(), &packed_file_decoded, packed_file_decoded_index, &master_schema) {
+                                    show_dialog(&app_ui.window, false, error.cause());
+                                }
+                                show_message_in_statusbar(&app_ui.status_bar, format!("{} rows deleted, with {} dependent rows.", cascade_delete.rows.len(), cascade_delete.dependent_rows.len()));
+                            }
+                            Err(error) => show_dialog(&app_ui.window, false, error.cause()),
+                        }
+                    }
+                }
+            ));
+
+            // When we hit the "Bulk edit column" button.
+            bulk_edit.connect_activate(clone!(
+                app_ui,
+                table_definition,
+                pack_file,
+                packed_file_decoded,
+                packed_file_decoded_index,
+                table => move |_,_| {
+
+                    // We hide the context menu first.
+                    table.context_menu.popdown();
+
+                    // We only do something in case the focus is in the TreeView. This should stop problems with
+                    // the accels working everywhere.
+                    if table.tree_view.has_focus() {
+
+                        // By default, we edit the column of the selected cell, and only the rows of the current filter.
+                        let columns = table_definition.fields.iter().map(|x| x.field_name.to_owned()).collect::<Vec<String>>();
+                        let default_column = match table.tree_view.get_cursor().1 {
+                            Some(column) if column.get_sort_column_id() > 0 => column.get_sort_column_id() as usize - 1,
+                            _ => 0,
+                        };
+                        let default_filter = table.filter_entry.get_buffer().get_text();
+                        let (column, expression, filter) = match show_bulk_edit_dialog(&app_ui.window, &columns, default_column, &default_filter) {
+                            Some(values) => values,
+                            None => return,
+                        };
+
+                        let bulk_edit = match BulkEdit::new(&column, &expression, Some(&filter)) {
+                            Ok(bulk_edit) => bulk_edit,
+                            Err(error) => return show_dialog(&app_ui.window, false, error.cause()),
+                        };
+
+                        // Sorting the `TreeView` changes the order of the rows, so we take them from it, and apply
+                        // the changes to a copy of them, so we can show them to the user before changing anything.
+                        let mut data = packed_file_decoded.borrow().data.clone();
+                        data.entries = match PackedFileDBTreeView::return_data_from_tree_view(&table_definition, &table.list_store) {
+                            Ok(entries) => entries,
+                            Err(error) => return show_dialog(&app_ui.window, false, error.cause()),
+                        };
+                        let db_type = packed_file_decoded.borrow().db_type.to_owned();
+                        let report = match bulk_edit.apply(&db_type, &mut data) {
+                            Ok(report) => report,
+                            Err(error) => return show_dialog(&app_ui.window, false, error.cause()),
+                        };
+
+                        if report.changes.is_empty() {
+                            return show_message_in_statusbar(&app_ui.status_bar, format!("{} rows matched the filter, but none of them changed.", report.matched_rows));
+                        }
+                        show_report(&app_ui.window, "Bulk Edit", &report);
+                        if !show_question(&app_ui.window, format!("Do you want to change these {} cells?", report.changes.len())) { return }
+
+                        packed_file_decoded.borrow_mut().data = data;
+                        if let Err(error) = update_packed_file_data_db(&*packed_file_decoded.borrow(), &mut *pack_file.borrow_mut(), packed_file_decoded_index) {
+                            return show_dialog(&app_ui.window, false, error.cause());
+                        }
+                        if let Err(error) = PackedFileDBTreeView::load_data_to_tree_view(&packed_file_decoded.borrow().data, &table.list_store) {
+                            return show_dialog(&app_ui.window, false, error.cause());
+                        }
+
+                        set_modified(true, &app_ui.window, &mut *pack_file.borrow_mut());
+                        show_message_in_statusbar(&app_ui.status_bar, format!("{} cells of the column \"{}\" changed.", report.changes.len(), report.column));
+                    }
+                }
+            ));
         }
 
         // Things that happen when you edit a cell. All of them in loops, because oops!... or because they are in vectors.
@@ -2086,11 +2683,16 @@ impl PackedFileDBTreeView{
                     table => move |_ ,tree_path , new_text| {
 
                         // If we got a cell...
-                        if let Some(tree_iter) = table.list_store.get_iter(&tree_path) {
+                        if let Some(tree_iter) = table.get_iter_from_view_path(&tree_path) {
 
                             // Get his column.
                             let edited_cell_column = table.tree_view.get_cursor().1.unwrap().get_sort_column_id() as u32;
 
+                            // Check the new value against the constraints of his field before changing it.
+                            if let Some(error) = validate_edited_value(&table_definition.fields[edited_cell_column as usize - 1], &new_text).first() {
+                                return show_dialog(&app_ui.window, false, format!("Invalid value: {}", error));
+                            }
+
                             // Change his value in the `TreeView`.
                             table.list_store.set_value(&tree_iter, edited_cell_column, &new_text.to_value());
 
@@ -2136,11 +2738,16 @@ impl PackedFileDBTreeView{
                     table => move |_ ,tree_path , new_text| {
 
                         // If we got a cell...
-                        if let Some(tree_iter) = table.list_store.get_iter(&tree_path) {
+                        if let Some(tree_iter) = table.get_iter_from_view_path(&tree_path) {
 
                             // Get his column.
                             let edited_cell_column = table.tree_view.get_cursor().1.unwrap().get_sort_column_id() as u32;
 
+                            // Check the new value against the constraints of his field before changing it.
+                            if let Some(error) = validate_edited_value(&table_definition.fields[edited_cell_column as usize - 1], &new_text).first() {
+                                return show_dialog(&app_ui.window, false, format!("Invalid value: {}", error));
+                            }
+
                             // Change his value in the `TreeView`.
                             table.list_store.set_value(&tree_iter, edited_cell_column, &new_text.to_value());
 
@@ -2186,11 +2793,16 @@ impl PackedFileDBTreeView{
                     table => move |_ ,tree_path , new_text|{
 
                         // If we got a cell...
-                        if let Some(tree_iter) = table.list_store.get_iter(&tree_path) {
+                        if let Some(tree_iter) = table.get_iter_from_view_path(&tree_path) {
 
                             // Get his column.
                             let edited_cell_column = table.tree_view.get_cursor().1.unwrap().get_sort_column_id() as u32;
 
+                            // Check the new value against the constraints of his field before changing it.
+                            if let Some(error) = validate_edited_value(&table_definition.fields[edited_cell_column as usize - 1], &new_text).first() {
+                                return show_dialog(&app_ui.window, false, format!("Invalid value: {}", error));
+                            }
+
                             // Change his value in the `TreeView`.
                             table.list_store.set_value(&tree_iter, edited_cell_column, &new_text.to_value());
 
@@ -2242,11 +2854,16 @@ impl PackedFileDBTreeView{
                             Ok(new_number) => {
 
                                 // If we got a cell...
-                                if let Some(tree_iter) = table.list_store.get_iter(&tree_path) {
+                                if let Some(tree_iter) = table.get_iter_from_view_path(&tree_path) {
 
                                     // Get his column.
                                     let edited_cell_column = table.tree_view.get_cursor().1.unwrap().get_sort_column_id() as u32;
 
+                                    // Check the new value against the constraints of his field before changing it.
+                                    if let Some(error) = validate_edited_value(&table_definition.fields[edited_cell_column as usize - 1], &new_text).first() {
+                                        return show_dialog(&app_ui.window, false, format!("Invalid value: {}", error));
+                                    }
+
                                     // Change his value in the `TreeView`.
                                     table.list_store.set_value(&tree_iter, edited_cell_column, &new_number.to_value());
 
@@ -2303,11 +2920,16 @@ impl PackedFileDBTreeView{
                             Ok(new_number) => {
 
                                 // If we got a cell...
-                                if let Some(tree_iter) = table.list_store.get_iter(&tree_path) {
+                                if let Some(tree_iter) = table.get_iter_from_view_path(&tree_path) {
 
                                     // Get his column.
                                     let edited_cell_column = table.tree_view.get_cursor().1.unwrap().get_sort_column_id() as u32;
 
+                                    // Check the new value against the constraints of his field before changing it.
+                                    if let Some(error) = validate_edited_value(&table_definition.fields[edited_cell_column as usize - 1], &new_text).first() {
+                                        return show_dialog(&app_ui.window, false, format!("Invalid value: {}", error));
+                                    }
+
                                     // Change his value in the `TreeView`.
                                     table.list_store.set_value(&tree_iter, edited_cell_column, &new_number.to_value());
 
@@ -2364,11 +2986,16 @@ impl PackedFileDBTreeView{
                             Ok(new_number) => {
 
                                 // If we got a cell...
-                                if let Some(tree_iter) = table.list_store.get_iter(&tree_path) {
+                                if let Some(tree_iter) = table.get_iter_from_view_path(&tree_path) {
 
                                     // Get his column.
                                     let edited_cell_column = table.tree_view.get_cursor().1.unwrap().get_sort_column_id() as u32;
 
+                                    // Check the new value against the constraints of his field before changing it.
+                                    if let Some(error) = validate_edited_value(&table_definition.fields[edited_cell_column as usize - 1], &new_text).first() {
+                                        return show_dialog(&app_ui.window, false, format!("Invalid value: {}", error));
+                                    }
+
                                     // Change his value in the `TreeView`.
                                     table.list_store.set_value(&tree_iter, edited_cell_column, &format!("{}", new_number).to_value());
 
@@ -2419,7 +3046,7 @@ impl PackedFileDBTreeView{
                     table => move |cell, tree_path| {
 
                         // Get his `TreeIter` and his column.
-                        let tree_iter = table.list_store.get_iter(&tree_path).unwrap();
+                        let tree_iter = table.get_iter_from_view_path(&tree_path).unwrap();
                         let edited_cell_column = table.tree_view.get_cursor().1.unwrap().get_sort_column_id() as u32;
 
                         // Get his new state.
@@ -2466,6 +3093,31 @@ impl PackedFileDBTreeView{
         Ok(())
     }
 
+    /// This function returns the `TreeIter` in the `ListStore` of a row of the `TreeView`. We need it
+    /// because, if the table is filtered, the `TreePath`s of the `TreeView` are not the ones of the `ListStore`.
+    pub fn get_iter_from_view_path(&self, tree_path: &TreePath) -> Option<TreeIter> {
+        match *self.filter_model.borrow() {
+            Some(ref filter_model) => filter_model.convert_path_to_child_path(tree_path).and_then(|tree_path| self.list_store.get_iter(&tree_path)),
+            None => self.list_store.get_iter(tree_path),
+        }
+    }
+
+    /// This function applies a filter to the `TreeView`, or removes it if we pass `None`. While filtered,
+    /// the table cannot be sorted, as `TreeModelFilter` doesn't support it.
+    pub fn set_filter(&self, filter_model: Option<TreeModelFilter>) {
+        match filter_model {
+            Some(ref filter_model) => self.tree_view.set_model(Some(filter_model)),
+            None => self.tree_view.set_model(Some(&self.list_store)),
+        }
+
+        // Only the columns with data can be sorted.
+        for column in self.tree_view.get_columns().iter().filter(|x| x.get_sort_column_id() >= 0) {
+            column.set_clickable(filter_model.is_none());
+        }
+
+        *self.filter_model.borrow_mut() = filter_model;
+    }
+
     /// This function decodes the data of a `DBData` and loads it into a `TreeView`.
     pub fn load_data_to_tree_view(
         packed_file_data: &DBData,
@@ -2503,6 +3155,54 @@ impl PackedFileDBTreeView{
         Ok(())
     }
 
+    /// This function returns the positions of the selected rows in the `ListStore`, sorted.
+    pub fn get_selected_rows(&self) -> Vec<usize> {
+        let mut rows = self.tree_view.get_selection().get_selected_rows().0.iter()
+            .filter_map(|x| self.get_iter_from_view_path(x))
+            .filter_map(|x| self.list_store.get_path(&x))
+            .map(|x| x.get_indices()[0] as usize)
+            .collect::<Vec<usize>>();
+        rows.sort();
+        rows
+    }
+
+    /// This function returns the positions of the selected rows in the table, saving the table to the PackFile
+    /// first, so they are the same positions the rows have in the PackedFile. Only for things that are going
+    /// to change the table, as the PackFile is marked as modified if the order of the rows has changed.
+    pub fn get_selected_rows_synced(
+        &self,
+        window: &ApplicationWindow,
+        table_definition: &TableDefinition,
+        pack_file: &Rc<RefCell<PackFile>>,
+        packed_file_decoded: &Rc<RefCell<DB>>,
+        packed_file_decoded_index: usize,
+    ) -> Result<Vec<usize>, Error> {
+
+        // Sorting the `TreeView` changes the order of the rows, so we save them first.
+        let old_data = pack_file.borrow().data.packed_files[packed_file_decoded_index].data.to_vec();
+        packed_file_decoded.borrow_mut().data.entries = PackedFileDBTreeView::return_data_from_tree_view(table_definition, &self.list_store)?;
+        update_packed_file_data_db(&*packed_file_decoded.borrow(), &mut *pack_file.borrow_mut(), packed_file_decoded_index)?;
+
+        let is_changed = pack_file.borrow().data.packed_files[packed_file_decoded_index].data != old_data;
+        if is_changed { set_modified(true, window, &mut *pack_file.borrow_mut()); }
+
+        Ok(self.get_selected_rows())
+    }
+
+    /// This function decodes again the table from the PackFile and reloads it into the `TreeView`. This is
+    /// for when something other than this view changes the table, like renaming a key.
+    pub fn reload_data(
+        &self,
+        pack_file: &PackFile,
+        packed_file_decoded: &Rc<RefCell<DB>>,
+        packed_file_decoded_index: usize,
+        schema: &Schema,
+    ) -> Result<(), Error> {
+        let packed_file = &pack_file.data.packed_files[packed_file_decoded_index];
+        *packed_file_decoded.borrow_mut() = DB::read(&packed_file.data, &packed_file.path[1], schema)?;
+        PackedFileDBTreeView::load_data_to_tree_view(&packed_file_decoded.borrow().data, &self.list_store)
+    }
+
     /// This function returns a `Vec<Vec<DataDecoded>>` with all the stuff in the table. We need for it the `ListStore` of that table.
     pub fn return_data_from_tree_view(
         table_definition: &TableDefinition,
@@ -2552,6 +3252,17 @@ impl PackedFileDBTreeView{
     }
 }
 
+/// This function returns the value of a cell of a table as text, the same way we write it in a TSV file.
+fn get_cell_text(model: &TreeModel, iter: &TreeIter, column: i32, field_type: &FieldType) -> String {
+    let value = model.get_value(iter, column);
+    match *field_type {
+        FieldType::Boolean => value.get::<bool>().map(|x| x.to_string()),
+        FieldType::Integer => value.get::<i32>().map(|x| x.to_string()),
+        FieldType::LongInteger => value.get::<i64>().map(|x| x.to_string()),
+        _ => value.get::<String>(),
+    }.unwrap_or_else(String::new)
+}
+
 /// Implementation of `PackedFileDBDecoder`.
 impl PackedFileDBDecoder {
 
@@ -2566,6 +3277,7 @@ impl PackedFileDBDecoder {
         table_name: String,
         packed_file_data: Vec<u8>,
         schema: &Rc<RefCell<Option<Schema>>>,
+        dependency_cache: &Rc<RefCell<Option<DependencyCache>>>,
     ) -> Result<(), Error> {
 
         // Create the index for the decoding.
@@ -2634,7 +3346,7 @@ impl PackedFileDBDecoder {
         // And here, the ScrolledWindow and the TreeView.
         let fields_tree_view_scroll = ScrolledWindow::new(None, None);
         let fields_tree_view = TreeView::new();
-        let fields_list_store = ListStore::new(&[String::static_type(), String::static_type(), String::static_type(), bool::static_type(), String::static_type(), String::static_type(), String::static_type(), String::static_type()]);
+        let fields_list_store = ListStore::new(&[String::static_type(), String::static_type(), String::static_type(), bool::static_type(), String::static_type(), String::static_type(), String::static_type(), String::static_type(), String::static_type()]);
         fields_tree_view.set_model(Some(&fields_list_store));
         fields_tree_view.set_margin_bottom(10);
         fields_tree_view.set_hexpand(true);
@@ -2745,6 +3457,16 @@ impl PackedFileDBDecoder {
         column_description.set_title("Description");
         fields_tree_view_cell_string.push(cell_description);
 
+        // The constraints are edited as text, like "required; max_length=32; values=a|b|c".
+        let column_constraints = TreeViewColumn::new();
+        let fields_tree_view_cell_constraints = CellRendererText::new();
+        fields_tree_view_cell_constraints.set_property_editable(true);
+        column_constraints.pack_start(&fields_tree_view_cell_constraints, true);
+        column_constraints.add_attribute(&fields_tree_view_cell_constraints, "text", 8);
+        column_constraints.set_sort_column_id(8);
+        column_constraints.set_clickable(false);
+        column_constraints.set_title("Constraints");
+
         fields_tree_view.append_column(&column_index);
         fields_tree_view.append_column(&column_name);
         fields_tree_view.append_column(&column_type);
@@ -2753,6 +3475,7 @@ impl PackedFileDBDecoder {
         fields_tree_view.append_column(&column_ref_column);
         fields_tree_view.append_column(&column_decoded);
         fields_tree_view.append_column(&column_description);
+        fields_tree_view.append_column(&column_constraints);
 
         // Here we create the context menu for the `fields_tree_view`.
         let context_menu = Popover::new_from_model(Some(&fields_tree_view), &app_ui.db_decoder_context_menu_model);
@@ -3102,6 +3825,7 @@ impl PackedFileDBDecoder {
             fields_tree_view_cell_combo,
             fields_tree_view_cell_combo_list_store,
             fields_tree_view_cell_string,
+            fields_tree_view_cell_constraints,
             delete_all_fields_button,
             decoder_grid_scroll,
             context_menu,
@@ -3204,6 +3928,7 @@ impl PackedFileDBDecoder {
             // Basically, hitting this button deletes the selected definition.
             decoder_view.all_table_versions_remove_definition.connect_button_release_event(clone!(
                 schema,
+                dependency_cache,
                 app_ui,
                 table_name,
                 decoder_view => move |_ ,_| {
@@ -3222,8 +3947,12 @@ impl PackedFileDBDecoder {
                                 // Try to remove that version form the schema.
                                 match DB::remove_table_version(&table_name, version_to_delete, schema) {
 
-                                    // If it worked, update the list.
-                                    Ok(_) => decoder_view.update_versions_list(schema, &table_name),
+                                    // If it worked, update the list and drop the cache of the dependency database,
+                                    // as it may depend on the removed definition.
+                                    Ok(_) => {
+                                        decoder_view.update_versions_list(schema, &table_name);
+                                        *dependency_cache.borrow_mut() = None;
+                                    }
                                     Err(error) => show_dialog(&app_ui.window, false, error.cause()),
                                 }
                             }
@@ -3269,6 +3998,7 @@ impl PackedFileDBDecoder {
             decoder_view.save_decoded_schema.connect_button_release_event(clone!(
                 app_ui,
                 schema,
+                dependency_cache,
                 table_definition,
                 table_name,
                 rpfm_path,
@@ -3306,13 +4036,16 @@ impl PackedFileDBDecoder {
                             schema.tables_definitions[table_definitions_index as usize].add_table_definition(table_definition.borrow().clone());
 
                             // And try to save the main `Schema`.
-                            match Schema::save(schema, &rpfm_path, &supported_games.borrow().iter().filter(|x| x.folder_name == *game_selected.borrow().game).map(|x| x.schema.to_owned()).collect::<String>()) {
+                            match Schema::save(schema, &rpfm_path, &supported_games.borrow(), &game_selected.borrow().game) {
                                 Ok(_) => show_dialog(&app_ui.window, true, "Schema successfully saved."),
                                 Err(error) => show_dialog(&app_ui.window, false, error.cause()),
                             }
 
                             // After all that, we need to update the version list, as this may have created a new version.
                             decoder_view.update_versions_list(schema, &table_name);
+
+                            // The cache of the dependency database may need this definition, so we drop it.
+                            *dependency_cache.borrow_mut() = None;
                         }
                         None => show_dialog(&app_ui.window, false, "Cannot save this table's definitions:\nSchemas for this game are not supported, yet.")
                     }
@@ -3639,6 +4372,18 @@ impl PackedFileDBDecoder {
                     }
                 ));
             }
+
+            // The constraints are checked before changing them, so we don't save a definition with broken constraints.
+            decoder_view.fields_tree_view_cell_constraints.connect_edited(clone!(
+                app_ui,
+                decoder_view => move |_ ,tree_path , new_text| {
+                    let tree_iter = decoder_view.fields_list_store.get_iter(&tree_path).unwrap();
+                    match Field::new(String::new(), FieldType::Boolean, false, None, String::new()).set_constraints(new_text) {
+                        Ok(_) => decoder_view.fields_list_store.set_value(&tree_iter, 8, &new_text.to_value()),
+                        Err(error) => show_dialog(&app_ui.window, false, error.cause()),
+                    }
+                }
+            ));
         }
 
         // Destruction event.
@@ -3788,6 +4533,7 @@ impl PackedFileDBDecoder {
                     field.field_is_key,
                     &field.field_is_reference,
                     &field.field_description,
+                    &field.get_constraints(),
                     &mut index_data,
                     Some(index)
                 );
@@ -3962,6 +4708,7 @@ impl PackedFileDBDecoder {
         field_is_key: bool,
         field_is_reference: &Option<(String, String)>,
         field_description: &str,
+        field_constraints: &str,
         mut index_data: &mut usize,
         index_row: Option<usize>
     ) {
@@ -4009,7 +4756,7 @@ impl PackedFileDBDecoder {
         if let Some(ref reference) = *field_is_reference {
             self.fields_list_store.insert_with_values(
                 None,
-                &[0, 1, 2, 3, 4, 5, 6, 7],
+                &[0, 1, 2, 3, 4, 5, 6, 7, 8],
                 &[
                     &field_index,
                     &field_name,
@@ -4019,13 +4766,14 @@ impl PackedFileDBDecoder {
                     &reference.1,
                     &decoded_data,
                     &field_description,
+                    &field_constraints,
                 ]
             );
         }
         else {
             self.fields_list_store.insert_with_values(
                 None,
-                &[0, 1, 2, 3, 4, 5, 6, 7],
+                &[0, 1, 2, 3, 4, 5, 6, 7, 8],
                 &[
                     &field_index,
                     &field_name,
@@ -4035,6 +4783,7 @@ impl PackedFileDBDecoder {
                     &String::new(),
                     &decoded_data,
                     &field_description,
+                    &field_constraints,
                 ]
             );
         }
@@ -4054,6 +4803,7 @@ impl PackedFileDBDecoder {
                 let ref_table: String = self.fields_list_store.get_value(&current_line, 4).get().unwrap();
                 let ref_column: String = self.fields_list_store.get_value(&current_line, 5).get().unwrap();
                 let field_description: String = self.fields_list_store.get_value(&current_line, 7).get().unwrap();
+                let field_constraints: String = self.fields_list_store.get_value(&current_line, 8).get().unwrap();
 
                 let field_type = match self.fields_list_store.get_value(&current_line, 2).get().unwrap() {
                     "Bool" => FieldType::Boolean,
@@ -4066,12 +4816,16 @@ impl PackedFileDBDecoder {
                     "OptionalStringU16" | _=> FieldType::OptionalStringU16,
                 };
 
-                if ref_table.is_empty() {
-                    fields.push(Field::new(field_name, field_type, field_is_key, None, field_description));
+                let mut field = if ref_table.is_empty() {
+                    Field::new(field_name, field_type, field_is_key, None, field_description)
                 }
                 else {
-                    fields.push(Field::new(field_name, field_type, field_is_key, Some((ref_table, ref_column)), field_description));
-                }
+                    Field::new(field_name, field_type, field_is_key, Some((ref_table, ref_column)), field_description)
+                };
+
+                // The constraints are checked when edited, so this cannot fail.
+                let _ = field.set_constraints(&field_constraints);
+                fields.push(field);
 
                 if !self.fields_list_store.iter_next(&current_line) {
                     done = true;
@@ -4098,6 +4852,7 @@ impl PackedFileDBDecoder {
             self.is_key_field_switch.get_active(),
             &None,
             &String::new(),
+            "",
             &mut index_data,
             None
         );
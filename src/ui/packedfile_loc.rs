@@ -20,6 +20,7 @@ use settings::*;
 use ui::*;
 use AppUI;
 use packedfile::SerializableToTSV;
+use packedfile::serializers::SerializableWithHeaders;
 
 /// Struct `PackedFileLocTreeView`: contains all the stuff we need to give to the program to show a
 /// `TreeView` with the data of a Loc PackedFile, allowing us to manipulate it.
@@ -243,6 +244,16 @@ impl PackedFileLocTreeView{
                 export_tsv_button.set_property_text(Some("Export to TSV"));
                 export_tsv_button.set_action_name("app.packedfile_loc_export_tsv");
 
+                // Create the "Import from JSON/CSV" button.
+                let import_headered_button = ModelButton::new();
+                import_headered_button.set_property_text(Some("Import from JSON/CSV"));
+                import_headered_button.set_action_name("app.packedfile_loc_import_headered");
+
+                // Create the "Export to JSON/CSV" button.
+                let export_headered_button = ModelButton::new();
+                export_headered_button.set_property_text(Some("Export to JSON/CSV"));
+                export_headered_button.set_action_name("app.packedfile_loc_export_headered");
+
                 // Right-click menu actions.
                 let add_rows = SimpleAction::new("packedfile_loc_add_rows", None);
                 let delete_rows = SimpleAction::new("packedfile_loc_delete_rows", None);
@@ -254,6 +265,8 @@ impl PackedFileLocTreeView{
                 let paste_columns = SimpleAction::new("packedfile_loc_paste_columns", None);
                 let import_tsv = SimpleAction::new("packedfile_loc_import_tsv", None);
                 let export_tsv = SimpleAction::new("packedfile_loc_export_tsv", None);
+                let import_headered = SimpleAction::new("packedfile_loc_import_headered", None);
+                let export_headered = SimpleAction::new("packedfile_loc_export_headered", None);
 
                 application.add_action(&add_rows);
                 application.add_action(&delete_rows);
@@ -265,6 +278,8 @@ impl PackedFileLocTreeView{
                 application.add_action(&paste_columns);
                 application.add_action(&import_tsv);
                 application.add_action(&export_tsv);
+                application.add_action(&import_headered);
+                application.add_action(&export_headered);
 
                 // Accels for popovers need to be specified here. Don't know why, but otherwise they do not work.
                 application.set_accels_for_action("app.packedfile_loc_add_rows", &["<Primary><Shift>a"]);
@@ -300,6 +315,8 @@ impl PackedFileLocTreeView{
                 context_menu_grid.attach(&separator_2, 0, 9, 2, 1);
                 context_menu_grid.attach(&import_tsv_button, 0, 10, 2, 1);
                 context_menu_grid.attach(&export_tsv_button, 0, 11, 2, 1);
+                context_menu_grid.attach(&import_headered_button, 0, 12, 2, 1);
+                context_menu_grid.attach(&export_headered_button, 0, 13, 2, 1);
 
                 // Add the `Grid` to the Context Menu and show it.
                 context_menu.add(&context_menu_grid);
@@ -1011,6 +1028,7 @@ impl PackedFileLocTreeView{
                     // When we hit the "Export to TSV" button.
                     export_tsv.connect_activate(clone!(
                         app_ui,
+                        tree_path,
                         packed_file_decoded,
                         decoded_view => move |_,_|{
 
@@ -1051,6 +1069,100 @@ impl PackedFileLocTreeView{
                             }
                         }
                     ));
+
+                    // When we hit the "Import from JSON/CSV" button.
+                    import_headered.connect_activate(clone!(
+                        app_ui,
+                        pack_file,
+                        packed_file_decoded,
+                        packed_file_decoded_index,
+                        decoded_view => move |_,_|{
+
+                            // We hide the context menu.
+                            decoded_view.context_menu.popdown();
+
+                            // We only do something in case the focus is in the TreeView. This should stop problems with
+                            // the accels working everywhere.
+                            if decoded_view.tree_view.has_focus() {
+
+                                // Create the `FileChooser`, with a filter for each format.
+                                let file_chooser = FileChooserNative::new(
+                                    "Select JSON/CSV/TSV File to Import...",
+                                    &app_ui.window,
+                                    FileChooserAction::Open,
+                                    "Import",
+                                    "Cancel"
+                                );
+                                file_chooser_filter_packfile(&file_chooser, "*.json");
+                                file_chooser_filter_packfile(&file_chooser, "*.csv");
+                                file_chooser_filter_packfile(&file_chooser, "*.tsv");
+
+                                // If we have selected a file to import...
+                                if file_chooser.run() == gtk_response_accept {
+
+                                    // If there is an error while importing the file, we report it.
+                                    let report = match packed_file_decoded.borrow_mut().data.import_with_headers(&file_chooser.get_filename().unwrap()) {
+                                        Ok(report) => report,
+                                        Err(error) => return show_dialog(&app_ui.window, false, error.cause()),
+                                    };
+
+                                    // Load the new data to the TreeView.
+                                    PackedFileLocTreeView::load_data_to_tree_view(&packed_file_decoded.borrow().data, &decoded_view.list_store);
+
+                                    // Update the PackFile to reflect the changes.
+                                    update_packed_file_data_loc(
+                                        &*packed_file_decoded.borrow_mut(),
+                                        &mut *pack_file.borrow_mut(),
+                                        packed_file_decoded_index
+                                    );
+
+                                    // Set the mod as "Modified", and tell the user what happened with the columns.
+                                    set_modified(true, &app_ui.window, &mut *pack_file.borrow_mut());
+                                    show_dialog(&app_ui.window, true, report);
+                                }
+                            }
+                        }
+                    ));
+
+                    // When we hit the "Export to JSON/CSV" button.
+                    export_headered.connect_activate(clone!(
+                        app_ui,
+                        tree_path,
+                        packed_file_decoded,
+                        decoded_view => move |_,_|{
+
+                            // We hide the context menu.
+                            decoded_view.context_menu.popdown();
+
+                            // We only do something in case the focus is in the TreeView. This should stop problems with
+                            // the accels working everywhere.
+                            if decoded_view.tree_view.has_focus() {
+
+                                // Create the `FileChooser`.
+                                let file_chooser = FileChooserNative::new(
+                                    "Export JSON/CSV/TSV File...",
+                                    &app_ui.window,
+                                    FileChooserAction::Save,
+                                    "Save",
+                                    "Cancel"
+                                );
+
+                                // We want to ask before overwriting files. Just in case. Otherwise, there can be an accident.
+                                file_chooser.set_do_overwrite_confirmation(true);
+
+                                // The format depends on the extension. By default, we use JSON.
+                                file_chooser.set_current_name(format!("{}.json", &tree_path.last().unwrap()));
+
+                                // If we hit "Save"...
+                                if file_chooser.run() == gtk_response_accept {
+                                    match packed_file_decoded.borrow().data.export_with_headers(&file_chooser.get_filename().unwrap()) {
+                                        Ok(result) => show_dialog(&app_ui.window, true, result),
+                                        Err(error) => show_dialog(&app_ui.window, false, error.cause())
+                                    }
+                                }
+                            }
+                        }
+                    ));
                 }
 
                 // Things that happen when we edit a cell.
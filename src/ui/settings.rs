@@ -39,6 +39,11 @@ pub struct SettingsWindow {
     pub settings_extra_allow_edition_of_ca_packfiles: CheckButton,
     pub settings_extra_check_updates_on_start: CheckButton,
     pub settings_extra_check_schema_updates_on_start: CheckButton,
+    pub settings_extra_validate_tables_on_save: CheckButton,
+    pub settings_extra_block_save_on_validation_errors: CheckButton,
+    pub settings_extra_sort_rows_on_save: CheckButton,
+    pub settings_extra_sort_rows_on_save_packfiles: Entry,
+    pub settings_extra_deterministic_guids: CheckButton,
     pub settings_theme_prefer_dark_theme: CheckButton,
     pub settings_theme_font_button: FontButton,
     pub settings_cancel: Button,
@@ -194,6 +199,42 @@ impl SettingsWindow {
         check_schema_updates_on_start_label.set_yalign(0.5);
         check_schema_updates_on_start_checkbox.set_hexpand(true);
 
+        let validate_tables_on_save_label = Label::new(Some("Validate Tables on Save:"));
+        let validate_tables_on_save_checkbox = CheckButton::new();
+        validate_tables_on_save_label.set_size_request(170, 0);
+        validate_tables_on_save_label.set_xalign(0.0);
+        validate_tables_on_save_label.set_yalign(0.5);
+        validate_tables_on_save_checkbox.set_hexpand(true);
+
+        let block_save_on_validation_errors_label = Label::new(Some("Block Save on Validation Errors:"));
+        let block_save_on_validation_errors_checkbox = CheckButton::new();
+        block_save_on_validation_errors_label.set_size_request(170, 0);
+        block_save_on_validation_errors_label.set_xalign(0.0);
+        block_save_on_validation_errors_label.set_yalign(0.5);
+        block_save_on_validation_errors_checkbox.set_hexpand(true);
+
+        let sort_rows_on_save_label = Label::new(Some("Sort Rows on Save:"));
+        let sort_rows_on_save_checkbox = CheckButton::new();
+        sort_rows_on_save_label.set_size_request(170, 0);
+        sort_rows_on_save_label.set_xalign(0.0);
+        sort_rows_on_save_label.set_yalign(0.5);
+        sort_rows_on_save_checkbox.set_hexpand(true);
+
+        let sort_rows_on_save_packfiles_label = Label::new(Some("Sort Rows only in PackFiles:"));
+        let sort_rows_on_save_packfiles_entry = Entry::new();
+        sort_rows_on_save_packfiles_label.set_size_request(170, 0);
+        sort_rows_on_save_packfiles_label.set_xalign(0.0);
+        sort_rows_on_save_packfiles_label.set_yalign(0.5);
+        sort_rows_on_save_packfiles_entry.set_hexpand(true);
+        sort_rows_on_save_packfiles_entry.set_placeholder_text("my_mod.pack, my_other_mod.pack");
+
+        let deterministic_guids_label = Label::new(Some("Deterministic Table GUIDs:"));
+        let deterministic_guids_checkbox = CheckButton::new();
+        deterministic_guids_label.set_size_request(170, 0);
+        deterministic_guids_label.set_xalign(0.0);
+        deterministic_guids_label.set_yalign(0.5);
+        deterministic_guids_checkbox.set_hexpand(true);
+
         let button_box = ButtonBox::new(Orientation::Horizontal);
         button_box.set_layout(ButtonBoxStyle::End);
         button_box.set_spacing(10);
@@ -226,6 +267,16 @@ impl SettingsWindow {
         extra_settings_grid.attach(&check_updates_on_start_checkbox, 1, 2, 1, 1);
         extra_settings_grid.attach(&check_schema_updates_on_start_label, 0, 3, 1, 1);
         extra_settings_grid.attach(&check_schema_updates_on_start_checkbox, 1, 3, 1, 1);
+        extra_settings_grid.attach(&validate_tables_on_save_label, 0, 4, 1, 1);
+        extra_settings_grid.attach(&validate_tables_on_save_checkbox, 1, 4, 1, 1);
+        extra_settings_grid.attach(&block_save_on_validation_errors_label, 0, 5, 1, 1);
+        extra_settings_grid.attach(&block_save_on_validation_errors_checkbox, 1, 5, 1, 1);
+        extra_settings_grid.attach(&sort_rows_on_save_label, 0, 6, 1, 1);
+        extra_settings_grid.attach(&sort_rows_on_save_checkbox, 1, 6, 1, 1);
+        extra_settings_grid.attach(&sort_rows_on_save_packfiles_label, 0, 7, 1, 1);
+        extra_settings_grid.attach(&sort_rows_on_save_packfiles_entry, 1, 7, 1, 1);
+        extra_settings_grid.attach(&deterministic_guids_label, 0, 8, 1, 1);
+        extra_settings_grid.attach(&deterministic_guids_checkbox, 1, 8, 1, 1);
 
         extra_settings_frame.add(&extra_settings_grid);
 
@@ -353,6 +404,11 @@ impl SettingsWindow {
             settings_extra_allow_edition_of_ca_packfiles: allow_edition_of_ca_packfiles_checkbox,
             settings_extra_check_updates_on_start: check_updates_on_start_checkbox,
             settings_extra_check_schema_updates_on_start: check_schema_updates_on_start_checkbox,
+            settings_extra_validate_tables_on_save: validate_tables_on_save_checkbox,
+            settings_extra_block_save_on_validation_errors: block_save_on_validation_errors_checkbox,
+            settings_extra_sort_rows_on_save: sort_rows_on_save_checkbox,
+            settings_extra_sort_rows_on_save_packfiles: sort_rows_on_save_packfiles_entry,
+            settings_extra_deterministic_guids: deterministic_guids_checkbox,
             settings_theme_prefer_dark_theme: prefer_dark_theme_checkbox,
             settings_theme_font_button: font_settings_button,
             settings_cancel: cancel_button,
@@ -387,6 +443,15 @@ impl SettingsWindow {
         self.settings_extra_check_updates_on_start.set_active(settings.check_updates_on_start);
         self.settings_extra_check_schema_updates_on_start.set_active(settings.check_schema_updates_on_start);
 
+        // Load the "Validation" settings.
+        self.settings_extra_validate_tables_on_save.set_active(settings.validate_tables_on_save);
+        self.settings_extra_block_save_on_validation_errors.set_active(settings.block_save_on_validation_errors);
+
+        // Load the "Normalization" settings.
+        self.settings_extra_sort_rows_on_save.set_active(settings.sort_rows_on_save);
+        self.settings_extra_sort_rows_on_save_packfiles.get_buffer().set_text(&settings.sort_rows_on_save_packfiles.join(", "));
+        self.settings_extra_deterministic_guids.set_active(settings.deterministic_guids);
+
         // Load the current Theme prefs.
         self.settings_theme_prefer_dark_theme.set_active(settings.prefer_dark_theme);
         self.settings_theme_font_button.set_font_name(&settings.font);
@@ -422,6 +487,15 @@ impl SettingsWindow {
         settings.check_updates_on_start = self.settings_extra_check_updates_on_start.get_active();
         settings.check_schema_updates_on_start = self.settings_extra_check_schema_updates_on_start.get_active();
 
+        // Get the "Validation" settings.
+        settings.validate_tables_on_save = self.settings_extra_validate_tables_on_save.get_active();
+        settings.block_save_on_validation_errors = self.settings_extra_block_save_on_validation_errors.get_active();
+
+        // Get the "Normalization" settings.
+        settings.sort_rows_on_save = self.settings_extra_sort_rows_on_save.get_active();
+        settings.sort_rows_on_save_packfiles = self.settings_extra_sort_rows_on_save_packfiles.get_buffer().get_text().split(',').map(|x| x.trim().to_owned()).filter(|x| !x.is_empty()).collect();
+        settings.deterministic_guids = self.settings_extra_deterministic_guids.get_active();
+
         // Get the Theme and Font settings.
         settings.prefer_dark_theme = self.settings_theme_prefer_dark_theme.get_active();
         settings.font = self.settings_theme_font_button.get_font_name().unwrap_or_else(|| String::from("Segoe UI 9"));
@@ -17,6 +17,8 @@ use ui;
 use settings::GameSelected;
 use settings::GameInfo;
 use packedfile::db::schemas::Schema;
+use packedfile::db::dependency_cache::DependencyCache;
+use packedfile::db::schemas_diff::SchemaDiff;
 use updater::*;
 
 /// This enum controls the posible responses from the server.
@@ -189,6 +191,7 @@ pub fn check_schema_updates(
     supported_games: &[GameInfo],
     game_selected: &Rc<RefCell<GameSelected>>,
     loaded_schema: &Rc<RefCell<Option<Schema>>>,
+    dependency_cache: &Rc<RefCell<Option<DependencyCache>>>,
     use_dialog: Option<&ApplicationWindow>,
     status_bar: Option<&Statusbar>
 ) {
@@ -232,13 +235,13 @@ pub fn check_schema_updates(
                 let mut message = String::new();
 
                 // For each schema supported...
-                for (index, schema) in current_versions.schemas.iter().enumerate() {
+                for schema in &current_versions.schemas {
 
                     // Add the name of the game, aligned to the left, with 20 characters.
                     message.push_str(&format!("{:width$}", schema.schema_file, width = 20));
 
                     // If the game exist in the local version, show both versions.
-                    if let Some(local_schema) = local_versions.schemas.get(index) {
+                    if let Some(local_schema) = local_versions.schemas.iter().find(|x| x.schema_file == schema.schema_file) {
                         message.push_str(&format!(": {} => {}\n", local_schema.version, schema.version))
                     }
 
@@ -283,6 +286,11 @@ pub fn check_schema_updates(
                 // Useless if, but easiest way I know to get local and current version at this point.
                 if let APIResponseSchema::SuccessNewUpdate(local_versions, current_versions) = apiresponse {
 
+                    // Keep the current schemas, so we can tell what changed after the update.
+                    let old_schemas = current_versions.schemas.iter()
+                        .map(|x| (x.schema_file.to_owned(), Schema::load_base(rpfm_path, &x.schema_file).unwrap_or_else(|_| Schema::new())))
+                        .collect::<Vec<(String, Schema)>>();
+
                     // Try to update the schemas.
                     let result = update_schemas(local_versions, current_versions, rpfm_path);
 
@@ -293,11 +301,29 @@ pub fn check_schema_updates(
                     match result {
                         Ok(_) => {
 
-                            // Reload the currently in-use schema, just in case it got updated.
-                            *loaded_schema.borrow_mut() = Schema::load(rpfm_path, &supported_games.iter().filter(|x| x.folder_name == *game_selected.borrow().game).map(|x| x.schema.to_owned()).collect::<String>()).ok();
+                            // Reload the currently in-use schema, just in case it got updated, and drop the cache of the dependency database.
+                            *loaded_schema.borrow_mut() = ui::load_schema(parent_window, rpfm_path, supported_games, &game_selected.borrow().game);
+                            *dependency_cache.borrow_mut() = None;
+
+                            // Get what changed in each schema.
+                            let mut changes = String::new();
+                            for &(ref schema_file, ref old_schema) in &old_schemas {
+                                if let Ok(new_schema) = Schema::load_base(rpfm_path, schema_file) {
+                                    let diff = SchemaDiff::new(old_schema, &new_schema);
+                                    if !diff.is_empty() { changes.push_str(&format!("# {}\n\n{}\n", schema_file, diff.to_markdown())); }
+                                }
+                            }
+
+                            // And which of the user's definitions are now in the downloaded schema, if any.
+                            if let Ok(report) = Schema::check_overlay(rpfm_path, supported_games, &game_selected.borrow().game) {
+                                if !report.identical.is_empty() || !report.different.is_empty() {
+                                    changes.push_str(&format!("# Your definitions\n\n{}", report));
+                                }
+                            }
 
                             // Report success.
-                            ui::show_dialog(parent_window, true, "Schemas successfully updated.");
+                            if changes.is_empty() { ui::show_dialog(parent_window, true, "Schemas successfully updated."); }
+                            else { ui::show_report(parent_window, "Schemas successfully updated", format!("Schemas successfully updated. These are the changes:\n\n{}", changes)); }
                         }
                         Err(_) => ui::show_dialog(parent_window, false, "Error while trying to update the schemas."),
                     }
@@ -57,13 +57,13 @@ pub fn update_schemas(
 ) -> Result<(), Error> {
 
     // For each schema in the repo...
-    for (index, schema) in current_versions.schemas.iter().enumerate() {
+    for schema in &current_versions.schemas {
 
         // Get the local_schema's path.
         let local_schema_path = rpfm_path.to_path_buf().join(PathBuf::from(format!("schemas/{}", schema.schema_file)));
 
-        // If the schema exist in our local_versions...
-        if let Some(local_schema) = local_versions.schemas.get(index) {
+        // If the schema exist in our local_versions... Each file is tracked by his name, as games can have their own files.
+        if let Some(local_schema) = local_versions.schemas.iter().find(|x| x.schema_file == schema.schema_file) {
 
             // If the current_version is greater than the local one...
             if schema.version > local_schema.version {